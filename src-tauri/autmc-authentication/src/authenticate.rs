@@ -1,8 +1,9 @@
 use crate::{
     consts::{
         CLIENT_ID, DEVICE_CODE_GRANT_TYPE, DEVICE_CODE_SCOPE, MICROSOFT_DEVICE_CODE_URL,
-        MICROSOFT_TOKEN_URL, MINECRAFT_AUTHENTICATE_URL, MINECRAFT_PROFILE_URL,
-        XBOX_LIVE_AUTHENTICATE_URL, XTXS_AUTHENTICATE_URL,
+        MICROSOFT_TOKEN_URL, MINECRAFT_ACTIVE_CAPE_URL, MINECRAFT_AUTHENTICATE_URL,
+        MINECRAFT_LICENSE_URL, MINECRAFT_PROFILE_URL, MINECRAFT_RELYING_PARTY,
+        XBOX_LIVE_AUTHENTICATE_URL, XBOX_LIVE_RELYING_PARTY, XTXS_AUTHENTICATE_URL,
     },
     error::{
         AuthenticationError, AuthenticationResult, MicrosoftErrorResponse,
@@ -10,11 +11,17 @@ use crate::{
     },
 };
 use autmc_log::debug_if;
-use log::debug;
-use reqwest::{Client, Response};
+use crypto::{digest::Digest, md5::Md5};
+use log::{debug, warn};
+use reqwest::{Client, Response, StatusCode};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
-use std::{collections::HashMap, thread::sleep, time::Duration};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::time::sleep;
+use tracing::instrument;
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct MinecraftAccount {
@@ -27,6 +34,12 @@ pub struct MinecraftAccount {
     pub microsoft_refresh_token: String,
     pub minecraft_access_token: String,
     pub minecraft_access_token_expiry: u64,
+    /// Xbox user id, empty for accounts saved before it was kept or if it couldn't be retrieved.
+    #[serde(default)]
+    pub xuid: String,
+    /// Created with [create_offline_account], it has no tokens and can only play offline.
+    #[serde(default)]
+    pub offline: bool,
 }
 
 impl Into<MicrosoftToken> for MinecraftAccount {
@@ -44,6 +57,7 @@ impl MinecraftAccount {
         minecraft_profile_response: MinecraftProfileResponse,
         microsoft_token: MicrosoftToken,
         minecraft_token_response: MinecraftTokenResponse,
+        xuid: String,
     ) -> Self {
         let skin_url = minecraft_profile_response.active_skin().url.clone();
 
@@ -59,10 +73,57 @@ impl MinecraftAccount {
             microsoft_refresh_token: microsoft_token.refresh_token,
             minecraft_access_token: minecraft_token_response.access_token,
             minecraft_access_token_expiry,
+            xuid,
+            offline: false,
+        }
+    }
+
+    /// Passed to the game as `${user_type}`, `msa` for accounts that signed in with a Microsoft
+    /// account.
+    pub fn user_type(&self) -> &'static str {
+        if self.offline {
+            "legacy"
+        } else {
+            "msa"
         }
     }
 }
 
+/// Creates an account to play offline with, without signing in. Its uuid is derived from
+/// `username` the same way offline mode servers do, so it keeps its player data between launches.
+pub fn create_offline_account(username: &str) -> AuthenticationResult<MinecraftAccount> {
+    let valid = (1..=16).contains(&username.len())
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(AuthenticationError::InvalidUsername(username.into()));
+    }
+    Ok(MinecraftAccount {
+        uuid: offline_uuid(username),
+        name: username.into(),
+        offline: true,
+        ..Default::default()
+    })
+}
+
+/// The version 3 uuid of `OfflinePlayer:<username>`, without dashes like profile ids.
+fn offline_uuid(username: &str) -> String {
+    let mut md5 = Md5::new();
+    md5.input_str(&format!("OfflinePlayer:{}", username));
+    let mut bytes = [0u8; 16];
+    md5.result(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x30;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The id of the Azure application the launcher authenticates with, passed to the game as
+/// `${clientid}`.
+pub fn client_id() -> &'static str {
+    CLIENT_ID.1
+}
+
 #[derive(Debug, Deserialize)]
 /// Response struct for the Microsoft OAuth process.  
 /// Commented out fields are currenty unused but exist in the response
@@ -101,6 +162,7 @@ pub enum OAuthRefreshMode {
     Minecraft { token: MicrosoftToken },
 }
 
+#[instrument(skip_all)]
 pub async fn refresh_access_tokens(
     refresh_mode: OAuthRefreshMode,
 ) -> AuthenticationResult<MinecraftAccount> {
@@ -128,32 +190,101 @@ pub async fn start_device_code_authentication() -> AuthenticationResult<DeviceCo
     Ok(device_code_response.into())
 }
 
-pub async fn poll_device_code_status(device_code: &str) -> AuthenticationResult<MinecraftAccount> {
-    // Maximum number of attempts, each attempts will sleep for 1s
-    const MAX_ATTEMPTS: usize = 120;
+/// Seconds between polls when the device code response doesn't say, as in RFC 8628.
+pub const DEFAULT_POLL_INTERVAL: u64 = 5;
+/// Seconds a device code is valid for when the device code response doesn't say.
+pub const DEFAULT_DEVICE_CODE_EXPIRY: u64 = 900;
+/// The shortest interval polled at, whatever the caller asks for, so a bad interval can't hammer
+/// the token endpoint.
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How much the interval grows on every `slow_down`, as in RFC 8628.
+const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+/// Upper bound of the random delay added to every poll, so launchers started together don't poll
+/// in lockstep.
+const MAX_POLL_JITTER_MILLIS: u32 = 500;
+
+/// What to do after the token endpoint answered a poll with an error.
+#[derive(Debug, PartialEq)]
+enum PollAction {
+    /// Poll again after the interval.
+    Wait(Duration),
+    Stop,
+}
+
+/// Handles the device code error responses of RFC 8628 section 3.5: `authorization_pending` keeps
+/// the interval, `slow_down` increases it and anything else, like `authorization_declined` or
+/// `expired_token`, ends the flow.
+fn poll_action(error_type: &str, interval: Duration) -> PollAction {
+    match error_type {
+        "authorization_pending" => PollAction::Wait(interval),
+        "slow_down" => PollAction::Wait(interval + SLOW_DOWN_INCREMENT),
+        _ => PollAction::Stop,
+    }
+}
+
+/// The interval of `interval` seconds, raised to [MIN_POLL_INTERVAL].
+fn poll_interval(interval: u64) -> Duration {
+    Duration::from_secs(interval).max(MIN_POLL_INTERVAL)
+}
+
+fn poll_jitter() -> Duration {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    Duration::from_millis((nanos % MAX_POLL_JITTER_MILLIS).into())
+}
+
+/// Polls the token endpoint every `interval` seconds, at least one, until the user signed in with
+/// the device code, declined, or it expired after `expires_in` seconds.
+#[instrument(skip_all)]
+pub async fn poll_device_code_status(
+    device_code: &str,
+    interval: u64,
+    expires_in: u64,
+) -> AuthenticationResult<MinecraftAccount> {
+    let expires_at = Instant::now() + Duration::from_secs(expires_in);
+    let mut interval = poll_interval(interval);
     let mut attempts = 0;
     debug!("Polling OAuth device code endpoint");
     let microsoft_token_response = loop {
+        sleep(interval + poll_jitter()).await;
+        if Instant::now() >= expires_at {
+            return Err(AuthenticationError::MaxAttemptsExceeded(format!(
+                "The device code expired after {} seconds.",
+                expires_in
+            )));
+        }
+        attempts += 1;
         debug_if!(
             "AUTHENTICATION",
             "Attempt #{} while polling device code endpoint.",
             attempts
         );
-        if attempts >= MAX_ATTEMPTS {
-            return Err(AuthenticationError::MaxAttemptsExceeded(
-                "Device code authentication took longer than 2 minutes.".into(),
-            ));
-        }
 
         let token_response = poll_microsoft_token_endpoint(device_code).await?;
-        if !token_response.status().is_success() {
-            sleep(Duration::from_secs(1));
-            attempts += 1;
-        } else {
-            break get_response_if_ok::<MicrosoftTokenResponse, MicrosoftErrorResponse>(
-                token_response,
-            )
-            .await?;
+        let error = match get_response_if_ok::<MicrosoftTokenResponse, MicrosoftErrorResponse>(
+            token_response,
+        )
+        .await
+        {
+            Ok(response) => break response,
+            Err(error) => error,
+        };
+        let action = match &error {
+            AuthenticationError::MicrosoftError { error_type, .. } => {
+                poll_action(error_type, interval)
+            }
+            _ => PollAction::Stop,
+        };
+        match action {
+            PollAction::Wait(next_interval) => {
+                if next_interval != interval {
+                    debug!("Slowing device code polling to {:?}", next_interval);
+                }
+                interval = next_interval;
+            }
+            PollAction::Stop => return Err(error),
         }
     };
     debug_if!(
@@ -164,6 +295,7 @@ pub async fn poll_device_code_status(device_code: &str) -> AuthenticationResult<
     continue_authentication_flow(microsoft_token_response.into()).await
 }
 
+#[instrument(name = "authenticate", skip_all)]
 async fn continue_authentication_flow(
     microsoft_token: MicrosoftToken,
 ) -> AuthenticationResult<MinecraftAccount> {
@@ -176,7 +308,8 @@ async fn continue_authentication_flow(
     );
 
     debug!("Requesting Xbox Secure Token Service access token.");
-    let xsts_token_response = get_xsts_token(&xbl_token_response.access_token).await?;
+    let xsts_token_response =
+        get_xsts_token(&xbl_token_response.access_token, MINECRAFT_RELYING_PARTY).await?;
     debug_if!(
         "AUTHENTICATION",
         "Received Xbox Secure Token Service access token '{}'",
@@ -187,6 +320,18 @@ async fn continue_authentication_flow(
         None => return Err(AuthenticationError::XSTSMissingUserHash),
     };
 
+    // Only Xbox Live's own relying party has the xuid. The game works without it, so failing to
+    // get it doesn't fail the login.
+    debug!("Requesting Xbox Live XSTS token for the xuid.");
+    let xuid = match get_xsts_token(&xbl_token_response.access_token, XBOX_LIVE_RELYING_PARTY).await
+    {
+        Ok(response) => response.get_xuid().unwrap_or_default(),
+        Err(error) => {
+            warn!("Could not get the xuid of the account: {}", error);
+            String::new()
+        }
+    };
+
     debug!("Requesting Minecraft access token.");
     let minecraft_token_response =
         get_minecraft_token(&xsts_token_response.access_token, &user_hash).await?;
@@ -195,14 +340,29 @@ async fn continue_authentication_flow(
         "Received Minecraft access token '{}'",
         minecraft_token_response.access_token
     );
-    // NOTE: Since Xbox Game Pass users don't technically own the game, the entitlement endpoint will show as such.
-    // It should be used to check the official public key from liblauncher.so but whats the point in checking if
-    // a user owns the game before attempting the next step, if it won't work for Xbox Game Pass users anyway?
-    // let _ = check_license(&minecraft_token_response.access_token).await?;
+    debug!("Requesting Minecraft entitlements.");
+    // Only a definite answer matters, the profile request below decides whether the login works.
+    let owns_minecraft = match check_license(&minecraft_token_response.access_token).await {
+        Ok(owns_minecraft) => Some(owns_minecraft),
+        Err(error) => {
+            warn!("Could not check the Minecraft entitlements: {}", error);
+            None
+        }
+    };
+    debug_if!(
+        "AUTHENTICATION",
+        "Account has Minecraft entitlement: {:?}",
+        owns_minecraft
+    );
 
     debug!("Requesting Minecraft profile.");
+    // NOTE: Xbox Game Pass accounts don't always show the entitlement but still have a profile, so the
+    // entitlement is only used to explain why a profile doesn't exist.
     let mincraft_profile_response =
-        get_minecraft_profile(&minecraft_token_response.access_token).await?;
+        match get_minecraft_profile(&minecraft_token_response.access_token).await {
+            Ok(profile) => profile,
+            Err(error) => return Err(profile_error(error, owns_minecraft)),
+        };
     debug_if!(
         "AUTHENTICATION",
         "Received Minecraft profile for '{}'",
@@ -213,6 +373,7 @@ async fn continue_authentication_flow(
         mincraft_profile_response,
         microsoft_token,
         minecraft_token_response,
+        xuid,
     );
     Ok(account)
 }
@@ -224,16 +385,28 @@ struct DeviceCodeResponse {
     user_code: String,
     device_code: String,
     // verification_uri: String,
-    // expires_in: u32,
-    // interval: u32,
+    #[serde(default = "default_device_code_expiry")]
+    expires_in: u64,
+    #[serde(default = "default_poll_interval")]
+    interval: u64,
     message: String,
 }
 
+fn default_device_code_expiry() -> u64 {
+    DEFAULT_DEVICE_CODE_EXPIRY
+}
+
+fn default_poll_interval() -> u64 {
+    DEFAULT_POLL_INTERVAL
+}
+
 impl Into<DeviceCode> for DeviceCodeResponse {
     fn into(self) -> DeviceCode {
         DeviceCode {
             message: self.message,
             device_code: self.device_code,
+            interval: self.interval,
+            expires_in: self.expires_in,
         }
     }
 }
@@ -242,6 +415,10 @@ impl Into<DeviceCode> for DeviceCodeResponse {
 pub struct DeviceCode {
     pub message: String,
     pub device_code: String,
+    /// Seconds to wait between polls.
+    pub interval: u64,
+    /// Seconds until the device code expires.
+    pub expires_in: u64,
 }
 
 async fn get_microsoft_devicecode() -> AuthenticationResult<DeviceCodeResponse> {
@@ -295,9 +472,17 @@ pub struct XboxTokenResponse {
 
 impl XboxTokenResponse {
     pub fn get_user_hash(&self) -> Option<String> {
+        self.get_xui_claim("uhs")
+    }
+
+    pub fn get_xuid(&self) -> Option<String> {
+        self.get_xui_claim("xid")
+    }
+
+    fn get_xui_claim(&self, claim: &str) -> Option<String> {
         let xui = self.display_claims.get("xui")?;
-        let uhs = xui.first()?.get("uhs")?;
-        Some(uhs.into())
+        let value = xui.first()?.get(claim)?;
+        Some(value.into())
     }
 }
 
@@ -326,7 +511,10 @@ async fn get_xbl_token(access_token: &str) -> AuthenticationResult<XboxTokenResp
 }
 
 /// Sends request to the Xbox Secure Token Service `/authorize` endpoint using an XboxLive access token
-async fn get_xsts_token(xbl_token: &str) -> AuthenticationResult<XboxTokenResponse> {
+async fn get_xsts_token(
+    xbl_token: &str,
+    relying_party: &str,
+) -> AuthenticationResult<XboxTokenResponse> {
     let client = reqwest::Client::new();
     let response = client
         .post(XTXS_AUTHENTICATE_URL)
@@ -338,7 +526,7 @@ async fn get_xsts_token(xbl_token: &str) -> AuthenticationResult<XboxTokenRespon
                         xbl_token
                     ]
                 },
-                "RelyingParty": "rp://api.minecraftservices.com/",
+                "RelyingParty": relying_party,
                 "TokenType": "JWT"
             })
             .to_string(),
@@ -382,37 +570,88 @@ async fn get_minecraft_token(
     get_response_if_ok::<MinecraftTokenResponse, MinecraftTokenErrorResponse>(response).await
 }
 
-// /// Unused for now, currently cannot show if a Xbox Game Pass user owns the game so whats the point in checking...
-// async fn check_license(access_token: &str) -> AuthenticationResult<()> {
-//     let client = reqwest::Client::new();
-//     let response = client
-//         .get(MINECRAFT_LICENSE_URL)
-//         .header("Content-Type", "application/json")
-//         .header("Accept", "application/json")
-//         .header("Authorization", format!("Bearer {}", access_token))
-//         .send()
-//         .await?;
+#[derive(Debug, Deserialize)]
+/// Response struct for the Minecraft entitlements request.  
+/// Commented out fields are currenty unused but exist in the response
+struct MinecraftEntitlementsResponse {
+    items: Vec<MinecraftEntitlement>,
+    // signature: String,
+    // #[serde(rename = "keyId")]
+    // key_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MinecraftEntitlement {
+    name: String,
+    // signature: String,
+}
 
-//     Ok(())
-// }
+/// Returns true if the account has a Minecraft: Java Edition entitlement.
+async fn check_license(access_token: &str) -> AuthenticationResult<bool> {
+    let client = reqwest::Client::new();
+    let response = client
+        .get(MINECRAFT_LICENSE_URL)
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await?;
+
+    let entitlements =
+        get_response_if_ok::<MinecraftEntitlementsResponse, MincraftProfileErrorResponse>(response)
+            .await?;
+    Ok(entitlements
+        .items
+        .iter()
+        .any(|item| item.name == "product_minecraft" || item.name == "game_minecraft"))
+}
+
+/// Whether the profile request failed because the account has no Minecraft profile.
+fn is_profile_not_found(error: &AuthenticationError) -> bool {
+    match error {
+        AuthenticationError::MinecraftProfileError { error, .. } => error == "NOT_FOUND",
+        AuthenticationError::HttpResponseError(status) => *status == StatusCode::NOT_FOUND,
+        _ => false,
+    }
+}
+
+/// The error to return when the profile request failed. A missing profile is only blamed on the
+/// account not owning the game when the entitlements said so, not when they couldn't be checked.
+fn profile_error(error: AuthenticationError, owns_minecraft: Option<bool>) -> AuthenticationError {
+    if is_profile_not_found(&error) && owns_minecraft == Some(false) {
+        AuthenticationError::MinecraftNotOwned
+    } else {
+        error
+    }
+}
 
 // TODO: Save the entire skin struct in the accounts file instead of just the URL.
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct MinecraftProfileSkin {
-    id: String,
-    state: String,
-    url: String,
-    variant: String,
-    alias: Option<String>,
+    pub id: String,
+    pub state: String,
+    pub url: String,
+    pub variant: String,
+    pub alias: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+/// A cape the account owns, `state` is "ACTIVE" for the one it wears.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct MinecraftProfileCape {
+    pub id: String,
+    pub state: String,
+    pub url: String,
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 /// Response struct for the Minecraft profile request.  
-struct MinecraftProfileResponse {
-    id: String,
-    name: String,
-    skins: Vec<MinecraftProfileSkin>,
+pub struct MinecraftProfileResponse {
+    pub id: String,
+    pub name: String,
+    pub skins: Vec<MinecraftProfileSkin>,
+    #[serde(default)]
+    pub capes: Vec<MinecraftProfileCape>,
 }
 
 impl MinecraftProfileResponse {
@@ -430,7 +669,7 @@ impl MinecraftProfileResponse {
 }
 
 /// Obtains the Minecraft profile information like uuid, username, skins, and capes
-async fn get_minecraft_profile(
+pub async fn get_minecraft_profile(
     access_token: &str,
 ) -> AuthenticationResult<MinecraftProfileResponse> {
     let client = reqwest::Client::new();
@@ -445,6 +684,27 @@ async fn get_minecraft_profile(
     get_response_if_ok::<MinecraftProfileResponse, MincraftProfileErrorResponse>(response).await
 }
 
+/// Wears the owned cape `cape_id`, or hides the cape if it is `None`. Returns the updated profile.
+pub async fn set_active_cape(
+    access_token: &str,
+    cape_id: Option<&str>,
+) -> AuthenticationResult<MinecraftProfileResponse> {
+    let client = reqwest::Client::new();
+    let request = match cape_id {
+        Some(cape_id) => client
+            .put(MINECRAFT_ACTIVE_CAPE_URL)
+            .json(&json!({ "capeId": cape_id })),
+        None => client.delete(MINECRAFT_ACTIVE_CAPE_URL),
+    };
+    let response = request
+        .header("Accept", "application/json")
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await?;
+
+    get_response_if_ok::<MinecraftProfileResponse, MincraftProfileErrorResponse>(response).await
+}
+
 /// Deserialize the response into `T` if the status is 200 OK  
 /// Otherwise attempt to deserialize into the error response struct `E`  
 ///
@@ -467,3 +727,85 @@ where
         }
     }
 }
+
+#[test]
+fn test_poll_action() {
+    let interval = Duration::from_secs(5);
+    assert_eq!(
+        poll_action("authorization_pending", interval),
+        PollAction::Wait(interval)
+    );
+    assert_eq!(
+        poll_action("slow_down", interval),
+        PollAction::Wait(Duration::from_secs(10))
+    );
+    assert_eq!(
+        poll_action("authorization_declined", interval),
+        PollAction::Stop
+    );
+    assert_eq!(poll_action("expired_token", interval), PollAction::Stop);
+    assert_eq!(poll_interval(0), Duration::from_secs(1));
+    assert_eq!(poll_interval(5), interval);
+}
+
+#[test]
+fn test_profile_capes() {
+    let profile: MinecraftProfileResponse = serde_json::from_str(
+        r#"{
+            "id": "uuid",
+            "name": "Player",
+            "skins": [{"id": "skin", "state": "ACTIVE", "url": "https://textures.minecraft.net/texture/a", "variant": "CLASSIC"}],
+            "capes": [
+                {"id": "migrator", "state": "ACTIVE", "url": "https://textures.minecraft.net/texture/b", "alias": "Migrator"},
+                {"id": "vanilla", "state": "INACTIVE", "url": "https://textures.minecraft.net/texture/c", "alias": "Vanilla"}
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(profile.capes.len(), 2);
+    assert_eq!(profile.capes[0].alias.as_deref(), Some("Migrator"));
+    assert_eq!(profile.capes[1].state, "INACTIVE");
+
+    let profile: MinecraftProfileResponse =
+        serde_json::from_str(r#"{"id": "uuid", "name": "Player", "skins": []}"#).unwrap();
+    assert!(profile.capes.is_empty());
+}
+
+#[test]
+fn test_create_offline_account() {
+    let account = create_offline_account("Notch").unwrap();
+    assert_eq!(account.uuid, "b50ad385829d3141a2167e7d7539ba7f");
+    assert!(account.offline);
+    assert_eq!(account.user_type(), "legacy");
+    assert!(account.minecraft_access_token.is_empty());
+
+    assert!(create_offline_account("").is_err());
+    assert!(create_offline_account("has space").is_err());
+    assert!(create_offline_account("a_name_that_is_too_long").is_err());
+}
+
+#[test]
+fn test_profile_error() {
+    let not_found = || AuthenticationError::HttpResponseError(StatusCode::NOT_FOUND);
+    assert!(matches!(
+        profile_error(not_found(), Some(false)),
+        AuthenticationError::MinecraftNotOwned
+    ));
+    // Game Pass accounts may have a profile without the entitlement, and a failed check says
+    // nothing about the license.
+    assert!(matches!(
+        profile_error(not_found(), Some(true)),
+        AuthenticationError::HttpResponseError(StatusCode::NOT_FOUND)
+    ));
+    assert!(matches!(
+        profile_error(not_found(), None),
+        AuthenticationError::HttpResponseError(StatusCode::NOT_FOUND)
+    ));
+    assert!(matches!(
+        profile_error(
+            AuthenticationError::HttpResponseError(StatusCode::TOO_MANY_REQUESTS),
+            Some(false)
+        ),
+        AuthenticationError::HttpResponseError(StatusCode::TOO_MANY_REQUESTS)
+    ));
+}
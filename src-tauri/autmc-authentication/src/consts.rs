@@ -11,16 +11,29 @@ pub(crate) const MICROSOFT_TOKEN_URL: &str =
 pub(crate) const XBOX_LIVE_AUTHENTICATE_URL: &str =
     "https://user.auth.xboxlive.com/user/authenticate";
 pub(crate) const XTXS_AUTHENTICATE_URL: &str = "https://xsts.auth.xboxlive.com/xsts/authorize";
+/// XSTS relying party whose token logs into Minecraft.
+pub(crate) const MINECRAFT_RELYING_PARTY: &str = "rp://api.minecraftservices.com/";
+/// XSTS relying party whose display claims include the account's xuid.
+pub(crate) const XBOX_LIVE_RELYING_PARTY: &str = "http://xboxlive.com";
 pub(crate) const MINECRAFT_AUTHENTICATE_URL: &str =
     "https://api.minecraftservices.com/authentication/login_with_xbox";
-// pub(crate) const MINECRAFT_LICENSE_URL: &str = "https://api.minecraftservices.com/entitlements/mcstore";
+pub(crate) const MINECRAFT_LICENSE_URL: &str =
+    "https://api.minecraftservices.com/entitlements/mcstore";
 pub(crate) const MINECRAFT_PROFILE_URL: &str =
     "https://api.minecraftservices.com/minecraft/profile";
+pub(crate) const MINECRAFT_ACTIVE_CAPE_URL: &str =
+    "https://api.minecraftservices.com/minecraft/profile/capes/active";
+
+/// Hint shown for XErr codes that aren't in `XERR_HINTS`.
+pub(crate) const UNKNOWN_XERR_HINT: &str = "Unknown Xbox Live error, try signing in at https://www.xbox.com to resolve any issues with the account.";
 
 pub(crate) static XERR_HINTS: phf::Map<&'static str, &'static str> = phf_map! {
-    "2148916233" => "2148916233: The account doesn't have an Xbox account. Once they sign up for one (or login through minecraft.net to create one) then they can proceed with the login. This shouldn't happen with accounts that have purchased Minecraft with a Microsoft account, as they would've already gone through that Xbox signup process.",
-    "2148916235" => "2148916235: The account is from a country where Xbox Live is not available/banned",
-    "2148916236" => "2148916236: The account needs adult verification on Xbox page. (South Korea)",
-    "2148916237" => "2148916237: The account needs adult verification on Xbox page. (South Korea)",
-    "2148916238" => "2148916238: The account is a child (under 18) and cannot proceed unless the account is added to a Family by an adult. This only seems to occur when using a custom Microsoft Azure application. When using the Minecraft launchers client id, this doesn't trigger."
+    "2148916227" => "The account was banned from Xbox for violating one of the Community Standards.",
+    "2148916229" => "The account is currently restricted and its guardian has not given it permission to play online. The guardian can change this on the Xbox privacy settings page.",
+    "2148916233" => "The account doesn't have an Xbox account. Once they sign up for one (or login through minecraft.net to create one) then they can proceed with the login. This shouldn't happen with accounts that have purchased Minecraft with a Microsoft account, as they would've already gone through that Xbox signup process.",
+    "2148916234" => "The account has not accepted the Xbox Terms of Service. Sign in at https://www.xbox.com to accept them.",
+    "2148916235" => "The account is from a country where Xbox Live is not available/banned",
+    "2148916236" => "The account needs adult verification on Xbox page. (South Korea)",
+    "2148916237" => "The account needs adult verification on Xbox page. (South Korea)",
+    "2148916238" => "The account is a child (under 18) and cannot proceed unless the account is added to a Family by an adult. This only seems to occur when using a custom Microsoft Azure application. When using the Minecraft launchers client id, this doesn't trigger."
 };
@@ -1,4 +1,4 @@
-use crate::consts::XERR_HINTS;
+use crate::consts::{UNKNOWN_XERR_HINT, XERR_HINTS};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize, Serializer};
 
@@ -14,7 +14,7 @@ pub enum AuthenticationError {
     },
     MaxAttemptsExceeded(String),
     XboxError {
-        xerr: String,
+        xerr: u32,
         message: String,
         hint: String,
     },
@@ -24,6 +24,8 @@ pub enum AuthenticationError {
         error: String,
         message: String,
     },
+    MinecraftNotOwned,
+    InvalidUsername(String),
 }
 
 impl std::fmt::Display for AuthenticationError {
@@ -52,6 +54,13 @@ impl std::fmt::Display for AuthenticationError {
             AuthenticationError::MinecraftProfileError { error, message } => {
                 f.write_fmt(format_args!("{}: {}", error, message))
             }
+            AuthenticationError::MinecraftNotOwned => {
+                f.write_str("This Microsoft account doesn't own Minecraft: Java Edition.")
+            }
+            AuthenticationError::InvalidUsername(username) => f.write_fmt(format_args!(
+                "`{}` isn't a valid username, use 1 to 16 letters, numbers or underscores.",
+                username
+            )),
         }
     }
 }
@@ -73,10 +82,11 @@ impl From<MicrosoftErrorResponse> for AuthenticationError {
 
 impl From<XboxErrorResponse> for AuthenticationError {
     fn from(value: XboxErrorResponse) -> Self {
-        let xerr = value.xerr.to_string();
-        let hint = XERR_HINTS.get(&xerr).unwrap_or(&"");
+        let hint = XERR_HINTS
+            .get(&value.xerr.to_string())
+            .unwrap_or(&UNKNOWN_XERR_HINT);
         AuthenticationError::XboxError {
-            xerr: xerr,
+            xerr: value.xerr,
             message: value.message,
             hint: hint.to_string(),
         }
@@ -134,7 +144,7 @@ pub(crate) struct XboxErrorResponse {
     // identity: String,
     #[serde(rename = "XErr")]
     xerr: u32,
-    #[serde(rename = "Message")]
+    #[serde(rename = "Message", default)]
     message: String,
     // Redirect is used for consoles.
     // #[serde(rename = "Redirect")]
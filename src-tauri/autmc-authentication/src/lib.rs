@@ -3,7 +3,9 @@ mod consts;
 mod error;
 
 pub use authenticate::{
-    poll_device_code_status, refresh_access_tokens, start_device_code_authentication, DeviceCode,
-    MicrosoftToken, MinecraftAccount, OAuthRefreshMode,
+    client_id, create_offline_account, get_minecraft_profile, poll_device_code_status,
+    refresh_access_tokens, set_active_cape, start_device_code_authentication, DeviceCode,
+    MicrosoftToken, MinecraftAccount, MinecraftProfileCape, MinecraftProfileResponse,
+    MinecraftProfileSkin, OAuthRefreshMode, DEFAULT_DEVICE_CODE_EXPIRY, DEFAULT_POLL_INTERVAL,
 };
 pub use error::{AuthenticationError, AuthenticationResult};
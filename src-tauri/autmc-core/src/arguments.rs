@@ -0,0 +1,441 @@
+//! Turns the arguments of version jsons into the command line the game is launched with.
+use std::{env, path::PathBuf};
+
+use log::{debug, error, warn};
+
+use crate::{
+    consts::{LAUNCHER_NAME, LAUNCHER_VERSION},
+    manifest::{
+        get_classpath_separator, path_to_utf8_str,
+        vanilla::{
+            Argument, LaunchArguments, LaunchArguments113, Rule, RuleType, VanillaManifestVersion,
+        },
+        ModloaderType,
+    },
+};
+
+/// Checks if a single rule matches every case.
+/// Returns true when an allow rule matches or a disallow rule does not match.
+fn rule_matches(rule: &Rule) -> bool {
+    let rule_type = rule.rule_type();
+    if rule_type.is_none() {
+        return match rule.action() {
+            "allow" => true,
+            "disallow" => false,
+            // Whatever the rule is about is left out rather than guessed.
+            action => {
+                warn!("Unknown rule action: {}", action);
+                false
+            }
+        };
+    }
+    match rule_type.as_ref().unwrap() {
+        RuleType::Features(_feature_rules) => {
+            error!(
+                "Implement feature rules for arguments: {:#?}",
+                _feature_rules
+            );
+            // FIXME: Currently just skipping these
+            false
+        }
+        RuleType::OperatingSystem(os_rules) => {
+            // Check if all the rules match the current system.
+            let mut rule_matches = false;
+            for (key, value) in os_rules {
+                match key.as_str() {
+                    "name" => {
+                        let os_type = env::consts::OS;
+                        if value == os_type || (os_type == "macos" && value == "osx") {
+                            rule_matches = true;
+                        }
+                    }
+                    "arch" => {
+                        let os_arch = env::consts::ARCH;
+                        if value == os_arch || (value == "x86" && os_arch == "x86_64") {
+                            rule_matches = true;
+                        }
+                    }
+                    "version" => { /*TODO: Check version of os to make sure it matches*/ }
+                    _ => warn!("Ignoring unknown rule map key: {}", key),
+                }
+            }
+            // Check if we allow or disallow this downloadable
+            match rule.action() {
+                "allow" => rule_matches,
+                "disallow" => !rule_matches,
+                action => {
+                    warn!("Unknown rule action: {}", action);
+                    false
+                }
+            }
+        }
+    }
+}
+
+/// Whether every rule matches this system, `false` if there are none.
+pub fn rules_match(rules: &[Rule]) -> bool {
+    let mut result = false;
+    for rule in rules {
+        if rule_matches(rule) {
+            result = true;
+        } else {
+            return false;
+        }
+    }
+    result
+}
+
+/// Where the files the launch arguments point to are.
+pub struct LaunchArgumentPaths {
+    // logging configurations are optional since they dont exist in versions 1.6.4 and older
+    pub logging: Option<(String, PathBuf)>,
+    pub library_paths: Vec<PathBuf>,
+    pub instance_path: PathBuf,
+    pub jar_path: PathBuf,
+    pub asset_dir_path: PathBuf,
+    // Where the game reads assets from for `${game_assets}`, differs for legacy asset indexes.
+    pub game_assets_path: PathBuf,
+    pub library_directory: PathBuf,
+}
+
+/// The arguments a version json and its modloader's json ask the game to be launched with.
+pub struct VersionArguments<'a> {
+    pub main_class: String,
+    pub arguments: &'a LaunchArguments,
+    pub modloader_arguments: Option<LaunchArguments>,
+    pub modloader_type: &'a ModloaderType,
+}
+
+// TODO: Add -Xmx and -Xms arguments for memory
+fn construct_jvm_arguments113(
+    arguments: &LaunchArguments113,
+    argument_paths: &LaunchArgumentPaths,
+    mc_version: &str,
+) -> Vec<String> {
+    let mut formatted_arguments = Vec::new();
+
+    if arguments.jvm().is_none() {
+        return formatted_arguments;
+    }
+
+    for jvm_arg in arguments.jvm().unwrap().iter() {
+        match jvm_arg {
+            // For normal arguments, check if it has something that should be replaced and replace it
+            Argument::Arg(value) => {
+                let sub_arg = substitute_jvm_arguments(value, mc_version, argument_paths);
+                formatted_arguments.push(match sub_arg {
+                    Some(argument) => argument,
+                    None => value.into(),
+                });
+            }
+            // For conditional args, check their rules before adding to formatted_arguments vec
+            Argument::ConditionalArg { rules, values } => {
+                if !rules_match(rules) {
+                    continue;
+                }
+                for value in values {
+                    let sub_arg = substitute_jvm_arguments(value, mc_version, argument_paths);
+                    formatted_arguments.push(match sub_arg {
+                        Some(argument) => argument,
+                        None => value.into(),
+                    });
+                }
+            }
+        }
+    }
+    formatted_arguments
+}
+
+// TODO: Add -Xmx and -Xms arguments for memory
+fn construct_jvm_arguments112(
+    mc_version: &str,
+    argument_paths: &LaunchArgumentPaths,
+) -> Vec<String> {
+    vec![
+        substitute_jvm_arguments(
+            "-Djava.library.path=${natives_directory}",
+            mc_version,
+            argument_paths,
+        )
+        .unwrap(),
+        "-cp".to_string(),
+        substitute_jvm_arguments("${classpath}", mc_version, argument_paths).unwrap(),
+    ]
+}
+
+pub fn construct_arguments(
+    version_arguments: VersionArguments,
+    additional_arguments: Vec<String>,
+    mc_version: &VanillaManifestVersion,
+    asset_index: &str,
+    argument_paths: LaunchArgumentPaths,
+) -> Vec<String> {
+    let VersionArguments {
+        main_class,
+        arguments,
+        modloader_arguments,
+        modloader_type,
+    } = version_arguments;
+    // IDEA: Vec could be 'with_capacity' if we calculate capacity first.
+    let mut formatted_arguments: Vec<String> = Vec::new();
+    let mut game_args: Vec<Argument> = Vec::new();
+
+    formatted_arguments.extend(additional_arguments);
+
+    if is_log4shell_vulnerable(mc_version.release_time()) {
+        // Covers log4j 2.10+ (1.17 - 1.18), older versions rely on the patched logging
+        // configuration Mojang now serves in the version json.
+        formatted_arguments.push("-Dlog4j2.formatMsgNoLookups=true".into());
+        if argument_paths.logging.is_none() {
+            warn!(
+                "Version {} is vulnerable to Log4Shell but has no logging configuration",
+                mc_version.id()
+            );
+        }
+    }
+
+    // Create game arguments from the launch arguments.
+    game_args.append(&mut match arguments {
+        // Substitute values in for placeholders in the jvm arguments.
+
+        // Versions <= 1.12  use a string of game arguments and do not provide any jvm arguments.
+        LaunchArguments::LaunchArguments112(game_args) => {
+            formatted_arguments.append(&mut construct_jvm_arguments112(
+                mc_version.id(),
+                &argument_paths,
+            ));
+
+            // If the modloader is forge and 1.12.2 or older, then ignore vanilla arguments since they
+            // are already provided by the forge arguments.
+            match modloader_arguments {
+                // If we have some arguments and the modloader type is forge
+                Some(_) if *modloader_type == ModloaderType::Forge => Vec::new(),
+                _ => {
+                    // Split game arg string on whitespace to get individual args
+                    game_args
+                        .split_ascii_whitespace()
+                        .map(|split| Argument::Arg(split.into()))
+                        .collect::<Vec<Argument>>()
+                }
+            }
+        }
+        // Versions >= 1.13 provide the game and jvm arguments.
+        LaunchArguments::LaunchArguments113(arguments) => {
+            formatted_arguments.append(&mut construct_jvm_arguments113(
+                arguments,
+                &argument_paths,
+                mc_version.id(),
+            ));
+            arguments.game().to_vec()
+        }
+    });
+
+    // Append modloader arguments if a modloader is selected.
+    if let Some(args) = modloader_arguments {
+        game_args.append(&mut match args {
+            LaunchArguments::LaunchArguments112(game_args) => {
+                formatted_arguments.append(&mut construct_jvm_arguments112(
+                    mc_version.id(),
+                    &argument_paths,
+                ));
+                // Split game arg string on whitespace to get individual args
+                game_args
+                    .split_ascii_whitespace()
+                    .map(|split| Argument::Arg(split.into()))
+                    .collect::<Vec<Argument>>()
+            }
+            // Versions >= 1.13 provide the game and jvm arguments.
+            LaunchArguments::LaunchArguments113(arguments) => {
+                formatted_arguments.append(&mut construct_jvm_arguments113(
+                    &arguments,
+                    &argument_paths,
+                    mc_version.id(),
+                ));
+                arguments.game().to_vec()
+            }
+        });
+    }
+
+    if let Some((arg, path)) = &argument_paths.logging {
+        // Construct the logging configuration argument
+        let logging_arg = substitute_placeholders(arg, |name| {
+            (name == "path").then(|| path_to_utf8_str(path).to_owned())
+        });
+        if let Some(logging_arg) = logging_arg {
+            formatted_arguments.push(logging_arg);
+        }
+    }
+
+    // Add main class
+    formatted_arguments.push(main_class);
+
+    // Substitute values in for placeholders in the game arguments, skipping account-specific arguments.
+    for game_arg in game_args.iter() {
+        match game_arg {
+            // For normal arguments, check if it has something that should be replaced and replace it
+            Argument::Arg(value) => {
+                let sub_arg =
+                    substitute_game_arguments(value, mc_version, asset_index, &argument_paths);
+                formatted_arguments.push(match sub_arg {
+                    Some(argument) => argument,
+                    None => value.into(),
+                });
+            }
+            // For conditional args, check their rules before adding to formatted_arguments vec
+            Argument::ConditionalArg { rules, values } => {
+                if !rules_match(rules) {
+                    continue;
+                }
+                for value in values {
+                    let sub_arg =
+                        substitute_game_arguments(value, mc_version, asset_index, &argument_paths);
+                    formatted_arguments.push(match sub_arg {
+                        Some(argument) => argument,
+                        None => value.into(),
+                    });
+                }
+            }
+        }
+    }
+    formatted_arguments
+}
+
+/// Whether a version, by its release time, ships a log4j vulnerable to CVE-2021-44228.
+/// That is everything after 1.6.4 (the first to use log4j is 1.7) and before 1.18.1.
+fn is_log4shell_vulnerable(release_time: &str) -> bool {
+    // Release times are rfc3339 in UTC, so the dates compare lexicographically.
+    match release_time.get(..10) {
+        Some(date) => date > "2013-09-19" && date < "2021-12-10",
+        None => false,
+    }
+}
+
+/// Replaces every `${name}` placeholder in `arg` with `value(name)`, leaving the ones it has no
+/// value for as they are. Substituted values aren't searched for placeholders again, so a path or
+/// player name containing `${` is passed on unchanged. Returns `None` if nothing was replaced.
+pub fn substitute_placeholders(
+    arg: &str,
+    value: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let mut substituted = String::with_capacity(arg.len());
+    let mut replaced = false;
+    let mut rest = arg;
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        substituted.push_str(&rest[..start]);
+        let name = &rest[start + 2..end];
+        match value(name) {
+            Some(value) => {
+                debug!("Substituting ${{{}}}", name);
+                substituted.push_str(&value);
+                replaced = true;
+            }
+            None => substituted.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    substituted.push_str(rest);
+    replaced.then_some(substituted)
+}
+
+// Returns a string with the substituted values in the jvm argument or None if none apply.
+// mc_version is only needed here for one forge specific launch option
+fn substitute_jvm_arguments(
+    arg: &str,
+    mc_version: &str,
+    argument_paths: &LaunchArgumentPaths,
+) -> Option<String> {
+    substitute_placeholders(arg, |name| match name {
+        "natives_directory" => {
+            Some(path_to_utf8_str(&argument_paths.instance_path.join("natives")).to_owned())
+        }
+        "launcher_name" => Some(LAUNCHER_NAME.into()),
+        "launcher_version" => Some(LAUNCHER_VERSION.into()),
+        "classpath" => {
+            let mut classpath: Vec<&str> = argument_paths
+                .library_paths
+                .iter()
+                .map(|path| path_to_utf8_str(path))
+                .collect();
+            classpath.push(path_to_utf8_str(&argument_paths.jar_path));
+            Some(classpath.join(get_classpath_separator()))
+        }
+        // Forge specific jvm arguments
+        "library_directory" => Some(path_to_utf8_str(&argument_paths.library_directory).to_owned()),
+        "classpath_separator" => Some(get_classpath_separator().into()),
+        "version_name" => Some(mc_version.into()),
+        _ => None,
+    })
+}
+
+fn substitute_game_arguments(
+    arg: &str,
+    mc_version: &VanillaManifestVersion,
+    asset_index: &str,
+    argument_paths: &LaunchArgumentPaths,
+) -> Option<String> {
+    substitute_placeholders(arg, |name| match name {
+        "version_name" => Some(mc_version.id().to_owned()),
+        "game_directory" => Some(path_to_utf8_str(&argument_paths.instance_path).to_owned()),
+        "assets_root" => Some(path_to_utf8_str(&argument_paths.asset_dir_path).to_owned()),
+        "game_assets" => Some(path_to_utf8_str(&argument_paths.game_assets_path).to_owned()),
+        "assets_index_name" => Some(asset_index.into()),
+        "version_type" => Some(mc_version.version_type().to_owned()),
+        "user_properties" => Some("{}".into()),
+        _ => None,
+    })
+}
+
+#[test]
+fn test_log4shell_vulnerable_versions() {
+    // 1.6.4
+    assert!(!is_log4shell_vulnerable("2013-09-19T15:52:37+00:00"));
+    // 1.7.2
+    assert!(is_log4shell_vulnerable("2013-10-25T13:00:00+00:00"));
+    // 1.12.2
+    assert!(is_log4shell_vulnerable("2017-09-18T08:39:46+00:00"));
+    // 1.18
+    assert!(is_log4shell_vulnerable("2021-11-30T09:16:29+00:00"));
+    // 1.18.1
+    assert!(!is_log4shell_vulnerable("2021-12-10T08:23:00+00:00"));
+    assert!(!is_log4shell_vulnerable(""));
+}
+
+#[test]
+fn test_substitute_placeholders() {
+    let value = |name: &str| match name {
+        "library_directory" => Some("/libraries".to_string()),
+        "classpath_separator" => Some(":".to_string()),
+        "auth_player_name" => Some("${user_type}".to_string()),
+        _ => None,
+    };
+    // <Argument, substituted argument>
+    let cases = [
+        ("--nogui", None),
+        ("${library_directory}", Some("/libraries")),
+        (
+            "-p ${library_directory}/a.jar${classpath_separator}${library_directory}/b.jar",
+            Some("-p /libraries/a.jar:/libraries/b.jar"),
+        ),
+        (
+            "${unknown}-${library_directory}",
+            Some("${unknown}-/libraries"),
+        ),
+        ("${unknown} and ${unclosed", None),
+        ("{}${classpath_separator}", Some("{}:")),
+        // Substituted values aren't substituted again.
+        ("${auth_player_name}", Some("${user_type}")),
+        ("é${classpath_separator}ü", Some("é:ü")),
+    ];
+    for (arg, expected) in cases {
+        assert_eq!(
+            substitute_placeholders(arg, value).as_deref(),
+            expected,
+            "{}",
+            arg
+        );
+    }
+}
@@ -0,0 +1,12 @@
+pub const FORGE_MAVEN_BASE_URL: &str = "https://maven.minecraftforge.net/net/minecraftforge/forge";
+pub const FORGE_FILES_BASE_URL: &str = "https://files.minecraftforge.net/net/minecraftforge/forge";
+pub const FABRIC_BASE_URL: &str = "https://meta.fabricmc.net/v2";
+/// The url to download assets from. Uses the hash as the endpoint: `...net/<first 2 hex letters of hash>/<whole hash>`
+pub const VANILLA_ASSET_BASE_URL: &str = "https://resources.download.minecraft.net";
+
+pub const MINECRAFT_LIBRARIES_URL: &str = "https://libraries.minecraft.net";
+/// LWJGL publishes the natives Mojang doesn't ship, like linux arm64, here.
+pub const MAVEN_CENTRAL_URL: &str = "https://repo1.maven.org/maven2";
+
+pub const LAUNCHER_NAME: &str = "Autmc";
+pub const LAUNCHER_VERSION: &str = "1.0.0";
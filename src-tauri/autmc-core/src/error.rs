@@ -0,0 +1,67 @@
+use std::{fmt, io, string::FromUtf8Error};
+
+use autmc_downloader::DownloadError;
+use zip::result::ZipError;
+
+pub type CoreResult<T> = Result<T, CoreError>;
+
+#[derive(Debug)]
+pub enum CoreError {
+    Download(DownloadError),
+    Io(io::Error),
+    Utf8(FromUtf8Error),
+    Json(serde_json::Error),
+    Zip(ZipError),
+    MismatchedFileHash(String),
+}
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoreError::Download(DownloadError::Request(error)) => write!(f, "{}", error),
+            CoreError::Download(DownloadError::FileWrite(error)) => write!(f, "{}", error),
+            CoreError::Download(DownloadError::InvalidFileHash(error)) => write!(f, "{}", error),
+            CoreError::Io(error) => write!(f, "{}", error),
+            CoreError::Utf8(error) => write!(f, "{}", error),
+            CoreError::Json(error) => write!(f, "{}", error),
+            CoreError::Zip(error) => write!(f, "{}", error),
+            CoreError::MismatchedFileHash(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<DownloadError> for CoreError {
+    fn from(error: DownloadError) -> Self {
+        CoreError::Download(error)
+    }
+}
+
+impl From<reqwest::Error> for CoreError {
+    fn from(error: reqwest::Error) -> Self {
+        CoreError::Download(DownloadError::Request(error))
+    }
+}
+
+impl From<io::Error> for CoreError {
+    fn from(error: io::Error) -> Self {
+        CoreError::Io(error)
+    }
+}
+
+impl From<FromUtf8Error> for CoreError {
+    fn from(error: FromUtf8Error) -> Self {
+        CoreError::Utf8(error)
+    }
+}
+
+impl From<serde_json::Error> for CoreError {
+    fn from(error: serde_json::Error) -> Self {
+        CoreError::Json(error)
+    }
+}
+
+impl From<ZipError> for CoreError {
+    fn from(error: ZipError) -> Self {
+        CoreError::Zip(error)
+    }
+}
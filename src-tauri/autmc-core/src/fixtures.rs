@@ -0,0 +1,16 @@
+//! Reads the synthetic api responses in the launcher's `fixtures/json`, see its `README.md`.
+use std::{fs, path::PathBuf};
+
+use serde::de::DeserializeOwned;
+
+pub fn read_fixture<T: DeserializeOwned>(name: &str) -> T {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("fixtures")
+        .join("json")
+        .join(name);
+    let bytes = fs::read(&path)
+        .unwrap_or_else(|error| panic!("Missing fixture {}: {}", path.display(), error));
+    serde_json::from_slice(&bytes)
+        .unwrap_or_else(|error| panic!("Fixture {} no longer deserializes: {}", name, error))
+}
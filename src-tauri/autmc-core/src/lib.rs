@@ -0,0 +1,10 @@
+//! The parts of installing and launching minecraft that don't depend on the launcher's window:
+//! reading version jsons and turning them into launch arguments.
+pub mod arguments;
+pub mod consts;
+mod error;
+#[cfg(test)]
+mod fixtures;
+pub mod manifest;
+
+pub use error::{CoreError, CoreResult};
@@ -1,12 +1,42 @@
-use std::{io::Read, path::Path};
+use std::{fmt, io::Read, path::Path};
 
 use log::error;
+use serde::{Deserialize, Serialize};
 use zip::read::ZipFile;
 
 pub mod fabric;
 pub mod forge;
+pub mod library_overrides;
+pub mod patches;
 pub mod vanilla;
 
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub enum ModloaderType {
+    Forge,
+    Fabric,
+    None,
+}
+
+impl From<&str> for ModloaderType {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "forge" => ModloaderType::Forge,
+            "fabric" => ModloaderType::Fabric,
+            _ => ModloaderType::None,
+        }
+    }
+}
+
+impl fmt::Display for ModloaderType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match &self {
+            ModloaderType::Forge => "forge",
+            ModloaderType::Fabric => "fabric",
+            ModloaderType::None => "",
+        })
+    }
+}
+
 pub fn maven_to_vec(
     maven_artifact: &str,
     append_str: Option<&str>,
@@ -25,16 +55,8 @@ pub fn maven_to_vec(
         format!(
             "{}{}{}",
             file_name_ending,
-            if let Some(append) = append_str {
-                append
-            } else {
-                ""
-            },
-            if let Some(ext) = force_extension {
-                ext
-            } else {
-                ".jar"
-            },
+            append_str.unwrap_or_default(),
+            force_extension.unwrap_or(".jar"),
         )
     };
 
@@ -63,13 +85,11 @@ pub fn path_to_utf8_str(path: &Path) -> &str {
     }
 }
 
-pub(crate) fn bytes_from_zip_file(file: ZipFile) -> Vec<u8> {
-    file.bytes()
-        .filter_map(|byte| match byte {
-            Ok(b) => Some(b),
-            Err(_) => None,
-        })
-        .collect()
+pub fn bytes_from_zip_file(mut file: ZipFile) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(file.size() as usize);
+    // Whatever could be read is kept, like the files that fail to validate later.
+    let _ = file.read_to_end(&mut bytes);
+    bytes
 }
 
 #[cfg(target_family = "unix")]
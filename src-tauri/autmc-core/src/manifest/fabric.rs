@@ -0,0 +1,214 @@
+use std::path::{Path, PathBuf};
+
+use autmc_downloader::{download_bytes_from_url, Downloadable};
+use serde::Deserialize;
+
+#[cfg(test)]
+use autmc_downloader::mock_http::start_mock_server;
+
+#[cfg(test)]
+use crate::fixtures::read_fixture;
+use crate::{consts::FABRIC_BASE_URL, CoreResult};
+
+use super::{
+    get_directory_separator, maven_to_vec,
+    patches::{PatchTarget, VersionPatches},
+    vanilla::LaunchArguments,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct FabricLoaderVersion {
+    // separator: String,
+    // build: i32,
+    // maven: String,
+    version: String,
+    stable: bool,
+}
+
+impl FabricLoaderVersion {
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn stable(&self) -> bool {
+        self.stable
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FabricLoaderManifest(Vec<FabricLoaderVersion>);
+
+impl FabricLoaderManifest {
+    /// Every loader version, newest first.
+    pub fn versions(&self) -> &[FabricLoaderVersion] {
+        &self.0
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FabricLibrary {
+    name: String,
+    url: String,
+}
+
+#[derive(Debug)]
+pub struct DownloadableFabricLibrary {
+    name: String,
+    url: String,
+    hash: String,
+}
+
+impl Downloadable for DownloadableFabricLibrary {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn url(&self) -> String {
+        self.url.to_owned()
+    }
+
+    fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    fn path(&self, base_dir: &Path) -> PathBuf {
+        let path = maven_to_vec(&self.name, None, None).join(get_directory_separator());
+        base_dir.join(path)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FabricProfile {
+    // id: String,
+    // #[serde(rename = "inheritsFrom")]
+    // inherits_from: String,
+    // #[serde(rename = "releaseTime")]
+    // release_time: String,
+    // time: String,
+    // #[serde(rename = "type")]
+    // version_type: String,
+    #[serde(rename = "mainClass")]
+    main_class: String,
+    arguments: LaunchArguments,
+    libraries: Vec<FabricLibrary>,
+}
+
+impl FabricProfile {
+    pub fn main_class(&self) -> &str {
+        &self.main_class
+    }
+
+    pub fn arguments(&self) -> &LaunchArguments {
+        &self.arguments
+    }
+
+    pub fn libraries(&self) -> &[FabricLibrary] {
+        &self.libraries
+    }
+}
+
+pub async fn download_fabric_profile(
+    minecraft_version: &str,
+    fabric_version: &str,
+    patches: &VersionPatches,
+) -> CoreResult<FabricProfile> {
+    let url = format!(
+        "{}/versions/loader/{}/{}/profile/json",
+        FABRIC_BASE_URL, minecraft_version, fabric_version
+    );
+    let bytes = download_bytes_from_url(&url).await?;
+    patches.read(PatchTarget::Fabric, fabric_version, &bytes)
+}
+
+pub async fn obtain_fabric_library_hashes(
+    libraries: &[FabricLibrary],
+) -> CoreResult<Vec<DownloadableFabricLibrary>> {
+    let mut result = Vec::with_capacity(libraries.len());
+    for library in libraries {
+        let hash_url = format!(
+            "{}{}",
+            library.url,
+            maven_to_vec(&library.name, None, Some(".jar.sha1")).join(get_directory_separator())
+        );
+        let bytes = download_bytes_from_url(&hash_url).await?;
+        let hash = String::from_utf8(bytes.to_vec())?;
+        result.push(DownloadableFabricLibrary {
+            name: library.name.to_owned(),
+            url: format!(
+                "{}{}",
+                library.url,
+                maven_to_vec(&library.name, None, None).join(get_directory_separator())
+            ),
+            hash,
+        });
+    }
+
+    Ok(result)
+}
+
+#[test]
+fn test_maven_to_fabric() {
+    let lib = FabricLibrary {
+        name: "org.ow2.asm:asm-commons:9.2".into(),
+        url: "https://maven.fabricmc.net/".into(),
+    };
+
+    let result = format!(
+        "{}{}",
+        lib.url,
+        maven_to_vec(&lib.name, None, None).join(get_directory_separator())
+    );
+    assert!(result == "https://maven.fabricmc.net/org/ow2/asm/asm-commons/9.2/asm-commons-9.2.jar");
+}
+
+#[test]
+fn test_fabric_profile() {
+    start_mock_server();
+
+    tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(async move {
+            let profile = download_fabric_profile("1.20.1", "0.14.21", &VersionPatches::default())
+                .await
+                .unwrap();
+            let libraries = obtain_fabric_library_hashes(&profile.libraries)
+                .await
+                .unwrap();
+            assert_eq!(libraries.len(), 4);
+            assert_eq!(
+                libraries[1].url,
+                "https://maven.fabricmc.net/org/ow2/asm/asm/9.5/asm-9.5.jar"
+            );
+            assert_eq!(
+                libraries[1].hash,
+                "dc6ea1875f4d64fbc85e1691c95b96a3d8569c90"
+            );
+        });
+}
+
+#[test]
+fn test_fabric_fixtures() {
+    let manifest: FabricLoaderManifest = read_fixture("fabric/loader_versions.json");
+    let stable: Vec<&str> = manifest
+        .0
+        .iter()
+        .filter(|loader| loader.stable)
+        .map(|loader| loader.version.as_str())
+        .collect();
+    assert_eq!(stable, ["0.14.21"]);
+
+    let profile: FabricProfile = read_fixture("fabric/profile-1.20.1-0.14.21.json");
+    assert_eq!(
+        profile.main_class,
+        "net.fabricmc.loader.impl.launch.knot.KnotClient"
+    );
+    assert!(matches!(
+        profile.arguments,
+        LaunchArguments::LaunchArguments113(ref arguments) if arguments.game().is_empty()
+    ));
+    assert_eq!(profile.libraries.len(), 4);
+    assert_eq!(
+        profile.libraries[3].name,
+        "net.fabricmc:fabric-loader:0.14.21"
+    );
+}
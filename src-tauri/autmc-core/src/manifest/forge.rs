@@ -0,0 +1,870 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader, Cursor, Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    time::Instant,
+};
+
+#[cfg(test)]
+use autmc_downloader::mock_http::start_mock_server;
+use autmc_downloader::{
+    download_bytes_from_url, download_json_object_from_url, validate_hash_md5, DownloadResult,
+    Downloadable,
+};
+use log::{debug, error, info};
+use serde::Deserialize;
+use serde_json::Value;
+#[cfg(test)]
+use tempdir::TempDir;
+
+#[cfg(test)]
+use crate::fixtures::read_fixture;
+
+use crate::{
+    consts::{FORGE_FILES_BASE_URL, FORGE_MAVEN_BASE_URL, MINECRAFT_LIBRARIES_URL},
+    manifest::get_classpath_separator,
+    CoreError, CoreResult,
+};
+
+use super::{
+    bytes_from_zip_file, get_directory_separator, maven_to_vec,
+    patches::{PatchTarget, VersionPatches},
+    path_to_utf8_str,
+    vanilla::{JarType, LaunchArguments, Library},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ForgeManifest(HashMap<String, Vec<String>>);
+
+impl ForgeManifest {
+    /// Forge versions by the minecraft version they are for.
+    pub fn versions(&self) -> &HashMap<String, Vec<String>> {
+        &self.0
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgeHashes {
+    classifiers: ForgeHashClassifiers,
+}
+
+impl ForgeHashes {
+    pub fn installer_hash(&self) -> &ForgeFileHash {
+        &self.classifiers.installer
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgeHashClassifiers {
+    installer: ForgeFileHash,
+}
+
+// Forge hashes are md5 NOT sha1
+#[derive(Debug, Deserialize)]
+pub struct ForgeFileHash {
+    #[serde(rename = "jar", alias = "txt", alias = "zip")]
+    hash: String,
+}
+
+impl ForgeFileHash {
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+}
+
+impl From<&str> for ForgeFileHash {
+    fn from(s: &str) -> Self {
+        Self { hash: s.into() }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgeVersionMetadata {
+    #[serde(rename = "mainClass")]
+    main_class: String,
+    // FIXME: Ignoring for now since this is just a empty json entry in 1.19.3, not sure about other versions
+    // logging: Option<ForgeLogging>,
+    #[serde(alias = "minecraftArguments")]
+    arguments: LaunchArguments,
+}
+
+impl ForgeVersionMetadata {
+    pub fn main_class(&self) -> &str {
+        &self.main_class
+    }
+
+    /// The launch arguments forge adds, or all of them for 1.12.2 and older.
+    pub fn arguments(&self) -> &LaunchArguments {
+        &self.arguments
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgeVersion112 {
+    #[serde(flatten)]
+    metadata: ForgeVersionMetadata,
+    libraries: Vec<Library>,
+}
+
+impl ForgeVersion112 {
+    pub fn metadata(&self) -> &ForgeVersionMetadata {
+        &self.metadata
+    }
+
+    pub fn libraries(&self) -> &[Library] {
+        &self.libraries
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgeVersion111 {
+    #[serde(flatten)]
+    metadata: ForgeVersionMetadata,
+    libraries: Vec<ForgeLibrary>,
+}
+
+impl ForgeVersion111 {
+    pub fn metadata(&self) -> &ForgeVersionMetadata {
+        &self.metadata
+    }
+
+    pub fn libraries(&self) -> &[ForgeLibrary] {
+        &self.libraries
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ForgeLibrary {
+    name: String,
+    url: Option<String>,
+    checksums: Option<Vec<String>>,
+}
+
+impl Downloadable for ForgeLibrary {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn url(&self) -> String {
+        match self.url {
+            Some(_) => {
+                let append_str = if self.name.contains("net.minecraftforge:forge:") {
+                    Some("-universal")
+                } else {
+                    None
+                };
+
+                maven_to_vec(&self.name, append_str, None).join("/")
+            }
+            None => {
+                let endpoint = maven_to_vec(&self.name, None, None).join("/");
+                format!("{}/{}", MINECRAFT_LIBRARIES_URL, endpoint)
+            }
+        }
+    }
+
+    fn hash(&self) -> &str {
+        if let Some(checksums) = &self.checksums {
+            checksums.first().unwrap()
+        } else {
+            // FIXME: Not sure what to do here.
+            ""
+        }
+    }
+
+    fn path(&self, base_dir: &Path) -> PathBuf {
+        base_dir.join(maven_to_vec(&self.name, None, None).join(get_directory_separator()))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ForgeData {
+    client: String,
+    server: String,
+}
+
+impl ForgeData {
+    fn for_side(&self, side: JarType) -> &str {
+        match side {
+            JarType::Client => &self.client,
+            JarType::Server => &self.server,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgeProcessor {
+    sides: Option<Vec<String>>,
+    jar: String,
+    classpath: Vec<String>,
+    args: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgeInstall112 {
+    version: String,
+    /// Coordinate of the forge library, only set by some installers.
+    path: Option<String>,
+    /// Where the server processors expect the vanilla server jar, with `{LIBRARY_DIR}` and
+    /// `{MINECRAFT_VERSION}` to substitute. Only set by 1.17 and newer installers.
+    #[serde(rename = "serverJarPath")]
+    server_jar_path: Option<String>,
+    data: HashMap<String, ForgeData>,
+    processors: Vec<ForgeProcessor>,
+    libraries: Vec<Library>,
+}
+
+impl ForgeInstall112 {
+    pub fn server_jar_path(&self) -> Option<&str> {
+        self.server_jar_path.as_deref()
+    }
+
+    /// Values the processors' arguments refer to by key.
+    pub fn data(&self) -> &HashMap<String, ForgeData> {
+        &self.data
+    }
+
+    pub fn processors(&self) -> &[ForgeProcessor] {
+        &self.processors
+    }
+
+    /// Libraries the processors need, which aren't part of the game's classpath.
+    pub fn libraries(&self) -> &[Library] {
+        &self.libraries
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgeInstallerProfile111 {
+    #[serde(rename = "versionInfo")]
+    version_info: ForgeVersion111,
+}
+
+impl ForgeInstallerProfile111 {
+    pub fn version_info(&self) -> &ForgeVersion111 {
+        &self.version_info
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub enum ForgeInstallerProfile {
+    Profile112 {
+        version: ForgeVersion112,
+        profile: ForgeInstall112,
+    },
+    Profile111(ForgeInstallerProfile111),
+}
+
+pub struct InstallerArgumentPaths {
+    pub libraries_path: PathBuf,
+    pub versions_dir_path: PathBuf,
+    pub minecraft_version: String,
+    pub forge_loader_version: String,
+    pub tmp_dir: PathBuf,
+    /// Where the processors' output is written.
+    pub log_path: PathBuf,
+    /// The installer profile's `serverJarPath`.
+    pub server_jar_path: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ForgePatchError {
+    Io(io::Error),
+    /// The installer of the version doesn't say which library it patches.
+    UnknownForgeLibrary(String),
+    /// The processor's output is in the install log at `log_path`.
+    ProcessorFailed {
+        processor: String,
+        status: ExitStatus,
+        log_path: PathBuf,
+    },
+}
+
+impl fmt::Display for ForgePatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForgePatchError::Io(error) => write!(f, "{}", error),
+            ForgePatchError::UnknownForgeLibrary(version) => write!(
+                f,
+                "Could not find the forge library in the installer of {}",
+                version
+            ),
+            ForgePatchError::ProcessorFailed {
+                processor,
+                status,
+                log_path,
+            } => write!(
+                f,
+                "Forge processor {} failed with {}, its output is in {}",
+                processor,
+                status,
+                log_path.display()
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for ForgePatchError {
+    fn from(error: io::Error) -> Self {
+        ForgePatchError::Io(error)
+    }
+}
+
+/// Group and artifact ids forge's own library is published under. NeoForge kept forge's artifact
+/// id for 1.20.1 before renaming it.
+const FORGE_ARTIFACTS: [&str; 3] = [
+    "net.minecraftforge:forge",
+    "net.neoforged:neoforge",
+    "net.neoforged:forge",
+];
+
+/// The maven coordinate, without a classifier, of the forge library the installer's processors
+/// patch. Taken from the installer profile's `path`, or otherwise the forge library listed by the
+/// profile or version.
+pub fn forge_library_name(
+    profile: &ForgeInstall112,
+    version: &ForgeVersion112,
+) -> Result<String, ForgePatchError> {
+    let library_names = profile
+        .libraries
+        .iter()
+        .chain(&version.libraries)
+        .map(|library| library.name());
+    profile
+        .path
+        .as_deref()
+        .into_iter()
+        .chain(library_names)
+        .map(|name| name.split(':').take(3).collect::<Vec<_>>())
+        .find(|parts| parts.len() == 3 && FORGE_ARTIFACTS.contains(&parts[..2].join(":").as_str()))
+        .map(|parts| parts.join(":"))
+        .ok_or_else(|| ForgePatchError::UnknownForgeLibrary(profile.version.clone()))
+}
+
+pub async fn download_forge_hashes(forge_version: &str) -> DownloadResult<ForgeHashes> {
+    let url = format!("{}/{}/meta.json", FORGE_FILES_BASE_URL, forge_version);
+    Ok(download_json_object_from_url::<ForgeHashes>(&url).await?)
+}
+
+pub async fn download_forge_version(
+    forge_version: &str,
+    minecraft_version: &str,
+    valid_hash: &ForgeFileHash,
+    version_path: &Path,
+    tmp_dir: &Path,
+    patches: &VersionPatches,
+) -> CoreResult<ForgeInstallerProfile> {
+    let terminal = "installer.jar";
+    let url = format!(
+        "{0}/{1}/forge-{1}-{2}",
+        FORGE_MAVEN_BASE_URL, forge_version, terminal
+    );
+    let bytes = download_bytes_from_url(&url).await?;
+
+    if !validate_hash_md5(&bytes, &valid_hash.hash) {
+        let error = "Could not validate installer hash, download aborted.".into();
+        error!("{}", &error);
+        return Err(CoreError::MismatchedFileHash(error));
+    }
+
+    // Write bytes to the forge installers path.
+    let dir_path = &version_path.join(minecraft_version).join("forgeInstallers");
+    fs::create_dir_all(dir_path)?;
+
+    // Save the forge installer file
+    let path = dir_path.join(format!("forge-{}-{}", forge_version, terminal));
+    if !path.exists() {
+        let mut file = File::create(path)?;
+        file.write_all(&bytes)?;
+    }
+
+    // Unzip the json files in memory
+    let cursor = Cursor::new(bytes);
+    let mut archive = zip::ZipArchive::new(cursor)?;
+
+    // Extract the archive into the tmp_dir
+    archive.extract(tmp_dir)?;
+
+    // Pull out install profile and version
+    let install_profile_file = archive.by_name("install_profile.json")?;
+    let install_profile_bytes = bytes_from_zip_file(install_profile_file);
+
+    let version_file_result = archive.by_name("version.json");
+    let installer_profile = match version_file_result {
+        Ok(version_file) => {
+            let version_bytes = bytes_from_zip_file(version_file);
+
+            ForgeInstallerProfile::Profile112 {
+                profile: serde_json::from_slice(&install_profile_bytes)?,
+                version: patches.read(PatchTarget::Forge, forge_version, &version_bytes)?,
+            }
+        }
+        Err(_) => {
+            // The version json of 1.11 and older is part of the install profile.
+            let mut install_profile: Value = serde_json::from_slice(&install_profile_bytes)?;
+            if let Some(version_info) = install_profile.get_mut("versionInfo") {
+                patches.apply(PatchTarget::Forge, forge_version, version_info);
+            }
+            ForgeInstallerProfile::Profile111(serde_json::from_value(install_profile)?)
+        }
+    };
+
+    Ok(installer_profile)
+}
+
+pub fn patch_forge(
+    java_path: &Path,
+    processors: &[ForgeProcessor],
+    data: &HashMap<String, ForgeData>,
+    forge_library_name: String,
+    side: JarType,
+    argument_paths: InstallerArgumentPaths,
+) -> Result<(), ForgePatchError> {
+    info!("Patching Forge for the {}", side.side());
+    // Copy the data map so it can be mutable.
+    let mut forge_data_map = data.clone();
+    let installer_lzma = argument_paths
+        .tmp_dir
+        .join("data")
+        .join(format!("{}.lzma", side.side()));
+    if !installer_lzma.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "The forge installer of {} has no {} patches",
+                forge_library_name,
+                side.side()
+            ),
+        )
+        .into());
+    }
+
+    // Format the lzma_path from the forge library's coordinate
+    let lzma_str = maven_to_vec(
+        &forge_library_name,
+        Some(&format!("-{}data", side.side())),
+        Some(".lzma"),
+    )
+    .join(get_directory_separator());
+    let lzma_path = argument_paths.libraries_path.join(lzma_str);
+    let lzma_parent = lzma_path.parent().unwrap();
+    if !lzma_parent.exists() {
+        fs::create_dir_all(lzma_parent)?;
+    }
+
+    debug!("Lzma path: {}", path_to_utf8_str(&lzma_path));
+
+    fs::copy(installer_lzma, &lzma_path)?;
+    // Patches issue with BINPATCH where it uses a relative path but should use the lzma_path created above
+    let lzma_path = path_to_utf8_str(&lzma_path).to_owned();
+    let binpatch = match side {
+        JarType::Client => ForgeData {
+            client: lzma_path,
+            server: String::new(),
+        },
+        JarType::Server => ForgeData {
+            client: String::new(),
+            server: lzma_path,
+        },
+    };
+    forge_data_map.insert("BINPATCH".into(), binpatch);
+
+    // Get the path to the version dir for a specific minecraft version.
+    let game_version_path = argument_paths
+        .versions_dir_path
+        .join(&argument_paths.minecraft_version);
+
+    // Newer server processors read the vanilla server jar from the libraries dir.
+    if let (JarType::Server, Some(server_jar_path)) = (side, &argument_paths.server_jar_path) {
+        let server_jar_path = PathBuf::from(
+            server_jar_path
+                .replace(
+                    "{LIBRARY_DIR}",
+                    path_to_utf8_str(&argument_paths.libraries_path),
+                )
+                .replace("{MINECRAFT_VERSION}", &argument_paths.minecraft_version),
+        );
+        if let Some(parent) = server_jar_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(
+            game_jar_path(&game_version_path, side, &argument_paths),
+            server_jar_path,
+        )?;
+    }
+
+    let mut install_log = File::create(&argument_paths.log_path)?;
+
+    // Iterate over each processor and run them with the correctly substituted arguments.
+    info!("Spawning forge patching processors...");
+    let timer = Instant::now();
+    for processor in processors {
+        // Processors without sides run for both.
+        if let Some(sides) = &processor.sides {
+            if !sides
+                .iter()
+                .any(|processor_side| processor_side == side.side())
+            {
+                continue;
+            }
+        }
+
+        let jar_path = argument_paths
+            .libraries_path
+            .join(maven_to_vec(&processor.jar, None, None).join(get_directory_separator()));
+
+        let classpaths: Vec<_> = processor
+            .classpath
+            .iter()
+            .map(|classpath| {
+                let processor_classpath =
+                    maven_to_vec(classpath, None, None).join(get_directory_separator());
+                path_to_utf8_str(&argument_paths.libraries_path.join(processor_classpath))
+                    .to_owned()
+            })
+            .collect();
+
+        // Create forge installer path inside the game version dir
+        let forge_installers_path = game_version_path.join("forgeInstallers");
+        if !forge_installers_path.exists() {
+            fs::create_dir_all(&forge_installers_path)?;
+        }
+
+        let formatted_args: Vec<String> = processor
+            .args
+            .iter()
+            .map(|argument| {
+                replace_arg_if_possible(
+                    argument,
+                    side,
+                    &forge_data_map,
+                    &forge_installers_path,
+                    &game_version_path,
+                    &argument_paths,
+                )
+            })
+            .map(|argument| compute_path_if_possible(&argument, &argument_paths.libraries_path))
+            .collect();
+
+        if let Some(main_class) = obtain_main_class_from_jar(&jar_path) {
+            // Format classpaths to include the processor jar
+            let formatted_classpaths = format!(
+                "{}{}{}",
+                classpaths.join(get_classpath_separator()),
+                get_classpath_separator(),
+                path_to_utf8_str(&jar_path),
+            );
+            let mut args: Vec<String> = vec!["-cp".into()];
+            args.push(formatted_classpaths);
+            args.push(main_class);
+            args.extend(formatted_args);
+
+            writeln!(install_log, "> {} {}", processor.jar, args.join(" "))?;
+            // Spawn a process for the forge processor, writing its output to the install log.
+            let mut command = Command::new(java_path);
+            command
+                .current_dir(&argument_paths.tmp_dir)
+                .args(args)
+                .stdout(Stdio::from(install_log.try_clone()?))
+                .stderr(Stdio::from(install_log.try_clone()?));
+            debug!("Forge Processor: {:#?}", command);
+            let mut child = command.spawn()?;
+            let id = child.id();
+            info!("Spawned forge processor with PID {}", id);
+            let status = child.wait()?;
+            info!("Forge processor({}) exited with exit code: {}", id, status);
+            writeln!(install_log, "Exited with {}", status)?;
+            if !status.success() {
+                return Err(ForgePatchError::ProcessorFailed {
+                    processor: processor.jar.clone(),
+                    status,
+                    log_path: argument_paths.log_path,
+                });
+            }
+        } else {
+            error!("Error obtaining main class from jar: {:#?}", &jar_path);
+        }
+    }
+    info!(
+        "Finished patching forge in {}ms",
+        timer.elapsed().as_millis()
+    );
+    Ok(())
+}
+
+/// Extracts the jar manifest into memory and pulls out the 'Main-Class' entry if it exists.
+fn obtain_main_class_from_jar(jar_path: &Path) -> Option<String> {
+    let file = File::open(jar_path).unwrap();
+    let reader = BufReader::new(file);
+
+    // Read jar and pull out MANIFEST.MF and convert to a string
+    let mut archive = zip::ZipArchive::new(reader).unwrap();
+    let mut version_file = archive.by_name("META-INF/MANIFEST.MF").unwrap();
+    let mut manifest = String::new();
+    version_file.read_to_string(&mut manifest).ok()?;
+
+    let id = "Main-Class: ";
+    // Split the manifest at id and again at the newline to get only the main class, trimming excess whitespace.
+    if let Some(entry) = manifest.find(id) {
+        let (_, main_class_line) = manifest.split_at(entry + id.len());
+        let main_class = &main_class_line[0..main_class_line.find('\n')?].trim();
+        Some(main_class.to_string())
+    } else {
+        None
+    }
+}
+
+/// The vanilla jar of `side`, where it's downloaded to in the version dir.
+fn game_jar_path(
+    game_version_path: &Path,
+    side: JarType,
+    argument_paths: &InstallerArgumentPaths,
+) -> PathBuf {
+    game_version_path
+        .join(side.side())
+        .join(format!("{}.jar", argument_paths.minecraft_version))
+}
+
+fn replace_arg_if_possible(
+    arg: &str,
+    side: JarType,
+    data: &HashMap<String, ForgeData>,
+    forge_installers_path: &Path,
+    game_version_path: &Path,
+    argument_paths: &InstallerArgumentPaths,
+) -> String {
+    // Early return the argument if there is no formatting to be done
+    if !arg.contains('{') {
+        return arg.into();
+    }
+
+    let mut formatted_arg = arg
+        .replace("{SIDE}", side.side())
+        .replace("{ROOT}", path_to_utf8_str(forge_installers_path)) // Dirname of ${app_dir}/versions/<version>/forgeInstallers/<loaderVersion>.jar
+        .replace(
+            "{MINECRAFT_JAR}",
+            path_to_utf8_str(&game_jar_path(game_version_path, side, argument_paths)),
+        ) // Minecraft jar path
+        .replace(
+            "{MINECRAFT_VERSION}",
+            path_to_utf8_str(
+                &game_version_path.join(format!("{}.json", argument_paths.minecraft_version)),
+            ),
+        ) // Minecraft version json path
+        .replace(
+            "{INSTALLER}",
+            path_to_utf8_str(
+                &forge_installers_path.join(format!("{}.jar", argument_paths.forge_loader_version)),
+            ),
+        ) // ${app_dir}/versions/<version>/forgeInstallers/<loaderVersion>.jar
+        .replace(
+            "{LIBRARY_DIR}",
+            path_to_utf8_str(&argument_paths.libraries_path),
+        ); // Libraries path
+
+    // Replace arguments from the installer_profile's 'data' entry
+    for (key, value) in data {
+        let substr = format!("{{{}}}", key);
+        formatted_arg = formatted_arg.replace(&substr, value.for_side(side));
+    }
+
+    formatted_arg
+}
+
+fn compute_path_if_possible(arg: &str, libraries_path: &Path) -> String {
+    if arg.starts_with('[') {
+        // Create path from maven with '[]' around them.
+        let mut path = libraries_path.to_path_buf();
+        for piece in maven_to_vec(&arg.replace(['[', ']'], ""), None, None) {
+            path = path.join(piece);
+        }
+        // If the parent dir doesnt exist yet, make it
+        let parent_dir = path.parent().unwrap();
+        if !parent_dir.exists() {
+            match fs::create_dir_all(parent_dir) {
+                Ok(_) => {}
+                Err(error) => {
+                    error!("Error creating parent directories: {}", error);
+                    return arg.to_owned();
+                }
+            }
+        }
+
+        path_to_utf8_str(&path).to_owned()
+    } else {
+        arg.to_owned()
+    }
+}
+
+#[test]
+pub fn test_download_forge_hashes() {
+    let forge_version = "1.19.3-44.1.16";
+    start_mock_server();
+
+    tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(async move {
+            let hashes = download_forge_hashes(forge_version).await.unwrap();
+            assert_eq!(
+                hashes.installer_hash().hash(),
+                "0ff0d2aee1801aacfb50f777afdc466e"
+            );
+        });
+}
+
+#[test]
+pub fn test_download_forge_version() {
+    let forge_version = "1.19.3-44.1.16";
+    let tmp_dir = TempDir::new("temp").unwrap();
+    let versions_dir = tmp_dir.path().join("versions");
+    let installer_dir = tmp_dir.path().join("installer");
+    start_mock_server();
+
+    tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(async move {
+            let profile = download_forge_version(
+                forge_version,
+                "1.19.3",
+                &"0ff0d2aee1801aacfb50f777afdc466e".into(),
+                &versions_dir,
+                &installer_dir,
+                &VersionPatches::default(),
+            )
+            .await
+            .unwrap();
+            assert!(versions_dir
+                .join("1.19.3")
+                .join("forgeInstallers")
+                .join("forge-1.19.3-44.1.16-installer.jar")
+                .exists());
+            assert!(installer_dir.join("install_profile.json").exists());
+
+            let ForgeInstallerProfile::Profile112 { version, profile } = profile else {
+                panic!("1.19.3 forge has a separate version json");
+            };
+            assert_eq!(
+                forge_library_name(&profile, &version).unwrap(),
+                "net.minecraftforge:forge:1.19.3-44.1.16"
+            );
+        });
+}
+
+#[test]
+fn test_replace_arg_for_side() {
+    let paths = InstallerArgumentPaths {
+        libraries_path: PathBuf::from("libraries"),
+        versions_dir_path: PathBuf::from("versions"),
+        minecraft_version: "1.19.3".into(),
+        forge_loader_version: "1.19.3-44.1.16".into(),
+        tmp_dir: PathBuf::from("tmp"),
+        log_path: PathBuf::from("forge-install.log"),
+        server_jar_path: None,
+    };
+    let data = HashMap::from([(
+        "PATCHED".to_owned(),
+        ForgeData {
+            client: "[net.minecraftforge:forge:1.19.3-44.1.16:client]".into(),
+            server: "[net.minecraftforge:forge:1.19.3-44.1.16:server]".into(),
+        },
+    )]);
+    let game_version_path = paths.versions_dir_path.join("1.19.3");
+    let replace = |arg: &str, side: JarType| {
+        replace_arg_if_possible(
+            arg,
+            side,
+            &data,
+            &game_version_path.join("forgeInstallers"),
+            &game_version_path,
+            &paths,
+        )
+    };
+
+    assert_eq!(
+        replace("{PATCHED}", JarType::Server),
+        "[net.minecraftforge:forge:1.19.3-44.1.16:server]"
+    );
+    assert_eq!(replace("--side={SIDE}", JarType::Client), "--side=client");
+    assert_eq!(
+        PathBuf::from(replace("{MINECRAFT_JAR}", JarType::Server)),
+        game_version_path.join("server").join("1.19.3.jar")
+    );
+}
+
+#[test]
+fn test_forge_fixtures() {
+    let manifest: ForgeManifest = read_fixture("forge/maven-metadata.json");
+    assert_eq!(manifest.0["1.19.3"][0], "1.19.3-44.1.16");
+    assert_eq!(manifest.0.len(), 3);
+
+    let hashes: ForgeHashes = read_fixture("forge/meta-1.19.3-44.1.16.json");
+    assert_eq!(
+        hashes.installer_hash().hash(),
+        "268bde630c51b1e94257d76377ec2424"
+    );
+    // 1.11.2 and older name the mdk `src` and have no sources jar.
+    let hashes: ForgeHashes = read_fixture("forge/meta-1.7.10-10.13.4.1614-1.7.10.json");
+    assert_eq!(
+        hashes.installer_hash().hash(),
+        "7c5a7e6a4d3c0b5bd7a6f1a4e8f2c1b0"
+    );
+
+    let mut profile: ForgeInstall112 = read_fixture("forge/install_profile-1.19.3-44.1.16.json");
+    assert_eq!(profile.version, "1.19.3-forge-44.1.16");
+    assert_eq!(profile.data["BINPATCH"].client, "/data/client.lzma");
+    assert_eq!(profile.processors.len(), 2);
+    assert_eq!(
+        profile.processors[0].sides.as_deref(),
+        Some(&["server".into()][..])
+    );
+    let mut version: ForgeVersion112 = read_fixture("forge/version-1.19.3-44.1.16.json");
+    // The profile has no `path`, the version lists the universal jar.
+    assert_eq!(
+        forge_library_name(&profile, &version).unwrap(),
+        "net.minecraftforge:forge:1.19.3-44.1.16"
+    );
+    profile.path = Some("net.neoforged:neoforge:20.4.80".into());
+    assert_eq!(
+        forge_library_name(&profile, &version).unwrap(),
+        "net.neoforged:neoforge:20.4.80"
+    );
+    profile.path = None;
+    let libraries = std::mem::take(&mut version.libraries);
+    assert!(matches!(
+        forge_library_name(&profile, &version),
+        Err(ForgePatchError::UnknownForgeLibrary(_))
+    ));
+    version.libraries = libraries;
+    assert!(matches!(
+        version.metadata.arguments,
+        LaunchArguments::LaunchArguments113(_)
+    ));
+    assert_eq!(version.libraries.len(), 2);
+
+    let profile: ForgeInstallerProfile111 =
+        read_fixture("forge/install_profile-1.7.10-10.13.4.1614-1.7.10.json");
+    let version = profile.version_info;
+    assert_eq!(
+        version.metadata.main_class,
+        "net.minecraft.launchwrapper.Launch"
+    );
+    assert!(matches!(
+        version.metadata.arguments,
+        LaunchArguments::LaunchArguments112(_)
+    ));
+    // Libraries without a url come from mojang's library server.
+    assert_eq!(
+        version.libraries[1].url(),
+        format!(
+            "{}/net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar",
+            MINECRAFT_LIBRARIES_URL
+        )
+    );
+    assert_eq!(
+        version.libraries[2].hash(),
+        "0a6ae0b15e6b0ddc4bd2a6cc5ad6f0b1c7c6ad5e"
+    );
+}
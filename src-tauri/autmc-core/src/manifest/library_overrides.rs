@@ -0,0 +1,139 @@
+//! Replacements for the libraries of version jsons that don't run on this platform. Mojang only
+//! ships x86 natives for linux, so on arm64 LWJGL's own builds are used instead.
+use std::env;
+
+use autmc_downloader::download_bytes_from_url;
+use log::{info, warn};
+
+use crate::{consts::MAVEN_CENTRAL_URL, CoreResult};
+
+use super::{maven_to_vec, vanilla::Artifact, vanilla::Library};
+
+/// The first LWJGL 3 release with linux arm64 natives, older ones are raised to it.
+const MIN_ARM64_LWJGL_VERSION: &str = "3.2.1";
+
+/// Whether this is a platform Mojang's libraries have no natives for.
+pub fn needs_library_overrides() -> bool {
+    env::consts::OS == "linux" && env::consts::ARCH == "aarch64"
+}
+
+/// Replaces the LWJGL libraries in `libraries` with builds that have linux arm64 natives.
+/// LWJGL 2, used by 1.12.2 and older, has no such builds and is left as is.
+pub async fn override_lwjgl_for_arm64(libraries: &mut [Library]) -> CoreResult<()> {
+    for library in libraries {
+        let natives_key = library.determine_key_for_classifiers();
+        if library.name().starts_with("org.lwjgl.lwjgl:") && natives_key.is_some() {
+            warn!(
+                "{} has no linux arm64 natives, the game is unlikely to start",
+                library.name()
+            );
+            continue;
+        }
+        let (artifact_coordinate, natives_coordinate) =
+            match arm64_lwjgl_coordinates(library.name()) {
+                Some(coordinates) => coordinates,
+                None => continue,
+            };
+
+        // 1.19 and newer list each natives jar as its own library, with the platform in the name.
+        let has_artifact = library.downloads().artifact().is_some();
+        if let Some(coordinate) = artifact_coordinate.filter(|_| has_artifact) {
+            info!("Using {} for {} on linux arm64", coordinate, library.name());
+            let artifact = maven_central_artifact(&coordinate).await?;
+            library.set_artifact(artifact);
+        }
+        // Older versions list them as classifiers of the library, extracted by the launcher.
+        if let Some(key) = natives_key {
+            info!(
+                "Using {} for the natives of {} on linux arm64",
+                natives_coordinate,
+                library.name()
+            );
+            let artifact = maven_central_artifact(&natives_coordinate).await?;
+            library.set_classifier(&key, artifact);
+        }
+    }
+    Ok(())
+}
+
+/// For an LWJGL 3 library's coordinate, the coordinate of the jar to download instead of its
+/// artifact, `None` if the artifact can be kept, and the coordinate of its linux arm64 natives.
+fn arm64_lwjgl_coordinates(name: &str) -> Option<(Option<String>, String)> {
+    let mut parts = name.split(':');
+    let (group, artifact, version) = (parts.next()?, parts.next()?, parts.next()?);
+    let classifier = parts.next();
+    if group != "org.lwjgl" {
+        return None;
+    }
+
+    let raised = version_parts(version) < version_parts(MIN_ARM64_LWJGL_VERSION);
+    let version = if raised {
+        MIN_ARM64_LWJGL_VERSION
+    } else {
+        version
+    };
+    let natives = format!("{}:{}:{}:natives-linux-arm64", group, artifact, version);
+    let replacement = match classifier {
+        Some(classifier) if classifier.starts_with("natives-linux") => Some(natives.clone()),
+        // Other platforms' natives are filtered out by their rules.
+        Some(_) => None,
+        None if raised => Some(format!("{}:{}:{}", group, artifact, version)),
+        None => None,
+    };
+    Some((replacement, natives))
+}
+
+/// The numbers of a version like `3.3.1`, ignoring suffixes like `-SNAPSHOT`.
+fn version_parts(version: &str) -> Vec<u32> {
+    version
+        .split(['.', '-'])
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+/// The jar at `coordinate` on maven central, with the hash maven central publishes next to it.
+async fn maven_central_artifact(coordinate: &str) -> CoreResult<Artifact> {
+    let path = maven_to_vec(coordinate, None, None).join("/");
+    let url = format!("{}/{}", MAVEN_CENTRAL_URL, path);
+    let bytes = download_bytes_from_url(&format!("{}.sha1", url)).await?;
+    // Some `.sha1` files are followed by the file name.
+    let sha1 = String::from_utf8(bytes.to_vec())?
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_owned();
+    Ok(Artifact::new(path, url, sha1))
+}
+
+#[test]
+fn test_arm64_lwjgl_coordinates() {
+    // 1.19 and newer: the natives are their own library.
+    assert_eq!(
+        arm64_lwjgl_coordinates("org.lwjgl:lwjgl-glfw:3.3.1:natives-linux"),
+        Some((
+            Some("org.lwjgl:lwjgl-glfw:3.3.1:natives-linux-arm64".into()),
+            "org.lwjgl:lwjgl-glfw:3.3.1:natives-linux-arm64".into()
+        ))
+    );
+    assert_eq!(
+        arm64_lwjgl_coordinates("org.lwjgl:lwjgl:3.3.1:natives-macos-arm64"),
+        Some((None, "org.lwjgl:lwjgl:3.3.1:natives-linux-arm64".into()))
+    );
+    assert_eq!(
+        arm64_lwjgl_coordinates("org.lwjgl:lwjgl:3.2.2"),
+        Some((None, "org.lwjgl:lwjgl:3.2.2:natives-linux-arm64".into()))
+    );
+    // 1.13 uses a release without arm64 builds, the classes and natives have to match.
+    assert_eq!(
+        arm64_lwjgl_coordinates("org.lwjgl:lwjgl-stb:3.1.6"),
+        Some((
+            Some("org.lwjgl:lwjgl-stb:3.2.1".into()),
+            "org.lwjgl:lwjgl-stb:3.2.1:natives-linux-arm64".into()
+        ))
+    );
+    assert_eq!(arm64_lwjgl_coordinates("com.mojang:logging:1.1.1"), None);
+    assert_eq!(
+        arm64_lwjgl_coordinates("org.lwjgl.lwjgl:lwjgl-platform:2.9.4-nightly-20150209"),
+        None
+    );
+}
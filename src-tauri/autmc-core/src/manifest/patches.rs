@@ -0,0 +1,248 @@
+//! Json overlays applied to version jsons before they are read, so fixes like swapping a library
+//! or the main class don't need a launcher release. Patches are the `.json` files in the patches
+//! folder, applied in the order of their file names.
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+};
+
+use log::{info, warn};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use crate::CoreResult;
+
+/// Which version jsons a patch applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PatchTarget {
+    Vanilla,
+    Fabric,
+    Forge,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionPatch {
+    /// The patch's file name.
+    #[serde(skip)]
+    name: String,
+    target: PatchTarget,
+    /// Minecraft versions for vanilla, loader versions for fabric and forge. A trailing `*` matches
+    /// every version starting with the rest, no versions match every version.
+    #[serde(default)]
+    versions: Vec<String>,
+    main_class: Option<String>,
+    /// Libraries whose names start with any of these are removed, like `org.lwjgl:lwjgl-glfw`.
+    #[serde(default)]
+    remove_libraries: Vec<String>,
+    /// Libraries added after the remaining ones, in the format of the target's version json.
+    #[serde(default)]
+    add_libraries: Vec<Value>,
+    #[serde(default)]
+    game_arguments: Vec<String>,
+    /// Not supported by versions that pass their arguments as a single string.
+    #[serde(default)]
+    jvm_arguments: Vec<String>,
+}
+
+impl VersionPatch {
+    fn applies_to(&self, target: PatchTarget, version: &str) -> bool {
+        self.target == target
+            && (self.versions.is_empty()
+                || self
+                    .versions
+                    .iter()
+                    .any(|pattern| match pattern.strip_suffix('*') {
+                        Some(prefix) => version.starts_with(prefix),
+                        None => version == pattern,
+                    }))
+    }
+
+    fn apply(&self, json: &mut Value) {
+        let object = match json.as_object_mut() {
+            Some(object) => object,
+            None => return,
+        };
+        if let Some(main_class) = &self.main_class {
+            object.insert("mainClass".into(), main_class.clone().into());
+        }
+
+        if let Some(Value::Array(libraries)) = object.get_mut("libraries") {
+            libraries.retain(|library| {
+                let name = library["name"].as_str().unwrap_or_default();
+                !self
+                    .remove_libraries
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix.as_str()))
+            });
+            libraries.extend(self.add_libraries.iter().cloned());
+        } else if !self.add_libraries.is_empty() {
+            object.insert("libraries".into(), self.add_libraries.clone().into());
+        }
+
+        // 1.12.2 and older pass the game arguments as a single string.
+        if let Some(Value::String(arguments)) = object.get_mut("minecraftArguments") {
+            for argument in &self.game_arguments {
+                arguments.push(' ');
+                arguments.push_str(argument);
+            }
+            if !self.jvm_arguments.is_empty() {
+                warn!(
+                    "Patch {} adds jvm arguments to a version that has none, they are skipped",
+                    self.name
+                );
+            }
+            return;
+        }
+        for (key, added) in [("game", &self.game_arguments), ("jvm", &self.jvm_arguments)] {
+            if added.is_empty() {
+                continue;
+            }
+            let arguments = object
+                .entry("arguments")
+                .or_insert_with(|| Value::Object(Default::default()));
+            if let Some(arguments) = arguments.as_object_mut() {
+                let list = arguments
+                    .entry(key)
+                    .or_insert_with(|| Value::Array(Vec::new()));
+                if let Some(list) = list.as_array_mut() {
+                    list.extend(added.iter().cloned().map(Value::from));
+                }
+            }
+        }
+    }
+}
+
+/// The patches in the patches folder.
+#[derive(Debug, Default)]
+pub struct VersionPatches(Vec<VersionPatch>);
+
+impl VersionPatches {
+    /// Reads the patches in `patches_dir`. Patches that can't be read are skipped.
+    pub fn load(patches_dir: &Path) -> Self {
+        let mut paths: Vec<_> = match fs::read_dir(patches_dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect(),
+            Err(_) => return Self::default(),
+        };
+        paths.sort();
+
+        let mut patches = Vec::new();
+        for path in paths {
+            let patch = File::open(&path)
+                .map_err(serde_json::Error::io)
+                .and_then(|file| serde_json::from_reader::<_, VersionPatch>(BufReader::new(file)));
+            match patch {
+                Ok(mut patch) => {
+                    patch.name = path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned();
+                    patches.push(patch);
+                }
+                Err(e) => warn!("Skipping version patch {}: {}", path.display(), e),
+            }
+        }
+        Self(patches)
+    }
+
+    /// Applies the patches for `target` and `version` to `json`.
+    pub fn apply(&self, target: PatchTarget, version: &str, json: &mut Value) {
+        for patch in self
+            .0
+            .iter()
+            .filter(|patch| patch.applies_to(target, version))
+        {
+            info!("Applying patch {} to {}", patch.name, version);
+            patch.apply(json);
+        }
+    }
+
+    /// Reads a version json after applying the patches for `target` and `version` to it.
+    pub fn read<T: DeserializeOwned>(
+        &self,
+        target: PatchTarget,
+        version: &str,
+        bytes: &[u8],
+    ) -> CoreResult<T> {
+        let mut json: Value = serde_json::from_slice(bytes)?;
+        self.apply(target, version, &mut json);
+        Ok(serde_json::from_value(json)?)
+    }
+}
+
+#[cfg(test)]
+use {serde_json::json, tempdir::TempDir};
+
+#[test]
+fn test_version_patches() {
+    let tmp_dir = TempDir::new("patches").unwrap();
+    let write_patch = |name: &str, patch: Value| {
+        fs::write(tmp_dir.path().join(name), patch.to_string()).unwrap();
+    };
+    write_patch(
+        "10-lwjgl.json",
+        json!({
+            "target": "vanilla",
+            "versions": ["1.20*"],
+            "removeLibraries": ["org.lwjgl:lwjgl:"],
+            "addLibraries": [{ "name": "org.lwjgl:lwjgl:3.3.2" }],
+            "jvmArguments": ["-Dorg.lwjgl.util.Debug=true"],
+        }),
+    );
+    write_patch(
+        "20-main-class.json",
+        json!({ "target": "vanilla", "mainClass": "net.minecraft.client.main.Patched" }),
+    );
+    write_patch(
+        "30-fabric.json",
+        json!({ "target": "fabric", "mainClass": "Unused" }),
+    );
+    fs::write(tmp_dir.path().join("broken.json"), "{").unwrap();
+    fs::write(tmp_dir.path().join("notes.txt"), "not a patch").unwrap();
+    let patches = VersionPatches::load(tmp_dir.path());
+    assert_eq!(patches.0.len(), 3);
+
+    let version = || {
+        json!({
+            "mainClass": "net.minecraft.client.main.Main",
+            "libraries": [{ "name": "com.mojang:logging:1.1.1" }, { "name": "org.lwjgl:lwjgl:3.3.1" }],
+            "arguments": { "game": ["--username"] },
+        })
+    };
+    let mut json = version();
+    patches.apply(PatchTarget::Vanilla, "1.20.1", &mut json);
+    assert_eq!(
+        json,
+        json!({
+            "mainClass": "net.minecraft.client.main.Patched",
+            "libraries": [{ "name": "com.mojang:logging:1.1.1" }, { "name": "org.lwjgl:lwjgl:3.3.2" }],
+            "arguments": { "game": ["--username"], "jvm": ["-Dorg.lwjgl.util.Debug=true"] },
+        })
+    );
+
+    // Only the patch for every version applies.
+    let mut json = version();
+    patches.apply(PatchTarget::Vanilla, "1.19.4", &mut json);
+    assert_eq!(json["mainClass"], "net.minecraft.client.main.Patched");
+    assert_eq!(json["libraries"], version()["libraries"]);
+
+    let mut legacy = json!({ "minecraftArguments": "--username ${auth_player_name}" });
+    let patch: VersionPatch = serde_json::from_value(json!({
+        "target": "vanilla",
+        "gameArguments": ["--demo"],
+        "jvmArguments": ["-Dskipped=true"],
+    }))
+    .unwrap();
+    patch.apply(&mut legacy);
+    assert_eq!(
+        legacy,
+        json!({ "minecraftArguments": "--username ${auth_player_name} --demo" })
+    );
+}
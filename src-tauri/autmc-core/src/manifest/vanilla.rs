@@ -0,0 +1,1024 @@
+use std::{
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+};
+
+use autmc_downloader::Downloadable;
+use indexmap::IndexMap;
+use log::{debug, error, warn};
+use serde::{
+    de::{Error, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+
+use crate::consts::VANILLA_ASSET_BASE_URL;
+#[cfg(test)]
+use crate::fixtures::read_fixture;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// The version metadata returned in the manifest request.
+pub struct VanillaManifestVersion {
+    id: String,
+    #[serde(rename = "type")]
+    version_type: String,
+    url: String,
+    // time: String,
+    #[serde(rename = "releaseTime")]
+    release_time: String,
+    sha1: String,
+    // #[serde(rename = "complianceLevel")]
+    // compliance_level: u32,
+}
+
+impl VanillaManifestVersion {
+    /// An entry for a version that isn't in the manifest.
+    pub fn new(
+        id: String,
+        version_type: String,
+        url: String,
+        release_time: String,
+        sha1: String,
+    ) -> Self {
+        Self {
+            id,
+            version_type,
+            url,
+            release_time,
+            sha1,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// `release`, `snapshot`, `old_beta` or `old_alpha`.
+    pub fn version_type(&self) -> &str {
+        &self.version_type
+    }
+
+    /// Where the version's json is downloaded from.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn release_time(&self) -> &str {
+        &self.release_time
+    }
+
+    /// The SHA1 of the version's json.
+    pub fn sha1(&self) -> &str {
+        &self.sha1
+    }
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct holding everything returned in the vanilla manifest json.
+pub struct VanillaManifest {
+    // latest: VanillaLatest,
+    #[serde(deserialize_with = "as_version_map")]
+    versions: IndexMap<String, VanillaManifestVersion>,
+}
+
+impl VanillaManifest {
+    /// Every version by its id, newest first.
+    pub fn versions(&self) -> &IndexMap<String, VanillaManifestVersion> {
+        &self.versions
+    }
+}
+
+fn as_version_map<'de, D>(
+    deserializer: D,
+) -> Result<IndexMap<String, VanillaManifestVersion>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let vanilla_versions: Vec<VanillaManifestVersion> = Deserialize::deserialize(deserializer)?;
+    // IndexMap keeps insertion order. Important here when deserializing the json since the
+    // vanilla manifest is in the correct order already.
+    let mut map: IndexMap<String, VanillaManifestVersion> = IndexMap::new();
+    for version in vanilla_versions {
+        map.insert(version.id.clone(), version);
+    }
+    Ok(map)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum RuleType {
+    #[serde(rename = "features")]
+    Features(HashMap<String, bool>),
+    #[serde(rename = "os")]
+    OperatingSystem(HashMap<String, String>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    action: String,
+    #[serde(flatten)]
+    rule_type: Option<RuleType>,
+}
+
+impl Rule {
+    /// `allow` or `disallow`.
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    /// What the rule applies to, `None` if it applies to everything.
+    pub fn rule_type(&self) -> Option<&RuleType> {
+        self.rule_type.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Argument {
+    Arg(String),
+    ConditionalArg {
+        rules: Vec<Rule>,
+        #[serde(rename = "value", deserialize_with = "string_or_strings_as_vec")]
+        values: Vec<String>,
+    },
+}
+
+fn string_or_strings_as_vec<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct StringVisitor;
+
+    impl<'de> Visitor<'de> for StringVisitor {
+        type Value = Vec<String>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a string or array of strings.")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: Error,
+        {
+            Ok(vec![v.into()])
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut vec: Vec<String> = Vec::new();
+
+            while let Some(elem) = seq.next_element::<String>()? {
+                vec.push(elem);
+            }
+            Ok(vec)
+        }
+    }
+    deserializer.deserialize_any(StringVisitor)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LaunchArguments113 {
+    game: Vec<Argument>,
+    // Optional since some older forge versions( < 1.15.2) only have game args
+    jvm: Option<Vec<Argument>>,
+}
+
+impl LaunchArguments113 {
+    pub fn game(&self) -> &[Argument] {
+        &self.game
+    }
+
+    pub fn jvm(&self) -> Option<&[Argument]> {
+        self.jvm.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum LaunchArguments {
+    LaunchArguments112(String),
+    LaunchArguments113(LaunchArguments113),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DownloadMetadata {
+    sha1: String,
+    size: u32,
+    url: String,
+}
+
+impl DownloadMetadata {
+    pub fn hash(&self) -> &str {
+        &self.sha1
+    }
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Asset {
+    path: String,
+    hash: String,
+    size: u32,
+}
+
+impl Downloadable for Asset {
+    fn name(&self) -> &str {
+        &self.path
+    }
+
+    fn url(&self) -> String {
+        let first_two_chars = &self.hash.split_at(2);
+        let url = format!(
+            "{}/{}/{}",
+            VANILLA_ASSET_BASE_URL, &first_two_chars.0, &self.hash
+        );
+        url
+    }
+
+    fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    fn path(&self, base_dir: &Path) -> PathBuf {
+        let first_two_chars = &self.hash.split_at(2);
+        base_dir.join(first_two_chars.0).join(&self.hash)
+    }
+}
+
+impl Asset {
+    /// Path of the asset by its name, used by the `virtual` and `map_to_resources` layouts.
+    pub fn virtual_path(&self, base_dir: &Path) -> PathBuf {
+        base_dir.join(&self.path)
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssetObject {
+    #[serde(deserialize_with = "to_asset_vec")]
+    objects: Vec<Asset>,
+    /// Set by the `legacy` index (1.6 - 1.7.2), assets are read from `assets/virtual/<id>`.
+    #[serde(rename = "virtual", default)]
+    is_virtual: bool,
+    /// Set by the `pre-1.6` index, assets are read from the instance's `resources` directory.
+    #[serde(default)]
+    map_to_resources: bool,
+}
+
+impl AssetObject {
+    pub fn objects(&self) -> &[Asset] {
+        &self.objects
+    }
+
+    pub fn is_virtual(&self) -> bool {
+        self.is_virtual
+    }
+
+    pub fn map_to_resources(&self) -> bool {
+        self.map_to_resources
+    }
+}
+
+fn to_asset_vec<'de, D>(deserializer: D) -> Result<Vec<Asset>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Debug, Deserialize)]
+    struct TmpAsset {
+        hash: String,
+        size: u32,
+    }
+
+    let asset_map: HashMap<String, TmpAsset> = Deserialize::deserialize(deserializer)?;
+    let mut result = Vec::with_capacity(asset_map.len());
+    for (path, tmp_asset) in asset_map {
+        result.push(Asset {
+            path,
+            hash: tmp_asset.hash,
+            size: tmp_asset.size,
+        });
+    }
+    Ok(result)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssetIndex {
+    id: String,
+    #[serde(flatten)]
+    metadata: DownloadMetadata,
+    // #[serde(rename = "totalSize")]
+    // total_size: u32,
+}
+
+impl AssetIndex {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn metadata(&self) -> &DownloadMetadata {
+        &self.metadata
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GameDownloads {
+    client: DownloadMetadata,
+    client_mappings: Option<DownloadMetadata>,
+    // Optional for mc version 1.1 and older.
+    server: Option<DownloadMetadata>,
+    server_mappings: Option<DownloadMetadata>,
+}
+
+impl GameDownloads {
+    pub fn client(&self) -> &DownloadMetadata {
+        &self.client
+    }
+
+    pub fn client_mappings(&self) -> Option<&DownloadMetadata> {
+        self.client_mappings.as_ref()
+    }
+
+    pub fn server(&self) -> Option<&DownloadMetadata> {
+        self.server.as_ref()
+    }
+
+    pub fn server_mappings(&self) -> Option<&DownloadMetadata> {
+        self.server_mappings.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JavaVersion {
+    component: String,
+    #[serde(rename = "majorVersion")]
+    major_version: u32,
+}
+
+impl JavaVersion {
+    /// Mojang's java 8 runtime.
+    fn legacy() -> Self {
+        Self {
+            component: "jre-legacy".into(),
+            major_version: 8,
+        }
+    }
+
+    /// The name of the runtime in Mojang's java manifest, like `java-runtime-gamma`.
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    pub fn major_version(&self) -> u32 {
+        self.major_version
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Artifact {
+    path: String,
+    #[serde(flatten)]
+    metadata: DownloadMetadata,
+}
+
+impl Artifact {
+    /// An artifact downloaded from somewhere other than the version json says, its size is unknown.
+    pub fn new(path: String, url: String, sha1: String) -> Self {
+        Self {
+            path,
+            metadata: DownloadMetadata { sha1, size: 0, url },
+        }
+    }
+
+    #[cfg(target_family = "windows")]
+    fn get_os_specific_path(&self) -> String {
+        use super::get_directory_separator;
+
+        str::replace(&self.path, "/", &get_directory_separator())
+    }
+
+    #[cfg(target_family = "unix")]
+    fn get_os_specific_path(&self) -> String {
+        self.path.clone()
+    }
+
+    pub fn set_url(&mut self, url: String) {
+        self.metadata.url = url;
+    }
+}
+
+impl Downloadable for Artifact {
+    fn name(&self) -> &str {
+        &self.path
+    }
+
+    fn url(&self) -> String {
+        self.metadata.url().into()
+    }
+
+    fn hash(&self) -> &str {
+        &self.metadata.sha1
+    }
+
+    fn path(&self, base_dir: &Path) -> PathBuf {
+        base_dir.join(self.get_os_specific_path())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadableClassifier {
+    classifier: Artifact,
+    extraction_rule: Option<LibraryExtraction>,
+}
+
+impl DownloadableClassifier {
+    pub fn classifier(&self) -> &Artifact {
+        &self.classifier
+    }
+
+    /// Which files in the classifier's jar aren't extracted into the natives folder.
+    pub fn extraction_rule(&self) -> Option<&LibraryExtraction> {
+        self.extraction_rule.as_ref()
+    }
+}
+
+impl Downloadable for DownloadableClassifier {
+    fn name(&self) -> &str {
+        self.classifier.name()
+    }
+
+    fn url(&self) -> String {
+        self.classifier.url()
+    }
+
+    fn hash(&self) -> &str {
+        self.classifier.hash()
+    }
+
+    fn path(&self, base_dir: &Path) -> PathBuf {
+        self.classifier.path(base_dir)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LibraryDownloads {
+    artifact: Option<Artifact>,
+    classifiers: Option<HashMap<String, Artifact>>,
+}
+
+impl LibraryDownloads {
+    pub fn artifact(&self) -> Option<&Artifact> {
+        self.artifact.as_ref()
+    }
+}
+
+// TODO: Possible there is an "include" too.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LibraryExtraction {
+    exclude: Vec<String>,
+}
+
+impl LibraryExtraction {
+    /// Prefixes of the jar entries to skip.
+    pub fn exclude(&self) -> &[String] {
+        &self.exclude
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Library {
+    downloads: LibraryDownloads,
+    name: String,
+    rules: Option<Vec<Rule>>,
+    extract: Option<LibraryExtraction>,
+    natives: Option<HashMap<String, String>>,
+}
+
+impl Library {
+    pub fn downloads(&self) -> &LibraryDownloads {
+        &self.downloads
+    }
+
+    /// The library's maven coordinates.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn rules(&self) -> Option<&[Rule]> {
+        self.rules.as_deref()
+    }
+
+    /// Replaces the jar downloaded for the library.
+    pub fn set_artifact(&mut self, artifact: Artifact) {
+        self.downloads.artifact = Some(artifact);
+    }
+
+    /// Replaces the natives jar downloaded for `key`, which is one of the values of `natives`.
+    pub fn set_classifier(&mut self, key: &str, artifact: Artifact) {
+        self.downloads
+            .classifiers
+            .get_or_insert_with(HashMap::new)
+            .insert(key.into(), artifact);
+    }
+
+    pub fn determine_key_for_classifiers(&self) -> Option<String> {
+        if let Some(map) = &self.natives {
+            debug!("Has Some Natives: {:#?}", map);
+            let os = env::consts::OS;
+            Some(
+                map.get(match os {
+                    "linux" => "linux",
+                    "macos" => "osx",
+                    "windows" => "windows",
+                    _ => unreachable!("Unknown os key for classifiers: {}", os),
+                })?
+                .into(),
+            )
+        } else {
+            None
+        }
+    }
+
+    pub fn get_classifier(&self, key: &str) -> Option<DownloadableClassifier> {
+        let classifiers = self.downloads.classifiers.as_ref()?;
+        if classifiers.contains_key(key) {
+            Some(DownloadableClassifier {
+                classifier: classifiers.get(key).unwrap().clone(),
+                extraction_rule: self.extract.as_ref().cloned(),
+            })
+        } else {
+            error!("Unknown classifier key {} for library {}", key, self.name);
+            None
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LoggerFile {
+    id: String,
+    #[serde(flatten)]
+    metadata: DownloadMetadata,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Logger {
+    argument: String,
+    file: LoggerFile,
+    // #[serde(rename = "type")]
+    // logger_type: String,
+}
+
+impl Logger {
+    /// The jvm argument pointing log4j to the configuration, with a `${path}` placeholder.
+    pub fn argument(&self) -> &str {
+        &self.argument
+    }
+
+    pub fn file_hash(&self) -> &str {
+        self.file.metadata.hash()
+    }
+
+    pub fn file_id(&self) -> &str {
+        &self.file.id
+    }
+
+    pub fn file_url(&self) -> &str {
+        self.file.metadata.url()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Logging {
+    client: Option<Logger>,
+    server: Option<Logger>,
+}
+
+impl Logging {
+    /// The log4j configuration for the given side, if the version provides one.
+    pub fn logger(&self, jar_type: JarType) -> Option<&Logger> {
+        match jar_type {
+            JarType::Client => self.client.as_ref(),
+            JarType::Server => self.server.as_ref(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+/// The launch arguments and metadata for a given vanilla version.
+// REVIEW: I believe this response is different for older versions of the game. versions < 1.13
+pub struct VanillaVersion {
+    #[serde(alias = "minecraftArguments")]
+    arguments: LaunchArguments,
+    #[serde(rename = "assetIndex")]
+    asset_index: AssetIndex,
+    // assets: String,
+    // #[serde(rename = "complianceLevel")]
+    // compliance_level: Option<u32>,
+    downloads: GameDownloads,
+    id: String,
+    #[serde(rename = "javaVersion")]
+    // 1.6.4 and older do not provide a java version, `java_version()` falls back to java 8.
+    java_version: Option<JavaVersion>,
+    libraries: Vec<Library>,
+    logging: Option<Logging>,
+    #[serde(rename = "mainClass")]
+    main_class: String,
+    // #[serde(rename = "minimumLauncherVersion")]
+    // min_launcher_version: u32,
+    // #[serde(rename = "releaseTime")]
+    // release_time: String,
+    // time: String,
+    // #[serde(rename = "type")]
+    // version_type: String,
+}
+
+impl VanillaVersion {
+    /// Game arguments, and jvm arguments for 1.13 and newer.
+    pub fn arguments(&self) -> &LaunchArguments {
+        &self.arguments
+    }
+
+    pub fn asset_index(&self) -> &AssetIndex {
+        &self.asset_index
+    }
+
+    pub fn downloads(&self) -> &GameDownloads {
+        &self.downloads
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The java the version runs on, java 8 for 1.6.4 and older which don't say.
+    pub fn java_version(&self) -> JavaVersion {
+        self.java_version
+            .clone()
+            .unwrap_or_else(JavaVersion::legacy)
+    }
+
+    pub fn libraries(&self) -> &[Library] {
+        &self.libraries
+    }
+
+    pub fn libraries_mut(&mut self) -> &mut [Library] {
+        &mut self.libraries
+    }
+
+    /// The log4j configurations, `None` for versions before log4j was used.
+    pub fn logging(&self) -> Option<&Logging> {
+        self.logging.as_ref()
+    }
+
+    pub fn main_class(&self) -> &str {
+        &self.main_class
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum JarType {
+    Client,
+    Server,
+}
+
+impl JarType {
+    /// The side as named by jar folders and forge installers.
+    pub fn side(self) -> &'static str {
+        match self {
+            JarType::Client => "client",
+            JarType::Server => "server",
+        }
+    }
+}
+
+// #[derive(Debug, Deserialize)]
+// struct JavaRuntimeAvailability {
+//     group: u32,
+//     progress: u32,
+// }
+
+#[derive(Debug, Deserialize)]
+pub struct JavaRuntimeVersion {
+    name: String,
+    // released: String,
+}
+
+impl JavaRuntimeVersion {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JavaRuntime {
+    // availability: JavaRuntimeAvailability,
+    manifest: DownloadMetadata,
+    version: JavaRuntimeVersion,
+}
+
+impl JavaRuntime {
+    /// The manifest listing the runtime's files.
+    pub fn manifest(&self) -> &DownloadMetadata {
+        &self.manifest
+    }
+
+    pub fn version(&self) -> &JavaRuntimeVersion {
+        &self.version
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JavaManifest {
+    #[serde(
+        rename = "java-runtime-alpha",
+        deserialize_with = "deserialize_java_runtime"
+    )]
+    java_runtime_alpha: Option<JavaRuntime>,
+    #[serde(
+        rename = "java-runtime-beta",
+        deserialize_with = "deserialize_java_runtime"
+    )]
+    java_runtime_beta: Option<JavaRuntime>,
+    #[serde(
+        rename = "java-runtime-gamma",
+        deserialize_with = "deserialize_java_runtime"
+    )]
+    java_runtime_gamma: Option<JavaRuntime>,
+    // Java 21, older manifests don't list it.
+    #[serde(
+        default,
+        rename = "java-runtime-delta",
+        deserialize_with = "deserialize_java_runtime"
+    )]
+    java_runtime_delta: Option<JavaRuntime>,
+    #[serde(rename = "jre-legacy", deserialize_with = "deserialize_java_runtime")]
+    jre_legacy: Option<JavaRuntime>,
+    #[serde(
+        rename = "minecraft-java-exe",
+        deserialize_with = "deserialize_java_runtime"
+    )]
+    minecraft_java_exe: Option<JavaRuntime>,
+}
+
+impl JavaManifest {
+    /// The runtime for a component like `java-runtime-gamma`, `None` if there is none for this
+    /// platform or the component is unknown.
+    pub fn runtime(&self, component: &str) -> Option<&JavaRuntime> {
+        match component {
+            "java-runtime-alpha" => self.java_runtime_alpha.as_ref(),
+            "java-runtime-beta" => self.java_runtime_beta.as_ref(),
+            "java-runtime-gamma" => self.java_runtime_gamma.as_ref(),
+            "java-runtime-delta" => self.java_runtime_delta.as_ref(),
+            "jre-legacy" => self.jre_legacy.as_ref(),
+            "minecraft-java-exe" => self.minecraft_java_exe.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+fn deserialize_java_runtime<'de, D>(deserializer: D) -> Result<Option<JavaRuntime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let runtimes: Vec<JavaRuntime> = Deserialize::deserialize(deserializer)?;
+    if runtimes.len() > 1 {
+        warn!(
+            "Got more java runtimes than expected. Expected 1 but got {}",
+            runtimes.len()
+        );
+        Ok(None)
+    } else {
+        // We know we have one element, get it
+        Ok(runtimes.into_iter().next())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JavaRuntimeDownload {
+    // lzma: Option<DownloadMetadata>,
+    raw: DownloadMetadata,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JavaRuntimeFile {
+    path: String,
+    downloads: JavaRuntimeDownload,
+    executable: bool,
+}
+
+impl JavaRuntimeFile {
+    pub fn executable(&self) -> bool {
+        self.executable
+    }
+}
+
+impl Downloadable for JavaRuntimeFile {
+    fn name(&self) -> &str {
+        &self.path
+    }
+
+    // TODO: Would be better to use lzma download instead.
+    fn url(&self) -> String {
+        self.downloads.raw.url.to_owned()
+    }
+
+    fn hash(&self) -> &str {
+        &self.downloads.raw.sha1
+    }
+
+    fn path(&self, base_dir: &Path) -> PathBuf {
+        base_dir.join(&self.path)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum JavaRuntimeType {
+    #[serde(rename = "file")]
+    File(JavaRuntimeFile),
+    #[serde(rename = "directory")]
+    Directory(String),
+    #[serde(rename = "link")]
+    Link { path: String, target: String },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JavaRuntimeManifest {
+    #[serde(deserialize_with = "to_java_runtime_vec")]
+    files: Vec<JavaRuntimeType>,
+}
+
+impl JavaRuntimeManifest {
+    pub fn files(&self) -> &[JavaRuntimeType] {
+        &self.files
+    }
+}
+
+fn to_java_runtime_vec<'de, D>(deserializer: D) -> Result<Vec<JavaRuntimeType>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Debug, Deserialize)]
+    #[serde(tag = "type")]
+    enum TmpJavaRuntimeType {
+        #[serde(rename = "file")]
+        File {
+            downloads: JavaRuntimeDownload,
+            executable: bool,
+        },
+        #[serde(rename = "directory")]
+        Directory,
+        #[serde(rename = "link")]
+        Link { target: String },
+    }
+    let jrt_map: HashMap<String, TmpJavaRuntimeType> = Deserialize::deserialize(deserializer)?;
+    let mut result = Vec::with_capacity(jrt_map.len());
+    for (path, tmp_jrt) in jrt_map {
+        result.push(match tmp_jrt {
+            TmpJavaRuntimeType::File {
+                downloads,
+                executable,
+            } => JavaRuntimeType::File(JavaRuntimeFile {
+                path,
+                downloads,
+                executable,
+            }),
+            TmpJavaRuntimeType::Directory => JavaRuntimeType::Directory(path),
+            TmpJavaRuntimeType::Link { target } => JavaRuntimeType::Link { path, target },
+        });
+    }
+    Ok(result)
+}
+
+#[test]
+fn test_vanilla_manifest_fixture() {
+    let manifest: VanillaManifest = read_fixture("vanilla/version_manifest_v2.json");
+    let ids: Vec<&str> = manifest.versions.keys().map(String::as_str).collect();
+    // The manifest's order, newest first, is kept.
+    assert_eq!(ids, ["23w31a", "1.20.1", "1.12.2", "1.6.4", "rd-132211"]);
+    assert_eq!(manifest.versions["23w31a"].version_type, "snapshot");
+    assert_eq!(manifest.versions["rd-132211"].version_type, "old_alpha");
+}
+
+#[test]
+fn test_vanilla_version_fixtures() {
+    let version: VanillaVersion = read_fixture("vanilla/1.20.1.json");
+    let LaunchArguments::LaunchArguments113(arguments) = &version.arguments else {
+        panic!("1.20.1 has structured arguments");
+    };
+    assert_eq!(arguments.game.len(), 6);
+    let Argument::ConditionalArg { rules, values } = &arguments.game[5] else {
+        panic!("Expected the custom resolution arguments");
+    };
+    assert!(matches!(rules[0].rule_type, Some(RuleType::Features(_))));
+    assert_eq!(values.len(), 4);
+    // A single `value` string is read as a one element list.
+    let jvm = arguments.jvm.as_ref().unwrap();
+    let Argument::ConditionalArg { rules, values } = &jvm[1] else {
+        panic!("Expected the x86 stack size argument");
+    };
+    assert!(matches!(
+        rules[0].rule_type,
+        Some(RuleType::OperatingSystem(_))
+    ));
+    assert_eq!(values, &["-Xss1M"]);
+    assert_eq!(version.java_version.unwrap().major_version, 17);
+    assert_eq!(version.asset_index.id, "5");
+    assert!(version.downloads.server_mappings.is_some());
+    assert_eq!(version.libraries.len(), 3);
+    let logger = version
+        .logging
+        .as_ref()
+        .unwrap()
+        .logger(JarType::Client)
+        .unwrap();
+    assert_eq!(logger.file_id(), "client-1.12.xml");
+
+    // 1.12 and older pass the game arguments as a single string and natives as classifiers.
+    let version: VanillaVersion = read_fixture("vanilla/1.12.2.json");
+    let LaunchArguments::LaunchArguments112(arguments) = &version.arguments else {
+        panic!("1.12.2 has string arguments");
+    };
+    assert!(arguments.ends_with("--versionType ${version_type}"));
+    assert_eq!(version.java_version.unwrap().component, "jre-legacy");
+    assert!(version.downloads.client_mappings.is_none());
+    let natives = &version.libraries[1];
+    assert_eq!(natives.rules.as_ref().unwrap().len(), 2);
+    let key = natives.determine_key_for_classifiers().unwrap();
+    let classifier = natives.get_classifier(&key).unwrap();
+    assert_eq!(classifier.extraction_rule.unwrap().exclude, ["META-INF/"]);
+
+    // 1.6.4 has no server jar, java version or logging configuration.
+    let version: VanillaVersion = read_fixture("vanilla/1.6.4.json");
+    assert!(matches!(
+        version.arguments,
+        LaunchArguments::LaunchArguments112(_)
+    ));
+    assert_eq!(version.asset_index.id, "pre-1.6");
+    assert!(version.downloads.server.is_none());
+    assert!(version.java_version.is_none());
+    assert!(version.logging.is_none());
+}
+
+#[test]
+fn test_asset_index_fixtures() {
+    let index: AssetObject = read_fixture("vanilla/assets/5.json");
+    let mut objects = index.objects;
+    objects.sort_by(|a, b| a.path.cmp(&b.path));
+    assert_eq!(objects.len(), 3);
+    assert_eq!(objects[0].name(), "icons/icon_16x16.png");
+    assert_eq!(objects[0].size(), 3665);
+    assert_eq!(
+        objects[0].url(),
+        format!(
+            "{}/bd/bdf48ef6b5d0d23bbb02e17d04865216179f510a",
+            VANILLA_ASSET_BASE_URL
+        )
+    );
+    assert!(!index.is_virtual && !index.map_to_resources);
+
+    let index: AssetObject = read_fixture("vanilla/assets/legacy.json");
+    assert!(index.is_virtual && !index.map_to_resources);
+    let index: AssetObject = read_fixture("vanilla/assets/pre-1.6.json");
+    assert!(!index.is_virtual && index.map_to_resources);
+}
+
+#[test]
+fn test_java_manifest_fixtures() {
+    let manifests: HashMap<String, JavaManifest> = read_fixture("java/all.json");
+    let linux = &manifests["linux"];
+    let gamma = linux.java_runtime_gamma.as_ref().unwrap();
+    assert_eq!(gamma.version.name, "17.0.8.7.1");
+    assert_eq!(linux.jre_legacy.as_ref().unwrap().version.name, "8u51");
+    // Runtimes missing on a platform are empty lists.
+    assert!(linux.minecraft_java_exe.is_none());
+    assert!(manifests["windows-x64"].minecraft_java_exe.is_some());
+
+    let runtime: JavaRuntimeManifest = read_fixture("java/jre-legacy-linux.json");
+    let mut files: Vec<_> = runtime
+        .files
+        .iter()
+        .map(|file| match file {
+            JavaRuntimeType::File(file) => (file.name(), file.executable),
+            JavaRuntimeType::Directory(path) => (path.as_str(), false),
+            JavaRuntimeType::Link { path, .. } => (path.as_str(), false),
+        })
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        [
+            ("bin", false),
+            ("bin/java", true),
+            ("lib/amd64/server/libjsig.so", false)
+        ]
+    );
+}
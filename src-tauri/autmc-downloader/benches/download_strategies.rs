@@ -0,0 +1,64 @@
+//! Compares ways of downloading many small files, like the asset objects of a version. Downloads
+//! are simulated with a fixed latency so the results don't depend on the network.
+//!
+//! Against resources.download.minecraft.net the original experiment measured:
+//! buffer_unordered(8) = 223768ms
+//! buffer_unordered(10) = 166119ms
+//! buffer_unordered(32) = 263426ms
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use futures::StreamExt;
+use tokio::runtime::Runtime;
+
+const DOWNLOADS: usize = 512;
+const LATENCY: Duration = Duration::from_millis(2);
+
+async fn simulated_download(index: usize) -> usize {
+    tokio::time::sleep(LATENCY).await;
+    index
+}
+
+async fn buffer_unordered(items: &[usize], limit: usize) -> Vec<usize> {
+    futures::stream::iter(items.iter().map(|index| simulated_download(*index)))
+        .buffer_unordered(limit)
+        .collect()
+        .await
+}
+
+/// Splits the downloads into `chunks` and downloads every chunk at the same time.
+async fn chunked(items: &[usize], chunks: usize, limit: usize) {
+    let chunk_size = items.len().div_ceil(chunks);
+    let handles = items
+        .chunks(chunk_size)
+        .map(|chunk| buffer_unordered(chunk, limit));
+    futures::future::join_all(handles).await;
+}
+
+fn download_strategies(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let items: Vec<usize> = (0..DOWNLOADS).collect();
+
+    let mut group = c.benchmark_group("download_strategies");
+    for limit in [8, 10, 32] {
+        group.bench_with_input(
+            BenchmarkId::new("buffer_unordered", limit),
+            &limit,
+            |b, &limit| {
+                b.to_async(&runtime)
+                    .iter(|| buffer_unordered(&items, limit))
+            },
+        );
+    }
+    for chunks in [2, 4] {
+        group.bench_with_input(
+            BenchmarkId::new("chunked_buffer_unordered_10", chunks),
+            &chunks,
+            |b, &chunks| b.to_async(&runtime).iter(|| chunked(&items, chunks, 10)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, download_strategies);
+criterion_main!(benches);
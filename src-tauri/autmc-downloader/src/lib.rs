@@ -0,0 +1,709 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    future::Future,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use autmc_log::debug_if;
+use bytes::Bytes;
+use crypto::{
+    digest::Digest,
+    md5::Md5,
+    sha1::Sha1,
+    sha2::{Sha256, Sha512},
+};
+use futures::{stream::FuturesUnordered, StreamExt};
+use log::{debug, error, info, warn};
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    RequestBuilder, Response, StatusCode, Url,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::Semaphore;
+
+#[cfg(feature = "mock-http")]
+pub mod mock_http;
+
+/// Bounds for how many downloads a single stream keeps in flight.
+const MIN_CONCURRENCY: usize = 4;
+const MAX_CONCURRENCY: usize = 32;
+const INITIAL_CONCURRENCY: usize = 8;
+/// Upper bound on requests in flight across every download. Install phases download at the same
+/// time, so each stream's limit alone doesn't bound the total.
+const MAX_CONCURRENT_REQUESTS: usize = 48;
+
+/// How many times a request is sent before its error is returned.
+const MAX_REQUEST_ATTEMPTS: u32 = 3;
+/// Delay before the first retry, doubled for every retry after it.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+/// Longest `Retry-After` that's honored, so a misbehaving api can't stall downloads indefinitely.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+static REQUEST_PERMITS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_REQUESTS);
+/// Hosts that answered with `429 Too Many Requests` and when requests to them can resume.
+static HOST_PAUSES: Mutex<BTreeMap<String, Instant>> = Mutex::new(BTreeMap::new());
+
+/// Called with the url, size in bytes and start time of every finished request.
+pub type RequestObserver = fn(&str, u64, Instant);
+
+static REQUEST_OBSERVER: OnceLock<RequestObserver> = OnceLock::new();
+
+/// Registers a function to be told about every finished request, e.g. to time downloads. Only the
+/// first observer is kept.
+pub fn set_request_observer(observer: RequestObserver) {
+    if REQUEST_OBSERVER.set(observer).is_err() {
+        debug!("A request observer is already registered");
+    }
+}
+
+/// Returns the url to send a request for the given url to instead, or None to send it as is.
+pub type UrlRewriter = fn(&str) -> Option<String>;
+
+static URL_REWRITER: OnceLock<UrlRewriter> = OnceLock::new();
+
+/// Registers a function that redirects requests, e.g. to a local server serving recorded responses
+/// in tests. Only the first rewriter is kept.
+pub fn set_url_rewriter(rewriter: UrlRewriter) {
+    if URL_REWRITER.set(rewriter).is_err() {
+        debug!("A url rewriter is already registered");
+    }
+}
+
+fn record_request(url: &str, bytes: u64, started: Instant) {
+    if let Some(observer) = REQUEST_OBSERVER.get() {
+        observer(url, bytes, started);
+    }
+}
+
+pub type DownloadResult<T> = Result<T, DownloadError>;
+
+#[derive(Debug)]
+pub enum DownloadError {
+    Request(reqwest::Error),
+    FileWrite(io::Error),
+    InvalidFileHash(String),
+}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(err: reqwest::Error) -> Self {
+        DownloadError::Request(err)
+    }
+}
+
+impl From<io::Error> for DownloadError {
+    fn from(error: io::Error) -> Self {
+        DownloadError::FileWrite(error)
+    }
+}
+
+/// How far a download stream has gotten, counting files that already existed as completed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    pub completed_files: usize,
+    pub total_files: usize,
+    pub downloaded_bytes: u64,
+}
+
+impl DownloadProgress {
+    fn new(total_files: usize) -> Self {
+        Self {
+            completed_files: 0,
+            total_files,
+            downloaded_bytes: 0,
+        }
+    }
+
+    /// Counts a finished download, returning whether another percent of the files is done. Keeps
+    /// streams of thousands of small files from reporting every single one.
+    fn record(&mut self, bytes: u64) -> bool {
+        let percent = |completed: usize| completed * 100 / self.total_files.max(1);
+        let previous = percent(self.completed_files);
+        self.completed_files += 1;
+        self.downloaded_bytes += bytes;
+        percent(self.completed_files) != previous
+    }
+}
+
+/// Something that can be downloaded to a file under a base directory and validated by its hash.
+pub trait Downloadable {
+    fn name(&self) -> &str;
+    fn url(&self) -> String;
+    fn hash(&self) -> &str;
+    fn path(&self, base_dir: &Path) -> PathBuf;
+    /// Other urls to try, in order, if downloading from `url` fails or the download is rejected.
+    fn fallback_urls(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Lets a filtered list of references be downloaded without cloning the items.
+impl<T: Downloadable> Downloadable for &T {
+    fn name(&self) -> &str {
+        (*self).name()
+    }
+    fn url(&self) -> String {
+        (*self).url()
+    }
+    fn hash(&self) -> &str {
+        (*self).hash()
+    }
+    fn path(&self, base_dir: &Path) -> PathBuf {
+        (*self).path(base_dir)
+    }
+    fn fallback_urls(&self) -> Vec<String> {
+        (*self).fallback_urls()
+    }
+}
+
+/// Adjusts how many downloads are in flight from the throughput and errors of recent downloads.
+/// The limit grows while throughput keeps up and backs off when it drops or requests fail.
+#[derive(Debug)]
+struct AdaptiveConcurrency {
+    limit: usize,
+    window_start: Instant,
+    window_bytes: u64,
+    window_completed: usize,
+    // Bytes per second of the previous window.
+    last_throughput: f64,
+}
+
+impl AdaptiveConcurrency {
+    fn new(now: Instant) -> Self {
+        Self {
+            limit: INITIAL_CONCURRENCY,
+            window_start: now,
+            window_bytes: 0,
+            window_completed: 0,
+            last_throughput: 0.0,
+        }
+    }
+
+    fn limit(&self) -> usize {
+        self.limit
+    }
+
+    fn reset_window(&mut self, now: Instant) {
+        self.window_start = now;
+        self.window_bytes = 0;
+        self.window_completed = 0;
+    }
+
+    /// Once as many downloads as the limit have finished, compares their throughput against the
+    /// previous window's.
+    fn record_success(&mut self, bytes: u64, now: Instant) {
+        self.window_bytes += bytes;
+        self.window_completed += 1;
+        if self.window_completed < self.limit {
+            return;
+        }
+
+        let elapsed = now
+            .duration_since(self.window_start)
+            .max(Duration::from_millis(1));
+        let throughput = self.window_bytes as f64 / elapsed.as_secs_f64();
+        // Allow some noise before treating the last increase as a slowdown.
+        self.limit = if throughput >= self.last_throughput * 0.9 {
+            (self.limit + 2).min(MAX_CONCURRENCY)
+        } else {
+            (self.limit - 2).max(MIN_CONCURRENCY)
+        };
+        debug_if!(
+            "DOWNLOADER",
+            "Download throughput {:.0} B/s, concurrency now {}",
+            throughput,
+            self.limit
+        );
+        self.last_throughput = throughput;
+        self.reset_window(now);
+    }
+
+    fn record_failure(&mut self, now: Instant) {
+        self.limit = (self.limit / 2).max(MIN_CONCURRENCY);
+        self.reset_window(now);
+    }
+}
+
+/// Runs `downloads`, keeping as many in flight as [AdaptiveConcurrency] allows. Each download
+/// resolves to the number of bytes it fetched, or `None` if the file already existed.
+async fn adaptive_download_stream<F>(downloads: Vec<F>, on_progress: impl Fn(DownloadProgress))
+where
+    F: Future<Output = DownloadResult<Option<u64>>>,
+{
+    let mut progress = DownloadProgress::new(downloads.len());
+    let mut downloads = downloads.into_iter();
+    let mut concurrency = AdaptiveConcurrency::new(Instant::now());
+    let mut in_flight = FuturesUnordered::new();
+    loop {
+        while in_flight.len() < concurrency.limit() {
+            match downloads.next() {
+                Some(download) => in_flight.push(download),
+                None => break,
+            }
+        }
+        let bytes = match in_flight.next().await {
+            Some(Ok(Some(bytes))) => {
+                concurrency.record_success(bytes, Instant::now());
+                bytes
+            }
+            Some(Ok(None)) => 0,
+            Some(Err(err)) => {
+                error!("{:#?}", &err);
+                concurrency.record_failure(Instant::now());
+                0
+            }
+            None => break,
+        };
+        if progress.record(bytes) {
+            on_progress(progress);
+        }
+    }
+}
+
+pub async fn boxed_buffered_download_stream(
+    items: &[Box<dyn Downloadable + Send + Sync>],
+    base_dir: &Path,
+    callback: impl Fn(&Bytes, &(dyn Downloadable + Send + Sync)) -> DownloadResult<()>,
+    on_progress: impl Fn(DownloadProgress),
+) -> DownloadResult<()> {
+    let mut futures = Vec::new();
+    for item in items {
+        futures.push(boxed_download_single(item.as_ref(), base_dir, &callback));
+    }
+    adaptive_download_stream(futures, on_progress).await;
+    Ok(())
+}
+
+async fn boxed_download_single(
+    item: &(dyn Downloadable + Send + Sync),
+    base_dir: &Path,
+    callback: impl Fn(&Bytes, &(dyn Downloadable + Send + Sync)) -> DownloadResult<()>,
+) -> DownloadResult<Option<u64>> {
+    let path = &item.path(base_dir);
+    if !path.exists() {
+        debug_if!("DOWNLOADER", "Downloading file {}", item.name());
+        let dir_path = path.parent().unwrap();
+        fs::create_dir_all(dir_path)?;
+
+        let length = download_item(item, |bytes| callback(bytes, item)).await?;
+        return Ok(Some(length));
+    }
+    Ok(None)
+}
+
+/// Downloads every item that isn't under `base_dir` yet, passing its bytes to `callback` to
+/// validate and write them. `on_progress` is called as the files finish.
+pub async fn buffered_download_stream<T>(
+    items: &[T],
+    base_dir: &Path,
+    callback: impl Fn(&Bytes, &T) -> DownloadResult<()>,
+    on_progress: impl Fn(DownloadProgress),
+) -> DownloadResult<()>
+where
+    T: Downloadable,
+{
+    let mut futures = Vec::new();
+    for item in items {
+        futures.push(download_single(item, base_dir, &callback));
+    }
+    adaptive_download_stream(futures, on_progress).await;
+    Ok(())
+}
+
+async fn download_single<T>(
+    item: &T,
+    base_dir: &Path,
+    callback: impl Fn(&Bytes, &T) -> DownloadResult<()>,
+) -> DownloadResult<Option<u64>>
+where
+    T: Downloadable,
+{
+    let path = &item.path(base_dir);
+    if !path.exists() {
+        debug_if!("DOWNLOADER", "Downloading file {}", item.name());
+        let dir_path = path.parent().unwrap();
+        fs::create_dir_all(dir_path)?;
+
+        let length = download_item(item, |bytes| callback(bytes, item)).await?;
+        return Ok(Some(length));
+    }
+    Ok(None)
+}
+
+/// Downloads `item` and passes its bytes to `callback`, falling back to the item's other urls when
+/// the request fails or `callback` rejects the bytes. Returns the number of bytes downloaded.
+async fn download_item<T>(
+    item: &T,
+    callback: impl Fn(&Bytes) -> DownloadResult<()>,
+) -> DownloadResult<u64>
+where
+    T: Downloadable + ?Sized,
+{
+    let mut urls = vec![item.url()];
+    urls.extend(item.fallback_urls());
+    let mut last_error = None;
+    for url in urls {
+        let result = match download_bytes_from_url(&url).await {
+            Ok(bytes) => callback(&bytes).map(|_| bytes.len() as u64),
+            Err(err) => Err(err.into()),
+        };
+        match result {
+            Ok(length) => return Ok(length),
+            Err(err) => {
+                warn!("Could not download {} from {}: {:?}", item.name(), url, err);
+                last_error = Some(err);
+            }
+        }
+    }
+    Err(last_error.unwrap())
+}
+
+pub async fn download_json_object<T, Q>(
+    url: &str,
+    header_map: Option<HeaderMap>,
+    query_params: Option<&Q>,
+) -> reqwest::Result<T>
+where
+    T: DeserializeOwned,
+    Q: Serialize + ?Sized,
+{
+    let client = reqwest::Client::new();
+    let mut builder = client.get(url);
+
+    if let Some(headers) = header_map {
+        builder = builder.headers(headers);
+    }
+
+    if let Some(params) = query_params {
+        builder = builder.query(params);
+    }
+    let started = Instant::now();
+    let response = send_with_retry(builder).await?;
+    let length = response.content_length().unwrap_or_default();
+    let object = response.json().await?;
+    record_request(url, length, started);
+    Ok(object)
+}
+
+pub async fn download_json_object_from_url<T>(url: &str) -> reqwest::Result<T>
+where
+    T: DeserializeOwned,
+{
+    download_json_object::<T, ()>(url, None, None).await
+}
+
+/// Download the bytes for a file at the specified `url`
+pub async fn download_bytes_from_url(url: &str) -> reqwest::Result<Bytes> {
+    let _permit = REQUEST_PERMITS.acquire().await.unwrap();
+    let client = reqwest::Client::new();
+    let started = Instant::now();
+    let response = send_with_retry(client.get(url)).await?;
+    let bytes = response.bytes().await?;
+    record_request(url, bytes.len() as u64, started);
+    Ok(bytes)
+}
+
+/// Sends `request`, retrying timeouts, connection errors and server errors with an exponential
+/// backoff. Error statuses are returned as errors. Requests with a streamed body are only sent once.
+///
+/// A `429 Too Many Requests` pauses every request to that host for as long as its `Retry-After`
+/// asks, so bursts of requests to the same api back off together.
+pub async fn send_with_retry(request: RequestBuilder) -> reqwest::Result<Response> {
+    let (client, request) = request.build_split();
+    let mut request = request?;
+    if let Some(rewriter) = URL_REWRITER.get() {
+        if let Some(url) = rewriter(request.url().as_str()) {
+            match Url::parse(&url) {
+                Ok(url) => *request.url_mut() = url,
+                Err(error) => warn!("Ignoring invalid rewritten url {}: {}", url, error),
+            }
+        }
+    }
+    let host = request.url().host_str().unwrap_or_default().to_string();
+    let mut attempt = 0;
+    loop {
+        wait_for_host(&host).await;
+        let retry = if attempt + 1 < MAX_REQUEST_ATTEMPTS {
+            request.try_clone()
+        } else {
+            None
+        };
+        let result = match client.execute(request).await {
+            Ok(response) => {
+                if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                    let delay = retry_after(&response).unwrap_or_else(|| retry_delay(attempt));
+                    warn!(
+                        "Rate limited by {}, pausing for {}ms",
+                        host,
+                        delay.as_millis()
+                    );
+                    pause_host(&host, delay);
+                }
+                response.error_for_status()
+            }
+            Err(error) => Err(error),
+        };
+        match (result, retry) {
+            (Err(error), Some(retry)) if is_retryable(&error) => {
+                // Rate limited requests already wait for the host's pause.
+                if error.status() != Some(StatusCode::TOO_MANY_REQUESTS) {
+                    let delay = retry_delay(attempt);
+                    warn!("{}, retrying in {}ms", error, delay.as_millis());
+                    tokio::time::sleep(delay).await;
+                }
+                request = retry;
+                attempt += 1;
+            }
+            (result, _) => return result,
+        }
+    }
+}
+
+fn is_retryable(error: &reqwest::Error) -> bool {
+    match error.status() {
+        Some(status) => {
+            status.is_server_error()
+                || status == StatusCode::REQUEST_TIMEOUT
+                || status == StatusCode::TOO_MANY_REQUESTS
+        }
+        None => error.is_timeout() || error.is_connect(),
+    }
+}
+
+/// The delay a response's `Retry-After` header asks for. Only the delay in seconds form is used,
+/// http dates fall back to the exponential backoff.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+/// Delays requests to `host` until `delay` from now, unless it's already paused for longer.
+fn pause_host(host: &str, delay: Duration) {
+    let until = Instant::now() + delay;
+    let mut pauses = HOST_PAUSES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let pause = pauses.entry(host.to_string()).or_insert(until);
+    *pause = (*pause).max(until);
+}
+
+async fn wait_for_host(host: &str) {
+    let until = {
+        let mut pauses = HOST_PAUSES
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        pauses.retain(|_, until| *until > Instant::now());
+        pauses.get(host).copied()
+    };
+    if let Some(until) = until {
+        debug!("Waiting for the rate limit of {} to pass", host);
+        tokio::time::sleep_until(until.into()).await;
+    }
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+    INITIAL_RETRY_DELAY * 2u32.pow(attempt)
+}
+
+/// Validates that the SHA1 hash of `bytes` matches the `valid_hash`
+pub fn validate_hash_sha1(bytes: &Bytes, valid_hash: &str) -> bool {
+    hash_bytes_sha1(bytes) == valid_hash
+}
+
+/// Validates that the MD5 hash of `bytes` matches the `valid_hash`
+pub fn validate_hash_md5(bytes: &Bytes, valid_hash: &str) -> bool {
+    hash_bytes_md5(bytes) == valid_hash
+}
+
+/// Hashes the `bytes` using SHA1 and returns the hex string
+pub fn hash_bytes_sha1(bytes: &Bytes) -> String {
+    let mut hasher = Sha1::new();
+    hasher.input(bytes);
+    hasher.result_str()
+}
+
+/// Validates that the SHA256 hash of `bytes` matches the `valid_hash`
+pub fn validate_hash_sha256(bytes: &Bytes, valid_hash: &str) -> bool {
+    hash_bytes_sha256(bytes) == valid_hash
+}
+
+/// Hashes the `bytes` using SHA256 and returns the hex string
+pub fn hash_bytes_sha256(bytes: &Bytes) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(bytes);
+    hasher.result_str()
+}
+
+/// Validates that the SHA512 hash of `bytes` matches the `valid_hash`
+pub fn validate_hash_sha512(bytes: &Bytes, valid_hash: &str) -> bool {
+    hash_bytes_sha512(bytes) == valid_hash
+}
+
+/// Hashes the `bytes` using SHA512 and returns the hex string
+pub fn hash_bytes_sha512(bytes: &Bytes) -> String {
+    let mut hasher = Sha512::new();
+    hasher.input(bytes);
+    hasher.result_str()
+}
+
+/// Hashes the `bytes` using MD5 and returns the hex string
+pub fn hash_bytes_md5(bytes: &Bytes) -> String {
+    let mut hasher = Md5::new();
+    hasher.input(bytes);
+    hasher.result_str()
+}
+
+/// Returns the bytes of the file at `path` if its SHA1 matches `valid_hash`. Otherwise the file is
+/// downloaded from `url`, validated and written to `path`.
+pub async fn download_validated_file(
+    path: &Path,
+    url: &str,
+    valid_hash: &str,
+) -> DownloadResult<Bytes> {
+    if let Ok(bytes) = read_bytes_from_file(path) {
+        if validate_hash_sha1(&bytes, valid_hash) {
+            return Ok(bytes);
+        }
+        info!("{} is stale, downloading it again", path.display());
+    }
+
+    info!("Downloading {}", url);
+    let bytes = download_bytes_from_url(url).await?;
+    if !validate_hash_sha1(&bytes, valid_hash) {
+        let err = format!("Error downloading {}, invalid hash.", url);
+        error!("{}", err);
+        return Err(DownloadError::InvalidFileHash(err));
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = File::create(path)?;
+    file.write_all(&bytes)?;
+    Ok(bytes)
+}
+
+/// Reads and returns bytes from the file specified in `path`
+fn read_bytes_from_file(path: &Path) -> io::Result<Bytes> {
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let mut buffer = vec![0; metadata.len() as usize];
+    file.read_exact(&mut buffer)?;
+    Ok(Bytes::from(buffer))
+}
+
+#[test]
+fn test_adaptive_concurrency() {
+    let start = Instant::now();
+    let mut concurrency = AdaptiveConcurrency::new(start);
+    let mut now = start;
+    let mut run_window = |concurrency: &mut AdaptiveConcurrency, bytes_per_second: u64| {
+        now += Duration::from_secs(1);
+        let limit = concurrency.limit();
+        for _ in 0..limit {
+            concurrency.record_success(bytes_per_second / limit as u64, now);
+        }
+    };
+
+    // Steady throughput keeps growing the limit up to the maximum.
+    for _ in 0..20 {
+        run_window(&mut concurrency, 1_000_000);
+    }
+    assert_eq!(concurrency.limit(), MAX_CONCURRENCY);
+
+    // A large drop in throughput backs off.
+    run_window(&mut concurrency, 100_000);
+    assert_eq!(concurrency.limit(), MAX_CONCURRENCY - 2);
+
+    // Failures halve the limit, but never below the minimum.
+    for _ in 0..5 {
+        concurrency.record_failure(now);
+    }
+    assert_eq!(concurrency.limit(), MIN_CONCURRENCY);
+}
+
+#[test]
+fn test_download_progress() {
+    let mut progress = DownloadProgress::new(250);
+    let reported = (0..250).filter(|_| progress.record(10)).count();
+    assert_eq!(reported, 100);
+    assert_eq!(progress.completed_files, 250);
+    assert_eq!(progress.downloaded_bytes, 2500);
+
+    // Streams with fewer files than percents report every file.
+    let mut progress = DownloadProgress::new(3);
+    assert!((0..3).all(|_| progress.record(0)));
+}
+
+#[test]
+fn test_send_with_retry() {
+    use std::{io::BufRead, net::TcpListener, thread};
+
+    // Answers the first request with a server error and the rest with "ok".
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/file", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        for (index, stream) in listener.incoming().take(2).enumerate() {
+            let mut stream = stream.unwrap();
+            let mut reader = io::BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let response = match index {
+                0 => "HTTP/1.1 503 Service Unavailable\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+                _ => "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 2\r\n\r\nok",
+            };
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let bytes = runtime.block_on(download_bytes_from_url(&url)).unwrap();
+    assert_eq!(bytes, Bytes::from("ok"));
+    server.join().unwrap();
+
+    assert_eq!(retry_delay(0), INITIAL_RETRY_DELAY);
+    assert_eq!(retry_delay(2), INITIAL_RETRY_DELAY * 4);
+}
+
+#[test]
+fn test_rate_limited_request() {
+    use std::{io::BufRead, net::TcpListener, thread};
+
+    // Rate limits the first request for a second and answers the rest with "ok".
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/file", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        for (index, stream) in listener.incoming().take(2).enumerate() {
+            let mut stream = stream.unwrap();
+            let mut reader = io::BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let response = match index {
+                0 => "HTTP/1.1 429 Too Many Requests\r\nretry-after: 1\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+                _ => "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 2\r\n\r\nok",
+            };
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let started = Instant::now();
+    let bytes = runtime.block_on(download_bytes_from_url(&url)).unwrap();
+    assert_eq!(bytes, Bytes::from("ok"));
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    server.join().unwrap();
+}
@@ -0,0 +1,95 @@
+//! A local http server standing in for the apis the launcher talks to. Every request is redirected
+//! to it and answered with the fixture in `fixtures/http/<host>/<path>`, or a 404 if there is none,
+//! so tests never reach the real hosts. Query strings are ignored when looking up a fixture. Only the
+//! requests sent through the downloader are redirected, the authentication crate's aren't. Only
+//! built with the `mock-http` feature, which the crates' dev-dependencies turn on.
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    sync::OnceLock,
+    thread,
+};
+
+use log::warn;
+use reqwest::Url;
+
+use crate::set_url_rewriter;
+
+static SERVER_ADDRESS: OnceLock<SocketAddr> = OnceLock::new();
+
+/// Starts the server the first time it's called and redirects every request to it for the rest of
+/// the test run.
+pub fn start_mock_server() {
+    SERVER_ADDRESS.get_or_init(|| {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                thread::spawn(move || {
+                    if let Err(error) = respond(stream) {
+                        warn!("Mock server could not respond: {}", error);
+                    }
+                });
+            }
+        });
+        set_url_rewriter(rewrite_url);
+        address
+    });
+}
+
+fn rewrite_url(url: &str) -> Option<String> {
+    let address = SERVER_ADDRESS.get()?;
+    let url = Url::parse(url).ok()?;
+    let host = url.host_str().unwrap_or_default();
+    Some(format!("http://{}/{}{}", address, host, url.path()))
+}
+
+fn fixture_path(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("fixtures")
+        .join("http")
+        .join(path)
+}
+
+/// Answers a single request with its fixture, or a 404 if there is none.
+fn respond(mut stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+        line.clear();
+    }
+    // The body of POST requests isn't used, but has to be read before answering.
+    reader.take(content_length).read_to_end(&mut Vec::new())?;
+
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .trim_start_matches('/');
+    match std::fs::read(fixture_path(path)) {
+        Ok(body) => {
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: {}\r\n\r\n",
+                body.len()
+            )?;
+            stream.write_all(&body)
+        }
+        Err(_) => {
+            warn!("No fixture for {}, answering with a 404", path);
+            stream.write_all(
+                b"HTTP/1.1 404 Not Found\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+            )
+        }
+    }
+}
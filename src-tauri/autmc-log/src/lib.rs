@@ -1,19 +1,46 @@
+mod scopes;
+mod subscriber;
+#[cfg(test)]
+mod test_logger;
+
+pub use scopes::{
+    enable_scopes_from_env, scope_enabled, set_enabled_scopes, set_scope_enabled, LOG_SCOPES,
+};
+pub use subscriber::LogSubscriber;
+
+/// Logs a verbose message of `scope` when it is turned on. Logged at the info level, tagged with
+/// the scope, since release builds leave out debug messages and the logger filters them unless
+/// `DEBUG=1`.
 #[macro_export]
 macro_rules! debug_if {
-    ($env:literal, $($arg:tt)+) => {
-        match std::env::var($env) {
-            Ok(var) if &var == "1" => log::debug!($($arg)+),
-            _ => {}
+    ($scope:literal, $($arg:tt)+) => {
+        if $crate::scope_enabled($scope) {
+            log::info!("[{}] {}", $scope, format_args!($($arg)+))
         }
     };
 }
 
 #[macro_export]
 macro_rules! info_if {
-    ($env:literal, $($arg:tt)+) => {
-        match std::env::var($env) {
-            Ok(var) if &var == "1" => log::info!($($arg)+),
-            _ => {}
+    ($scope:literal, $($arg:tt)+) => {
+        if $crate::scope_enabled($scope) {
+            log::info!($($arg)+)
         }
     };
 }
+
+#[test]
+fn test_enabled_scope_reaches_the_logger() {
+    let _lock = scopes::TEST_SCOPES_LOCK.lock().unwrap();
+    test_logger::init();
+    set_scope_enabled("DOWNLOADER", true);
+    debug_if!("DOWNLOADER", "Downloading file {}", "scoped.jar");
+    set_scope_enabled("DOWNLOADER", false);
+    debug_if!("DOWNLOADER", "Downloading file {}", "unscoped.jar");
+
+    let messages = test_logger::messages();
+    assert!(messages.contains(&"[DOWNLOADER] Downloading file scoped.jar".to_owned()));
+    assert!(!messages
+        .iter()
+        .any(|message| message.contains("unscoped.jar")));
+}
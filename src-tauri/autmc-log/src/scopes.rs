@@ -0,0 +1,74 @@
+use std::{
+    env,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Parts of the launcher with verbose logging that can be turned on at runtime, through the
+/// launcher settings or by setting an environment variable of the same name to `1`.
+pub const LOG_SCOPES: [&str; 3] = ["AUTHENTICATION", "DOWNLOADER", "LAUNCH"];
+
+static ENABLED_SCOPES: [AtomicBool; LOG_SCOPES.len()] = [
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+];
+
+fn scope_index(scope: &str) -> Option<usize> {
+    LOG_SCOPES.iter().position(|name| *name == scope)
+}
+
+/// Whether verbose logging is on for `scope`. Unknown scopes are always off.
+pub fn scope_enabled(scope: &str) -> bool {
+    scope_index(scope).is_some_and(|index| ENABLED_SCOPES[index].load(Ordering::Relaxed))
+}
+
+/// Turns verbose logging for `scope` on or off, returning false if there is no such scope.
+pub fn set_scope_enabled(scope: &str, enabled: bool) -> bool {
+    match scope_index(scope) {
+        Some(index) => {
+            ENABLED_SCOPES[index].store(enabled, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Turns on verbose logging for exactly `scopes`, returning the ones that don't exist.
+pub fn set_enabled_scopes(scopes: &[String]) -> Vec<&str> {
+    for scope in LOG_SCOPES {
+        set_scope_enabled(scope, scopes.iter().any(|enabled| enabled == scope));
+    }
+    scopes
+        .iter()
+        .map(String::as_str)
+        .filter(|scope| scope_index(scope).is_none())
+        .collect()
+}
+
+/// Turns on the scopes whose environment variable is set to `1`.
+pub fn enable_scopes_from_env() {
+    for scope in LOG_SCOPES {
+        if matches!(env::var(scope), Ok(var) if var == "1") {
+            set_scope_enabled(scope, true);
+        }
+    }
+}
+
+/// Held by the tests that turn scopes on and off, they would race otherwise.
+#[cfg(test)]
+pub static TEST_SCOPES_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[test]
+fn test_log_scopes() {
+    let _lock = TEST_SCOPES_LOCK.lock().unwrap();
+    assert!(set_enabled_scopes(&["LAUNCH".into()]).is_empty());
+    assert!(scope_enabled("LAUNCH"));
+    assert!(!scope_enabled("AUTHENTICATION"));
+
+    let scopes = ["DOWNLOADER".into(), "UNKNOWN".into()];
+    assert_eq!(set_enabled_scopes(&scopes), ["UNKNOWN"]);
+    assert!(scope_enabled("DOWNLOADER"));
+    assert!(!scope_enabled("LAUNCH"));
+    assert!(!scope_enabled("UNKNOWN"));
+    assert!(!set_scope_enabled("UNKNOWN", true));
+}
@@ -0,0 +1,323 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{self, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
+
+use tracing::{
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    span::{Attributes, Id, Record},
+    subscriber::Interest,
+    Event, Level, Metadata, Subscriber,
+};
+
+thread_local! {
+    /// Spans entered on this thread, innermost last.
+    static ENTERED: RefCell<Vec<Id>> = const { RefCell::new(Vec::new()) };
+}
+
+struct SpanData {
+    metadata: &'static Metadata<'static>,
+    parent: Option<Id>,
+    fields: String,
+    started: Instant,
+    references: usize,
+}
+
+impl SpanData {
+    /// `name{fields}`, or only the name if the span has no fields.
+    fn name(&self) -> String {
+        if self.fields.is_empty() {
+            self.metadata.name().to_owned()
+        } else {
+            format!("{}{{{}}}", self.metadata.name(), self.fields)
+        }
+    }
+}
+
+/// A `tracing` subscriber writing through the `log` logger, so spans and events end up in the
+/// launcher log with the same format. Events are prefixed with the spans they happened in and every
+/// span logs how long it took once it closes.
+///
+/// Filtering is left to the logger, the `DEBUG` and `REQWEST_DEBUG` toggles apply to spans too.
+#[derive(Default)]
+pub struct LogSubscriber {
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<Id, SpanData>>,
+}
+
+impl LogSubscriber {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn current_span_id(&self) -> Option<Id> {
+        ENTERED.with(|entered| entered.borrow().last().cloned())
+    }
+
+    /// The names of the span `id` and its parents that are still open, outermost first.
+    fn scope(&self, id: Option<&Id>) -> Vec<String> {
+        let spans = self.spans.lock().unwrap();
+        let mut scope = Vec::new();
+        let mut current = id.and_then(|id| spans.get(id));
+        while let Some(span) = current {
+            scope.push(span.name());
+            current = span.parent.as_ref().and_then(|parent| spans.get(parent));
+        }
+        scope.reverse();
+        scope
+    }
+
+    /// `outer{fields}:inner{fields}: ` for the span `id` and its parents that are still open.
+    fn scope_prefix(&self, id: Option<&Id>) -> String {
+        let scope = self.scope(id);
+        if scope.is_empty() {
+            return String::new();
+        }
+        format!("{}: ", scope.join(":"))
+    }
+
+    /// Drops a handle to `id`, returning the span once the last one is gone.
+    fn release(&self, id: &Id) -> Option<SpanData> {
+        let mut spans = self.spans.lock().unwrap();
+        let span = spans.get_mut(id)?;
+        span.references -= 1;
+        if span.references > 0 {
+            return None;
+        }
+        spans.remove(id)
+    }
+}
+
+impl Subscriber for LogSubscriber {
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        // Never cached, the logger decides on every call.
+        Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        log::logger().enabled(
+            &log::Metadata::builder()
+                .level(log_level(metadata.level()))
+                .target(metadata.target())
+                .build(),
+        )
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(match log::max_level() {
+            log::LevelFilter::Off => LevelFilter::OFF,
+            log::LevelFilter::Error => LevelFilter::ERROR,
+            log::LevelFilter::Warn => LevelFilter::WARN,
+            log::LevelFilter::Info => LevelFilter::INFO,
+            log::LevelFilter::Debug => LevelFilter::DEBUG,
+            log::LevelFilter::Trace => LevelFilter::TRACE,
+        })
+    }
+
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+        let id = Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let parent = if attributes.is_contextual() {
+            self.current_span_id()
+        } else {
+            attributes.parent().cloned()
+        };
+        let mut visitor = FieldVisitor::default();
+        attributes.record(&mut visitor);
+
+        self.spans.lock().unwrap().insert(
+            id.clone(),
+            SpanData {
+                metadata: attributes.metadata(),
+                parent,
+                fields: visitor.fields,
+                started: Instant::now(),
+                references: 1,
+            },
+        );
+        id
+    }
+
+    fn record(&self, id: &Id, values: &Record<'_>) {
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        if let Some(span) = self.spans.lock().unwrap().get_mut(id) {
+            if !span.fields.is_empty() && !visitor.fields.is_empty() {
+                span.fields.push(' ');
+            }
+            span.fields.push_str(&visitor.fields);
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let parent = if event.is_contextual() {
+            self.current_span_id()
+        } else {
+            event.parent().cloned()
+        };
+        let mut message = visitor.message;
+        if !message.is_empty() && !visitor.fields.is_empty() {
+            message.push(' ');
+        }
+        message.push_str(&visitor.fields);
+        log_record(
+            event.metadata(),
+            format_args!("{}{}", self.scope_prefix(parent.as_ref()), message),
+        );
+    }
+
+    fn enter(&self, id: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().push(id.clone()));
+    }
+
+    fn exit(&self, id: &Id) {
+        ENTERED.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            if let Some(index) = entered.iter().rposition(|entered| entered == id) {
+                entered.remove(index);
+            }
+        });
+    }
+
+    fn clone_span(&self, id: &Id) -> Id {
+        if let Some(span) = self.spans.lock().unwrap().get_mut(id) {
+            span.references += 1;
+        }
+        id.clone()
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        let Some(span) = self.release(&id) else {
+            return false;
+        };
+        let mut scope = self.scope(span.parent.as_ref());
+        scope.push(span.name());
+        log_record(
+            span.metadata,
+            format_args!(
+                "{}: finished in {}ms",
+                scope.join(":"),
+                span.started.elapsed().as_millis()
+            ),
+        );
+        true
+    }
+}
+
+fn log_level(level: &Level) -> log::Level {
+    match *level {
+        Level::ERROR => log::Level::Error,
+        Level::WARN => log::Level::Warn,
+        Level::INFO => log::Level::Info,
+        Level::DEBUG => log::Level::Debug,
+        Level::TRACE => log::Level::Trace,
+    }
+}
+
+fn log_record(metadata: &Metadata<'_>, args: fmt::Arguments<'_>) {
+    log::logger().log(
+        &log::Record::builder()
+            .level(log_level(metadata.level()))
+            .target(metadata.target())
+            .module_path(metadata.module_path())
+            .file(metadata.file())
+            .line(metadata.line())
+            .args(args)
+            .build(),
+    );
+}
+
+/// Collects the message and the other fields, as `name=value`, of a span or event.
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl FieldVisitor {
+    fn field(&mut self, field: &Field) -> &mut String {
+        if field.name() == "message" {
+            return &mut self.message;
+        }
+        if !self.fields.is_empty() {
+            self.fields.push(' ');
+        }
+        let _ = write!(self.fields, "{}=", field.name());
+        &mut self.fields
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.field(field).push_str(value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let _ = write!(self.field(field), "{:?}", value);
+    }
+}
+
+#[cfg(test)]
+use crate::test_logger;
+
+#[test]
+fn test_events_are_prefixed_with_their_spans() {
+    test_logger::init();
+    tracing::subscriber::with_default(LogSubscriber::new(), || {
+        let install = tracing::info_span!("install", instance = "Vanilla");
+        let download = tracing::info_span!(parent: &install, "download", phase = "assets");
+        {
+            let _install = install.enter();
+            let _download = download.enter();
+            tracing::info!(count = 3, "Fetching assets");
+        }
+        tracing::info!("Outside of install");
+        drop(download);
+        drop(install);
+    });
+
+    let messages = test_logger::messages();
+    assert!(messages.contains(
+        &"install{instance=Vanilla}:download{phase=assets}: Fetching assets count=3".to_owned()
+    ));
+    assert!(messages.contains(&"Outside of install".to_owned()));
+    // Closed innermost first, the parent is still open when the child logs its time.
+    let download = messages
+        .iter()
+        .position(|message| {
+            message.starts_with("install{instance=Vanilla}:download{phase=assets}: finished in ")
+        })
+        .unwrap();
+    let install = messages
+        .iter()
+        .position(|message| message.starts_with("install{instance=Vanilla}: finished in "))
+        .unwrap();
+    assert!(download < install);
+}
+
+#[test]
+fn test_spans_and_events_are_filtered_by_the_logger() {
+    test_logger::init();
+    tracing::subscriber::with_default(LogSubscriber::new(), || {
+        let verbose = tracing::debug_span!("verbose_span");
+        let _verbose = verbose.enter();
+        tracing::debug!("Filtered debug event");
+        tracing::warn!("Kept warning");
+    });
+
+    let messages = test_logger::messages();
+    assert!(!messages.iter().any(
+        |message| message.contains("Filtered debug event") || message.contains("verbose_span")
+    ));
+    assert!(messages.contains(&"Kept warning".to_owned()));
+}
@@ -0,0 +1,44 @@
+//! A logger keeping what is logged in memory, filtered at the info level like the launcher's
+//! logger is by default.
+use std::sync::{Mutex, Once};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+static LOGGER: TestLogger = TestLogger {
+    messages: Mutex::new(Vec::new()),
+};
+static INIT: Once = Once::new();
+
+struct TestLogger {
+    messages: Mutex<Vec<String>>,
+}
+
+impl Log for TestLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= LevelFilter::Info
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.messages
+                .lock()
+                .unwrap()
+                .push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the logger, once for all the tests.
+pub fn init() {
+    INIT.call_once(|| {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Info);
+    });
+}
+
+/// Everything logged so far, by every test.
+pub fn messages() -> Vec<String> {
+    LOGGER.messages.lock().unwrap().clone()
+}
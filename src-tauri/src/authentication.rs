@@ -1,12 +1,58 @@
 use autmc_authentication::{
-    refresh_access_tokens, AuthenticationResult, MinecraftAccount, OAuthRefreshMode,
+    refresh_access_tokens, AuthenticationError, AuthenticationResult, MinecraftAccount,
+    OAuthRefreshMode,
 };
 use autmc_log::debug_if;
+use futures::{stream, StreamExt};
 use log::debug;
+use serde::Serialize;
+
+/// How many stored accounts [validate_accounts] refreshes at once.
+const MAX_CONCURRENT_VALIDATIONS: usize = 4;
+
+/// Whether a stored account can be used to play online.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AccountHealth {
+    Valid,
+    /// Its tokens expired and haven't been refreshed yet, usually because the servers couldn't be
+    /// reached.
+    NeedsRefresh,
+    /// Refreshing its tokens was rejected, it has to sign in again.
+    NeedsReauth,
+}
+
+/// The health of `account` at the unix time `now`.
+pub fn account_health(account: &MinecraftAccount, needs_reauth: bool, now: u64) -> AccountHealth {
+    if account.offline {
+        // There are no tokens to refresh.
+        AccountHealth::Valid
+    } else if needs_reauth {
+        AccountHealth::NeedsReauth
+    } else if account.microsoft_access_token_expiry <= now
+        || account.minecraft_access_token_expiry <= now
+    {
+        AccountHealth::NeedsRefresh
+    } else {
+        AccountHealth::Valid
+    }
+}
+
+/// Whether refreshing failed because the servers couldn't be reached rather than the tokens being
+/// rejected, so trying again later can still work.
+pub fn is_transient_error(error: &AuthenticationError) -> bool {
+    match error {
+        AuthenticationError::RequestError(_) => true,
+        AuthenticationError::HttpResponseError(status) => status.is_server_error(),
+        _ => false,
+    }
+}
 
 pub async fn validate_account(account: MinecraftAccount) -> AuthenticationResult<MinecraftAccount> {
     let now = chrono::Local::now().timestamp() as u64;
-    let refresh_mode = if account.microsoft_access_token_expiry <= now {
+    let refresh_mode = if account.offline {
+        None
+    } else if account.microsoft_access_token_expiry <= now {
         debug_if!(
             "AUTHENTICATION",
             "Microsoft token expired on {} its now {}",
@@ -37,3 +83,45 @@ pub async fn validate_account(account: MinecraftAccount) -> AuthenticationResult
         Ok(account)
     }
 }
+
+/// Validates `accounts` with [validate_account], a few at a time. The results are in the order
+/// they finished in, paired with the uuid of the account they are for.
+pub async fn validate_accounts(
+    accounts: Vec<MinecraftAccount>,
+) -> Vec<(String, AuthenticationResult<MinecraftAccount>)> {
+    stream::iter(accounts)
+        .map(|account| async move { (account.uuid.clone(), validate_account(account).await) })
+        .buffer_unordered(MAX_CONCURRENT_VALIDATIONS)
+        .collect()
+        .await
+}
+
+#[test]
+fn test_account_health() {
+    let account = MinecraftAccount {
+        uuid: "uuid".into(),
+        name: "Player".into(),
+        skin_url: String::new(),
+        microsoft_access_token: String::new(),
+        microsoft_access_token_expiry: 200,
+        microsoft_refresh_token: String::new(),
+        minecraft_access_token: String::new(),
+        minecraft_access_token_expiry: 100,
+        xuid: String::new(),
+        offline: false,
+    };
+    assert_eq!(account_health(&account, false, 50), AccountHealth::Valid);
+    assert_eq!(
+        account_health(&account, false, 150),
+        AccountHealth::NeedsRefresh
+    );
+    assert_eq!(
+        account_health(&account, true, 50),
+        AccountHealth::NeedsReauth
+    );
+    let offline = MinecraftAccount {
+        offline: true,
+        ..account
+    };
+    assert_eq!(account_health(&offline, false, 150), AccountHealth::Valid);
+}
@@ -1,44 +1,391 @@
 use crate::state::{
-    account_manager::AccountManager, resource_manager::ResourceManager, ManagerFromAppHandle,
+    account_manager::{emit_profile_change, AccountManager},
+    resource_manager::{ManifestSnapshot, ResourceManager},
+    settings_manager::{LauncherSettings, SettingsManager},
+    ManagerFromAppHandle,
 };
 use crate::{
-    consts::GZIP_SIGNATURE,
+    authentication::{is_transient_error, validate_account, AccountHealth},
+    consts::{CURSEFORGE_MODPACK_CLASS_ID, FORGE_INSTALL_LOG, GZIP_SIGNATURE, LATEST_LAUNCHER_LOG},
+    crash_incident::{self, CrashIncident},
+    crash_restart::RestartPolicy,
+    data_roots::{move_dir_with_progress, MoveProgress},
+    deep_link::{DeepLinkRequest, DeepLinkState},
+    deobfuscation::{crash_report_path, crash_report_version, Mappings},
+    error_catalog::error_catalog,
+    instance_export::{
+        extract_bundle, read_bundle_info, write_bundle, BundleInfo, InstanceExportError,
+    },
+    instance_sync::{sync_instance, InstanceSync, InstanceSyncError, SyncReport},
+    instance_template::{delete_template, list_templates, save_template, InstanceTemplate},
+    jar_conflicts::{scan_conflicts, JarConflict},
+    jar_mods::{apply_jar_mods, custom_jar_path, read_jar_mods, JarMods},
+    keybind_profile::{self, list_keybind_profiles, KeybindProfile},
+    lan_share::{
+        download_lan_share, start_lan_share, LanShareError, LanShareInfo, LanShareManager,
+    },
+    mod_bisect::{
+        cancel_bisect, load_session, record_bisect_result, start_bisect, BisectStatus,
+        ModBisectError,
+    },
+    notifications::notify,
+    onboarding::OnboardingInfo,
+    option_parser::{copy_options, OptionCategory},
+    safe_mode::SafeModeState,
+    secondary_window::SecondaryWindow,
+    server_properties::{
+        read_server_properties, write_server_properties, ServerProperties, ServerPropertiesError,
+    },
+    shutdown::exit_after_tasks,
+    skin::{cached_skin_path, render_heads, AccountSkin, SkinError},
+    startup_diagnostics::{Diagnostic, StartupDiagnosticsState},
     state::{
-        instance_manager::{InstanceConfiguration, InstanceManager},
-        resource_manager::ManifestResult,
+        instance_manager::{
+            available_directory_name, InstanceConfiguration, InstanceManager, InstanceStats,
+            InstanceSubdir, LaunchCommand,
+        },
+        resource_manager::ManifestError,
+    },
+    support_bundle::{write_support_bundle, SupportBundleError},
+    system_info::{MemoryBounds, SystemInfo},
+    trash::{
+        child_path, get_trash_entry, list_trash, move_to_trash, restore_trash_entry, TrashEntry,
+        TrashError, TrashKind,
     },
+    update_check::{InstanceUpdates, UpdateNotificationsState, UpdateWatch},
     web_services::{
-        manifest::{path_to_utf8_str, vanilla::VanillaManifestVersion},
+        api_cache::{api_cache_path, cached_response, ApiCacheError},
+        install_report::{install_reports_path, last_install_report, InstallReport},
+        manifest::vanilla::JarType,
+        manifest::vanilla::VanillaManifestVersion,
         modpack::{
             curseforge::{
-                import_curseforge_zip, retrieve_curseforge_categories, search_curseforge_modpacks,
-                CurseforgeCategory, CurseforgeSearchAuthors, CurseforgeSearchEntry,
-                CurseforgeSearchImage, CurseforgeSortField,
+                class_install_dir, download_curseforge_modpack, download_main_file_id,
+                extract_manifest_from_curseforge_zip, import_curseforge_zip,
+                install_curseforge_project, retrieve_curseforge_categories,
+                search_curseforge_projects, CurseforgeCategory, CurseforgeManifestInfo,
+                CurseforgeSearchAuthors, CurseforgeSearchEntry, CurseforgeSearchImage,
+                CurseforgeSortField, DependencyError, SearchCompatibility, ALL_VERSIONS,
             },
-            modrinth::import_modrinth_zip,
+            modrinth::{
+                download_modrinth_modpack, import_modrinth_zip, modrinth_optional_files,
+                read_modrinth_manifest, retrieve_modrinth_categories, ModrinthCategory,
+                OptionalPackFile,
+            },
+            PackFormatError,
+        },
+        resources::{
+            create_instance, ensure_asset_index, mappings_path, InstanceSettings, ModloaderType,
         },
-        resources::{create_instance, InstanceSettings},
     },
 };
 use autmc_authentication::{
-    poll_device_code_status, start_device_code_authentication, AuthenticationResult, DeviceCode,
+    create_offline_account, get_minecraft_profile, poll_device_code_status, set_active_cape,
+    start_device_code_authentication, AuthenticationError, DeviceCode, MinecraftAccount,
+    MinecraftProfileResponse, DEFAULT_DEVICE_CODE_EXPIRY, DEFAULT_POLL_INTERVAL,
 };
+use autmc_downloader::download_bytes_from_url;
+use autmc_log::{scope_enabled, LOG_SCOPES};
 use flate2::read::GzDecoder;
 use log::{debug, error, info, warn};
-use serde::{Deserialize, Serialize};
+use serde::{ser::SerializeStruct, Deserialize, Serialize};
 use std::{
-    collections::HashMap,
-    env,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
     fs::{self, File},
     io::{self, BufRead, BufReader, Read},
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    time::Duration,
 };
 use tauri::{AppHandle, Emitter, Manager, Wry};
+use tauri_plugin_opener::OpenerExt;
 use zip::ZipArchive;
+#[cfg(test)]
+use {crate::error_catalog::format_message, tempdir::TempDir};
+
+pub type CommandResult<T> = Result<T, CommandError>;
+
+/// Error returned to the frontend by commands. Serialized as
+/// `{ "kind": ..., "params": { ... }, "message": ... }`, the kind and params can be looked up in
+/// [error_catalog] to show the message in the user's language.
+#[derive(Debug)]
+pub enum CommandError {
+    NoActiveAccount,
+    UnknownInstance(String),
+    UnknownGameVersion(String),
+    UnsupportedProjectClass(u32),
+    NoTargetInstance,
+    SafeMode,
+    UnknownLogScope(String),
+    MemoryOutOfBounds(u64, MemoryBounds),
+    InstanceRunning(String),
+    UnknownDataRoot(String),
+    UnmanagedPath(String),
+    UnknownAccount(String),
+    OfflineAccount(String),
+    Http(reqwest::Error),
+    Io(io::Error),
+    Manifest(ManifestError),
+    ModBisect(ModBisectError),
+    LanShare(LanShareError),
+    InstanceSync(InstanceSyncError),
+    InstanceExport(InstanceExportError),
+    PackFormat(PackFormatError),
+    ApiCache(ApiCacheError),
+    Dependency(DependencyError),
+    SupportBundle(SupportBundleError),
+    Trash(TrashError),
+    ServerProperties(ServerPropertiesError),
+    Skin(SkinError),
+    Authentication(AuthenticationError),
+    Opener(tauri_plugin_opener::Error),
+    Tauri(tauri::Error),
+}
+
+impl CommandError {
+    fn kind(&self) -> &'static str {
+        match self {
+            CommandError::NoActiveAccount => "noActiveAccount",
+            CommandError::UnknownInstance(_) => "unknownInstance",
+            CommandError::UnknownGameVersion(_) => "unknownGameVersion",
+            CommandError::UnsupportedProjectClass(_) => "unsupportedProjectClass",
+            CommandError::NoTargetInstance => "noTargetInstance",
+            CommandError::SafeMode => "safeMode",
+            CommandError::UnknownLogScope(_) => "unknownLogScope",
+            CommandError::MemoryOutOfBounds(..) => "memoryOutOfBounds",
+            CommandError::InstanceRunning(_) => "instanceRunning",
+            CommandError::UnknownDataRoot(_) => "unknownDataRoot",
+            CommandError::UnmanagedPath(_) => "unmanagedPath",
+            CommandError::UnknownAccount(_) => "unknownAccount",
+            CommandError::OfflineAccount(_) => "offlineAccount",
+            CommandError::Http(_) => "http",
+            CommandError::Io(_) => "io",
+            CommandError::Manifest(_) => "manifest",
+            CommandError::ModBisect(_) => "modBisect",
+            CommandError::LanShare(_) => "lanShare",
+            CommandError::InstanceSync(_) => "instanceSync",
+            CommandError::InstanceExport(_) => "instanceExport",
+            CommandError::PackFormat(_) => "packFormat",
+            CommandError::ApiCache(_) => "apiCache",
+            CommandError::Dependency(_) => "dependency",
+            CommandError::SupportBundle(_) => "supportBundle",
+            CommandError::Trash(_) => "trash",
+            CommandError::ServerProperties(_) => "serverProperties",
+            CommandError::Skin(_) => "skin",
+            CommandError::Authentication(_) => "authentication",
+            CommandError::Opener(_) => "opener",
+            CommandError::Tauri(_) => "tauri",
+        }
+    }
+
+    /// The values filled into the kind's message template.
+    fn params(&self) -> BTreeMap<&'static str, String> {
+        match self {
+            CommandError::NoActiveAccount
+            | CommandError::NoTargetInstance
+            | CommandError::SafeMode => BTreeMap::new(),
+            CommandError::UnknownInstance(name) | CommandError::InstanceRunning(name) => {
+                BTreeMap::from([("instance", name.clone())])
+            }
+            CommandError::UnknownGameVersion(version) => {
+                BTreeMap::from([("version", version.clone())])
+            }
+            CommandError::UnsupportedProjectClass(class_id) => {
+                BTreeMap::from([("classId", class_id.to_string())])
+            }
+            CommandError::UnknownLogScope(scope) => BTreeMap::from([("scope", scope.clone())]),
+            CommandError::UnknownDataRoot(name) => BTreeMap::from([("dataRoot", name.clone())]),
+            CommandError::UnmanagedPath(path) => BTreeMap::from([("path", path.clone())]),
+            CommandError::UnknownAccount(uuid) => BTreeMap::from([("uuid", uuid.clone())]),
+            CommandError::OfflineAccount(name) => BTreeMap::from([("account", name.clone())]),
+            CommandError::MemoryOutOfBounds(memory, bounds) => BTreeMap::from([
+                ("memoryMb", memory.to_string()),
+                ("minMb", bounds.min_mb.to_string()),
+                ("maxMb", bounds.max_mb.to_string()),
+            ]),
+            _ => BTreeMap::from([("error", self.to_string())]),
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::NoActiveAccount => f.write_str("There is no active account"),
+            CommandError::UnknownInstance(name) => write!(f, "Unknown instance `{}`", name),
+            CommandError::UnknownGameVersion(version) => {
+                write!(f, "Unknown Minecraft version `{}`", version)
+            }
+            CommandError::UnsupportedProjectClass(class_id) => {
+                write!(f, "Projects of class {} can't be installed", class_id)
+            }
+            CommandError::NoTargetInstance => {
+                f.write_str("Choose an instance to install the project into")
+            }
+            CommandError::SafeMode => f.write_str(
+                "The launcher was started in safe mode, restart it normally to use the network",
+            ),
+            CommandError::UnknownLogScope(scope) => write!(f, "Unknown log scope `{}`", scope),
+            CommandError::MemoryOutOfBounds(memory, bounds) => write!(
+                f,
+                "Can't give an instance {}MB of memory, it must be between {}MB and {}MB",
+                memory, bounds.min_mb, bounds.max_mb
+            ),
+            CommandError::InstanceRunning(name) => {
+                write!(f, "`{}` is running, close it first", name)
+            }
+            CommandError::UnknownDataRoot(name) => {
+                write!(f, "There is no data root named `{}`", name)
+            }
+            CommandError::UnmanagedPath(path) => {
+                write!(f, "`{}` isn't managed by the launcher", path)
+            }
+            CommandError::UnknownAccount(uuid) => write!(f, "There is no account `{}`", uuid),
+            CommandError::OfflineAccount(name) => {
+                write!(
+                    f,
+                    "`{}` is an offline account, it has no Minecraft profile",
+                    name
+                )
+            }
+            CommandError::Http(error) => write!(f, "{}", error),
+            CommandError::Io(error) => write!(f, "{}", error),
+            CommandError::Manifest(error) => write!(f, "{}", error),
+            CommandError::ModBisect(error) => write!(f, "{}", error),
+            CommandError::LanShare(error) => write!(f, "{}", error),
+            CommandError::InstanceSync(error) => write!(f, "{}", error),
+            CommandError::InstanceExport(error) => write!(f, "{}", error),
+            CommandError::PackFormat(error) => write!(f, "{}", error),
+            CommandError::ApiCache(error) => write!(f, "{}", error),
+            CommandError::Dependency(error) => write!(f, "{}", error),
+            CommandError::SupportBundle(error) => write!(f, "{}", error),
+            CommandError::Trash(error) => write!(f, "{}", error),
+            CommandError::ServerProperties(error) => write!(f, "{}", error),
+            CommandError::Skin(error) => write!(f, "{}", error),
+            CommandError::Authentication(error) => write!(f, "{}", error),
+            CommandError::Opener(error) => write!(f, "{}", error),
+            CommandError::Tauri(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Serialize for CommandError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("CommandError", 3)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("params", &self.params())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl From<reqwest::Error> for CommandError {
+    fn from(error: reqwest::Error) -> Self {
+        CommandError::Http(error)
+    }
+}
+
+impl From<io::Error> for CommandError {
+    fn from(error: io::Error) -> Self {
+        CommandError::Io(error)
+    }
+}
+
+impl From<ManifestError> for CommandError {
+    fn from(error: ManifestError) -> Self {
+        CommandError::Manifest(error)
+    }
+}
+
+impl From<ModBisectError> for CommandError {
+    fn from(error: ModBisectError) -> Self {
+        CommandError::ModBisect(error)
+    }
+}
+
+impl From<LanShareError> for CommandError {
+    fn from(error: LanShareError) -> Self {
+        CommandError::LanShare(error)
+    }
+}
+
+impl From<InstanceSyncError> for CommandError {
+    fn from(error: InstanceSyncError) -> Self {
+        CommandError::InstanceSync(error)
+    }
+}
+
+impl From<InstanceExportError> for CommandError {
+    fn from(error: InstanceExportError) -> Self {
+        CommandError::InstanceExport(error)
+    }
+}
+
+impl From<PackFormatError> for CommandError {
+    fn from(error: PackFormatError) -> Self {
+        CommandError::PackFormat(error)
+    }
+}
+
+impl From<ApiCacheError> for CommandError {
+    fn from(error: ApiCacheError) -> Self {
+        CommandError::ApiCache(error)
+    }
+}
+
+impl From<DependencyError> for CommandError {
+    fn from(error: DependencyError) -> Self {
+        CommandError::Dependency(error)
+    }
+}
+
+impl From<SupportBundleError> for CommandError {
+    fn from(error: SupportBundleError) -> Self {
+        CommandError::SupportBundle(error)
+    }
+}
+
+impl From<TrashError> for CommandError {
+    fn from(error: TrashError) -> Self {
+        CommandError::Trash(error)
+    }
+}
+
+impl From<ServerPropertiesError> for CommandError {
+    fn from(error: ServerPropertiesError) -> Self {
+        CommandError::ServerProperties(error)
+    }
+}
+
+impl From<SkinError> for CommandError {
+    fn from(error: SkinError) -> Self {
+        CommandError::Skin(error)
+    }
+}
+
+impl From<AuthenticationError> for CommandError {
+    fn from(error: AuthenticationError) -> Self {
+        CommandError::Authentication(error)
+    }
+}
+
+impl From<tauri_plugin_opener::Error> for CommandError {
+    fn from(error: tauri_plugin_opener::Error) -> Self {
+        CommandError::Opener(error)
+    }
+}
+
+impl From<tauri::Error> for CommandError {
+    fn from(error: tauri::Error) -> Self {
+        CommandError::Tauri(error)
+    }
+}
 
 #[tauri::command(async)]
-pub async fn start_authentication_flow() -> AuthenticationResult<DeviceCode> {
+pub async fn start_authentication_flow() -> CommandResult<DeviceCode> {
     let device_code = start_device_code_authentication().await?;
     debug!("Got device code: {:#?}", device_code);
     Ok(device_code)
@@ -47,9 +394,16 @@ pub async fn start_authentication_flow() -> AuthenticationResult<DeviceCode> {
 #[tauri::command(async)]
 pub async fn poll_device_code_authentication(
     device_code: String,
+    interval: Option<u64>,
+    expires_in: Option<u64>,
     app_handle: tauri::AppHandle<Wry>,
-) -> AuthenticationResult<()> {
-    let account = poll_device_code_status(&device_code).await?;
+) -> CommandResult<()> {
+    let account = poll_device_code_status(
+        &device_code,
+        interval.unwrap_or(DEFAULT_POLL_INTERVAL),
+        expires_in.unwrap_or(DEFAULT_DEVICE_CODE_EXPIRY),
+    )
+    .await?;
     debug!("Got Account: {:#?}", account);
 
     let mut account_manager = AccountManager::from_app_handle(&app_handle).await;
@@ -66,6 +420,26 @@ pub async fn poll_device_code_authentication(
     Ok(())
 }
 
+/// Adds and activates an account that plays offline as `username`, for users without a Microsoft
+/// account.
+#[tauri::command(async)]
+pub async fn add_offline_account(
+    username: String,
+    app_handle: tauri::AppHandle<Wry>,
+) -> CommandResult<()> {
+    let account = create_offline_account(&username)?;
+    let mut account_manager = AccountManager::from_app_handle(&app_handle).await;
+    account_manager.add_and_activate_account(account, app_handle.clone());
+
+    if let Err(error) = account_manager.serialize_accounts() {
+        warn!(
+            "Could not properly serialize account information: {}",
+            error
+        );
+    }
+    Ok(())
+}
+
 #[derive(Deserialize)]
 pub struct VersionFilter {
     pub id: String,
@@ -86,8 +460,8 @@ impl VersionEntry {
     pub fn new(version: &str, version_info: &VanillaManifestVersion) -> Self {
         Self {
             version: version.into(),
-            released_date: version_info.release_time.clone(),
-            version_type: version_info.version_type.clone(),
+            released_date: version_info.release_time().to_owned(),
+            version_type: version_info.version_type().to_owned(),
         }
     }
 }
@@ -99,9 +473,18 @@ pub struct VersionManifest {
     forge_versions: HashMap<String, Vec<String>>,
 }
 
+/// Fails when the launcher was started in safe mode, which doesn't use the network.
+fn ensure_online(app_handle: &AppHandle<Wry>) -> CommandResult<()> {
+    if app_handle.state::<SafeModeState>().0 {
+        return Err(CommandError::SafeMode);
+    }
+    Ok(())
+}
+
 #[tauri::command(async)]
-pub async fn obtain_manifests(app_handle: AppHandle<Wry>) -> ManifestResult<VersionManifest> {
-    let mut resource_manager = ResourceManager::from_app_handle(&app_handle).await;
+pub async fn obtain_manifests(app_handle: AppHandle<Wry>) -> CommandResult<VersionManifest> {
+    ensure_online(&app_handle)?;
+    let resource_manager = ResourceManager::from_app_handle(&app_handle);
 
     let vanilla_versions = resource_manager.get_vanilla_version_list().await?;
     let fabric_versions = resource_manager.get_fabric_version_list().await?;
@@ -118,7 +501,8 @@ pub async fn obtain_manifests(app_handle: AppHandle<Wry>) -> ManifestResult<Vers
 pub async fn obtain_version(
     settings: InstanceSettings,
     app_handle: AppHandle<Wry>,
-) -> ManifestResult<()> {
+) -> CommandResult<()> {
+    ensure_online(&app_handle)?;
     debug!("Settings: {:#?}", settings);
     info!(
         "Creating instance {} with Minecraft version {} and modloader {} {}",
@@ -133,15 +517,24 @@ pub async fn obtain_version(
     let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
 
     instance_manager.deserialize_instances();
-    app_handle.emit("new-instance", instance_name).unwrap();
+    drop(instance_manager);
+    notify(
+        &app_handle,
+        "Instance installed",
+        &format!("{} is ready to play", instance_name),
+    )
+    .await;
+    app_handle.emit("new-instance", instance_name)?;
     Ok(())
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BasicAccount {
     uuid: String,
     name: String,
     skin_url: String,
+    /// Updated by the startup validation, which emits `accounts-validated` once done.
+    health: AccountHealth,
 }
 
 #[derive(Debug, Serialize)]
@@ -161,6 +554,7 @@ pub async fn get_accounts(app_handle: AppHandle<Wry>) -> AccountInformation {
             (
                 key,
                 BasicAccount {
+                    health: account_manager.get_account_health(&value),
                     uuid: value.uuid,
                     name: value.name,
                     skin_url: value.skin_url,
@@ -174,251 +568,1788 @@ pub async fn get_accounts(app_handle: AppHandle<Wry>) -> AccountInformation {
     }
 }
 
+/// Makes the stored account `uuid` the active one, refreshing its tokens first if they expired.
+/// Emits `account-changed` with the account so the frontend can show its skin.
 #[tauri::command(async)]
-pub async fn get_account_skin(app_handle: AppHandle<Wry>) -> String {
-    let account_manager = AccountManager::from_app_handle(&app_handle).await;
-
-    let account = account_manager.get_active_account().unwrap();
-    debug!("Skin URL: {}", account.skin_url);
-    account.skin_url.clone()
-}
+pub async fn set_active_account(
+    uuid: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<BasicAccount> {
+    let account = AccountManager::from_app_handle(&app_handle)
+        .await
+        .get_account(&uuid)
+        .cloned()
+        .ok_or_else(|| CommandError::UnknownAccount(uuid.clone()))?;
+    // Not locked while refreshing, it can take a while.
+    let validation_result = validate_account(account).await;
 
-#[tauri::command(async)]
-pub async fn load_instances(app_handle: AppHandle<Wry>) -> Vec<InstanceConfiguration> {
-    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    let mut account_manager = AccountManager::from_app_handle(&app_handle).await;
+    let uuid = match validation_result {
+        Ok(account) => {
+            let uuid = account.uuid.clone();
+            if let Some(change) = account_manager.add_account(account) {
+                emit_profile_change(&change, &app_handle);
+            }
+            uuid
+        }
+        // Switch anyway, like on startup the account stays usable for singleplayer.
+        Err(e) if is_transient_error(&e) => {
+            warn!("Could not refresh account {}: {}", uuid, e);
+            uuid
+        }
+        Err(e) => {
+            warn!("Account {} was rejected: {}", uuid, e);
+            account_manager.mark_needs_reauth(&uuid, &app_handle);
+            uuid
+        }
+    };
+    // It could have been removed while refreshing.
+    let account = account_manager
+        .get_account(&uuid)
+        .cloned()
+        .ok_or_else(|| CommandError::UnknownAccount(uuid.clone()))?;
+    account_manager.activate_account(&uuid, app_handle.clone());
+    account_manager.serialize_accounts()?;
+    info!("Switched to account {} ({})", account.name, uuid);
 
-    instance_manager.deserialize_instances();
-    debug!("load_instances");
-    instance_manager.get_instance_configurations()
+    let basic_account = BasicAccount {
+        health: account_manager.get_account_health(&account),
+        uuid: account.uuid,
+        name: account.name,
+        skin_url: account.skin_url,
+    };
+    app_handle.emit_to("main", "account-changed", &basic_account)?;
+    Ok(basic_account)
 }
 
+/// Downloads a version json again even if the cached copy still matches the manifest.
 #[tauri::command(async)]
-pub async fn launch_instance(instance_name: String, app_handle: AppHandle<Wry>) {
-    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
-
-    let account_manager = AccountManager::from_app_handle(&app_handle).await;
+pub async fn refresh_version(version_id: String, app_handle: AppHandle<Wry>) -> CommandResult<()> {
+    ensure_online(&app_handle)?;
+    let resource_manager = ResourceManager::from_app_handle(&app_handle);
+    resource_manager.refresh_version(&version_id).await?;
+    Ok(())
+}
 
-    // Assumed there is an active account.
-    instance_manager.launch_instance(
-        &instance_name,
-        account_manager.get_active_account().unwrap(),
-        app_handle.clone(),
-    );
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestSnapshotStatus {
+    snapshot: ManifestSnapshot,
+    upstream_changed: bool,
 }
 
-// FIXME: Instance names can be different from the directory name its stored in.
+/// Checks the version json pinned when the instance was created can still be loaded, and whether
+/// the upstream manifest has changed since. Instances created before snapshots existed return `None`.
 #[tauri::command(async)]
-pub async fn open_folder(instance_name: String, app_handle: AppHandle<Wry>) {
-    debug!("open_folder with name: {}", instance_name);
-    let instance_manager = InstanceManager::from_app_handle(&app_handle).await;
-
-    // Determine the command to open the default file explorer
-    let command = match env::consts::OS {
-        "linux" => "xdg-open",
-        "macos" => "open",
-        "windows" => "explorer",
-        _ => unimplemented!(
-            "Cannot open file explorer, unknown OS type: {}",
-            env::consts::OS
-        ),
+pub async fn verify_manifest_snapshot(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Option<ManifestSnapshotStatus>> {
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    let snapshot = match ManifestSnapshot::load(&instance_dir)? {
+        Some(snapshot) => snapshot,
+        None => return Ok(None),
     };
 
-    // Spawn process of file explorer, can outlive parent.
-    let result = Command::new(command)
-        .arg(instance_manager.instances_dir().join(instance_name))
-        .stdout(Stdio::null())
-        .spawn();
-
-    if let Err(e) = result {
-        error!("Error spawning file manager process: {}", e);
-    }
+    ensure_online(&app_handle)?;
+    let resource_manager = ResourceManager::from_app_handle(&app_handle);
+    resource_manager
+        .download_pinned_vanilla_version(&snapshot)
+        .await?;
+    let upstream_changed = resource_manager.is_snapshot_outdated(&snapshot).await?;
+    Ok(Some(ManifestSnapshotStatus {
+        snapshot,
+        upstream_changed,
+    }))
 }
 
+/// Imports a version that isn't in the vanilla manifest, like an experimental snapshot, from the
+/// url or path of its version json or zip. It is listed with the vanilla versions afterwards.
 #[tauri::command(async)]
-pub async fn get_screenshots(app_handle: AppHandle<Wry>) -> HashMap<String, Vec<String>> {
-    let instance_manager = InstanceManager::from_app_handle(&app_handle).await;
-
-    let instance_dir = instance_manager.instances_dir();
-
-    let mut instance_screenshots = HashMap::new();
-    for instance in instance_manager.get_instance_names() {
-        let paths = fs::read_dir(instance_dir.join(&instance).join("screenshots"));
+pub async fn import_custom_version(
+    source: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<VersionEntry> {
+    ensure_online(&app_handle)?;
+    let resource_manager = ResourceManager::from_app_handle(&app_handle);
+    let version = resource_manager
+        .import_custom_version(source.trim())
+        .await?;
+    Ok(VersionEntry::new(version.id(), &version))
+}
 
-        if let Ok(paths) = paths {
-            let mut screenshots: Vec<String> = Vec::new();
-            for path in paths {
-                let file_name = path.unwrap().file_name();
-                let file_name_str = file_name.to_str().unwrap();
-                let path = app_handle
-                    .path()
-                    .app_config_dir()
-                    .unwrap()
-                    .join(format!(
-                        "instances/{}/screenshots/{}",
-                        &instance, file_name_str
-                    ));
-                screenshots.push(path_to_utf8_str(&path).into());
+/// Deletes the cached version jsons of versions no instance uses. Nothing is deleted if the version
+/// of an instance can't be told.
+#[tauri::command(async)]
+pub async fn evict_unused_versions(app_handle: AppHandle<Wry>) -> CommandResult<Vec<String>> {
+    let instances = InstanceManager::from_app_handle(&app_handle)
+        .await
+        .get_instance_configurations();
+    let mut referenced: HashSet<String> = HashSet::new();
+    for instance in &instances {
+        match instance.vanilla_version() {
+            Some(version) => referenced.insert(version.to_owned()),
+            None => {
+                warn!(
+                    "Can't tell which version {} uses, keeping every version json",
+                    instance.instance_name
+                );
+                return Ok(Vec::new());
             }
-            instance_screenshots.insert(instance, screenshots);
-        }
+        };
     }
-    info!(
-        "Found {} screenshots across all intances",
-        instance_screenshots.len()
-    );
-    instance_screenshots
+    let resource_manager = ResourceManager::from_app_handle(&app_handle);
+    Ok(resource_manager.evict_version_jsons(&referenced)?)
 }
 
-fn create_instance_log_map(
-    instance_dir: &Path,
-    instance_names: &[String],
-) -> io::Result<HashMap<String, Vec<String>>> {
-    let mut result = HashMap::new();
+/// `account`, unless it plays offline and has no Minecraft profile or skin to request.
+fn online_account(account: &MinecraftAccount) -> CommandResult<&MinecraftAccount> {
+    if account.offline {
+        return Err(CommandError::OfflineAccount(account.name.clone()));
+    }
+    Ok(account)
+}
 
-    for instance in instance_names {
-        let directory_entries = fs::read_dir(instance_dir.join(instance).join("logs"));
-        if directory_entries.is_err() {
-            result.insert(instance.clone(), Vec::new());
-            continue;
-        }
+/// The skin of the account with `uuid`, or the active account, with its face rendered as avatars.
+/// Skins are downloaded once and cached in the skins folder.
+#[tauri::command(async)]
+pub async fn get_account_skin(
+    uuid: Option<String>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<AccountSkin> {
+    let skin_url = {
+        let account_manager = AccountManager::from_app_handle(&app_handle).await;
+        let account = account_manager
+            .get_account_or_active(uuid.as_deref())
+            .ok_or(CommandError::NoActiveAccount)?;
+        online_account(account)?.skin_url.clone()
+    };
+    debug!("Skin URL: {}", skin_url);
 
-        // Traverse every entry in the dir
-        for dir_entry in directory_entries.unwrap() {
-            let path = dir_entry?.path();
-            if path.is_file() {
-                let filename = path.file_name().unwrap().to_str().unwrap().into();
-                if result.contains_key(instance) {
-                    let existing_vec = result.get_mut(instance).unwrap();
-                    existing_vec.push(filename);
-                } else {
-                    result.insert(instance.to_owned(), vec![filename]);
-                }
-            }
+    let skins_dir = ResourceManager::from_app_handle(&app_handle).skins_dir();
+    let path = cached_skin_path(&skins_dir, &skin_url);
+    let skin = match fs::read(&path) {
+        Ok(skin) => skin,
+        Err(_) => {
+            ensure_online(&app_handle)?;
+            let skin = download_bytes_from_url(&skin_url).await?;
+            fs::create_dir_all(&skins_dir)?;
+            fs::write(&path, &skin)?;
+            skin.to_vec()
         }
-    }
-
-    Ok(result)
+    };
+    Ok(AccountSkin {
+        heads: render_heads(&skin)?,
+        skin_url,
+    })
 }
 
+/// The Minecraft profile of the account with `uuid`, or the active account, with its skins and
+/// owned capes.
 #[tauri::command(async)]
-pub async fn get_logs(app_handle: AppHandle<Wry>) -> HashMap<String, Vec<String>> {
-    let instance_manager = InstanceManager::from_app_handle(&app_handle).await;
-
-    let instance_dir = instance_manager.instances_dir();
+pub async fn get_account_profile(
+    uuid: Option<String>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<MinecraftProfileResponse> {
+    ensure_online(&app_handle)?;
+    let (account_uuid, access_token) = {
+        let account_manager = AccountManager::from_app_handle(&app_handle).await;
+        let account = account_manager
+            .get_account_or_active(uuid.as_deref())
+            .ok_or(CommandError::NoActiveAccount)?;
+        let account = online_account(account)?;
+        (account.uuid.clone(), account.minecraft_access_token.clone())
+    };
+    let profile = get_minecraft_profile(&access_token).await?;
+    update_stored_profile(&account_uuid, &profile, &app_handle).await;
+    Ok(profile)
+}
 
-    match create_instance_log_map(&instance_dir, &instance_manager.get_instance_names()) {
-        Ok(map) => map,
-        Err(e) => {
-            error!("Error creating logging maps: {}", e);
-            HashMap::new()
-        }
+/// Keeps the stored account in line with its freshly fetched `profile`, in case it was renamed.
+async fn update_stored_profile(
+    account_uuid: &str,
+    profile: &MinecraftProfileResponse,
+    app_handle: &AppHandle<Wry>,
+) {
+    let mut account_manager = AccountManager::from_app_handle(app_handle).await;
+    let skin_url = profile
+        .skins
+        .iter()
+        .find(|skin| skin.state == "ACTIVE")
+        .map(|skin| skin.url.as_str());
+    let change = account_manager.apply_profile(account_uuid, &profile.id, &profile.name, skin_url);
+    if let Err(e) = account_manager.serialize_accounts() {
+        warn!("Could not save the profile of {}: {}", profile.name, e);
+    }
+    if let Some(change) = change {
+        emit_profile_change(&change, app_handle);
     }
 }
 
-#[derive(Debug, Serialize, PartialEq, Clone)]
-#[serde(rename_all = "lowercase")]
-#[repr(u8)]
-enum LineType {
-    Unknown,
-    Normal,
-    Error,
-    Warning,
+/// Wears the owned cape `cape_id` on the active account, or hides its cape if it is `None`.
+#[tauri::command(async)]
+pub async fn set_account_cape(
+    cape_id: Option<String>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<MinecraftProfileResponse> {
+    ensure_online(&app_handle)?;
+    let (account_uuid, access_token) = {
+        let account_manager = AccountManager::from_app_handle(&app_handle).await;
+        let account = account_manager
+            .get_active_account()
+            .ok_or(CommandError::NoActiveAccount)?;
+        let account = online_account(account)?;
+        (account.uuid.clone(), account.minecraft_access_token.clone())
+    };
+    let profile = set_active_cape(&access_token, cape_id.as_deref()).await?;
+    update_stored_profile(&account_uuid, &profile, &app_handle).await;
+    info!("Changed the cape of {} to {:?}", profile.name, cape_id);
+    Ok(profile)
 }
 
 #[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct TaggedLine {
-    line: String,
-    line_type: LineType,
+pub struct InstanceInformation {
+    #[serde(flatten)]
+    configuration: InstanceConfiguration,
+    /// Cached stats, `None` until they've been computed. Updates are sent with `instance-stats`.
+    stats: Option<InstanceStats>,
 }
 
-fn get_tag_for_line(line: &String) -> LineType {
-    if line.contains("/ERROR]:") {
-        LineType::Error
-    } else if line.contains("/WARN]:") {
-        LineType::Warning
-    } else if line.contains("/INFO]:") || line.contains("/DEBUG]:") {
-        LineType::Normal
-    } else {
-        LineType::Unknown
-    }
+#[tauri::command(async)]
+pub async fn load_instances(app_handle: AppHandle<Wry>) -> Vec<InstanceInformation> {
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+
+    instance_manager.deserialize_instances();
+    debug!("load_instances");
+    instance_manager.refresh_instance_stats(app_handle.clone());
+    instance_manager
+        .get_instance_configurations()
+        .into_iter()
+        .map(|configuration| InstanceInformation {
+            stats: instance_manager.get_cached_stats(&configuration.instance_name),
+            configuration,
+        })
+        .collect()
 }
 
-// Read bytes of log file and extract lines, decompressing gzip'd files if necessary
-pub fn read_log_file(path: &Path) -> io::Result<Vec<TaggedLine>> {
-    let bytes = fs::read(path)?;
-    let lines: Vec<String> = if !bytes.is_empty() && bytes[..2] == GZIP_SIGNATURE {
-        let mut decoder = GzDecoder::new(bytes.as_slice());
-        let mut tmp_str = String::new();
-        decoder.read_to_string(&mut tmp_str)?;
+async fn launch(
+    instance_name: String,
+    safe_mode: bool,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let resource_manager = ResourceManager::from_app_handle(&app_handle);
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
 
-        tmp_str.lines().map(|line| line.into()).collect()
-    } else {
-        BufReader::new(bytes.as_slice())
-            .lines()
-            .filter_map(|line| line.ok())
-            .collect()
+    let account_manager = AccountManager::from_app_handle(&app_handle).await;
+    let settings_manager = SettingsManager::from_app_handle(&app_handle).await;
+
+    let account = account_manager
+        .get_active_account()
+        .ok_or(CommandError::NoActiveAccount)?;
+    if account_manager.needs_reauth(&account.uuid) {
+        // Singleplayer still works with expired tokens, ask to sign in without blocking the launch.
+        warn!(
+            "Launching {} with an account that needs to sign in again",
+            instance_name
+        );
+        if let Err(e) = app_handle.emit_to("main", "account-needs-reauth", &account.uuid) {
+            error!("{}", e);
+        }
+    }
+    let (asset_index, assets_dir) = match (
+        instance_manager.get_instance(&instance_name),
+        instance_manager.instance_dir(&instance_name),
+    ) {
+        (Some(instance), Some(instance_dir)) => (
+            instance.asset_index.clone(),
+            resource_manager.instance_assets_dir(&instance_dir, instance.isolated_resources),
+        ),
+        _ => return Err(CommandError::UnknownInstance(instance_name)),
     };
-    let mut tagged_lines = Vec::with_capacity(lines.len());
-    let mut previous_tag = LineType::Normal;
-    for line in lines.into_iter() {
-        let line_type = get_tag_for_line(&line);
-        tagged_lines.push(if line_type != LineType::Unknown {
-            previous_tag = line_type.clone();
-            TaggedLine { line, line_type }
-        } else {
-            TaggedLine {
-                line,
-                line_type: previous_tag.clone(),
-            }
-        });
+    if let Some(asset_index) = asset_index {
+        ensure_asset_index(&assets_dir, &asset_index).await?;
     }
-    debug!("Done tagging log lines");
-
-    Ok(tagged_lines)
+    if safe_mode {
+        instance_manager.launch_instance_safe_mode(
+            &instance_name,
+            account,
+            settings_manager.get_settings(),
+            app_handle.clone(),
+        )?;
+    } else {
+        instance_manager.launch_instance(
+            &instance_name,
+            account,
+            settings_manager.get_settings(),
+            app_handle.clone(),
+        )?;
+    }
+    Ok(())
 }
 
+/// The command the instance would be launched with by the active account, without launching it.
 #[tauri::command(async)]
-pub async fn read_log_lines(
+pub async fn preview_launch_command(
     instance_name: String,
-    log_name: String,
     app_handle: AppHandle<Wry>,
-) -> Vec<TaggedLine> {
-    info!("Getting logs for {}", log_name);
+) -> CommandResult<LaunchCommand> {
     let instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    let account_manager = AccountManager::from_app_handle(&app_handle).await;
+    let settings_manager = SettingsManager::from_app_handle(&app_handle).await;
+
+    let account = account_manager
+        .get_active_account()
+        .ok_or(CommandError::NoActiveAccount)?;
+    instance_manager
+        .preview_launch_command(&instance_name, account, settings_manager.get_settings())
+        .ok_or(CommandError::UnknownInstance(instance_name))
+}
+
+/// The name to suggest for a new instance called `instance_name`, suffixed with ` (2)`, ` (3)`...
+/// if it is taken.
+#[tauri::command(async)]
+pub async fn get_available_instance_name(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<String> {
+    Ok(InstanceManager::from_app_handle(&app_handle)
+        .await
+        .available_instance_name(&instance_name))
+}
+
+#[tauri::command(async)]
+pub async fn launch_instance(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    launch(instance_name, false, app_handle).await
+}
+
+/// Launches the instance with all of its mods disabled, to check if a crash is caused by a mod.
+#[tauri::command(async)]
+pub async fn launch_instance_safe_mode(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    launch(instance_name, true, app_handle).await
+}
+
+async fn existing_instance_dir(
+    instance_name: String,
+    app_handle: &AppHandle<Wry>,
+) -> CommandResult<PathBuf> {
+    InstanceManager::from_app_handle(app_handle)
+        .await
+        .instance_dir(&instance_name)
+        .ok_or(CommandError::UnknownInstance(instance_name))
+}
+
+/// The `subdir` folder of an instance, which may not exist yet.
+async fn existing_instance_subdir(
+    instance_name: String,
+    subdir: InstanceSubdir,
+    app_handle: &AppHandle<Wry>,
+) -> CommandResult<PathBuf> {
+    InstanceManager::from_app_handle(app_handle)
+        .await
+        .instance_subdir(&instance_name, subdir)
+        .ok_or(CommandError::UnknownInstance(instance_name))
+}
+
+/// Looks for jars in the instance's mods folder that are the same mod or contain the same classes.
+#[tauri::command(async)]
+pub async fn scan_instance_conflicts(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Vec<JarConflict>> {
+    let mods_dir =
+        existing_instance_subdir(instance_name, InstanceSubdir::Mods, &app_handle).await?;
+    if !mods_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    Ok(scan_conflicts(&mods_dir)?)
+}
+
+/// The jar mods merged into the instance's client jar, `None` if it uses the vanilla jar.
+#[tauri::command(async)]
+pub async fn get_instance_jar_mods(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Option<JarMods>> {
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    Ok(read_jar_mods(&instance_dir)?)
+}
+
+/// Merges the zips and jars at `mod_paths` into a copy of the instance's vanilla client jar, in
+/// order, and launches the instance with it. This is how mods were installed before 1.6. An empty
+/// list goes back to the vanilla jar.
+#[tauri::command(async)]
+pub async fn set_instance_jar_mods(
+    instance_name: String,
+    mod_paths: Vec<PathBuf>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Option<JarMods>> {
+    let versions_dir = ResourceManager::from_app_handle(&app_handle).version_dir();
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    if instance_manager.is_running(&instance_name) {
+        return Err(CommandError::InstanceRunning(instance_name));
+    }
+    let (instance_dir, version) = match (
+        instance_manager.instance_dir(&instance_name),
+        instance_manager
+            .get_instance(&instance_name)
+            .and_then(InstanceConfiguration::vanilla_version),
+    ) {
+        (Some(instance_dir), Some(version)) => (instance_dir, version.to_owned()),
+        _ => return Err(CommandError::UnknownInstance(instance_name)),
+    };
+    let vanilla_jar = versions_dir
+        .join(&version)
+        .join(JarType::Client.side())
+        .join(format!("{}.jar", version));
+    if !vanilla_jar.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("The client jar of {} is not downloaded", version),
+        )
+        .into());
+    }
+
+    let jar_mods = apply_jar_mods(&instance_dir, &vanilla_jar, &mod_paths)?;
+    let vanilla_jar = vanilla_jar.to_string_lossy();
+    let custom_jar = custom_jar_path(&instance_dir);
+    let custom_jar = custom_jar.to_string_lossy();
+    let (from, to) = match jar_mods {
+        Some(_) => (vanilla_jar, custom_jar),
+        None => (custom_jar, vanilla_jar),
+    };
+    instance_manager.replace_in_arguments(&instance_name, &from, &to)?;
+    Ok(jar_mods)
+}
+
+/// Starts narrowing down which mod crashes an instance. Half of the mods are disabled, the user
+/// launches the instance and reports back with `report_mod_bisect_result`.
+#[tauri::command(async)]
+pub async fn start_mod_bisect(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<BisectStatus> {
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    Ok(start_bisect(&instance_dir)?)
+}
+
+#[tauri::command(async)]
+pub async fn report_mod_bisect_result(
+    instance_name: String,
+    crashed: bool,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<BisectStatus> {
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    Ok(record_bisect_result(&instance_dir, crashed)?)
+}
+
+/// The current step of the instance's bisect, if one is in progress.
+#[tauri::command(async)]
+pub async fn get_mod_bisect_status(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Option<BisectStatus>> {
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    Ok(load_session(&instance_dir)?.map(|session| session.status()))
+}
+
+#[tauri::command(async)]
+pub async fn cancel_mod_bisect(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    Ok(cancel_bisect(&instance_dir)?)
+}
+
+/// Sets the folder an instance is synced with, `None` stops syncing it.
+#[tauri::command(async)]
+pub async fn set_instance_sync(
+    instance_name: String,
+    sync: Option<InstanceSync>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    if !instance_manager.has_instance(&instance_name) {
+        return Err(CommandError::UnknownInstance(instance_name));
+    }
+    instance_manager.set_instance_sync(&instance_name, sync)?;
+    Ok(())
+}
+
+/// The heap sizes instances can be given, based on the machine's memory.
+#[tauri::command(async)]
+pub async fn get_memory_bounds() -> MemoryBounds {
+    MemoryBounds::detect()
+}
+
+/// Sets the heap size of an instance, `None` to leave it to the JVM.
+#[tauri::command(async)]
+pub async fn update_instance_memory(
+    instance_name: String,
+    max_memory_mb: Option<u64>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    if let Some(memory) = max_memory_mb {
+        let bounds = MemoryBounds::detect();
+        if !bounds.contains(memory) {
+            return Err(CommandError::MemoryOutOfBounds(memory, bounds));
+        }
+    }
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    if !instance_manager.has_instance(&instance_name) {
+        return Err(CommandError::UnknownInstance(instance_name));
+    }
+    instance_manager.set_instance_memory(&instance_name, max_memory_mb)?;
+    Ok(())
+}
+
+/// Sets whether an instance should run on the discrete GPU of hybrid graphics machines.
+#[tauri::command(async)]
+pub async fn set_instance_gpu_preference(
+    instance_name: String,
+    prefer_discrete_gpu: bool,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    if !instance_manager.has_instance(&instance_name) {
+        return Err(CommandError::UnknownInstance(instance_name));
+    }
+    instance_manager.set_instance_gpu_preference(&instance_name, prefer_discrete_gpu)?;
+    Ok(())
+}
+
+/// Sets how the instance is launched again when it crashes, `None` to leave it closed. Each restart
+/// is emitted as `instance-restart`.
+#[tauri::command(async)]
+pub async fn set_instance_restart_policy(
+    instance_name: String,
+    policy: Option<RestartPolicy>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    if !instance_manager.has_instance(&instance_name) {
+        return Err(CommandError::UnknownInstance(instance_name));
+    }
+    instance_manager.set_instance_restart_policy(&instance_name, policy)?;
+    Ok(())
+}
+
+/// Watches an instance for new loader builds, pack versions and mod updates, or stops watching it.
+/// `pack` is the pack version it was installed from, which is only checked for updates if given.
+#[tauri::command(async)]
+pub async fn watch_instance_updates(
+    instance_name: String,
+    watch: bool,
+    pack: Option<DeepLinkRequest>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    if !instance_manager.has_instance(&instance_name) {
+        return Err(CommandError::UnknownInstance(instance_name));
+    }
+    let update_watch = watch.then_some(UpdateWatch { pack });
+    instance_manager.set_instance_update_watch(&instance_name, update_watch)?;
+    if !watch {
+        let state = app_handle.state::<UpdateNotificationsState>();
+        let mut notifications = state
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        notifications.retain(|updates| updates.instance_name != instance_name);
+    }
+    Ok(())
+}
+
+/// The updates found for the watched instances by the last check, also emitted as
+/// `update-notifications` when they change.
+#[tauri::command(async)]
+pub async fn get_update_notifications(app_handle: AppHandle<Wry>) -> Vec<InstanceUpdates> {
+    let state = app_handle.state::<UpdateNotificationsState>();
+    let notifications = state
+        .0
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    notifications.clone()
+}
+
+/// Copies the keybinds, video or audio settings of `source_instance` to `target_instance`,
+/// returning how many options were copied.
+#[tauri::command(async)]
+pub async fn copy_instance_options(
+    source_instance: String,
+    target_instance: String,
+    categories: Vec<OptionCategory>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<usize> {
+    let src_dir = existing_instance_dir(source_instance, &app_handle).await?;
+    let dst_dir = existing_instance_dir(target_instance, &app_handle).await?;
+    Ok(copy_options(&src_dir, &dst_dir, &categories)?)
+}
+
+/// Saves the settings of an instance as a template new instances can be created from, optionally
+/// with its `options.txt` and mods.
+#[tauri::command(async)]
+pub async fn save_instance_template(
+    instance_name: String,
+    template_name: String,
+    include_options: bool,
+    include_mods: bool,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<InstanceTemplate> {
+    let templates_dir = ResourceManager::from_app_handle(&app_handle).templates_dir();
+    let instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    let config = match instance_manager.get_instance(&instance_name) {
+        Some(config) => config,
+        None => return Err(CommandError::UnknownInstance(instance_name)),
+    };
+    let instance_dir = match instance_manager.instance_dir(&instance_name) {
+        Some(instance_dir) => instance_dir,
+        None => return Err(CommandError::UnknownInstance(instance_name)),
+    };
+    Ok(save_template(
+        &templates_dir,
+        &template_name,
+        config,
+        &instance_dir,
+        include_options,
+        include_mods,
+    )?)
+}
+
+#[tauri::command(async)]
+pub async fn get_instance_templates(
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Vec<InstanceTemplate>> {
+    let templates_dir = ResourceManager::from_app_handle(&app_handle).templates_dir();
+    Ok(list_templates(&templates_dir)?)
+}
+
+#[tauri::command(async)]
+pub async fn delete_instance_template(
+    template_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let templates_dir = ResourceManager::from_app_handle(&app_handle).templates_dir();
+    delete_template(&templates_dir, &template_name)?;
+    Ok(())
+}
+
+/// Saves the keybinds of an instance's `options.txt` as a profile that can be applied to others.
+#[tauri::command(async)]
+pub async fn save_keybind_profile(
+    instance_name: String,
+    profile_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<KeybindProfile> {
+    let profiles_dir = ResourceManager::from_app_handle(&app_handle).keybind_profiles_dir();
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    Ok(keybind_profile::save_keybind_profile(
+        &profiles_dir,
+        &profile_name,
+        &instance_dir,
+    )?)
+}
+
+#[tauri::command(async)]
+pub async fn get_keybind_profiles(
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Vec<KeybindProfile>> {
+    let profiles_dir = ResourceManager::from_app_handle(&app_handle).keybind_profiles_dir();
+    Ok(list_keybind_profiles(&profiles_dir)?)
+}
+
+#[tauri::command(async)]
+pub async fn delete_keybind_profile(
+    profile_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let profiles_dir = ResourceManager::from_app_handle(&app_handle).keybind_profiles_dir();
+    Ok(keybind_profile::delete_keybind_profile(
+        &profiles_dir,
+        &profile_name,
+    )?)
+}
+
+/// Sets the profile's keybinds in an instance's `options.txt`, returning how many were set.
+#[tauri::command(async)]
+pub async fn apply_keybind_profile(
+    profile_name: String,
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<usize> {
+    let profiles_dir = ResourceManager::from_app_handle(&app_handle).keybind_profiles_dir();
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    Ok(keybind_profile::apply_keybind_profile(
+        &profiles_dir,
+        &profile_name,
+        &instance_dir,
+    )?)
+}
+
+/// Writes a keybind profile to `path` so it can be shared.
+#[tauri::command(async)]
+pub async fn export_keybind_profile(
+    profile_name: String,
+    path: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let profiles_dir = ResourceManager::from_app_handle(&app_handle).keybind_profiles_dir();
+    Ok(keybind_profile::export_keybind_profile(
+        &profiles_dir,
+        &profile_name,
+        Path::new(&path),
+    )?)
+}
+
+/// Saves the keybind profile exported to `path`, replacing a profile with the same name.
+#[tauri::command(async)]
+pub async fn import_keybind_profile(
+    path: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<KeybindProfile> {
+    let profiles_dir = ResourceManager::from_app_handle(&app_handle).keybind_profiles_dir();
+    Ok(keybind_profile::import_keybind_profile(
+        &profiles_dir,
+        Path::new(&path),
+    )?)
+}
+
+/// Moves an instance to the trash, it can be restored with `restore_from_trash` until the trash
+/// retention period is over.
+#[tauri::command(async)]
+pub async fn delete_instance(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<TrashEntry> {
+    let trash_dir = ResourceManager::from_app_handle(&app_handle).trash_dir();
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    if instance_manager.is_running(&instance_name) {
+        return Err(CommandError::InstanceRunning(instance_name));
+    }
+    let instance_dir = match instance_manager.instance_dir(&instance_name) {
+        Some(instance_dir) => instance_dir,
+        None => return Err(CommandError::UnknownInstance(instance_name)),
+    };
+    let entry = move_to_trash(
+        &trash_dir,
+        &instance_dir,
+        TrashKind::Instance,
+        &instance_name,
+    )?;
+    instance_manager.remove_instance(&instance_name);
+    Ok(entry)
+}
+
+/// Moves an instance to the data root named `data_root`, or the launcher's own folder for `None`.
+/// Emits `instance-move-progress` while it is copied to another drive.
+#[tauri::command(async)]
+pub async fn move_instance_to_data_root(
+    instance_name: String,
+    data_root: Option<String>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    #[derive(Serialize, Clone)]
+    #[serde(rename_all = "camelCase")]
+    struct MoveProgressEvent<'a> {
+        instance_name: &'a str,
+        #[serde(flatten)]
+        progress: MoveProgress,
+    }
+
+    let instances_dir = ResourceManager::from_app_handle(&app_handle)
+        .instances_dir_in(data_root.as_deref())
+        .ok_or_else(|| CommandError::UnknownDataRoot(data_root.clone().unwrap_or_default()))?;
+    // Forgotten while it is moved, so it can't be launched half copied.
+    let (mut config, instance_dir) = {
+        let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+        if instance_manager.is_running(&instance_name) {
+            return Err(CommandError::InstanceRunning(instance_name));
+        }
+        let instance_dir = match instance_manager.instance_dir(&instance_name) {
+            Some(instance_dir) => instance_dir,
+            None => return Err(CommandError::UnknownInstance(instance_name)),
+        };
+        let already_there = instance_manager
+            .get_instance(&instance_name)
+            .is_some_and(|config| config.data_root == data_root);
+        if already_there {
+            return Ok(());
+        }
+        match instance_manager.remove_instance(&instance_name) {
+            Some(config) => (config, instance_dir),
+            None => return Err(CommandError::UnknownInstance(instance_name)),
+        }
+    };
+
+    let directory_name = available_directory_name(&instances_dir, config.directory_name());
+    let new_instance_dir = instances_dir.join(&directory_name);
+    let moved = {
+        let (from, to) = (instance_dir.clone(), new_instance_dir.clone());
+        let (instance_name, app_handle) = (instance_name.clone(), app_handle.clone());
+        // Copying to another drive can take minutes, keep it off the async workers.
+        tauri::async_runtime::spawn_blocking(move || {
+            move_dir_with_progress(&from, &to, |progress| {
+                let event = MoveProgressEvent {
+                    instance_name: &instance_name,
+                    progress,
+                };
+                if let Err(e) = app_handle.emit_to("main", "instance-move-progress", event) {
+                    error!(
+                        "Could not emit the progress of moving {}: {}",
+                        instance_name, e
+                    );
+                }
+            })
+        })
+        .await
+    };
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    if let Ok(Ok(())) = moved {
+        // The game and natives folders are absolute paths in the launch arguments.
+        config.replace_in_arguments(
+            &instance_dir.to_string_lossy(),
+            &new_instance_dir.to_string_lossy(),
+        );
+        config.directory_name = directory_name;
+        config.data_root = data_root;
+        instance_manager.add_instance(config)?;
+        // Whatever couldn't be removed from the old folder mustn't load as a second copy.
+        let old_config = instance_dir.join("config.json");
+        if old_config.exists() {
+            if let Err(e) = fs::remove_file(&old_config) {
+                warn!("Could not remove {}: {}", old_config.display(), e);
+            }
+        }
+    }
+    instance_manager.deserialize_instances();
+    moved??;
+    Ok(())
+}
+
+/// Moves a world from the instance's `saves` folder to the trash.
+#[tauri::command(async)]
+pub async fn delete_world(
+    instance_name: String,
+    world_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<TrashEntry> {
+    let trash_dir = ResourceManager::from_app_handle(&app_handle).trash_dir();
+    let saves_dir =
+        existing_instance_subdir(instance_name.clone(), InstanceSubdir::Saves, &app_handle).await?;
+    let world_dir = child_path(&saves_dir, &world_name)?;
+    Ok(move_to_trash(
+        &trash_dir,
+        &world_dir,
+        TrashKind::World,
+        &instance_name,
+    )?)
+}
+
+/// Moves a mod from the instance's `mods` folder to the trash.
+#[tauri::command(async)]
+pub async fn remove_mod(
+    instance_name: String,
+    file_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<TrashEntry> {
+    let trash_dir = ResourceManager::from_app_handle(&app_handle).trash_dir();
+    let mods_dir =
+        existing_instance_subdir(instance_name.clone(), InstanceSubdir::Mods, &app_handle).await?;
+    let mod_path = child_path(&mods_dir, &file_name)?;
+    Ok(move_to_trash(
+        &trash_dir,
+        &mod_path,
+        TrashKind::Mod,
+        &instance_name,
+    )?)
+}
+
+/// Everything in the trash, most recently deleted first.
+#[tauri::command(async)]
+pub async fn get_trash(app_handle: AppHandle<Wry>) -> CommandResult<Vec<TrashEntry>> {
+    let trash_dir = ResourceManager::from_app_handle(&app_handle).trash_dir();
+    Ok(list_trash(&trash_dir)?)
+}
+
+/// Moves a trashed instance back to the instances folder, or a world or mod back into its
+/// instance. Fails if something has taken its place since.
+#[tauri::command(async)]
+pub async fn restore_from_trash(
+    id: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<TrashEntry> {
+    let trash_dir = ResourceManager::from_app_handle(&app_handle).trash_dir();
+    let entry = get_trash_entry(&trash_dir, &id)?;
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    let target = match entry.kind {
+        TrashKind::Instance => {
+            if instance_manager.has_instance(&entry.instance_name) {
+                return Err(ManifestError::InstanceExists(entry.instance_name).into());
+            }
+            child_path(&instance_manager.instances_dir(), &entry.name)?
+        }
+        kind => {
+            let subdir = if kind == TrashKind::World {
+                InstanceSubdir::Saves
+            } else {
+                InstanceSubdir::Mods
+            };
+            let dir = instance_manager
+                .instance_subdir(&entry.instance_name, subdir)
+                .ok_or_else(|| CommandError::UnknownInstance(entry.instance_name.clone()))?;
+            child_path(&dir, &entry.name)?
+        }
+    };
+    let entry = restore_trash_entry(&trash_dir, &id, &target)?;
+    if entry.kind == TrashKind::Instance {
+        instance_manager.deserialize_instances();
+    }
+    Ok(entry)
+}
+
+/// Syncs an instance with its folder now instead of waiting for the next launch.
+#[tauri::command(async)]
+pub async fn sync_instance_now(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Option<SyncReport>> {
+    let sync = InstanceManager::from_app_handle(&app_handle)
+        .await
+        .get_instance_sync(&instance_name);
+    let instance_dir = existing_instance_dir(instance_name.clone(), &app_handle).await?;
+    match sync {
+        Some(sync) => Ok(Some(sync_instance(&instance_dir, &instance_name, &sync)?)),
+        None => Ok(None),
+    }
+}
+
+#[tauri::command(async)]
+pub async fn get_launcher_settings(app_handle: AppHandle<Wry>) -> LauncherSettings {
+    let settings_manager = SettingsManager::from_app_handle(&app_handle).await;
+    settings_manager.get_settings().clone()
+}
+
+#[tauri::command(async)]
+pub async fn update_launcher_settings(
+    settings: LauncherSettings,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let data_roots = settings.data_roots.clone();
+    let mut settings_manager = SettingsManager::from_app_handle(&app_handle).await;
+    settings_manager.set_settings(settings);
+    settings_manager.serialize_settings()?;
+    drop(settings_manager);
+
+    ResourceManager::from_app_handle(&app_handle).set_data_roots(data_roots);
+    InstanceManager::from_app_handle(&app_handle)
+        .await
+        .deserialize_instances();
+    Ok(())
+}
+
+/// Everything the first-run flow needs: whether it already ran, where data is stored, memory
+/// defaults and other launchers to offer importing from.
+#[tauri::command(async)]
+pub async fn get_onboarding_info(app_handle: AppHandle<Wry>) -> CommandResult<OnboardingInfo> {
+    let first_run_completed = SettingsManager::from_app_handle(&app_handle)
+        .await
+        .get_settings()
+        .first_run_completed;
+    let path_resolver = app_handle.path();
+    Ok(OnboardingInfo::detect(
+        first_run_completed,
+        &path_resolver.app_config_dir()?,
+        &path_resolver.home_dir()?,
+        &path_resolver.data_dir()?,
+    ))
+}
+
+#[tauri::command(async)]
+pub async fn complete_first_run(app_handle: AppHandle<Wry>) -> CommandResult<()> {
+    let mut settings_manager = SettingsManager::from_app_handle(&app_handle).await;
+    settings_manager.complete_first_run();
+    settings_manager.serialize_settings()?;
+    Ok(())
+}
+
+/// Answers `exit-requested`, cancelling the running tasks or waiting for them before exiting.
+#[tauri::command(async)]
+pub async fn exit_launcher(cancel_tasks: bool, app_handle: AppHandle<Wry>) {
+    exit_after_tasks(&app_handle, cancel_tasks).await;
+}
+
+/// The message templates for each error kind in `locale`, falling back to english.
+#[tauri::command(async)]
+pub async fn get_error_messages(locale: String) -> HashMap<&'static str, &'static str> {
+    error_catalog(&locale)
+        .or_else(|| error_catalog("en"))
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogScope {
+    name: &'static str,
+    enabled: bool,
+}
+
+#[tauri::command(async)]
+pub async fn get_log_scopes() -> Vec<LogScope> {
+    LOG_SCOPES
+        .iter()
+        .map(|&name| LogScope {
+            name,
+            enabled: scope_enabled(name),
+        })
+        .collect()
+}
+
+/// Turns verbose logging for `scope` on or off and saves it in the launcher settings.
+#[tauri::command(async)]
+pub async fn set_log_scope(
+    scope: String,
+    enabled: bool,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    if !LOG_SCOPES.contains(&scope.as_str()) {
+        return Err(CommandError::UnknownLogScope(scope));
+    }
+    let mut settings_manager = SettingsManager::from_app_handle(&app_handle).await;
+    let mut settings = settings_manager.get_settings().clone();
+    settings
+        .verbose_log_scopes
+        .retain(|enabled| *enabled != scope);
+    if enabled {
+        settings.verbose_log_scopes.push(scope);
+    }
+    settings_manager.set_settings(settings);
+    settings_manager.serialize_settings()?;
+    Ok(())
+}
+
+/// Opens `path` with the system's default application, folders open in the file manager.
+fn open_with_default_application(path: &Path, app_handle: &AppHandle<Wry>) -> CommandResult<()> {
+    if let Err(e) = app_handle
+        .opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+    {
+        error!(
+            "Error opening {} with the default application: {}",
+            path.display(),
+            e
+        );
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// `path` with symlinks and `..` resolved, if it is inside one of `roots`.
+fn managed_path(path: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    let path = path.canonicalize().ok()?;
+    roots
+        .iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .any(|root| path.starts_with(root))
+        .then_some(path)
+}
+
+#[tauri::command(async)]
+pub async fn open_folder(instance_name: String, app_handle: AppHandle<Wry>) -> CommandResult<()> {
+    debug!("open_folder with name: {}", instance_name);
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    open_with_default_application(&instance_dir, &app_handle)
+}
+
+/// Opens a folder the launcher manages, like an instance's screenshots or logs, the launcher's own
+/// logs or the trash, in the file manager. Files are shown in their folder rather than run by
+/// whatever the system opens them with, which for a jar or a script would execute it. Other paths
+/// are refused.
+#[tauri::command(async)]
+pub async fn open_path(path: String, app_handle: AppHandle<Wry>) -> CommandResult<()> {
+    debug!("open_path with path: {}", path);
+    let resource_manager = ResourceManager::from_app_handle(&app_handle);
+    let mut roots: Vec<PathBuf> = resource_manager
+        .all_instances_dirs()
+        .into_iter()
+        .map(|(_, instances_dir)| instances_dir)
+        .collect();
+    roots.push(app_handle.path().app_log_dir()?);
+    roots.push(resource_manager.trash_dir());
+    let path = managed_path(Path::new(&path), &roots).ok_or(CommandError::UnmanagedPath(path))?;
+    let folder = match path.parent() {
+        Some(parent) if !path.is_dir() => parent,
+        _ => &path,
+    };
+    open_with_default_application(folder, &app_handle)
+}
+
+#[tauri::command(async)]
+pub async fn get_screenshots(
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<HashMap<String, Vec<String>>> {
+    let instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+
+    let mut instance_screenshots = HashMap::new();
+    for (instance, screenshots_dir) in
+        instance_manager.instance_subdirs(InstanceSubdir::Screenshots)
+    {
+        let paths = fs::read_dir(screenshots_dir);
+
+        if let Ok(paths) = paths {
+            let mut screenshots: Vec<String> = Vec::new();
+            for path in paths.filter_map(|path| path.ok()) {
+                screenshots.push(path.path().to_string_lossy().into_owned());
+            }
+            instance_screenshots.insert(instance, screenshots);
+        }
+    }
+    info!(
+        "Found {} screenshots across all intances",
+        instance_screenshots.len()
+    );
+    Ok(instance_screenshots)
+}
+
+/// Maps each instance to the names of the files in its folder from `instance_subdirs`.
+fn create_instance_file_map(
+    instance_subdirs: &[(String, PathBuf)],
+) -> io::Result<HashMap<String, Vec<String>>> {
+    let mut result = HashMap::new();
+
+    for (instance, subdir) in instance_subdirs {
+        let directory_entries = fs::read_dir(subdir);
+        if directory_entries.is_err() {
+            result.insert(instance.clone(), Vec::new());
+            continue;
+        }
+
+        // Traverse every entry in the dir
+        for dir_entry in directory_entries? {
+            let path = dir_entry?.path();
+            if let (true, Some(filename)) = (path.is_file(), path.file_name()) {
+                result
+                    .entry(instance.to_owned())
+                    .or_insert_with(Vec::new)
+                    .push(filename.to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+#[tauri::command(async)]
+pub async fn get_logs(app_handle: AppHandle<Wry>) -> CommandResult<HashMap<String, Vec<String>>> {
+    let instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+
+    Ok(create_instance_file_map(
+        &instance_manager.instance_subdirs(InstanceSubdir::Logs),
+    )?)
+}
+
+#[tauri::command(async)]
+pub async fn get_crash_reports(
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<HashMap<String, Vec<String>>> {
+    let instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+
+    Ok(create_instance_file_map(
+        &instance_manager.instance_subdirs(InstanceSubdir::CrashReports),
+    )?)
+}
+
+/// The crashes of an instance with the mods and versions it was launched with, compared to the
+/// last launch that didn't crash.
+#[tauri::command(async)]
+pub async fn list_incidents(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Vec<CrashIncident>> {
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    Ok(crash_incident::list_incidents(&instance_dir)?)
+}
+
+/// Reads a crash report, deobfuscating its stack traces if the client mappings for its version
+/// have been downloaded.
+#[tauri::command(async)]
+pub async fn read_crash_report(
+    instance_name: String,
+    report_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<String> {
+    let crash_reports_dir =
+        existing_instance_subdir(instance_name, InstanceSubdir::CrashReports, &app_handle).await?;
+    let path = crash_report_path(&crash_reports_dir, &report_name)?;
+    let report = fs::read_to_string(&path)?;
+
+    let version = match crash_report_version(&report) {
+        Some(version) => version,
+        None => return Ok(report),
+    };
+    let versions_dir = ResourceManager::from_app_handle(&app_handle).version_dir();
+    let mappings_path = mappings_path(&versions_dir, JarType::Client, version);
+    if !mappings_path.is_file() {
+        debug!("No client mappings for {}, skipping deobfuscation", version);
+        return Ok(report);
+    }
+
+    let mappings = Mappings::load(&mappings_path)?;
+    Ok(mappings.deobfuscate(&report))
+}
+
+#[derive(Debug, Serialize, PartialEq, Clone)]
+#[serde(rename_all = "lowercase")]
+#[repr(u8)]
+enum LineType {
+    Unknown,
+    Normal,
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaggedLine {
+    line: String,
+    line_type: LineType,
+}
+
+fn get_tag_for_line(line: &String) -> LineType {
+    if line.contains("/ERROR]:") {
+        LineType::Error
+    } else if line.contains("/WARN]:") {
+        LineType::Warning
+    } else if line.contains("/INFO]:") || line.contains("/DEBUG]:") {
+        LineType::Normal
+    } else {
+        LineType::Unknown
+    }
+}
+
+/// Tags a line of the launcher's own log, written as plain text or json lines.
+fn get_tag_for_launcher_line(line: &String) -> LineType {
+    let level = match serde_json::from_str::<serde_json::Value>(line) {
+        Ok(json) => json["level"].as_str().map(str::to_owned),
+        Err(_) => line
+            .split_once("] - ")
+            .and_then(|(prefix, _)| prefix.rsplit_once("]["))
+            .map(|(_, level)| level.to_owned()),
+    };
+    match level.as_deref() {
+        Some("ERROR") => LineType::Error,
+        Some("WARN") => LineType::Warning,
+        Some("INFO" | "DEBUG" | "TRACE") => LineType::Normal,
+        _ => LineType::Unknown,
+    }
+}
+
+// Read bytes of log file and extract lines, decompressing gzip'd files if necessary. Lines that
+// `tag_line` can't tag, like stack traces, continue the previous line's tag.
+fn read_log_file(path: &Path, tag_line: fn(&String) -> LineType) -> io::Result<Vec<TaggedLine>> {
+    let bytes = fs::read(path)?;
+    let lines: Vec<String> = if bytes.starts_with(&GZIP_SIGNATURE) {
+        let mut decoder = GzDecoder::new(bytes.as_slice());
+        let mut tmp_str = String::new();
+        decoder.read_to_string(&mut tmp_str)?;
 
-    let instance_dir = instance_manager.instances_dir();
+        tmp_str.lines().map(|line| line.into()).collect()
+    } else {
+        BufReader::new(bytes.as_slice())
+            .lines()
+            .filter_map(|line| line.ok())
+            .collect()
+    };
+    let mut tagged_lines = Vec::with_capacity(lines.len());
+    let mut previous_tag = LineType::Normal;
+    for line in lines.into_iter() {
+        let line_type = tag_line(&line);
+        tagged_lines.push(if line_type != LineType::Unknown {
+            previous_tag = line_type.clone();
+            TaggedLine { line, line_type }
+        } else {
+            TaggedLine {
+                line,
+                line_type: previous_tag.clone(),
+            }
+        });
+    }
+    debug!("Done tagging log lines");
+
+    Ok(tagged_lines)
+}
 
-    let path = instance_dir.join(instance_name).join("logs").join(log_name);
+#[tauri::command(async)]
+pub async fn read_log_lines(
+    instance_name: String,
+    log_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Vec<TaggedLine>> {
+    info!("Getting logs for {}", log_name);
+    let logs_dir =
+        existing_instance_subdir(instance_name, InstanceSubdir::Logs, &app_handle).await?;
+
+    let path = child_path(&logs_dir, &log_name)?;
     debug!("path: {:#?}", path);
-    read_log_file(&path).unwrap()
+    Ok(read_log_file(&path, get_tag_for_line)?)
+}
+
+/// The editable properties of the server run in the instance's folder, the defaults if it hasn't
+/// been started yet.
+#[tauri::command(async)]
+pub async fn get_server_properties(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<ServerProperties> {
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    Ok(read_server_properties(&instance_dir)?)
+}
+
+/// Saves the properties to the `server.properties` in the instance's folder, keeping the ones the
+/// launcher doesn't edit. They are applied the next time the server starts.
+#[tauri::command(async)]
+pub async fn update_server_properties(
+    instance_name: String,
+    properties: ServerProperties,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    write_server_properties(&instance_dir, &properties)?;
+    Ok(())
+}
+
+/// Opens a window showing the live output of the instance, to keep it on another monitor. It
+/// receives the game's lines as `console-line` events and its exit code as `console-exit`.
+#[tauri::command(async)]
+pub async fn open_console_window(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    existing_instance_dir(instance_name.clone(), &app_handle).await?;
+    SecondaryWindow::Console {
+        instance_name: &instance_name,
+    }
+    .open(&app_handle)?;
+    Ok(())
+}
+
+/// Opens one of the instance's logs in its own window, it reads the log with `read_log_lines`.
+#[tauri::command(async)]
+pub async fn open_log_viewer_window(
+    instance_name: String,
+    log_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let logs_dir =
+        existing_instance_subdir(instance_name.clone(), InstanceSubdir::Logs, &app_handle).await?;
+    if !child_path(&logs_dir, &log_name)?.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} has no log named {}", instance_name, log_name),
+        )
+        .into());
+    }
+    SecondaryWindow::LogViewer {
+        instance_name: &instance_name,
+        log_name: &log_name,
+    }
+    .open(&app_handle)?;
+    Ok(())
+}
+
+/// Names of the launcher's own logs in `log_dir`, `latest.log` first and then newest first.
+fn launcher_log_names(log_dir: &Path) -> io::Result<Vec<String>> {
+    let mut names: Vec<String> = fs::read_dir(log_dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.starts_with("launcher_log_") && name.ends_with(".log"))
+        .collect();
+    // The datetime in their names sorts chronologically.
+    names.sort_by(|a, b| b.cmp(a));
+    if log_dir.join(LATEST_LAUNCHER_LOG).is_file() {
+        names.insert(0, LATEST_LAUNCHER_LOG.into());
+    }
+    Ok(names)
+}
+
+#[tauri::command(async)]
+pub async fn get_launcher_logs(app_handle: AppHandle<Wry>) -> CommandResult<Vec<String>> {
+    let log_dir = app_handle.path().app_log_dir()?;
+    Ok(launcher_log_names(&log_dir)?)
+}
+
+/// Reads one of the launcher's own logs, as listed by `get_launcher_logs`.
+#[tauri::command(async)]
+pub async fn read_launcher_log_lines(
+    log_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Vec<TaggedLine>> {
+    let log_dir = app_handle.path().app_log_dir()?;
+    // Only the listed logs can be read, not arbitrary paths.
+    if !launcher_log_names(&log_dir)?.contains(&log_name) {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("There is no launcher log named {}", log_name),
+        )
+        .into());
+    }
+    Ok(read_log_file(
+        &log_dir.join(log_name),
+        get_tag_for_launcher_line,
+    )?)
+}
+
+/// The machine's OS, architecture, memory and GPUs, with the free space of the drives the launcher
+/// stores instances and downloads on.
+#[tauri::command(async)]
+pub async fn get_system_info(app_handle: AppHandle<Wry>) -> CommandResult<SystemInfo> {
+    let dirs = storage_dirs(&app_handle)?;
+    Ok(SystemInfo::detect(
+        &dirs.iter().map(PathBuf::as_path).collect::<Vec<_>>(),
+    ))
+}
+
+/// The directories the launcher stores files in: its own, the java installs and every data root's
+/// instances. Data roots are usually on other drives than the launcher's directory.
+fn storage_dirs(app_handle: &AppHandle<Wry>) -> CommandResult<Vec<PathBuf>> {
+    let resource_manager = ResourceManager::from_app_handle(app_handle);
+    let mut dirs = vec![
+        app_handle.path().app_config_dir()?,
+        resource_manager.java_dir(),
+    ];
+    dirs.extend(
+        resource_manager
+            .all_instances_dirs()
+            .into_iter()
+            .map(|(_, instances_dir)| instances_dir),
+    );
+    Ok(dirs)
+}
+
+/// The output of the forge installer's processors from when the instance was created, `None` if
+/// the instance didn't need them.
+#[tauri::command(async)]
+pub async fn read_forge_install_log(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Option<String>> {
+    let instance_dir = existing_instance_dir(instance_name, &app_handle).await?;
+    match fs::read(instance_dir.join(FORGE_INSTALL_LOG)) {
+        Ok(bytes) => Ok(Some(String::from_utf8_lossy(&bytes).into_owned())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Zips the launcher's latest log, system info and, if `instance_name` is given, that instance's
+/// config, latest log and newest crash report to `path` so they can be attached to bug reports.
+#[tauri::command(async)]
+pub async fn generate_support_bundle(
+    instance_name: Option<String>,
+    path: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let instance_dir = match instance_name {
+        Some(instance_name) => Some(existing_instance_dir(instance_name, &app_handle).await?),
+        None => None,
+    };
+    let dirs = storage_dirs(&app_handle)?;
+    let log_dir = app_handle.path().app_log_dir()?;
+    let home_dir = app_handle.path().home_dir().ok();
+    Ok(write_support_bundle(
+        Path::new(&path),
+        SystemInfo::detect(&dirs.iter().map(PathBuf::as_path).collect::<Vec<_>>()),
+        &log_dir,
+        instance_dir.as_deref(),
+        home_dir.as_deref(),
+    )?)
+}
+
+/// Import a curseforge `.zip` or modrinth `.mrpack` at `path`, chosen by its extension.
+/// `optional_files` are the optional files of a modrinth pack to install.
+async fn import_modpack_archive(
+    path: &Path,
+    optional_files: &[String],
+    app_handle: &AppHandle<Wry>,
+) -> CommandResult<()> {
+    // Open the zip archive at `path`
+    let zip_file = File::open(path)?;
+    let mut archive = ZipArchive::new(&zip_file).map_err(PackFormatError::from)?;
+
+    let pack_name = match path.extension() {
+        Some(extension) if extension == "zip" => {
+            let manifest = extract_manifest_from_curseforge_zip(&mut archive)?;
+            let pack_name = manifest.modpack_name().to_owned();
+            import_curseforge_zip(manifest, &mut archive, app_handle).await?;
+            pack_name
+        }
+        Some(extension) if extension == "mrpack" => {
+            let manifest = read_modrinth_manifest(&mut archive)?;
+            let pack_name = manifest.name().to_owned();
+            import_modrinth_zip(manifest, &mut archive, optional_files, app_handle).await?;
+            pack_name
+        }
+        extension => {
+            return Err(PackFormatError::UnsupportedExtension(
+                extension.unwrap_or_default().to_string_lossy().into(),
+            )
+            .into())
+        }
+    };
+    notify(
+        app_handle,
+        "Modpack installed",
+        &format!("{} is ready to play", pack_name),
+    )
+    .await;
+    Ok(())
+}
+
+/// The files of a modrinth pack the user can pick from before importing it, curseforge packs
+/// have none.
+#[tauri::command(async)]
+pub async fn get_modpack_optional_files(zip_path: String) -> CommandResult<Vec<OptionalPackFile>> {
+    let path = PathBuf::from(&zip_path);
+    if path
+        .extension()
+        .map_or(true, |extension| extension != "mrpack")
+    {
+        return Ok(Vec::new());
+    }
+    let zip_file = File::open(&path)?;
+    let mut archive = ZipArchive::new(&zip_file).map_err(PackFormatError::from)?;
+    let manifest = read_modrinth_manifest(&mut archive)?;
+    Ok(modrinth_optional_files(&manifest))
 }
 
 #[tauri::command(async)]
-pub async fn import_zip(zip_path: String, app_handle: AppHandle<Wry>) {
+pub async fn import_zip(
+    zip_path: String,
+    optional_files: Option<Vec<String>>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    ensure_online(&app_handle)?;
     info!("Imporing modpack from {}", zip_path);
     let path = PathBuf::from(&zip_path);
 
-    // Open the zip archive at `zip_path`
-    let zip_file = File::open(&path).unwrap();
-    let mut archive = ZipArchive::new(&zip_file).unwrap();
+    import_modpack_archive(&path, &optional_files.unwrap_or_default(), &app_handle).await?;
 
-    match path.extension() {
-        Some(extension) if extension == "zip" => import_curseforge_zip(&mut archive, &app_handle)
-            .await
-            .unwrap(),
-        Some(extension) if extension == "mrpack" => import_modrinth_zip(&mut archive, &app_handle)
+    debug!("Invoked import_zip: {}", zip_path);
+    Ok(())
+}
+
+/// Returns the install requests from deep links the launcher was started with.
+#[tauri::command(async)]
+pub async fn get_startup_deep_links(app_handle: AppHandle<Wry>) -> Vec<DeepLinkRequest> {
+    let state = app_handle.state::<DeepLinkState>();
+    let mut pending = state
+        .0
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    pending.drain(..).collect()
+}
+
+/// Whether the launcher was started with `--safe-mode`, without refreshing accounts or using the
+/// network.
+#[tauri::command(async)]
+pub async fn get_safe_mode(app_handle: AppHandle<Wry>) -> bool {
+    app_handle.state::<SafeModeState>().0
+}
+
+/// Problems found with the launcher's files on startup, also emitted as `startup-diagnostics`
+/// before the frontend may have been listening.
+#[tauri::command(async)]
+pub async fn get_startup_diagnostics(app_handle: AppHandle<Wry>) -> Vec<Diagnostic> {
+    app_handle.state::<StartupDiagnosticsState>().0.clone()
+}
+
+/// Downloads and imports the pack from a deep link once the user has accepted the install prompt.
+#[tauri::command(async)]
+pub async fn install_deep_link(
+    request: DeepLinkRequest,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    ensure_online(&app_handle)?;
+    info!("Installing modpack from deep link {:?}", request);
+    let cache_dir = ResourceManager::from_app_handle(&app_handle).modpack_cache_dir();
+
+    let path = match &request {
+        DeepLinkRequest::Curseforge {
+            project_id,
+            file_id,
+        } => download_curseforge_modpack(*project_id, *file_id, &cache_dir)
             .await
-            .unwrap(),
-        _ => {}
-    }
+            .map_err(ManifestError::from)?,
+        DeepLinkRequest::Modrinth { version_id, .. } => {
+            download_modrinth_modpack(version_id, &cache_dir)
+                .await
+                .map_err(ManifestError::from)?
+        }
+    };
 
-    debug!("Invoked import_zip: {}", zip_path);
+    import_modpack_archive(&path, &[], &app_handle).await?;
+    Ok(())
+}
+
+/// The directory of an instance and what's needed to recreate it elsewhere.
+async fn instance_bundle_info(
+    instance_name: String,
+    app_handle: &AppHandle<Wry>,
+) -> CommandResult<(PathBuf, BundleInfo)> {
+    let configuration = instance_configuration(instance_name.clone(), app_handle).await?;
+    let info = BundleInfo::new(&configuration)?;
+    let instance_dir = existing_instance_dir(instance_name, app_handle).await?;
+    Ok((instance_dir, info))
+}
+
+/// Installs the instance in an exported or shared bundle, then extracts its files.
+async fn import_bundle(
+    bundle_path: &Path,
+    passphrase: Option<&str>,
+    app_handle: &AppHandle<Wry>,
+) -> CommandResult<()> {
+    let bundle = File::open(bundle_path)?;
+    let mut archive = ZipArchive::new(&bundle).map_err(InstanceExportError::from)?;
+    let info = read_bundle_info(&mut archive, passphrase)?;
+
+    let instances_dir = {
+        let mut instance_manager = InstanceManager::from_app_handle(app_handle).await;
+        instance_manager.deserialize_instances();
+        if instance_manager.has_instance(&info.instance_name) {
+            return Err(InstanceExportError::InstanceExists(info.instance_name).into());
+        }
+        instance_manager.instances_dir()
+    };
+
+    let mut settings = info.instance_settings();
+    // The bundle may come from another machine, only its folder is held to this machine's rules,
+    // the name it's shown with is kept as it was.
+    settings.directory_name = available_directory_name(&instances_dir, &info.instance_name);
+    let instance_dir = instances_dir.join(&settings.directory_name);
+    create_instance(settings, app_handle, Some(&info.author)).await?;
+    extract_bundle(&instance_dir, &mut archive, passphrase)?;
+
+    InstanceManager::from_app_handle(app_handle)
+        .await
+        .deserialize_instances();
+    app_handle.emit("new-instance", info.instance_name)?;
+    Ok(())
+}
+
+/// Exports an instance to a zip at `path`. With a `passphrase` the zip is encrypted with AES-256,
+/// for backups kept somewhere shared.
+#[tauri::command(async)]
+pub async fn export_instance(
+    instance_name: String,
+    path: String,
+    passphrase: Option<String>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    let (instance_dir, info) = instance_bundle_info(instance_name, &app_handle).await?;
+    let passphrase = passphrase.filter(|passphrase| !passphrase.is_empty());
+    write_bundle(
+        &instance_dir,
+        &info,
+        Path::new(&path),
+        passphrase.as_deref(),
+    )?;
+    Ok(())
+}
+
+/// Imports an instance exported with `export_instance`, `passphrase` is needed if it's encrypted.
+#[tauri::command(async)]
+pub async fn import_instance_export(
+    path: String,
+    passphrase: Option<String>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    info!("Importing instance export from {}", path);
+    let passphrase = passphrase.filter(|passphrase| !passphrase.is_empty());
+    import_bundle(Path::new(&path), passphrase.as_deref(), &app_handle).await
+}
+
+/// Shares an instance with launchers on the local network. Other launchers import it with the
+/// returned code, replacing any instance that was already being shared.
+#[tauri::command(async)]
+pub async fn start_instance_lan_share(
+    instance_name: String,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<LanShareInfo> {
+    ensure_online(&app_handle)?;
+    let (instance_dir, info) = instance_bundle_info(instance_name, &app_handle).await?;
+    let bundle_dir = ResourceManager::from_app_handle(&app_handle).create_tmp_dir("lan-share")?;
+    let share = start_lan_share(&instance_dir, &info, bundle_dir).await?;
+    let share_info = share.info();
+    LanShareManager::from_app_handle(&app_handle)
+        .await
+        .set_share(share);
+    Ok(share_info)
+}
+
+/// The instance currently shared on the local network, if any.
+#[tauri::command(async)]
+pub async fn get_instance_lan_share(app_handle: AppHandle<Wry>) -> Option<LanShareInfo> {
+    LanShareManager::from_app_handle(&app_handle)
+        .await
+        .share_info()
+}
+
+#[tauri::command(async)]
+pub async fn stop_instance_lan_share(app_handle: AppHandle<Wry>) {
+    LanShareManager::from_app_handle(&app_handle)
+        .await
+        .stop_share();
+}
+
+/// Imports an instance shared by another launcher on the local network. The sharing launcher is
+/// found by broadcasting `code` unless its `address` is given.
+#[tauri::command(async)]
+pub async fn import_instance_lan_share(
+    code: String,
+    address: Option<String>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    ensure_online(&app_handle)?;
+    let tmp_dir = ResourceManager::from_app_handle(&app_handle).create_tmp_dir("lan-share")?;
+    let bundle_path = download_lan_share(&code, address.as_deref(), tmp_dir.path()).await?;
+    import_bundle(&bundle_path, None, &app_handle).await?;
+    tmp_dir.close()?;
+    Ok(())
+}
+
+/// The most recent install report, for `instance_name` if given.
+#[tauri::command(async)]
+pub async fn get_last_install_report(
+    instance_name: Option<String>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Option<InstallReport>> {
+    let path = install_reports_path(&app_handle)?;
+    Ok(last_install_report(&path, instance_name.as_deref())?)
+}
+
+/// Categories rarely change, they're only refetched once a day.
+const CATEGORIES_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[tauri::command(async)]
+pub async fn get_curseforge_categories(
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Vec<CurseforgeCategory>> {
+    ensure_online(&app_handle)?;
+    let path = api_cache_path(&app_handle, "curseforge_categories")?;
+    Ok(cached_response(&path, CATEGORIES_TTL, retrieve_curseforge_categories()).await?)
 }
 
 #[tauri::command(async)]
-pub async fn get_curseforge_categories() -> Vec<CurseforgeCategory> {
-    retrieve_curseforge_categories().await.unwrap()
+pub async fn get_modrinth_categories(
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Vec<ModrinthCategory>> {
+    ensure_online(&app_handle)?;
+    let path = api_cache_path(&app_handle, "modrinth_categories")?;
+    Ok(cached_response(&path, CATEGORIES_TTL, retrieve_modrinth_categories()).await?)
 }
 
 #[derive(Debug, Serialize)]
@@ -431,6 +2362,10 @@ pub struct ModpackInformation {
     authors: Vec<CurseforgeSearchAuthors>,
     logo: CurseforgeSearchImage,
     categories: Vec<CurseforgeCategory>,
+    class_id: u32,
+    main_file_id: u32,
+    /// Set when searching for an instance and the result can be added to it.
+    compatibility: Option<SearchCompatibility>,
 }
 
 impl From<CurseforgeSearchEntry> for ModpackInformation {
@@ -444,39 +2379,270 @@ impl From<CurseforgeSearchEntry> for ModpackInformation {
             authors: value.authors,
             logo: value.logo,
             categories,
+            class_id: value.class_id,
+            main_file_id: value.main_file_id,
+            compatibility: None,
         }
     }
 }
 
+/// The configuration of the instance named `instance_name`.
+async fn instance_configuration(
+    instance_name: String,
+    app_handle: &AppHandle<Wry>,
+) -> CommandResult<InstanceConfiguration> {
+    InstanceManager::from_app_handle(app_handle)
+        .await
+        .get_instance_configurations()
+        .into_iter()
+        .find(|configuration| configuration.instance_name == instance_name)
+        .ok_or(CommandError::UnknownInstance(instance_name))
+}
+
+/// The game versions and modloaders modpacks can be searched by.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFilters {
+    /// Release versions newest first, after the "All Versions" option.
+    game_versions: Vec<String>,
+    loaders: Vec<ModloaderType>,
+}
+
+#[tauri::command(async)]
+pub async fn get_search_filters(app_handle: AppHandle<Wry>) -> CommandResult<SearchFilters> {
+    ensure_online(&app_handle)?;
+    let mut game_versions = vec![ALL_VERSIONS.to_string()];
+    let releases = ResourceManager::from_app_handle(&app_handle)
+        .get_vanilla_releases()
+        .await?;
+    game_versions.extend(releases.iter().cloned());
+    Ok(SearchFilters {
+        game_versions,
+        loaders: vec![
+            ModloaderType::None,
+            ModloaderType::Forge,
+            ModloaderType::Fabric,
+        ],
+    })
+}
+
+/// Searches curseforge projects of `class_id`, modpacks by default. With a `target_instance` every
+/// result says whether it's compatible with that instance.
 #[tauri::command(async)]
 pub async fn search_curseforge(
     page: u32,
     search_filter: String,
     selected_version: String,
+    selected_loader: Option<ModloaderType>,
     selected_category: u32,
     selected_sort: String,
-) -> Vec<ModpackInformation> {
+    class_id: Option<u32>,
+    target_instance: Option<String>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<Vec<ModpackInformation>> {
+    ensure_online(&app_handle)?;
     debug!("selected_sort: {}", selected_sort);
     let field = CurseforgeSortField::from(selected_sort);
-    let version = if selected_version == "All Versions" {
+    let version = if selected_version == ALL_VERSIONS {
         ""
-    } else {
+    } else if ResourceManager::from_app_handle(&app_handle)
+        .get_vanilla_releases()
+        .await?
+        .contains(&selected_version)
+    {
         selected_version.as_str()
+    } else {
+        return Err(CommandError::UnknownGameVersion(selected_version));
     };
+    let loader = selected_loader.unwrap_or(ModloaderType::None);
     debug!("Page: {}", page);
     debug!("selected_version: {}", selected_version);
     debug!("selected_category: {}", selected_category);
 
-    let response =
-        search_curseforge_modpacks(page, &search_filter, version, selected_category, field)
-            .await
-            .unwrap();
+    let target = match target_instance {
+        Some(instance_name) => Some(instance_configuration(instance_name, &app_handle).await?),
+        None => None,
+    };
+
+    let response = search_curseforge_projects(
+        class_id.unwrap_or(CURSEFORGE_MODPACK_CLASS_ID),
+        page,
+        &search_filter,
+        version,
+        &loader,
+        selected_category,
+        field,
+    )
+    .await?;
 
     debug!("Data: {:#?}", response.data.get(0));
 
-    response
+    Ok(response
         .data
         .into_iter()
-        .map(|entry| ModpackInformation::from(entry))
-        .collect()
+        .map(|entry| {
+            let compatibility = target.as_ref().and_then(|configuration| {
+                entry.compatibility(
+                    configuration.vanilla_version()?,
+                    &configuration.modloader_type,
+                )
+            });
+            ModpackInformation {
+                compatibility,
+                ..ModpackInformation::from(entry)
+            }
+        })
+        .collect())
+}
+
+/// Installs a search result. Modpacks are imported as a new instance, mods, resource packs and
+/// shaders are added to `instance_name`. Without a `file_id` the newest compatible file is used.
+#[tauri::command(async)]
+pub async fn install_search_result(
+    project_id: u32,
+    class_id: u32,
+    file_id: Option<u32>,
+    instance_name: Option<String>,
+    app_handle: AppHandle<Wry>,
+) -> CommandResult<()> {
+    ensure_online(&app_handle)?;
+    info!(
+        "Installing curseforge project {} of class {}",
+        project_id, class_id
+    );
+    if class_id == CURSEFORGE_MODPACK_CLASS_ID {
+        let file_id = match file_id {
+            Some(file_id) => file_id,
+            None => download_main_file_id(project_id).await?,
+        };
+        let cache_dir = ResourceManager::from_app_handle(&app_handle).modpack_cache_dir();
+        let path = download_curseforge_modpack(project_id, file_id, &cache_dir)
+            .await
+            .map_err(ManifestError::from)?;
+        import_modpack_archive(&path, &[], &app_handle).await?;
+        return Ok(());
+    }
+
+    let install_dir =
+        class_install_dir(class_id).ok_or(CommandError::UnsupportedProjectClass(class_id))?;
+    let instance_name = instance_name.ok_or(CommandError::NoTargetInstance)?;
+    let configuration = instance_configuration(instance_name.clone(), &app_handle).await?;
+    let game_version = configuration
+        .vanilla_version()
+        .ok_or_else(|| InstanceExportError::UnknownVersion(instance_name.clone()))?
+        .to_string();
+    let allow_beta = SettingsManager::from_app_handle(&app_handle)
+        .await
+        .get_settings()
+        .allow_beta_dependencies;
+    let instance_dir = existing_instance_dir(instance_name.clone(), &app_handle).await?;
+
+    let info = CurseforgeManifestInfo {
+        game_version,
+        modloader_type: configuration.modloader_type,
+        allow_beta,
+    };
+    install_curseforge_project(
+        project_id,
+        class_id,
+        file_id,
+        &instance_dir.join(install_dir),
+        &info,
+        &ResourceManager::from_app_handle(&app_handle).mod_cache(),
+    )
+    .await?;
+    Ok(())
+}
+
+#[test]
+fn test_error_messages_match_catalog() {
+    let en = error_catalog("en").unwrap();
+    let errors = [
+        CommandError::NoActiveAccount,
+        CommandError::UnknownInstance("Vanilla".into()),
+        CommandError::UnsupportedProjectClass(12),
+        CommandError::MemoryOutOfBounds(
+            256,
+            MemoryBounds {
+                min_mb: 512,
+                recommended_mb: 4096,
+                max_mb: 14336,
+            },
+        ),
+        CommandError::InstanceRunning("Vanilla".into()),
+        CommandError::OfflineAccount("Notch".into()),
+        CommandError::Io(io::Error::other("disk full")),
+    ];
+    for error in errors {
+        assert_eq!(
+            format_message(en[error.kind()], &error.params()),
+            error.to_string()
+        );
+    }
+}
+
+#[test]
+fn test_read_launcher_log() {
+    let tmp_dir = TempDir::new("launcher_logs").unwrap();
+    let log_dir = tmp_dir.path();
+    for name in [
+        "launcher_log_2023-08-01T10-00-00.log",
+        "launcher_log_2023-08-02T10-00-00.log",
+        "latest.log",
+        "install_reports.json",
+    ] {
+        File::create(log_dir.join(name)).unwrap();
+    }
+    assert_eq!(
+        launcher_log_names(log_dir).unwrap(),
+        [
+            "latest.log",
+            "launcher_log_2023-08-02T10-00-00.log",
+            "launcher_log_2023-08-01T10-00-00.log"
+        ]
+    );
+
+    let path = log_dir.join("latest.log");
+    fs::write(
+        &path,
+        concat!(
+            "[src/main.rs:150 2023-08-02T10:00:00][INFO] - Starting Autmc\n",
+            "[src/commands.rs:10 2023-08-02T10:00:01][ERROR] - Could not launch: [io] failed\n",
+            "caused by: something\n",
+            r#"{"timestamp":"2023-08-02T10:00:02+02:00","level":"WARN","target":"autmc","message":"a] - b"}"#,
+        ),
+    )
+    .unwrap();
+    let line_types: Vec<LineType> = read_log_file(&path, get_tag_for_launcher_line)
+        .unwrap()
+        .into_iter()
+        .map(|line| line.line_type)
+        .collect();
+    assert_eq!(
+        line_types,
+        [
+            LineType::Normal,
+            LineType::Error,
+            LineType::Error,
+            LineType::Warning
+        ]
+    );
+}
+
+#[test]
+fn test_managed_path() {
+    let tmp_dir = TempDir::new("managed-path").unwrap();
+    let instances_dir = tmp_dir.path().join("instances");
+    let screenshots_dir = instances_dir.join("Survival").join("screenshots");
+    fs::create_dir_all(&screenshots_dir).unwrap();
+    let roots = [instances_dir.clone()];
+
+    assert_eq!(
+        managed_path(&screenshots_dir, &roots),
+        Some(screenshots_dir.canonicalize().unwrap())
+    );
+    assert_eq!(managed_path(&instances_dir.join(".."), &roots), None);
+    assert_eq!(managed_path(tmp_dir.path(), &roots), None);
+    // Paths that don't exist can't be opened either.
+    assert_eq!(managed_path(&instances_dir.join("Missing"), &roots), None);
 }
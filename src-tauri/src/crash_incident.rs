@@ -0,0 +1,255 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::Local;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    state::instance_manager::InstanceConfiguration, web_services::resources::ModloaderType,
+};
+
+/// Folder in the instance directory crash incidents are recorded in.
+pub const INCIDENTS_DIR: &str = "incidents";
+/// The snapshot of the last launch that exited without crashing, in the incidents folder.
+const LAST_WORKING_FILE: &str = "last_working.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModFile {
+    pub file_name: String,
+    pub size: u64,
+}
+
+/// The versions and mods an instance was launched with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceSnapshot {
+    pub vanilla_version: Option<String>,
+    pub modloader_type: ModloaderType,
+    pub modloader_version: String,
+    /// Enabled mods, sorted by file name.
+    pub mods: Vec<ModFile>,
+}
+
+impl InstanceSnapshot {
+    pub fn capture(config: &InstanceConfiguration, instance_dir: &Path) -> Self {
+        let mut mods: Vec<ModFile> = fs::read_dir(instance_dir.join("mods"))
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "jar"))
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok().filter(|meta| meta.is_file())?;
+                Some(ModFile {
+                    file_name: entry.file_name().to_string_lossy().into_owned(),
+                    size: metadata.len(),
+                })
+            })
+            .collect();
+        mods.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        Self {
+            vanilla_version: config.vanilla_version().map(str::to_owned),
+            modloader_type: config.modloader_type.clone(),
+            modloader_version: config.modloader_version.clone(),
+            mods,
+        }
+    }
+}
+
+/// How the mods of a crashing launch differ from the last working one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Mods with the same file name whose contents changed.
+    pub changed: Vec<String>,
+}
+
+impl ModChanges {
+    fn between(working: &[ModFile], crashing: &[ModFile]) -> Self {
+        let mut changes = Self::default();
+        for file in crashing {
+            match working.iter().find(|old| old.file_name == file.file_name) {
+                None => changes.added.push(file.file_name.clone()),
+                Some(old) if old.size != file.size => changes.changed.push(file.file_name.clone()),
+                Some(_) => {}
+            }
+        }
+        for old in working {
+            if !crashing.iter().any(|file| file.file_name == old.file_name) {
+                changes.removed.push(old.file_name.clone());
+            }
+        }
+        changes
+    }
+}
+
+/// A launch that crashed, with what it was launched with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashIncident {
+    pub id: String,
+    pub instance_name: String,
+    /// RFC 3339 time the game exited at.
+    pub occurred_at: String,
+    pub exit_code: Option<i32>,
+    /// File name of the crash report the game wrote in `crash-reports`, if any.
+    pub crash_report: Option<String>,
+    pub snapshot: InstanceSnapshot,
+    /// The last launch that didn't crash, `None` if there hasn't been one since incidents are
+    /// recorded.
+    pub last_working: Option<InstanceSnapshot>,
+    pub mod_changes: Option<ModChanges>,
+}
+
+fn incidents_dir(instance_dir: &Path) -> PathBuf {
+    instance_dir.join(INCIDENTS_DIR)
+}
+
+/// Called once the game exits. Records an incident if it crashed, which is when it exited with an
+/// error or wrote a crash report after `launched_at`, otherwise remembers `snapshot` as working.
+pub fn record_exit(
+    instance_dir: &Path,
+    instance_name: &str,
+    snapshot: InstanceSnapshot,
+    exit_code: Option<i32>,
+    launched_at: SystemTime,
+) -> io::Result<Option<CrashIncident>> {
+    let dir = incidents_dir(instance_dir);
+    fs::create_dir_all(&dir)?;
+    let crash_report = new_crash_report(&instance_dir.join("crash-reports"), launched_at);
+    if exit_code == Some(0) && crash_report.is_none() {
+        serde_json::to_writer(File::create(dir.join(LAST_WORKING_FILE))?, &snapshot)?;
+        return Ok(None);
+    }
+
+    let last_working = match File::open(dir.join(LAST_WORKING_FILE)) {
+        Ok(file) => serde_json::from_reader::<_, InstanceSnapshot>(BufReader::new(file))
+            .map_err(|e| warn!("Could not read the last working launch: {}", e))
+            .ok(),
+        Err(_) => None,
+    };
+    let occurred_at = Local::now();
+    let incident = CrashIncident {
+        id: occurred_at.format("%Y%m%d%H%M%S%3f").to_string(),
+        instance_name: instance_name.into(),
+        occurred_at: occurred_at.to_rfc3339(),
+        exit_code,
+        crash_report,
+        mod_changes: last_working
+            .as_ref()
+            .map(|working| ModChanges::between(&working.mods, &snapshot.mods)),
+        snapshot,
+        last_working,
+    };
+    let path = dir.join(format!("{}.json", incident.id));
+    serde_json::to_writer(File::create(path)?, &incident)?;
+    info!(
+        "Recorded crash incident {} of {}",
+        incident.id, instance_name
+    );
+    Ok(Some(incident))
+}
+
+/// The newest crash report in `crash_reports_dir` written after `since`.
+fn new_crash_report(crash_reports_dir: &Path, since: SystemTime) -> Option<String> {
+    fs::read_dir(crash_reports_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let modified = entry.metadata().and_then(|meta| meta.modified()).ok()?;
+            (modified >= since).then_some((modified, entry.file_name()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, name)| name.to_string_lossy().into_owned())
+}
+
+/// The crash incidents of the instance at `instance_dir`, newest first. Incidents that can't be
+/// read are skipped.
+pub fn list_incidents(instance_dir: &Path) -> io::Result<Vec<CrashIncident>> {
+    let dir = incidents_dir(instance_dir);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut incidents = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .file_name()
+            .is_some_and(|name| name == LAST_WORKING_FILE)
+        {
+            continue;
+        }
+        let incident = File::open(&path)
+            .map_err(serde_json::Error::io)
+            .and_then(|file| serde_json::from_reader::<_, CrashIncident>(BufReader::new(file)));
+        match incident {
+            Ok(incident) => incidents.push(incident),
+            Err(e) => warn!("Skipping crash incident {}: {}", path.display(), e),
+        }
+    }
+    incidents.sort_by(|a, b| b.occurred_at.cmp(&a.occurred_at));
+    Ok(incidents)
+}
+
+#[cfg(test)]
+use tempdir::TempDir;
+
+#[test]
+fn test_record_exit() {
+    let tmp_dir = TempDir::new("incidents").unwrap();
+    let instance_dir = tmp_dir.path();
+    let snapshot = |mods: &[(&str, u64)]| InstanceSnapshot {
+        vanilla_version: Some("1.20.1".into()),
+        modloader_type: ModloaderType::Fabric,
+        modloader_version: "0.14.21".into(),
+        mods: mods
+            .iter()
+            .map(|(file_name, size)| ModFile {
+                file_name: (*file_name).into(),
+                size: *size,
+            })
+            .collect(),
+    };
+    // File times can lag slightly behind the clock.
+    let launched_at = SystemTime::now() - std::time::Duration::from_secs(1);
+
+    let working = snapshot(&[("a.jar", 1), ("b.jar", 2), ("c.jar", 3)]);
+    let incident = record_exit(
+        instance_dir,
+        "Fabric",
+        working.clone(),
+        Some(0),
+        launched_at,
+    );
+    assert_eq!(incident.unwrap(), None);
+    assert!(list_incidents(instance_dir).unwrap().is_empty());
+
+    fs::create_dir_all(instance_dir.join("crash-reports")).unwrap();
+    fs::write(
+        instance_dir.join("crash-reports").join("crash-client.txt"),
+        "",
+    )
+    .unwrap();
+    let crashing = snapshot(&[("a.jar", 1), ("b.jar", 5), ("d.jar", 4)]);
+    let incident = record_exit(instance_dir, "Fabric", crashing, Some(0), launched_at)
+        .unwrap()
+        .unwrap();
+    assert_eq!(incident.crash_report.as_deref(), Some("crash-client.txt"));
+    assert_eq!(incident.last_working, Some(working));
+    assert_eq!(
+        incident.mod_changes,
+        Some(ModChanges {
+            added: vec!["d.jar".into()],
+            removed: vec!["c.jar".into()],
+            changed: vec!["b.jar".into()],
+        })
+    );
+    assert_eq!(list_incidents(instance_dir).unwrap(), [incident]);
+}
@@ -0,0 +1,226 @@
+use std::{future::Future, time::Duration};
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Wry};
+
+use crate::state::{
+    account_manager::AccountManager, instance_manager::InstanceManager,
+    settings_manager::SettingsManager, ManagerFromAppHandle,
+};
+
+/// The longest wait between two restarts, however many times the instance crashed.
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// Set on instances that are launched again when they crash.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestartPolicy {
+    /// How many times in a row the instance is restarted before giving up.
+    pub max_retries: u32,
+    /// Seconds to wait before the first restart, doubled before each of the following ones.
+    pub backoff_secs: u64,
+}
+
+impl RestartPolicy {
+    /// How long to wait before restarting an instance that was already restarted `restarts` times
+    /// since the user launched it, `None` once it was restarted `max_retries` times.
+    pub fn delay_before_restart(&self, restarts: u32) -> Option<Duration> {
+        if restarts >= self.max_retries {
+            return None;
+        }
+        let delay =
+            Duration::from_secs(self.backoff_secs).saturating_mul(2u32.saturating_pow(restarts));
+        Some(delay.min(MAX_RESTART_DELAY))
+    }
+}
+
+/// Emitted as `instance-restart` when a crashed instance is about to be launched again.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InstanceRestart<'a> {
+    instance_name: &'a str,
+    /// 1 for the first restart after the user launched the instance.
+    attempt: u32,
+    max_retries: u32,
+    delay_secs: u64,
+}
+
+/// Waits out the delay before restarting an instance that `policy` already restarted `restarts`
+/// times, calling `on_wait` with the event to emit first. Returns the number of the attempt, or
+/// `None` if it crashed too often in a row. The user may have changed the policy while waiting, so
+/// it is read again with `current_policy` once the delay is over and `None` means they turned
+/// restarting off.
+async fn wait_for_restart(
+    instance_name: &str,
+    policy: &RestartPolicy,
+    restarts: u32,
+    on_wait: impl FnOnce(InstanceRestart),
+    current_policy: impl Future<Output = Option<RestartPolicy>>,
+) -> Option<u32> {
+    let delay = match policy.delay_before_restart(restarts) {
+        Some(delay) => delay,
+        None => {
+            info!(
+                "{} crashed {} times in a row, not restarting it again",
+                instance_name,
+                restarts + 1
+            );
+            return None;
+        }
+    };
+    let attempt = restarts + 1;
+    info!(
+        "Restarting {} in {}s, attempt {} of {}",
+        instance_name,
+        delay.as_secs(),
+        attempt,
+        policy.max_retries
+    );
+    on_wait(InstanceRestart {
+        instance_name,
+        attempt,
+        max_retries: policy.max_retries,
+        delay_secs: delay.as_secs(),
+    });
+    tokio::time::sleep(delay).await;
+
+    match current_policy.await {
+        Some(policy) if restarts < policy.max_retries => Some(attempt),
+        Some(_) => {
+            info!("Not restarting {}, its retries were lowered", instance_name);
+            None
+        }
+        None => {
+            info!(
+                "Not restarting {}, restarting was turned off",
+                instance_name
+            );
+            None
+        }
+    }
+}
+
+/// Launches the crashed instance again after the delay of its `policy`, with the account that is
+/// active by then. `restarts` is how many times it was already restarted.
+pub async fn restart_crashed_instance(
+    app_handle: AppHandle<Wry>,
+    instance_name: String,
+    policy: RestartPolicy,
+    restarts: u32,
+) {
+    let emit = |event: InstanceRestart<'_>| {
+        if let Err(e) = app_handle.emit("instance-restart", event) {
+            error!("{}", e);
+        }
+    };
+    let current_policy = async {
+        InstanceManager::from_app_handle(&app_handle)
+            .await
+            .get_instance(&instance_name)
+            .and_then(|config| config.restart_on_crash.clone())
+    };
+    let attempt =
+        match wait_for_restart(&instance_name, &policy, restarts, emit, current_policy).await {
+            Some(attempt) => attempt,
+            None => return,
+        };
+
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    if instance_manager.is_running(&instance_name) {
+        // The user launched it again while waiting.
+        return;
+    }
+    let account_manager = AccountManager::from_app_handle(&app_handle).await;
+    let settings_manager = SettingsManager::from_app_handle(&app_handle).await;
+    let account = match account_manager.get_active_account() {
+        Some(account) => account,
+        None => {
+            error!(
+                "Can't restart {}, there is no active account",
+                instance_name
+            );
+            return;
+        }
+    };
+    if let Err(e) = instance_manager.relaunch_crashed_instance(
+        &instance_name,
+        account,
+        settings_manager.get_settings(),
+        app_handle.clone(),
+        attempt,
+    ) {
+        error!("Could not restart {}: {}", instance_name, e);
+    }
+}
+
+#[cfg(test)]
+use tauri::async_runtime::block_on;
+
+#[test]
+fn test_restart_delays() {
+    let policy = RestartPolicy {
+        max_retries: 3,
+        backoff_secs: 10,
+    };
+    let delays: Vec<_> = (0..4)
+        .map(|restarts| policy.delay_before_restart(restarts))
+        .collect();
+    assert_eq!(
+        delays,
+        [
+            Some(Duration::from_secs(10)),
+            Some(Duration::from_secs(20)),
+            Some(Duration::from_secs(40)),
+            None
+        ]
+    );
+
+    let policy = RestartPolicy {
+        max_retries: 100,
+        backoff_secs: 60,
+    };
+    assert_eq!(policy.delay_before_restart(40), Some(MAX_RESTART_DELAY));
+}
+
+#[test]
+fn test_wait_for_restart() {
+    let policy = RestartPolicy {
+        max_retries: 2,
+        backoff_secs: 0,
+    };
+    let mut events = Vec::new();
+    let attempt = block_on(wait_for_restart(
+        "Instance",
+        &policy,
+        1,
+        |event| events.push((event.attempt, event.max_retries, event.delay_secs)),
+        async { Some(policy.clone()) },
+    ));
+    assert_eq!(attempt, Some(2));
+    assert_eq!(events, [(2, 2, 0)]);
+
+    // Turned off or lowered while waiting.
+    let attempt = block_on(wait_for_restart("Instance", &policy, 0, |_| {}, async {
+        None
+    }));
+    assert_eq!(attempt, None);
+    let lowered = RestartPolicy {
+        max_retries: 1,
+        ..policy.clone()
+    };
+    let attempt = block_on(wait_for_restart("Instance", &policy, 1, |_| {}, async {
+        Some(lowered)
+    }));
+    assert_eq!(attempt, None);
+
+    // Out of retries, nothing is emitted and the policy isn't read again.
+    let attempt = block_on(wait_for_restart(
+        "Instance",
+        &policy,
+        2,
+        |_| panic!("no restart is scheduled"),
+        async { panic!("the policy isn't read again") },
+    ));
+    assert_eq!(attempt, None);
+}
@@ -0,0 +1,151 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// A folder besides the launcher's own that instances can be stored in, like an archive on a
+/// bigger but slower drive. Its instances are in its `instances` folder.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DataRoot {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// How far along moving an instance to another data root is, counted in bytes copied.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveProgress {
+    pub copied_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Moves the folder `from` to `to`, which must not exist yet. Renamed when both are on the same
+/// drive, otherwise copied with [copy_dir_with_progress] and removed after. Once copied the move
+/// succeeded, whatever is left of `from` if it can't be removed is only logged.
+pub fn move_dir_with_progress(
+    from: &Path,
+    to: &Path,
+    on_progress: impl FnMut(MoveProgress),
+) -> io::Result<()> {
+    if to.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        ));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if let Err(e) = copy_dir_with_progress(from, to, on_progress) {
+        // The instance is still complete at `from`, don't leave half of it behind.
+        let _ = fs::remove_dir_all(to);
+        return Err(e);
+    }
+    if let Err(e) = fs::remove_dir_all(from) {
+        warn!(
+            "Could not remove {} after copying it: {}",
+            from.display(),
+            e
+        );
+    }
+    Ok(())
+}
+
+/// Copies the files in `from` to `to`, calling `on_progress` every percent and once done.
+fn copy_dir_with_progress(
+    from: &Path,
+    to: &Path,
+    mut on_progress: impl FnMut(MoveProgress),
+) -> io::Result<()> {
+    let mut files = Vec::new();
+    list_files(from, Path::new(""), &mut files)?;
+    let total_bytes = files.iter().map(|(_, size)| size).sum();
+    let mut progress = MoveProgress {
+        copied_bytes: 0,
+        total_bytes,
+    };
+    let step = (total_bytes / 100).max(1);
+    let mut last_reported = 0;
+    for (relative_path, size) in files {
+        let target = to.join(&relative_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(from.join(&relative_path), target)?;
+        progress.copied_bytes += size;
+        if progress.copied_bytes - last_reported >= step {
+            last_reported = progress.copied_bytes;
+            on_progress(progress);
+        }
+    }
+    if last_reported != total_bytes || total_bytes == 0 {
+        on_progress(progress);
+    }
+    Ok(())
+}
+
+/// The files in `dir` with their size, relative to the folder the listing started in.
+fn list_files(dir: &Path, relative_dir: &Path, files: &mut Vec<(PathBuf, u64)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let relative_path = relative_dir.join(entry.file_name());
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            list_files(&entry.path(), &relative_path, files)?;
+        } else {
+            files.push((relative_path, metadata.len()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+use tempdir::TempDir;
+
+#[test]
+fn test_move_dir_with_progress() {
+    let tmp_dir = TempDir::new("data_roots").unwrap();
+    let from = tmp_dir.path().join("instances").join("Vanilla");
+    fs::create_dir_all(from.join("saves").join("World")).unwrap();
+    fs::write(from.join("config.json"), [0; 20]).unwrap();
+    fs::write(from.join("saves").join("World").join("level.dat"), [0; 80]).unwrap();
+
+    let copy = tmp_dir.path().join("copy");
+    let mut reports = Vec::new();
+    copy_dir_with_progress(&from, &copy, |progress| reports.push(progress)).unwrap();
+    assert_eq!(
+        fs::read(copy.join("saves").join("World").join("level.dat")).unwrap(),
+        [0; 80]
+    );
+    assert_eq!(reports.len(), 2);
+    assert_eq!(
+        reports.last(),
+        Some(&MoveProgress {
+            copied_bytes: 100,
+            total_bytes: 100,
+        })
+    );
+
+    let to = tmp_dir
+        .path()
+        .join("archive")
+        .join("instances")
+        .join("Vanilla");
+    move_dir_with_progress(&from, &to, |_| {}).unwrap();
+    assert!(!from.exists());
+    assert_eq!(fs::read(to.join("config.json")).unwrap(), [0; 20]);
+
+    assert_eq!(
+        move_dir_with_progress(&to, &copy, |_| {})
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::AlreadyExists
+    );
+    assert!(to.join("config.json").exists());
+}
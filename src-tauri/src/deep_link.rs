@@ -0,0 +1,166 @@
+use std::{
+    fmt,
+    sync::{Mutex, PoisonError},
+};
+
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Wry};
+use url::Url;
+
+use crate::consts::DEEP_LINK_SCHEME;
+
+/// A pack installation requested through an `autmc://install/...` link.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "camelCase")]
+pub enum DeepLinkRequest {
+    /// `autmc://install/curseforge/<project id>/<file id>`
+    #[serde(rename_all = "camelCase")]
+    Curseforge { project_id: u32, file_id: u32 },
+    /// `autmc://install/modrinth/<project id>/<version id>`
+    #[serde(rename_all = "camelCase")]
+    Modrinth {
+        project_id: String,
+        version_id: String,
+    },
+}
+
+/// Install requests from the links the launcher was started with. These are held until the main
+/// window has loaded and asks for them, since events emitted during setup would be missed.
+#[derive(Debug, Default)]
+pub struct DeepLinkState(pub Mutex<Vec<DeepLinkRequest>>);
+
+#[derive(Debug, PartialEq)]
+pub enum DeepLinkError {
+    InvalidScheme(String),
+    UnknownAction(String),
+    UnknownSource(String),
+    MalformedPath(String),
+}
+
+impl fmt::Display for DeepLinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeepLinkError::InvalidScheme(scheme) => {
+                write!(
+                    f,
+                    "Expected an `{}://` link but got `{}`",
+                    DEEP_LINK_SCHEME, scheme
+                )
+            }
+            DeepLinkError::UnknownAction(action) => {
+                write!(f, "Unknown deep link action `{}`", action)
+            }
+            DeepLinkError::UnknownSource(source) => {
+                write!(f, "Unknown deep link source `{}`", source)
+            }
+            DeepLinkError::MalformedPath(path) => write!(f, "Malformed deep link path `{}`", path),
+        }
+    }
+}
+
+/// Parses an `autmc://` url into the install request it describes.
+pub fn parse_deep_link(url: &Url) -> Result<DeepLinkRequest, DeepLinkError> {
+    if url.scheme() != DEEP_LINK_SCHEME {
+        return Err(DeepLinkError::InvalidScheme(url.scheme().into()));
+    }
+
+    // `autmc://install/...` parses "install" as the host of the url.
+    let action = url.host_str().unwrap_or_default();
+    if action != "install" {
+        return Err(DeepLinkError::UnknownAction(action.into()));
+    }
+
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+        .unwrap_or_default();
+    let malformed = || DeepLinkError::MalformedPath(url.path().into());
+
+    match segments.as_slice() {
+        ["curseforge", project_id, file_id] => Ok(DeepLinkRequest::Curseforge {
+            project_id: project_id.parse().map_err(|_| malformed())?,
+            file_id: file_id.parse().map_err(|_| malformed())?,
+        }),
+        ["modrinth", project_id, version_id] => Ok(DeepLinkRequest::Modrinth {
+            project_id: (*project_id).into(),
+            version_id: (*version_id).into(),
+        }),
+        [source, ..] if *source != "curseforge" && *source != "modrinth" => {
+            Err(DeepLinkError::UnknownSource((*source).into()))
+        }
+        _ => Err(malformed()),
+    }
+}
+
+/// Parses the urls the launcher was started with and queues them in [DeepLinkState].
+pub fn queue_startup_deep_links(state: &DeepLinkState, urls: Vec<Url>) {
+    let mut pending = state.0.lock().unwrap_or_else(PoisonError::into_inner);
+    for url in urls {
+        info!("Launched with deep link {}", url);
+        match parse_deep_link(&url) {
+            Ok(request) => pending.push(request),
+            Err(error) => warn!("Ignoring deep link {}: {}", url, error),
+        }
+    }
+}
+
+/// Parses every opened url and asks the main window to prompt the user before installing.
+pub fn handle_deep_link_urls(app_handle: &AppHandle<Wry>, urls: Vec<Url>) {
+    for url in urls {
+        info!("Received deep link {}", url);
+        match parse_deep_link(&url) {
+            Ok(request) => {
+                if let Err(error) = app_handle.emit_to("main", "deep-link-install", request) {
+                    error!("{}", error.to_string());
+                }
+            }
+            Err(error) => warn!("Ignoring deep link {}: {}", url, error),
+        }
+    }
+}
+
+#[test]
+fn test_parse_curseforge_deep_link() {
+    let url = Url::parse("autmc://install/curseforge/715572/4965355").unwrap();
+    assert_eq!(
+        parse_deep_link(&url),
+        Ok(DeepLinkRequest::Curseforge {
+            project_id: 715572,
+            file_id: 4965355
+        })
+    );
+}
+
+#[test]
+fn test_parse_modrinth_deep_link() {
+    let url = Url::parse("autmc://install/modrinth/1KVo5zza/dZo6ZjhA/").unwrap();
+    assert_eq!(
+        parse_deep_link(&url),
+        Ok(DeepLinkRequest::Modrinth {
+            project_id: "1KVo5zza".into(),
+            version_id: "dZo6ZjhA".into()
+        })
+    );
+}
+
+#[test]
+fn test_parse_invalid_deep_links() {
+    let parse = |s: &str| parse_deep_link(&Url::parse(s).unwrap());
+    assert!(matches!(
+        parse("https://install/curseforge/1/2"),
+        Err(DeepLinkError::InvalidScheme(_))
+    ));
+    assert!(matches!(
+        parse("autmc://launch/curseforge/1/2"),
+        Err(DeepLinkError::UnknownAction(_))
+    ));
+    assert!(matches!(
+        parse("autmc://install/technic/1/2"),
+        Err(DeepLinkError::UnknownSource(_))
+    ));
+    assert!(matches!(
+        parse("autmc://install/curseforge/abc/2"),
+        Err(DeepLinkError::MalformedPath(_))
+    ));
+}
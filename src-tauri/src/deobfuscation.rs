@@ -0,0 +1,207 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use regex::{Captures, Regex};
+
+use crate::trash::child_path;
+
+/// Obfuscated to readable names, read from Mojang's ProGuard formatted mappings.
+#[derive(Debug, Default)]
+pub struct Mappings {
+    // <Obfuscated class name, class mapping>
+    classes: HashMap<String, ClassMapping>,
+}
+
+#[derive(Debug, Default)]
+struct ClassMapping {
+    name: String,
+    // <Obfuscated method name, readable names>. Overloads can share an obfuscated name.
+    methods: HashMap<String, Vec<String>>,
+}
+
+impl Mappings {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Parses a mappings file. Classes look like `net.minecraft.Foo -> abc:` and are followed by
+    /// their indented members, methods look like `    1:5:void tick(int) -> a`.
+    pub fn parse(contents: &str) -> Self {
+        let mut mappings = Mappings::default();
+        let mut current_class: Option<String> = None;
+
+        for line in contents.lines() {
+            if line.trim_start().starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let (original, obfuscated) = match line.trim().split_once(" -> ") {
+                Some(split) => split,
+                None => continue,
+            };
+
+            if !line.starts_with(char::is_whitespace) {
+                let obfuscated = obfuscated.trim_end_matches(':').to_string();
+                mappings.classes.insert(
+                    obfuscated.clone(),
+                    ClassMapping {
+                        name: original.into(),
+                        methods: HashMap::new(),
+                    },
+                );
+                current_class = Some(obfuscated);
+            } else if let (Some(class), Some(signature)) = (
+                &current_class,
+                original.split_once('(').map(|split| split.0),
+            ) {
+                // Fields don't have parameters, skip them since they don't show up in stack traces.
+                let method_name = signature.rsplit(' ').next().unwrap_or(signature);
+                if let Some(class_mapping) = mappings.classes.get_mut(class) {
+                    let names = class_mapping
+                        .methods
+                        .entry(obfuscated.into())
+                        .or_insert_with(Vec::new);
+                    if !names.iter().any(|name| name == method_name) {
+                        names.push(method_name.into());
+                    }
+                }
+            }
+        }
+        mappings
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    fn class_name<'a>(&'a self, obfuscated: &'a str) -> &'a str {
+        self.classes
+            .get(obfuscated)
+            .map(|class| class.name.as_str())
+            .unwrap_or(obfuscated)
+    }
+
+    fn method_name(&self, class: &str, obfuscated: &str) -> String {
+        match self
+            .classes
+            .get(class)
+            .and_then(|class| class.methods.get(obfuscated))
+        {
+            // Can't tell overloads apart without line numbers, show every candidate.
+            Some(names) => names.join("|"),
+            None => obfuscated.into(),
+        }
+    }
+
+    /// Rewrites obfuscated names in the stack traces of a crash report.
+    pub fn deobfuscate(&self, report: &str) -> String {
+        let frame = Regex::new(r"\bat (?P<class>[\w$.]+)\.(?P<method>[\w$<>]+)\(").unwrap();
+        let caused_by = Regex::new(r"Caused by: (?P<class>[\w$.]+)").unwrap();
+
+        let report = frame.replace_all(report, |captures: &Captures| {
+            let class = &captures["class"];
+            format!(
+                "at {}.{}(",
+                self.class_name(class),
+                self.method_name(class, &captures["method"])
+            )
+        });
+        caused_by
+            .replace_all(&report, |captures: &Captures| {
+                format!("Caused by: {}", self.class_name(&captures["class"]))
+            })
+            .into_owned()
+    }
+}
+
+/// Reads the Minecraft version from a vanilla crash report's system details.
+pub fn crash_report_version(report: &str) -> Option<&str> {
+    report
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Minecraft Version: "))
+        .map(|version| version.trim())
+}
+
+/// The crash report named `report_name` in `crash_reports_dir`. The name comes from the frontend,
+/// so it must be a plain `.txt` file name, not a path to some other file of the instance.
+pub fn crash_report_path(crash_reports_dir: &Path, report_name: &str) -> io::Result<PathBuf> {
+    let path = child_path(crash_reports_dir, report_name)?;
+    if path
+        .extension()
+        .map_or(true, |extension| extension != "txt")
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("`{}` is not a crash report", report_name),
+        ));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+const TEST_MAPPINGS: &str = "# {\"id\":\"sourceFile\",\"fileName\":\"client.txt\"}
+net.minecraft.client.Minecraft -> emh:
+    net.minecraft.client.gui.Gui gui -> l
+    1:3:void <init>(net.minecraft.client.main.GameConfig) -> <init>
+    4:9:void run() -> e
+    10:12:void tick() -> r
+    13:15:void runTick(boolean) -> f
+net.minecraft.client.renderer.GameRenderer -> fjq:
+    1:5:void render(float,long,boolean) -> a
+    6:7:void renderLevel(float,long,com.mojang.blaze3d.vertex.PoseStack) -> a
+net.minecraft.ReportedException -> z:
+";
+
+#[test]
+fn test_parse_mappings() {
+    let mappings = Mappings::parse(TEST_MAPPINGS);
+    assert!(!mappings.is_empty());
+    assert_eq!(mappings.class_name("emh"), "net.minecraft.client.Minecraft");
+    assert_eq!(mappings.method_name("emh", "r"), "tick");
+    assert_eq!(mappings.method_name("fjq", "a"), "render|renderLevel");
+    // Unknown names are left as is.
+    assert_eq!(mappings.class_name("java.lang.Thread"), "java.lang.Thread");
+    assert_eq!(mappings.method_name("emh", "zz"), "zz");
+}
+
+#[test]
+fn test_deobfuscate_crash_report() {
+    let mappings = Mappings::parse(TEST_MAPPINGS);
+    let report = "---- Minecraft Crash Report ----
+Description: Unexpected error
+
+java.lang.NullPointerException: Cannot invoke \"Object.toString()\"
+\tat emh.r(SourceFile:1123)
+\tat emh.e(SourceFile:718)
+\tat java.lang.Thread.run(Thread.java:833)
+Caused by: z: Rendering
+\tat fjq.a(SourceFile:42)
+
+-- System Details --
+\tMinecraft Version: 1.20.1
+";
+    let deobfuscated = mappings.deobfuscate(report);
+    assert!(deobfuscated.contains("at net.minecraft.client.Minecraft.tick(SourceFile:1123)"));
+    assert!(deobfuscated.contains("at net.minecraft.client.Minecraft.run(SourceFile:718)"));
+    assert!(deobfuscated.contains("at java.lang.Thread.run(Thread.java:833)"));
+    assert!(deobfuscated.contains("Caused by: net.minecraft.ReportedException: Rendering"));
+    assert!(deobfuscated.contains(
+        "at net.minecraft.client.renderer.GameRenderer.render|renderLevel(SourceFile:42)"
+    ));
+    assert_eq!(crash_report_version(report), Some("1.20.1"));
+}
+
+#[test]
+fn test_crash_report_path() {
+    let dir = Path::new("crash-reports");
+    assert_eq!(
+        crash_report_path(dir, "crash-2024-01-06_12.00.00-client.txt").unwrap(),
+        dir.join("crash-2024-01-06_12.00.00-client.txt")
+    );
+    assert!(crash_report_path(dir, "../config.json").is_err());
+    assert!(crash_report_path(dir, "..").is_err());
+    assert!(crash_report_path(dir, "../../options.txt").is_err());
+    assert!(crash_report_path(dir, "hs_err.log").is_err());
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+/// Message templates for the `kind` of each `CommandError`, keyed by locale. `{name}` is replaced
+/// with the error's `name` param. Errors wrapping another error only have an `{error}` param, the
+/// wrapped error's english message.
+const CATALOGS: &[(&str, &[(&str, &str)])] = &[(
+    "en",
+    &[
+        ("noActiveAccount", "There is no active account"),
+        ("unknownInstance", "Unknown instance `{instance}`"),
+        ("unknownGameVersion", "Unknown Minecraft version `{version}`"),
+        (
+            "unsupportedProjectClass",
+            "Projects of class {classId} can't be installed",
+        ),
+        (
+            "noTargetInstance",
+            "Choose an instance to install the project into",
+        ),
+        (
+            "safeMode",
+            "The launcher was started in safe mode, restart it normally to use the network",
+        ),
+        ("unknownLogScope", "Unknown log scope `{scope}`"),
+        (
+            "memoryOutOfBounds",
+            "Can't give an instance {memoryMb}MB of memory, it must be between {minMb}MB and {maxMb}MB",
+        ),
+        ("instanceRunning", "`{instance}` is running, close it first"),
+        ("unknownDataRoot", "There is no data root named `{dataRoot}`"),
+        ("unmanagedPath", "`{path}` isn't managed by the launcher"),
+        ("unknownAccount", "There is no account `{uuid}`"),
+        (
+            "offlineAccount",
+            "`{account}` is an offline account, it has no Minecraft profile",
+        ),
+        ("http", "{error}"),
+        ("io", "{error}"),
+        ("manifest", "{error}"),
+        ("modBisect", "{error}"),
+        ("lanShare", "{error}"),
+        ("instanceSync", "{error}"),
+        ("instanceExport", "{error}"),
+        ("packFormat", "{error}"),
+        ("apiCache", "{error}"),
+        ("dependency", "{error}"),
+        ("supportBundle", "{error}"),
+        ("trash", "{error}"),
+        ("serverProperties", "{error}"),
+        ("skin", "{error}"),
+        ("authentication", "{error}"),
+        ("opener", "{error}"),
+        ("tauri", "{error}"),
+    ],
+)];
+
+/// The message templates of `locale`, like `en` or `en-US`, falling back to the language without
+/// its region. `None` if there is no catalog for it.
+pub fn error_catalog(locale: &str) -> Option<HashMap<&'static str, &'static str>> {
+    let language = locale.split(['-', '_']).next().unwrap_or(locale);
+    CATALOGS
+        .iter()
+        .find(|(catalog_locale, _)| *catalog_locale == locale)
+        .or_else(|| {
+            CATALOGS
+                .iter()
+                .find(|(catalog_locale, _)| *catalog_locale == language)
+        })
+        .map(|(_, messages)| messages.iter().copied().collect())
+}
+
+/// Fills the `{name}` placeholders of `template` from `params` the way the frontend does.
+/// Unknown placeholders are kept.
+#[cfg(test)]
+pub fn format_message(template: &str, params: &BTreeMap<&str, String>) -> String {
+    let mut message = template.to_owned();
+    for (name, value) in params {
+        message = message.replace(&format!("{{{}}}", name), value);
+    }
+    message
+}
+
+#[cfg(test)]
+use std::collections::BTreeMap;
+
+#[test]
+fn test_error_catalog() {
+    let en = error_catalog("en").unwrap();
+    assert_eq!(error_catalog("en-US").unwrap(), en);
+    assert!(error_catalog("xx").is_none());
+
+    let params = BTreeMap::from([("instance", "Vanilla".to_owned())]);
+    assert_eq!(
+        format_message(en["unknownInstance"], &params),
+        "Unknown instance `Vanilla`"
+    );
+    assert_eq!(format_message("{missing}", &params), "{missing}");
+}
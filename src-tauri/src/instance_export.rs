@@ -0,0 +1,367 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Component, Path},
+};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use zip::{
+    read::ZipFile, result::ZipError, write::SimpleFileOptions, AesMode, CompressionMethod,
+    ZipArchive, ZipWriter,
+};
+
+use crate::{
+    consts::{CLASSPATH_ARGFILE, FORGE_INSTALL_LOG},
+    crash_incident::INCIDENTS_DIR,
+    instance_sync::SYNC_STATE_FILE,
+    state::instance_manager::InstanceConfiguration,
+    web_services::resources::{InstanceSettings, ModloaderType},
+};
+
+/// Describes how to recreate the instance the bundle was made from.
+const BUNDLE_INFO_FILE: &str = "autmc-share.json";
+/// The instance's files are stored under this directory of the bundle.
+const BUNDLE_INSTANCE_DIR: &str = "instance";
+/// Files that only make sense on the exporting machine, these are recreated when importing.
+const EXCLUDED_ENTRIES: [&str; 11] = [
+    "config.json",
+    FORGE_INSTALL_LOG,
+    CLASSPATH_ARGFILE,
+    SYNC_STATE_FILE,
+    "manifest_snapshot.json",
+    "bisect.json",
+    "natives",
+    "logs",
+    "crash-reports",
+    INCIDENTS_DIR,
+    "mods-safe-mode",
+];
+/// Already compressed files are stored as is.
+const STORED_EXTENSIONS: [&str; 3] = ["jar", "zip", "png"];
+
+#[derive(Debug)]
+pub enum InstanceExportError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Zip(ZipError),
+    /// The instance was created before its vanilla version was recorded.
+    UnknownVersion(String),
+    InstanceExists(String),
+    PassphraseRequired,
+    InvalidPassphrase,
+}
+
+impl fmt::Display for InstanceExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstanceExportError::Io(error) => write!(f, "{}", error),
+            InstanceExportError::Json(error) => write!(f, "{}", error),
+            InstanceExportError::Zip(error) => write!(f, "{}", error),
+            InstanceExportError::UnknownVersion(name) => {
+                write!(f, "The Minecraft version of `{}` is unknown", name)
+            }
+            InstanceExportError::InstanceExists(name) => {
+                write!(f, "An instance named `{}` already exists", name)
+            }
+            InstanceExportError::PassphraseRequired => {
+                f.write_str("The archive is encrypted, a passphrase is needed to import it")
+            }
+            InstanceExportError::InvalidPassphrase => f.write_str("Wrong passphrase"),
+        }
+    }
+}
+
+impl From<io::Error> for InstanceExportError {
+    fn from(error: io::Error) -> Self {
+        InstanceExportError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for InstanceExportError {
+    fn from(error: serde_json::Error) -> Self {
+        InstanceExportError::Json(error)
+    }
+}
+
+impl From<ZipError> for InstanceExportError {
+    fn from(error: ZipError) -> Self {
+        match error {
+            ZipError::InvalidPassword => InstanceExportError::InvalidPassphrase,
+            ZipError::UnsupportedArchive(message) if message == ZipError::PASSWORD_REQUIRED => {
+                InstanceExportError::PassphraseRequired
+            }
+            error => InstanceExportError::Zip(error),
+        }
+    }
+}
+
+pub type InstanceExportResult<T> = Result<T, InstanceExportError>;
+
+/// Everything needed to install the exported instance's version before its files are extracted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleInfo {
+    pub instance_name: String,
+    vanilla_version: String,
+    modloader_type: ModloaderType,
+    modloader_version: String,
+    pub author: String,
+}
+
+impl BundleInfo {
+    pub fn new(configuration: &InstanceConfiguration) -> InstanceExportResult<Self> {
+        let vanilla_version = configuration.vanilla_version().ok_or_else(|| {
+            InstanceExportError::UnknownVersion(configuration.instance_name.clone())
+        })?;
+        Ok(Self {
+            instance_name: configuration.instance_name.clone(),
+            vanilla_version: vanilla_version.into(),
+            modloader_type: configuration.modloader_type.clone(),
+            modloader_version: configuration.modloader_version.clone(),
+            author: configuration.author.clone(),
+        })
+    }
+
+    pub fn instance_settings(&self) -> InstanceSettings {
+        InstanceSettings::new(
+            self.instance_name.clone(),
+            self.vanilla_version.clone(),
+            self.modloader_type.clone(),
+            self.modloader_version.clone(),
+            None,
+        )
+    }
+}
+
+/// Zips the instance at `instance_dir` into `bundle_path`. With a `passphrase` every entry is
+/// encrypted with AES-256.
+pub fn write_bundle(
+    instance_dir: &Path,
+    info: &BundleInfo,
+    bundle_path: &Path,
+    passphrase: Option<&str>,
+) -> InstanceExportResult<()> {
+    info!(
+        "Exporting {} to {}{}",
+        info.instance_name,
+        bundle_path.display(),
+        if passphrase.is_some() {
+            " with encryption"
+        } else {
+            ""
+        }
+    );
+    let options = |method| {
+        let options = SimpleFileOptions::default().compression_method(method);
+        match passphrase {
+            Some(passphrase) => options.with_aes_encryption(AesMode::Aes256, passphrase),
+            None => options,
+        }
+    };
+
+    let mut zip = ZipWriter::new(File::create(bundle_path)?);
+    zip.start_file(BUNDLE_INFO_FILE, options(CompressionMethod::Deflated))?;
+    zip.write_all(&serde_json::to_vec(info)?)?;
+
+    let mut pending = vec![instance_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let relative = path.strip_prefix(instance_dir).unwrap();
+            if is_excluded(relative) {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+
+            let name = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let stored = path.extension().map_or(false, |extension| {
+                STORED_EXTENSIONS.iter().any(|e| extension == *e)
+            });
+            let method = if stored {
+                CompressionMethod::Stored
+            } else {
+                CompressionMethod::Deflated
+            };
+            zip.start_file(format!("{}/{}", BUNDLE_INSTANCE_DIR, name), options(method))?;
+            io::copy(&mut File::open(&path)?, &mut zip)?;
+        }
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+fn bundle_entry<'a>(
+    archive: &'a mut ZipArchive<&File>,
+    index: usize,
+    passphrase: Option<&str>,
+) -> InstanceExportResult<ZipFile<'a>> {
+    Ok(match passphrase {
+        Some(passphrase) => archive.by_index_decrypt(index, passphrase.as_bytes())?,
+        None => archive.by_index(index)?,
+    })
+}
+
+pub fn read_bundle_info(
+    archive: &mut ZipArchive<&File>,
+    passphrase: Option<&str>,
+) -> InstanceExportResult<BundleInfo> {
+    let index = archive
+        .index_for_name(BUNDLE_INFO_FILE)
+        .ok_or(ZipError::FileNotFound)?;
+    let mut bytes = Vec::new();
+    bundle_entry(archive, index, passphrase)?.read_to_end(&mut bytes)?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Whether `relative` stays inside the folder it is relative to, `instance/../file` is enclosed
+/// in the bundle but not in the instance.
+fn is_enclosed(relative: &Path) -> bool {
+    relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Whether `relative`, a path in the instance's folder, is or is in one of the [EXCLUDED_ENTRIES].
+fn is_excluded(relative: &Path) -> bool {
+    relative
+        .iter()
+        .next()
+        .is_some_and(|first| EXCLUDED_ENTRIES.iter().any(|name| first == *name))
+}
+
+/// Extracts the instance's files from the bundle into `instance_dir`.
+pub fn extract_bundle(
+    instance_dir: &Path,
+    archive: &mut ZipArchive<&File>,
+    passphrase: Option<&str>,
+) -> InstanceExportResult<()> {
+    for index in 0..archive.len() {
+        let mut zip_file = bundle_entry(archive, index, passphrase)?;
+        let name = match zip_file.enclosed_name() {
+            Some(name) if zip_file.is_file() => name,
+            _ => continue,
+        };
+        let relative = match name.strip_prefix(BUNDLE_INSTANCE_DIR) {
+            // A bundle from someone else mustn't replace the config.json the launch command is
+            // read from.
+            Ok(relative) if is_enclosed(relative) && !is_excluded(relative) => relative,
+            _ => continue,
+        };
+        let path = instance_dir.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut zip_file, &mut File::create(&path)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+fn test_instance(dir: &Path) -> (std::path::PathBuf, BundleInfo) {
+    let instance_dir = dir.join("Pack");
+    fs::create_dir_all(instance_dir.join("mods")).unwrap();
+    fs::create_dir_all(instance_dir.join("logs")).unwrap();
+    fs::create_dir_all(instance_dir.join("config/sub")).unwrap();
+    for file in [
+        "config.json",
+        "options.txt",
+        "mods/a.jar",
+        "logs/latest.log",
+        "config/sub/b.toml",
+    ] {
+        fs::write(instance_dir.join(file), file).unwrap();
+    }
+    let info = BundleInfo {
+        instance_name: "Pack".into(),
+        vanilla_version: "1.20.1".into(),
+        modloader_type: ModloaderType::Fabric,
+        modloader_version: "0.15.0".into(),
+        author: "You".into(),
+    };
+    (instance_dir, info)
+}
+
+#[test]
+fn test_bundle_round_trip() {
+    let tmp_dir = tempdir::TempDir::new("export").unwrap();
+    let (instance_dir, info) = test_instance(tmp_dir.path());
+    let bundle_path = tmp_dir.path().join("bundle.zip");
+    write_bundle(&instance_dir, &info, &bundle_path, None).unwrap();
+
+    let bundle = File::open(&bundle_path).unwrap();
+    let mut archive = ZipArchive::new(&bundle).unwrap();
+    assert_eq!(read_bundle_info(&mut archive, None).unwrap(), info);
+
+    let imported_dir = tmp_dir.path().join("Imported");
+    extract_bundle(&imported_dir, &mut archive, None).unwrap();
+    assert_eq!(
+        fs::read_to_string(imported_dir.join("config/sub/b.toml")).unwrap(),
+        "config/sub/b.toml"
+    );
+    assert!(imported_dir.join("mods/a.jar").is_file());
+    assert!(imported_dir.join("options.txt").is_file());
+    assert!(!imported_dir.join("config.json").exists());
+    assert!(!imported_dir.join("logs").exists());
+}
+
+#[test]
+fn test_encrypted_bundle() {
+    let tmp_dir = tempdir::TempDir::new("export").unwrap();
+    let (instance_dir, info) = test_instance(tmp_dir.path());
+    let bundle_path = tmp_dir.path().join("bundle.zip");
+    write_bundle(&instance_dir, &info, &bundle_path, Some("hunter2")).unwrap();
+
+    let bundle = File::open(&bundle_path).unwrap();
+    let mut archive = ZipArchive::new(&bundle).unwrap();
+    assert!(matches!(
+        read_bundle_info(&mut archive, None),
+        Err(InstanceExportError::PassphraseRequired)
+    ));
+    assert!(matches!(
+        read_bundle_info(&mut archive, Some("wrong")),
+        Err(InstanceExportError::InvalidPassphrase)
+    ));
+    assert_eq!(
+        read_bundle_info(&mut archive, Some("hunter2")).unwrap(),
+        info
+    );
+
+    let imported_dir = tmp_dir.path().join("Imported");
+    extract_bundle(&imported_dir, &mut archive, Some("hunter2")).unwrap();
+    assert_eq!(
+        fs::read_to_string(imported_dir.join("options.txt")).unwrap(),
+        "options.txt"
+    );
+}
+
+#[test]
+fn test_bundle_cannot_replace_the_config() {
+    let tmp_dir = tempdir::TempDir::new("export").unwrap();
+    let bundle_path = tmp_dir.path().join("bundle.zip");
+    let mut zip = ZipWriter::new(File::create(&bundle_path).unwrap());
+    for name in [
+        "instance/config.json",
+        "instance/../escaped.txt",
+        "instance/options.txt",
+    ] {
+        zip.start_file(name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(name.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let bundle = File::open(&bundle_path).unwrap();
+    let imported_dir = tmp_dir.path().join("Imported");
+    extract_bundle(&imported_dir, &mut ZipArchive::new(&bundle).unwrap(), None).unwrap();
+    assert!(imported_dir.join("options.txt").is_file());
+    assert!(!imported_dir.join("config.json").exists());
+    assert!(!tmp_dir.path().join("escaped.txt").exists());
+}
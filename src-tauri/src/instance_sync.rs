@@ -0,0 +1,461 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Component, Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, Local};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use crate::state::instance_manager::directory_slug;
+
+/// File in the instance directory remembering the state of every file after the last sync.
+pub const SYNC_STATE_FILE: &str = "sync_state.json";
+
+#[derive(Debug)]
+pub enum InstanceSyncError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// Synced entries must be relative paths inside the instance.
+    InvalidEntry(String),
+    /// The sync folder is missing or empty although files were synced to it, e.g. because the
+    /// drive isn't mounted. Syncing would delete the instance's files.
+    RemoteMissing(PathBuf),
+}
+
+impl fmt::Display for InstanceSyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstanceSyncError::Io(error) => write!(f, "{}", error),
+            InstanceSyncError::Json(error) => write!(f, "{}", error),
+            InstanceSyncError::InvalidEntry(entry) => {
+                write!(f, "`{}` is not a path inside the instance", entry)
+            }
+            InstanceSyncError::RemoteMissing(path) => write!(
+                f,
+                "The sync folder {} is missing or empty, check that it is available",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for InstanceSyncError {
+    fn from(error: io::Error) -> Self {
+        InstanceSyncError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for InstanceSyncError {
+    fn from(error: serde_json::Error) -> Self {
+        InstanceSyncError::Json(error)
+    }
+}
+
+pub type InstanceSyncResult<T> = Result<T, InstanceSyncError>;
+
+/// Mirrors parts of an instance to a folder, e.g. one kept in sync by a cloud drive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceSync {
+    /// The instance is mirrored to `<directory>/<instance name>`.
+    pub directory: PathBuf,
+    /// Files and folders relative to the instance directory, `saves`, `config` and `options.txt`
+    /// by default.
+    #[serde(default = "default_entries")]
+    pub entries: Vec<String>,
+}
+
+fn default_entries() -> Vec<String> {
+    vec!["saves".into(), "config".into(), "options.txt".into()]
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    copied_to_folder: Vec<String>,
+    copied_to_instance: Vec<String>,
+    deleted: Vec<String>,
+    /// Files changed on both sides since the last sync. The newer copy is kept and the other is
+    /// saved next to it with the time it was modified in its name.
+    conflicts: Vec<String>,
+}
+
+/// Modification times in milliseconds of a file on both sides after it was last synced.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct SyncedFile {
+    local: u64,
+    remote: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    /// The folder the files were synced with, `None` for states saved before it was kept.
+    #[serde(default)]
+    remote_dir: Option<PathBuf>,
+    files: HashMap<String, SyncedFile>,
+}
+
+impl SyncState {
+    fn load(instance_dir: &Path) -> InstanceSyncResult<Self> {
+        let path = instance_dir.join(SYNC_STATE_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    fn save(&self, instance_dir: &Path) -> InstanceSyncResult<()> {
+        let file = File::create(instance_dir.join(SYNC_STATE_FILE))?;
+        serde_json::to_writer(file, self)?;
+        Ok(())
+    }
+}
+
+/// Syncs the instance at `instance_dir` with its folder. Files changed on only one side since the
+/// last sync are copied over, deletions are mirrored if the other side hasn't changed since.
+pub fn sync_instance(
+    instance_dir: &Path,
+    instance_name: &str,
+    sync: &InstanceSync,
+) -> InstanceSyncResult<SyncReport> {
+    let mut state = SyncState::load(instance_dir)?;
+    let remote_dir = remote_dir(sync, instance_name, &state);
+    info!(
+        "Syncing {} with {}",
+        instance_dir.display(),
+        remote_dir.display()
+    );
+    if state.remote_dir.as_ref() != Some(&remote_dir) {
+        // What was synced with another folder says nothing about this one, nothing is deleted
+        // until both sides have been synced once.
+        state.files.clear();
+        state.remote_dir = Some(remote_dir.clone());
+    }
+    if !state.files.is_empty() && is_missing_or_empty(&remote_dir) {
+        return Err(InstanceSyncError::RemoteMissing(remote_dir));
+    }
+    let mut report = SyncReport::default();
+
+    let mut files = BTreeSet::new();
+    for entry in &sync.entries {
+        let entry_path = Path::new(entry);
+        if !entry_path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(InstanceSyncError::InvalidEntry(entry.clone()));
+        }
+        collect_files(instance_dir, entry_path, &mut files)?;
+        collect_files(&remote_dir, entry_path, &mut files)?;
+    }
+    // Files that were synced before but are gone from both sides.
+    state.files.retain(|file, _| files.contains(file));
+
+    for file in files {
+        let local_path = instance_dir.join(&file);
+        let remote_path = remote_dir.join(&file);
+        let last_sync = state.files.get(&file).copied();
+        match (modified_millis(&local_path), modified_millis(&remote_path)) {
+            (Some(local), None) => {
+                if last_sync.map_or(false, |synced| synced.local == local) {
+                    fs::remove_file(&local_path)?;
+                    state.files.remove(&file);
+                    report.deleted.push(file);
+                    continue;
+                }
+                copy_file(&local_path, &remote_path)?;
+                report.copied_to_folder.push(file.clone());
+            }
+            (None, Some(remote)) => {
+                if last_sync.map_or(false, |synced| synced.remote == remote) {
+                    fs::remove_file(&remote_path)?;
+                    state.files.remove(&file);
+                    report.deleted.push(file);
+                    continue;
+                }
+                copy_file(&remote_path, &local_path)?;
+                report.copied_to_instance.push(file.clone());
+            }
+            (Some(local), Some(remote)) => {
+                let local_changed = last_sync.map_or(true, |synced| synced.local != local);
+                let remote_changed = last_sync.map_or(true, |synced| synced.remote != remote);
+                match (local_changed, remote_changed) {
+                    (false, false) => continue,
+                    (true, false) => {
+                        copy_file(&local_path, &remote_path)?;
+                        report.copied_to_folder.push(file.clone());
+                    }
+                    (false, true) => {
+                        copy_file(&remote_path, &local_path)?;
+                        report.copied_to_instance.push(file.clone());
+                    }
+                    (true, true) if same_contents(&local_path, &remote_path)? => {}
+                    (true, true) => {
+                        debug!("{} changed in the instance and the sync folder", file);
+                        if local >= remote {
+                            keep_conflicting_copy(&remote_path, remote)?;
+                            copy_file(&local_path, &remote_path)?;
+                        } else {
+                            keep_conflicting_copy(&local_path, local)?;
+                            copy_file(&remote_path, &local_path)?;
+                        }
+                        report.conflicts.push(file.clone());
+                    }
+                }
+            }
+            (None, None) => continue,
+        }
+
+        if let (Some(local), Some(remote)) =
+            (modified_millis(&local_path), modified_millis(&remote_path))
+        {
+            state.files.insert(file, SyncedFile { local, remote });
+        }
+    }
+
+    state.save(instance_dir)?;
+    Ok(report)
+}
+
+/// The folder in the sync directory the instance is mirrored to. Instances synced before folder
+/// names were made safe keep using the folder named after the instance.
+fn remote_dir(sync: &InstanceSync, instance_name: &str, state: &SyncState) -> PathBuf {
+    match &state.remote_dir {
+        Some(dir) if dir.parent() == Some(sync.directory.as_path()) => dir.clone(),
+        None if !state.files.is_empty() => sync.directory.join(instance_name),
+        // The name could contain separators or characters the shared drive doesn't allow.
+        _ => sync.directory.join(directory_slug(instance_name)),
+    }
+}
+
+fn is_missing_or_empty(dir: &Path) -> bool {
+    fs::read_dir(dir).map_or(true, |mut entries| entries.next().is_none())
+}
+
+/// Adds the files at `entry` under `root` to `files` as `/` separated paths relative to `root`.
+fn collect_files(root: &Path, entry: &Path, files: &mut BTreeSet<String>) -> io::Result<()> {
+    let path = root.join(entry);
+    if path.is_file() {
+        files.insert(relative_name(entry));
+        return Ok(());
+    }
+    if !path.is_dir() {
+        return Ok(());
+    }
+    for dir_entry in fs::read_dir(&path)? {
+        let name = dir_entry?.file_name();
+        collect_files(root, &entry.join(name), files)?;
+    }
+    Ok(())
+}
+
+fn relative_name(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn modified_millis(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+/// Copies `from` to `to`, keeping the modification time so the newer side can be told apart.
+fn copy_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(from, to)?;
+    let modified = fs::metadata(from)?.modified()?;
+    File::options().write(true).open(to)?.set_modified(modified)
+}
+
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+    Ok(fs::read(a)? == fs::read(b)?)
+}
+
+/// Copies the file at `path` to e.g. `options.conflict-2023-06-01T12-30-00.txt` beside it.
+fn keep_conflicting_copy(path: &Path, modified: u64) -> io::Result<()> {
+    let modified: DateTime<Local> = (UNIX_EPOCH + Duration::from_millis(modified)).into();
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut name = format!("{}.conflict-{}", stem, modified.format("%Y-%m-%dT%H-%M-%S"));
+    if let Some(extension) = path.extension() {
+        name = format!("{}.{}", name, extension.to_string_lossy());
+    }
+    copy_file(path, &path.with_file_name(name))
+}
+
+#[cfg(test)]
+fn set_modified(path: &Path, seconds: u64) {
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(UNIX_EPOCH + Duration::from_secs(seconds))
+        .unwrap();
+}
+
+#[test]
+fn test_sync_instance() {
+    let tmp_dir = tempdir::TempDir::new("sync").unwrap();
+    let instance_dir = tmp_dir.path().join("instance");
+    let sync = InstanceSync {
+        directory: tmp_dir.path().join("cloud"),
+        entries: default_entries(),
+    };
+    let remote_dir = sync.directory.join("Test");
+    fs::create_dir_all(instance_dir.join("saves/World")).unwrap();
+    fs::write(instance_dir.join("saves/World/level.dat"), "level").unwrap();
+    fs::write(instance_dir.join("options.txt"), "fov:70").unwrap();
+    fs::create_dir_all(instance_dir.join("mods")).unwrap();
+    fs::write(instance_dir.join("mods/a.jar"), "").unwrap();
+
+    // The first sync copies everything selected to the folder.
+    let report = sync_instance(&instance_dir, "Test", &sync).unwrap();
+    assert_eq!(
+        report.copied_to_folder,
+        vec!["options.txt", "saves/World/level.dat"]
+    );
+    assert!(remote_dir.join("saves/World/level.dat").is_file());
+    assert!(!remote_dir.join("mods").exists());
+
+    // Another machine changed the options and deleted the world.
+    fs::write(remote_dir.join("options.txt"), "fov:90").unwrap();
+    set_modified(&remote_dir.join("options.txt"), 2_000_000_000);
+    fs::remove_file(remote_dir.join("saves/World/level.dat")).unwrap();
+    let report = sync_instance(&instance_dir, "Test", &sync).unwrap();
+    assert_eq!(report.copied_to_instance, vec!["options.txt"]);
+    assert_eq!(report.deleted, vec!["saves/World/level.dat"]);
+    assert_eq!(
+        fs::read_to_string(instance_dir.join("options.txt")).unwrap(),
+        "fov:90"
+    );
+    assert!(!instance_dir.join("saves/World/level.dat").exists());
+
+    // Nothing changed since.
+    assert_eq!(
+        sync_instance(&instance_dir, "Test", &sync).unwrap(),
+        SyncReport::default()
+    );
+
+    // Both sides changed, the newer local copy wins and the other is kept beside it.
+    fs::write(remote_dir.join("options.txt"), "fov:80").unwrap();
+    set_modified(&remote_dir.join("options.txt"), 2_000_000_100);
+    fs::write(instance_dir.join("options.txt"), "fov:100").unwrap();
+    set_modified(&instance_dir.join("options.txt"), 2_000_000_200);
+    let report = sync_instance(&instance_dir, "Test", &sync).unwrap();
+    assert_eq!(report.conflicts, vec!["options.txt"]);
+    assert_eq!(
+        fs::read_to_string(remote_dir.join("options.txt")).unwrap(),
+        "fov:100"
+    );
+    let conflicts: Vec<_> = fs::read_dir(&remote_dir)
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("options.conflict-")
+        })
+        .collect();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(fs::read_to_string(conflicts[0].path()).unwrap(), "fov:80");
+}
+
+#[test]
+fn test_sync_keeps_files_when_folder_is_missing() {
+    let tmp_dir = tempdir::TempDir::new("sync").unwrap();
+    let instance_dir = tmp_dir.path().join("instance");
+    let sync = InstanceSync {
+        directory: tmp_dir.path().join("cloud"),
+        entries: default_entries(),
+    };
+    fs::create_dir_all(&instance_dir).unwrap();
+    fs::write(instance_dir.join("options.txt"), "fov:70").unwrap();
+    sync_instance(&instance_dir, "Test", &sync).unwrap();
+
+    // The drive isn't mounted, or mounted without its contents yet.
+    fs::remove_dir_all(&sync.directory).unwrap();
+    assert!(matches!(
+        sync_instance(&instance_dir, "Test", &sync),
+        Err(InstanceSyncError::RemoteMissing(_))
+    ));
+    fs::create_dir_all(sync.directory.join("Test")).unwrap();
+    assert!(matches!(
+        sync_instance(&instance_dir, "Test", &sync),
+        Err(InstanceSyncError::RemoteMissing(_))
+    ));
+    assert!(instance_dir.join("options.txt").is_file());
+}
+
+#[test]
+fn test_sync_keeps_folder_of_earlier_syncs() {
+    let tmp_dir = tempdir::TempDir::new("sync").unwrap();
+    let instance_dir = tmp_dir.path().join("instance");
+    let sync = InstanceSync {
+        directory: tmp_dir.path().join("cloud"),
+        entries: default_entries(),
+    };
+    let legacy_dir = sync.directory.join("Test: Modded");
+    fs::create_dir_all(&instance_dir).unwrap();
+    fs::create_dir_all(&legacy_dir).unwrap();
+    fs::write(instance_dir.join("options.txt"), "fov:70").unwrap();
+    fs::write(legacy_dir.join("options.txt"), "fov:70").unwrap();
+    // Saved before the folder was remembered, by a sync with the folder named after the instance.
+    let modified = modified_millis(&instance_dir.join("options.txt")).unwrap();
+    fs::write(
+        instance_dir.join(SYNC_STATE_FILE),
+        format!(
+            r#"{{"files": {{"options.txt": {{"local": {0}, "remote": {0}}}}}}}"#,
+            modified
+        ),
+    )
+    .unwrap();
+
+    let report = sync_instance(&instance_dir, "Test: Modded", &sync).unwrap();
+    assert!(report.deleted.is_empty());
+    assert!(instance_dir.join("options.txt").is_file());
+    assert!(!sync.directory.join("Test_ Modded").exists());
+    let state = SyncState::load(&instance_dir).unwrap();
+    assert_eq!(state.remote_dir, Some(legacy_dir));
+
+    // A new sync directory starts over instead of mirroring deletions.
+    let moved = InstanceSync {
+        directory: tmp_dir.path().join("other-cloud"),
+        entries: default_entries(),
+    };
+    let report = sync_instance(&instance_dir, "Test: Modded", &moved).unwrap();
+    assert_eq!(report.copied_to_folder, vec!["options.txt"]);
+    assert!(moved
+        .directory
+        .join("Test_ Modded")
+        .join("options.txt")
+        .is_file());
+}
+
+#[test]
+fn test_sync_rejects_entries_outside_instance() {
+    let tmp_dir = tempdir::TempDir::new("sync").unwrap();
+    let sync = InstanceSync {
+        directory: tmp_dir.path().join("cloud"),
+        entries: vec!["../other".into()],
+    };
+    assert!(matches!(
+        sync_instance(tmp_dir.path(), "Test", &sync),
+        Err(InstanceSyncError::InvalidEntry(_))
+    ));
+}
@@ -0,0 +1,216 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::state::instance_manager::{deserialize_jvm_arguments, InstanceConfiguration};
+
+/// Describes the template, stored next to its `options.txt` and mods.
+const TEMPLATE_FILE: &str = "template.json";
+
+/// Settings saved from an instance that new instances can start from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceTemplate {
+    pub name: String,
+    #[serde(deserialize_with = "deserialize_jvm_arguments")]
+    pub additional_jvm_arguments: Vec<String>,
+    pub max_memory_mb: Option<u64>,
+    pub resolution_width: Option<u32>,
+    pub resolution_height: Option<u32>,
+    pub fullscreen: Option<bool>,
+    pub prefer_discrete_gpu: bool,
+    /// New instances get a copy of the template's `options.txt`.
+    pub has_options: bool,
+    /// File names of the mods copied into new instances.
+    pub mods: Vec<String>,
+}
+
+/// Template names are used as folder names, so they can't point outside the templates folder.
+fn template_dir(templates_dir: &Path, name: &str) -> io::Result<PathBuf> {
+    if name.is_empty() || name == ".." || name.contains(['/', '\\']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("`{}` is not a valid template name", name),
+        ));
+    }
+    Ok(templates_dir.join(name))
+}
+
+/// Saves the settings of the instance at `instance_dir` as the template `name`, replacing any
+/// template with the same name. Its `options.txt` and mods are only kept if asked for.
+pub fn save_template(
+    templates_dir: &Path,
+    name: &str,
+    config: &InstanceConfiguration,
+    instance_dir: &Path,
+    include_options: bool,
+    include_mods: bool,
+) -> io::Result<InstanceTemplate> {
+    let template_dir = template_dir(templates_dir, name)?;
+    if template_dir.exists() {
+        fs::remove_dir_all(&template_dir)?;
+    }
+    fs::create_dir_all(&template_dir)?;
+
+    let options_path = instance_dir.join("options.txt");
+    let has_options = include_options && options_path.is_file();
+    if has_options {
+        fs::copy(&options_path, template_dir.join("options.txt"))?;
+    }
+
+    let mut mods = Vec::new();
+    let mods_dir = instance_dir.join("mods");
+    if include_mods && mods_dir.is_dir() {
+        fs::create_dir_all(template_dir.join("mods"))?;
+        for entry in fs::read_dir(&mods_dir)? {
+            let entry = entry?;
+            if !entry.path().is_file() {
+                continue;
+            }
+            fs::copy(
+                entry.path(),
+                template_dir.join("mods").join(entry.file_name()),
+            )?;
+            mods.push(entry.file_name().to_string_lossy().into_owned());
+        }
+        mods.sort();
+    }
+
+    let template = InstanceTemplate {
+        name: name.into(),
+        additional_jvm_arguments: config.additional_jvm_arguments.clone(),
+        max_memory_mb: config.max_memory_mb,
+        resolution_width: config.resolution_width,
+        resolution_height: config.resolution_height,
+        fullscreen: config.fullscreen,
+        prefer_discrete_gpu: config.prefer_discrete_gpu,
+        has_options,
+        mods,
+    };
+    serde_json::to_writer(File::create(template_dir.join(TEMPLATE_FILE))?, &template)?;
+    info!("Saved {} as template {}", config.instance_name, name);
+    Ok(template)
+}
+
+pub fn load_template(templates_dir: &Path, name: &str) -> io::Result<InstanceTemplate> {
+    let path = template_dir(templates_dir, name)?.join(TEMPLATE_FILE);
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// Every saved template, sorted by name. Templates that can't be read are skipped.
+pub fn list_templates(templates_dir: &Path) -> io::Result<Vec<InstanceTemplate>> {
+    if !templates_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut templates = Vec::new();
+    for entry in fs::read_dir(templates_dir)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        match load_template(templates_dir, &name) {
+            Ok(template) => templates.push(template),
+            Err(e) => warn!("Skipping template {}: {}", name, e),
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+pub fn delete_template(templates_dir: &Path, name: &str) -> io::Result<()> {
+    fs::remove_dir_all(template_dir(templates_dir, name)?)
+}
+
+/// Copies the template's `options.txt` and mods into a newly created instance.
+pub fn copy_template_files(
+    templates_dir: &Path,
+    template: &InstanceTemplate,
+    instance_dir: &Path,
+) -> io::Result<()> {
+    let template_dir = template_dir(templates_dir, &template.name)?;
+    if template.has_options {
+        fs::copy(
+            template_dir.join("options.txt"),
+            instance_dir.join("options.txt"),
+        )?;
+    }
+    if !template.mods.is_empty() {
+        fs::create_dir_all(instance_dir.join("mods"))?;
+    }
+    for name in &template.mods {
+        fs::copy(
+            template_dir.join("mods").join(name),
+            instance_dir.join("mods").join(name),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+use {crate::web_services::resources::ModloaderType, tempdir::TempDir};
+
+#[test]
+fn test_templates() {
+    let tmp_dir = TempDir::new("templates").unwrap();
+    let templates_dir = tmp_dir.path().join("templates");
+    let instance_dir = tmp_dir.path().join("Source");
+    fs::create_dir_all(instance_dir.join("mods")).unwrap();
+    fs::write(instance_dir.join("options.txt"), "renderDistance:16\n").unwrap();
+    fs::write(instance_dir.join("mods").join("sodium.jar"), "sodium").unwrap();
+    let config = InstanceConfiguration {
+        instance_name: "Source".into(),
+        jvm_path: PathBuf::from("java"),
+        arguments: Vec::new(),
+        modloader_type: ModloaderType::Fabric,
+        modloader_version: "0.15.0".into(),
+        author: "You".into(),
+        instance_icon: None,
+        playtime: 0,
+        resolution_width: Some(1280),
+        resolution_height: Some(720),
+        fullscreen: None,
+        vanilla_version: Some("1.20.1".into()),
+        sync: None,
+        max_memory_mb: Some(4096),
+        prefer_discrete_gpu: true,
+        additional_jvm_arguments: vec!["-XX:+UseG1GC".into()],
+        asset_index: None,
+        directory_name: String::new(),
+        data_root: None,
+        isolated_resources: false,
+        update_watch: None,
+        restart_on_crash: None,
+    };
+
+    let template = save_template(
+        &templates_dir,
+        "Testing",
+        &config,
+        &instance_dir,
+        true,
+        true,
+    )
+    .unwrap();
+    assert_eq!(template.mods, ["sodium.jar"]);
+    assert!(template.has_options);
+    assert_eq!(
+        list_templates(&templates_dir).unwrap(),
+        vec![template.clone()]
+    );
+    assert!(save_template(&templates_dir, "../x", &config, &instance_dir, false, false).is_err());
+
+    let new_instance_dir = tmp_dir.path().join("New");
+    fs::create_dir_all(&new_instance_dir).unwrap();
+    copy_template_files(&templates_dir, &template, &new_instance_dir).unwrap();
+    assert_eq!(
+        fs::read_to_string(new_instance_dir.join("options.txt")).unwrap(),
+        "renderDistance:16\n"
+    );
+    assert!(new_instance_dir.join("mods").join("sodium.jar").is_file());
+
+    delete_template(&templates_dir, "Testing").unwrap();
+    assert!(list_templates(&templates_dir).unwrap().is_empty());
+}
@@ -0,0 +1,269 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use zip::ZipArchive;
+
+/// How many of the overlapping classes of two jars are listed, there are often thousands.
+const MAX_CLASS_EXAMPLES: usize = 5;
+
+/// Jars in an instance's mods folder that shouldn't be installed together.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum JarConflict {
+    /// Several jars are the same mod, usually two versions of it.
+    #[serde(rename_all = "camelCase")]
+    DuplicateModId { mod_id: String, jars: Vec<String> },
+    /// The jars contain the same classes, only one of each can be loaded.
+    #[serde(rename_all = "camelCase")]
+    OverlappingClasses {
+        jars: Vec<String>,
+        class_count: usize,
+        examples: Vec<String>,
+    },
+}
+
+/// What a jar declares and contains.
+#[derive(Debug, Default, PartialEq)]
+struct JarContents {
+    mod_ids: Vec<String>,
+    classes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct FabricModJson {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct McmodInfoEntry {
+    modid: String,
+}
+
+/// Finds the jars in `mods_dir` that are the same mod or contain the same classes. Jars that can't
+/// be read are skipped.
+pub fn scan_conflicts(mods_dir: &Path) -> io::Result<Vec<JarConflict>> {
+    let mut jars = Vec::new();
+    for entry in mods_dir.read_dir()? {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().map_or(true, |ext| ext != "jar") {
+            continue;
+        }
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        match read_jar_contents(&path) {
+            Ok(contents) => jars.push((name, contents)),
+            Err(e) => warn!("Skipping {} while scanning for conflicts: {}", name, e),
+        }
+    }
+    jars.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(find_conflicts(&jars))
+}
+
+fn find_conflicts(jars: &[(String, JarContents)]) -> Vec<JarConflict> {
+    let mut mod_ids: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    let mut classes: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (name, contents) in jars {
+        for mod_id in &contents.mod_ids {
+            mod_ids.entry(mod_id).or_default().push(name.clone());
+        }
+        for class in &contents.classes {
+            classes.entry(class).or_default().push(name);
+        }
+    }
+
+    let mut conflicts: Vec<JarConflict> = mod_ids
+        .into_iter()
+        .filter(|(_, jars)| jars.len() > 1)
+        .map(|(mod_id, jars)| JarConflict::DuplicateModId {
+            mod_id: mod_id.into(),
+            jars,
+        })
+        .collect();
+
+    // <Jars containing the same classes, the classes>
+    let mut overlaps: BTreeMap<Vec<&str>, Vec<&str>> = BTreeMap::new();
+    for (class, jars) in classes {
+        if jars.len() > 1 {
+            overlaps.entry(jars).or_default().push(class);
+        }
+    }
+    for (overlapping_jars, classes) in overlaps {
+        let jars: Vec<String> = overlapping_jars.iter().map(|jar| jar.to_string()).collect();
+        // Same mod twice is already reported, its classes overlapping is no news.
+        let same_mod = conflicts.iter().any(|conflict| match conflict {
+            JarConflict::DuplicateModId {
+                jars: duplicates, ..
+            } => jars.iter().all(|jar| duplicates.contains(jar)),
+            _ => false,
+        });
+        if same_mod {
+            continue;
+        }
+        conflicts.push(JarConflict::OverlappingClasses {
+            jars,
+            class_count: classes.len(),
+            examples: classes
+                .iter()
+                .take(MAX_CLASS_EXAMPLES)
+                .map(|class| class.to_string())
+                .collect(),
+        });
+    }
+    conflicts
+}
+
+fn read_jar_contents(path: &Path) -> io::Result<JarContents> {
+    let mut archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+    let mut contents = JarContents::default();
+    for name in archive.file_names() {
+        // Multi-release jars repeat classes for newer java versions, and every module has these.
+        let is_class = name.ends_with(".class")
+            && !name.starts_with("META-INF/")
+            && !name.ends_with("module-info.class")
+            && !name.ends_with("package-info.class");
+        if is_class {
+            contents.classes.push(name.to_owned());
+        }
+    }
+
+    for metadata_file in ["fabric.mod.json", "quilt.mod.json"] {
+        if let Some(text) = read_text_entry(&mut archive, metadata_file)? {
+            if let Ok(fabric_mod) = serde_json::from_str::<FabricModJson>(&text) {
+                contents.mod_ids.push(fabric_mod.id);
+            }
+        }
+    }
+    for metadata_file in ["META-INF/mods.toml", "META-INF/neoforge.mods.toml"] {
+        if let Some(text) = read_text_entry(&mut archive, metadata_file)? {
+            contents.mod_ids.extend(mods_toml_ids(&text));
+        }
+    }
+    if let Some(text) = read_text_entry(&mut archive, "mcmod.info")? {
+        if let Ok(entries) = serde_json::from_str::<Vec<McmodInfoEntry>>(&text) {
+            contents
+                .mod_ids
+                .extend(entries.into_iter().map(|entry| entry.modid));
+        }
+    }
+    contents.mod_ids.sort();
+    contents.mod_ids.dedup();
+    Ok(contents)
+}
+
+fn read_text_entry(
+    archive: &mut ZipArchive<BufReader<File>>,
+    name: &str,
+) -> io::Result<Option<String>> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(_) => return Ok(None),
+    };
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+/// The `modId`s of the `[[mods]]` tables in a forge `mods.toml`. Dependencies have a `modId` too,
+/// so the ones in other tables are skipped.
+fn mods_toml_ids(text: &str) -> Vec<String> {
+    let mut in_mods_table = false;
+    let mut ids = Vec::new();
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or(line).trim();
+        if line.starts_with('[') {
+            in_mods_table = line == "[[mods]]";
+            continue;
+        }
+        if !in_mods_table {
+            continue;
+        }
+        let value = line
+            .strip_prefix("modId")
+            .map(str::trim_start)
+            .and_then(|rest| rest.strip_prefix('='))
+            .map(str::trim);
+        if let Some(value) = value {
+            ids.push(value.trim_matches(['"', '\'']).to_owned());
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+use {
+    std::{fs, io::Write},
+    tempdir::TempDir,
+    zip::write::SimpleFileOptions,
+};
+
+#[test]
+fn test_mods_toml_ids() {
+    let toml = r#"
+modLoader="javafml"
+[[mods]] # The mod
+modId = "create" # Used everywhere
+version="0.5.1"
+[[dependencies.create]]
+    modId="forge"
+"#;
+    assert_eq!(mods_toml_ids(toml), ["create"]);
+}
+
+#[test]
+fn test_scan_conflicts() {
+    let tmp_dir = TempDir::new("jar_conflicts").unwrap();
+    let write_jar = |name: &str, entries: &[(&str, &str)]| {
+        let mut zip = zip::ZipWriter::new(File::create(tmp_dir.path().join(name)).unwrap());
+        for (path, text) in entries {
+            zip.start_file(*path, SimpleFileOptions::default()).unwrap();
+            zip.write_all(text.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    };
+    let sodium = r#"{"id": "sodium"}"#;
+    write_jar(
+        "sodium-0.4.jar",
+        &[("fabric.mod.json", sodium), ("me/Sodium.class", "")],
+    );
+    write_jar(
+        "sodium-0.5.jar",
+        &[("fabric.mod.json", sodium), ("me/Sodium.class", "")],
+    );
+    write_jar(
+        "library.jar",
+        &[("lib/Shared.class", ""), ("lib/Other.class", "")],
+    );
+    write_jar(
+        "shaded.jar",
+        &[
+            ("fabric.mod.json", r#"{"id": "shaded"}"#),
+            ("lib/Shared.class", ""),
+            ("META-INF/versions/9/module-info.class", ""),
+        ],
+    );
+    fs::write(tmp_dir.path().join("broken.jar"), "not a zip").unwrap();
+
+    assert_eq!(
+        scan_conflicts(tmp_dir.path()).unwrap(),
+        [
+            JarConflict::DuplicateModId {
+                mod_id: "sodium".into(),
+                jars: vec!["sodium-0.4.jar".into(), "sodium-0.5.jar".into()],
+            },
+            JarConflict::OverlappingClasses {
+                jars: vec!["library.jar".into(), "shaded.jar".into()],
+                class_count: 1,
+                examples: vec!["lib/Shared.class".into()],
+            },
+        ]
+    );
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+use autmc_downloader::hash_bytes_sha1;
+use bytes::Bytes;
+use log::info;
+use serde::{Deserialize, Serialize};
+use zip::{ZipArchive, ZipWriter};
+
+/// Folder in the instance directory the applied jar mods are kept in, so the jar can be rebuilt.
+pub const JAR_MODS_DIR: &str = "jarmods";
+/// Records which jar mods were merged into the instance's jar, in the instance directory.
+const JAR_MODS_FILE: &str = "jarmods.json";
+
+/// A zip or jar merged into the instance's client jar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JarMod {
+    /// Its file name in [JAR_MODS_DIR].
+    pub file_name: String,
+    pub sha1: String,
+}
+
+/// The jar mods applied to an instance, enough to build the same jar again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JarMods {
+    /// The vanilla client jar the mods were merged into, which the merged jar replaces in the
+    /// instance's classpath.
+    pub vanilla_jar: PathBuf,
+    pub vanilla_sha1: String,
+    /// In the order they were merged, later mods overwrite the files of earlier ones.
+    pub mods: Vec<JarMod>,
+}
+
+/// Where the merged jar of the instance at `instance_dir` is stored.
+pub fn custom_jar_path(instance_dir: &Path) -> PathBuf {
+    instance_dir.join("bin").join("minecraft.jar")
+}
+
+/// The jar mods applied to the instance at `instance_dir`, `None` if it uses the vanilla jar.
+pub fn read_jar_mods(instance_dir: &Path) -> io::Result<Option<JarMods>> {
+    let file = match File::open(instance_dir.join(JAR_MODS_FILE)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Ok(Some(serde_json::from_reader(BufReader::new(file))?))
+}
+
+fn sha1_of_file(path: &Path) -> io::Result<String> {
+    Ok(hash_bytes_sha1(&Bytes::from(fs::read(path)?)))
+}
+
+/// Merges `mods` into a copy of `vanilla_jar` at `output`. Later mods overwrite the files of
+/// earlier ones and of the vanilla jar. `META-INF` is left out, its signatures wouldn't match the
+/// modified classes.
+pub fn merge_jar_mods(vanilla_jar: &Path, mods: &[PathBuf], output: &Path) -> io::Result<()> {
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut writer = ZipWriter::new(File::create(output)?);
+    let mut written = HashSet::new();
+    for path in mods.iter().rev().map(PathBuf::as_path).chain([vanilla_jar]) {
+        let mut archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+        for index in 0..archive.len() {
+            let file = archive.by_index_raw(index)?;
+            let name = file.name().to_owned();
+            if name.starts_with("META-INF/") || !written.insert(name) {
+                continue;
+            }
+            writer.raw_copy_file(file)?;
+        }
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Copies `mod_paths` into the instance's [JAR_MODS_DIR], merges them into `vanilla_jar` at
+/// [custom_jar_path] and records them. An empty list removes the merged jar instead. Returns the
+/// applied jar mods, `None` when they were removed.
+pub fn apply_jar_mods(
+    instance_dir: &Path,
+    vanilla_jar: &Path,
+    mod_paths: &[PathBuf],
+) -> io::Result<Option<JarMods>> {
+    let custom_jar = custom_jar_path(instance_dir);
+    if mod_paths.is_empty() {
+        for path in [custom_jar, instance_dir.join(JAR_MODS_FILE)] {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        info!("Removed the jar mods of {}", instance_dir.display());
+        return Ok(None);
+    }
+
+    let mods_dir = instance_dir.join(JAR_MODS_DIR);
+    fs::create_dir_all(&mods_dir)?;
+    let mut stored_paths = Vec::new();
+    let mut mods = Vec::new();
+    for path in mod_paths {
+        let file_name = path.file_name().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a file", path.display()),
+            )
+        })?;
+        let stored_path = mods_dir.join(file_name);
+        // Reapplying keeps the mods that are already stored.
+        if path.parent() != Some(mods_dir.as_path()) {
+            fs::copy(path, &stored_path)?;
+        }
+        mods.push(JarMod {
+            file_name: file_name.to_string_lossy().into_owned(),
+            sha1: sha1_of_file(&stored_path)?,
+        });
+        stored_paths.push(stored_path);
+    }
+    merge_jar_mods(vanilla_jar, &stored_paths, &custom_jar)?;
+
+    let jar_mods = JarMods {
+        vanilla_jar: vanilla_jar.into(),
+        vanilla_sha1: sha1_of_file(vanilla_jar)?,
+        mods,
+    };
+    serde_json::to_writer_pretty(File::create(instance_dir.join(JAR_MODS_FILE))?, &jar_mods)?;
+    info!(
+        "Merged {} jar mods into {}",
+        jar_mods.mods.len(),
+        custom_jar.display()
+    );
+    Ok(Some(jar_mods))
+}
+
+#[cfg(test)]
+use {std::io::Read, std::io::Write, tempdir::TempDir, zip::write::SimpleFileOptions};
+
+#[test]
+fn test_apply_jar_mods() {
+    let tmp_dir = TempDir::new("jar_mods").unwrap();
+    let write_zip = |name: &str, entries: &[(&str, &str)]| {
+        let path = tmp_dir.path().join(name);
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        for (entry, text) in entries {
+            zip.start_file(*entry, SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(text.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        path
+    };
+    let vanilla_jar = write_zip(
+        "1.5.2.jar",
+        &[
+            ("META-INF/MOJANG_C.SF", "signature"),
+            ("net/minecraft/Minecraft.class", "vanilla"),
+            ("gui/items.png", "vanilla"),
+        ],
+    );
+    let mod_loader = write_zip(
+        "ModLoader.zip",
+        &[
+            ("net/minecraft/Minecraft.class", "modloader"),
+            ("ModLoader.class", "modloader"),
+        ],
+    );
+    let textures = write_zip(
+        "textures.zip",
+        &[
+            ("gui/items.png", "textures"),
+            ("ModLoader.class", "textures"),
+        ],
+    );
+    let instance_dir = tmp_dir.path().join("Legacy");
+
+    let jar_mods = apply_jar_mods(&instance_dir, &vanilla_jar, &[mod_loader, textures.clone()])
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        read_jar_mods(&instance_dir).unwrap(),
+        Some(jar_mods.clone())
+    );
+    assert_eq!(
+        jar_mods
+            .mods
+            .iter()
+            .map(|jar_mod| jar_mod.file_name.as_str())
+            .collect::<Vec<_>>(),
+        ["ModLoader.zip", "textures.zip"]
+    );
+    assert!(instance_dir
+        .join(JAR_MODS_DIR)
+        .join("textures.zip")
+        .exists());
+
+    let mut merged = ZipArchive::new(File::open(custom_jar_path(&instance_dir)).unwrap()).unwrap();
+    let mut read = |name: &str| {
+        let mut text = String::new();
+        merged
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    };
+    assert_eq!(read("net/minecraft/Minecraft.class"), "modloader");
+    assert_eq!(read("ModLoader.class"), "textures");
+    assert_eq!(read("gui/items.png"), "textures");
+    assert!(merged.by_name("META-INF/MOJANG_C.SF").is_err());
+
+    // Reapplying from the stored copies, without the first mod.
+    let stored = instance_dir.join(JAR_MODS_DIR).join("textures.zip");
+    let jar_mods = apply_jar_mods(&instance_dir, &vanilla_jar, &[stored]).unwrap();
+    assert_eq!(jar_mods.unwrap().mods.len(), 1);
+
+    assert_eq!(
+        apply_jar_mods(&instance_dir, &vanilla_jar, &[]).unwrap(),
+        None
+    );
+    assert!(!custom_jar_path(&instance_dir).exists());
+    assert_eq!(read_jar_mods(&instance_dir).unwrap(), None);
+}
@@ -0,0 +1,182 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+use indexmap::IndexMap;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::option_parser::{category_options, merge_options, OptionCategory};
+
+/// The `key_*` options of an instance's `options.txt`, saved under a name so they can be applied to
+/// other instances or shared as a file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeybindProfile {
+    pub name: String,
+    /// `options.txt` keys like `key_key.jump` with their key, like `key.keyboard.space`.
+    pub keybinds: IndexMap<String, String>,
+}
+
+/// Profile names are used as file names, so they can't point outside the profiles folder.
+fn profile_path(profiles_dir: &Path, name: &str) -> io::Result<PathBuf> {
+    if name.is_empty() || name == ".." || name.contains(['/', '\\']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("`{}` is not a valid keybind profile name", name),
+        ));
+    }
+    Ok(profiles_dir.join(format!("{}.json", name)))
+}
+
+fn write_profile(profiles_dir: &Path, profile: &KeybindProfile) -> io::Result<()> {
+    let path = profile_path(profiles_dir, &profile.name)?;
+    fs::create_dir_all(profiles_dir)?;
+    serde_json::to_writer_pretty(File::create(path)?, profile)?;
+    Ok(())
+}
+
+/// Reads a profile from `path`, dropping any options that aren't keybinds.
+fn read_profile(path: &Path) -> io::Result<KeybindProfile> {
+    let mut profile: KeybindProfile = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    profile
+        .keybinds
+        .retain(|key, _| OptionCategory::Keybinds.contains(key));
+    Ok(profile)
+}
+
+/// Saves the keybinds of the instance at `instance_dir` as the profile `name`, replacing any
+/// profile with the same name.
+pub fn save_keybind_profile(
+    profiles_dir: &Path,
+    name: &str,
+    instance_dir: &Path,
+) -> io::Result<KeybindProfile> {
+    let profile = KeybindProfile {
+        name: name.into(),
+        keybinds: category_options(instance_dir, OptionCategory::Keybinds)?,
+    };
+    write_profile(profiles_dir, &profile)?;
+    info!(
+        "Saved {} keybinds as profile {}",
+        profile.keybinds.len(),
+        name
+    );
+    Ok(profile)
+}
+
+pub fn load_keybind_profile(profiles_dir: &Path, name: &str) -> io::Result<KeybindProfile> {
+    read_profile(&profile_path(profiles_dir, name)?)
+}
+
+/// Every saved profile, sorted by name. Profiles that can't be read are skipped.
+pub fn list_keybind_profiles(profiles_dir: &Path) -> io::Result<Vec<KeybindProfile>> {
+    if !profiles_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut profiles = Vec::new();
+    for entry in fs::read_dir(profiles_dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .map_or(true, |extension| extension != "json")
+        {
+            continue;
+        }
+        match read_profile(&path) {
+            Ok(profile) => profiles.push(profile),
+            Err(e) => warn!("Skipping keybind profile {}: {}", path.display(), e),
+        }
+    }
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(profiles)
+}
+
+pub fn delete_keybind_profile(profiles_dir: &Path, name: &str) -> io::Result<()> {
+    fs::remove_file(profile_path(profiles_dir, name)?)
+}
+
+/// Sets the profile's keybinds in the `options.txt` of the instance at `instance_dir`, keeping its
+/// other options. Returns how many keybinds were set.
+pub fn apply_keybind_profile(
+    profiles_dir: &Path,
+    name: &str,
+    instance_dir: &Path,
+) -> io::Result<usize> {
+    let profile = load_keybind_profile(profiles_dir, name)?;
+    merge_options(instance_dir, profile.keybinds)
+}
+
+/// Writes the profile `name` to `path` to be shared.
+pub fn export_keybind_profile(profiles_dir: &Path, name: &str, path: &Path) -> io::Result<()> {
+    let profile = load_keybind_profile(profiles_dir, name)?;
+    serde_json::to_writer_pretty(File::create(path)?, &profile)?;
+    Ok(())
+}
+
+/// Saves the profile exported to `path`, replacing any profile with the same name.
+pub fn import_keybind_profile(profiles_dir: &Path, path: &Path) -> io::Result<KeybindProfile> {
+    let profile = read_profile(path)?;
+    write_profile(profiles_dir, &profile)?;
+    Ok(profile)
+}
+
+#[cfg(test)]
+use tempdir::TempDir;
+
+#[test]
+fn test_keybind_profiles() {
+    let tmp_dir = TempDir::new("keybind-profiles").unwrap();
+    let profiles_dir = tmp_dir.path().join("keybind_profiles");
+    let src_dir = tmp_dir.path().join("src");
+    let dst_dir = tmp_dir.path().join("dst");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::create_dir_all(&dst_dir).unwrap();
+    fs::write(
+        src_dir.join("options.txt"),
+        "version:3465\nkey_key.jump:key.keyboard.space\nkey_key.sneak:key.keyboard.left.control\nrenderDistance:16\n",
+    )
+    .unwrap();
+    fs::write(
+        dst_dir.join("options.txt"),
+        "version:3337\nkey_key.jump:key.keyboard.j\nrenderDistance:8\n",
+    )
+    .unwrap();
+
+    let profile = save_keybind_profile(&profiles_dir, "PvP", &src_dir).unwrap();
+    assert_eq!(profile.keybinds.len(), 2);
+    assert_eq!(list_keybind_profiles(&profiles_dir).unwrap(), [profile]);
+
+    assert_eq!(
+        apply_keybind_profile(&profiles_dir, "PvP", &dst_dir).unwrap(),
+        2
+    );
+    assert_eq!(
+        fs::read_to_string(dst_dir.join("options.txt")).unwrap(),
+        "version:3337\nkey_key.jump:key.keyboard.space\nrenderDistance:8\nkey_key.sneak:key.keyboard.left.control\n"
+    );
+
+    // Imported profiles only keep their keybinds.
+    let shared = tmp_dir.path().join("shared.json");
+    fs::write(
+        &shared,
+        r#"{"name": "Shared", "keybinds": {"key_key.drop": "key.keyboard.q", "gamma": "1.0"}}"#,
+    )
+    .unwrap();
+    let imported = import_keybind_profile(&profiles_dir, &shared).unwrap();
+    assert_eq!(
+        imported.keybinds.keys().collect::<Vec<_>>(),
+        ["key_key.drop"]
+    );
+    export_keybind_profile(&profiles_dir, "Shared", &shared).unwrap();
+    assert_eq!(
+        load_keybind_profile(&profiles_dir, "Shared").unwrap(),
+        imported
+    );
+
+    delete_keybind_profile(&profiles_dir, "PvP").unwrap();
+    assert_eq!(list_keybind_profiles(&profiles_dir).unwrap().len(), 1);
+    assert!(save_keybind_profile(&profiles_dir, "../escape", &src_dir).is_err());
+}
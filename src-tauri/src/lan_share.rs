@@ -0,0 +1,351 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    fs::File,
+    hash::{BuildHasher, Hasher},
+    io::{self, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    instance_export::{write_bundle, BundleInfo, InstanceExportError},
+    state::{InnerState, ManagerFromAppHandle},
+};
+use log::{debug, info, warn};
+use serde::Serialize;
+use tauri::async_runtime::{JoinHandle, Mutex};
+use tempdir::TempDir;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream, UdpSocket},
+};
+
+/// Port every sharing launcher listens on for discovery broadcasts.
+const DISCOVERY_PORT: u16 = 47831;
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
+const DISCOVERY_REQUEST: &str = "autmc-share?";
+const DISCOVERY_RESPONSE: &str = "autmc-share!";
+// Leaves out characters that are easy to mix up when reading the code to someone.
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 6;
+
+#[derive(Debug)]
+pub enum LanShareError {
+    Io(io::Error),
+    Http(reqwest::Error),
+    Export(InstanceExportError),
+    InvalidAddress(String),
+    ShareNotFound(String),
+}
+
+impl fmt::Display for LanShareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LanShareError::Io(error) => write!(f, "{}", error),
+            LanShareError::Http(error) => write!(f, "{}", error),
+            LanShareError::Export(error) => write!(f, "{}", error),
+            LanShareError::InvalidAddress(address) => write!(f, "Invalid address `{}`", address),
+            LanShareError::ShareNotFound(code) => {
+                write!(f, "No launcher is sharing `{}` on the local network", code)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for LanShareError {
+    fn from(error: io::Error) -> Self {
+        LanShareError::Io(error)
+    }
+}
+
+impl From<reqwest::Error> for LanShareError {
+    fn from(error: reqwest::Error) -> Self {
+        LanShareError::Http(error)
+    }
+}
+
+impl From<InstanceExportError> for LanShareError {
+    fn from(error: InstanceExportError) -> Self {
+        LanShareError::Export(error)
+    }
+}
+
+pub type LanShareResult<T> = Result<T, LanShareError>;
+
+/// The share this launcher is currently hosting. Dropping it stops the share.
+pub struct LanShare {
+    code: String,
+    instance_name: String,
+    port: u16,
+    // Holds the bundle until the share stops.
+    _bundle_dir: TempDir,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Drop for LanShare {
+    fn drop(&mut self) {
+        info!("Stopping LAN share of {}", self.instance_name);
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanShareInfo {
+    code: String,
+    instance_name: String,
+    /// `ip:port` to import from when discovery doesn't reach this launcher.
+    address: Option<String>,
+}
+
+impl LanShare {
+    pub fn info(&self) -> LanShareInfo {
+        LanShareInfo {
+            code: self.code.clone(),
+            instance_name: self.instance_name.clone(),
+            address: local_ip().map(|ip| SocketAddr::new(ip, self.port).to_string()),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LanShareState(pub Arc<Mutex<LanShareManager>>);
+
+impl InnerState<Arc<Mutex<LanShareManager>>> for LanShareState {
+    fn inner_state(&self) -> Arc<Mutex<LanShareManager>> {
+        self.0.clone()
+    }
+}
+
+impl ManagerFromAppHandle for LanShareManager {
+    type State = LanShareState;
+}
+
+/// Only one instance is shared at a time, starting a new share stops the previous one.
+#[derive(Default)]
+pub struct LanShareManager {
+    share: Option<LanShare>,
+}
+
+impl LanShareManager {
+    /// Hosts `share`, stopping the previous one.
+    pub fn set_share(&mut self, share: LanShare) {
+        self.share = Some(share);
+    }
+
+    /// The share currently hosted, if any.
+    pub fn share_info(&self) -> Option<LanShareInfo> {
+        self.share.as_ref().map(|share| share.info())
+    }
+
+    pub fn stop_share(&mut self) {
+        self.share.take();
+    }
+}
+
+/// Bundles the instance at `instance_dir` into `bundle_dir` and serves it to launchers on the local
+/// network that know the returned share's code.
+pub async fn start_lan_share(
+    instance_dir: &Path,
+    info: &BundleInfo,
+    bundle_dir: TempDir,
+) -> LanShareResult<LanShare> {
+    let bundle_path = bundle_dir.path().join("bundle.zip");
+    write_bundle(instance_dir, info, &bundle_path, None)?;
+
+    let code = generate_code();
+    let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    let port = listener.local_addr()?.port();
+    let mut tasks = vec![tauri::async_runtime::spawn(serve_bundle(
+        listener,
+        code.clone(),
+        bundle_path,
+    ))];
+    match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT)).await {
+        Ok(socket) => tasks.push(tauri::async_runtime::spawn(answer_discovery(
+            socket,
+            code.clone(),
+            port,
+        ))),
+        Err(error) => warn!(
+            "Could not listen for discovery on port {}, the share can only be imported by address: {}",
+            DISCOVERY_PORT, error
+        ),
+    }
+    info!("Sharing {} on port {}", info.instance_name, port);
+
+    Ok(LanShare {
+        code,
+        instance_name: info.instance_name.clone(),
+        port,
+        _bundle_dir: bundle_dir,
+        tasks,
+    })
+}
+
+async fn serve_bundle(listener: TcpListener, code: String, bundle_path: PathBuf) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(error) => {
+                warn!("Could not accept LAN share connection: {}", error);
+                continue;
+            }
+        };
+        let code = code.clone();
+        let bundle_path = bundle_path.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(error) = send_bundle(stream, &code, &bundle_path).await {
+                warn!("Could not send LAN share to {}: {}", peer, error);
+            }
+        });
+    }
+}
+
+/// Answers a single `GET /<code>` request with the bundle.
+async fn send_bundle(stream: TcpStream, code: &str, bundle_path: &Path) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    // Skip the headers, nothing in them is needed.
+    let mut line = String::new();
+    while reader.read_line(&mut line).await? > 2 {
+        line.clear();
+    }
+    let mut stream = reader.into_inner();
+
+    let expected_path = format!("/{}", code);
+    if request_line.split_whitespace().nth(1) != Some(expected_path.as_str()) {
+        stream
+            .write_all(b"HTTP/1.1 404 Not Found\r\nconnection: close\r\ncontent-length: 0\r\n\r\n")
+            .await?;
+        return stream.shutdown().await;
+    }
+
+    let mut file = tokio::fs::File::open(bundle_path).await?;
+    let length = file.metadata().await?.len();
+    let header = format!(
+        "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-type: application/zip\r\ncontent-length: {}\r\n\r\n",
+        length
+    );
+    stream.write_all(header.as_bytes()).await?;
+    tokio::io::copy(&mut file, &mut stream).await?;
+    stream.shutdown().await
+}
+
+async fn answer_discovery(socket: UdpSocket, code: String, port: u16) {
+    let request = format!("{}{}", DISCOVERY_REQUEST, code);
+    let response = format!("{}{}:{}", DISCOVERY_RESPONSE, code, port);
+    let mut buffer = [0; 64];
+    loop {
+        match socket.recv_from(&mut buffer).await {
+            Ok((length, sender)) if &buffer[..length] == request.as_bytes() => {
+                debug!("Answering LAN share discovery from {}", sender);
+                if let Err(error) = socket.send_to(response.as_bytes(), sender).await {
+                    warn!("Could not answer LAN share discovery: {}", error);
+                }
+            }
+            Ok(_) => {}
+            Err(error) => warn!("Could not receive LAN share discovery: {}", error),
+        }
+    }
+}
+
+/// Broadcasts `code` on the local network and returns the address of the launcher sharing it.
+async fn discover_share(code: &str) -> LanShareResult<SocketAddr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.set_broadcast(true)?;
+    let request = format!("{}{}", DISCOVERY_REQUEST, code);
+    socket
+        .send_to(request.as_bytes(), (Ipv4Addr::BROADCAST, DISCOVERY_PORT))
+        .await?;
+
+    let expected = format!("{}{}:", DISCOVERY_RESPONSE, code);
+    let mut buffer = [0; 64];
+    let discovery = async {
+        loop {
+            let (length, sender) = socket.recv_from(&mut buffer).await?;
+            let response = String::from_utf8_lossy(&buffer[..length]);
+            if let Some(port) = response
+                .strip_prefix(&expected)
+                .and_then(|port| port.parse().ok())
+            {
+                return Ok::<_, io::Error>(SocketAddr::new(sender.ip(), port));
+            }
+        }
+    };
+    match tokio::time::timeout(DISCOVERY_TIMEOUT, discovery).await {
+        Ok(address) => Ok(address?),
+        Err(_) => Err(LanShareError::ShareNotFound(code.into())),
+    }
+}
+
+/// Downloads the bundle shared as `code` into `dir`. The sharing launcher is found with a
+/// broadcast unless its `address` is given.
+pub async fn download_lan_share(
+    code: &str,
+    address: Option<&str>,
+    dir: &Path,
+) -> LanShareResult<PathBuf> {
+    let code = code.trim().to_uppercase();
+    let address = match address {
+        Some(address) => address
+            .trim()
+            .parse()
+            .map_err(|_| LanShareError::InvalidAddress(address.into()))?,
+        None => discover_share(&code).await?,
+    };
+    info!("Downloading LAN share {} from {}", code, address);
+
+    let mut response = reqwest::get(format!("http://{}/{}", address, code)).await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(LanShareError::ShareNotFound(code));
+    }
+    response = response.error_for_status()?;
+
+    let path = dir.join("bundle.zip");
+    let mut file = File::create(&path)?;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk)?;
+    }
+    Ok(path)
+}
+
+fn generate_code() -> String {
+    // Every `RandomState` is seeded differently, mixing in the time avoids reusing a seed.
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    let mut seed = hasher.finish();
+    (0..CODE_LENGTH)
+        .map(|_| {
+            let character = CODE_ALPHABET[(seed % CODE_ALPHABET.len() as u64) as usize];
+            seed /= CODE_ALPHABET.len() as u64;
+            character as char
+        })
+        .collect()
+}
+
+/// The address other machines on the network can reach this one at. Connecting a udp socket
+/// doesn't send anything, it only picks the interface.
+fn local_ip() -> Option<IpAddr> {
+    let socket = std::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(8, 8, 8, 8), 80)).ok()?;
+    Some(socket.local_addr().ok()?.ip())
+}
+
+#[test]
+fn test_generate_code() {
+    let code = generate_code();
+    assert_eq!(code.len(), CODE_LENGTH);
+    assert!(code.bytes().all(|byte| CODE_ALPHABET.contains(&byte)));
+}
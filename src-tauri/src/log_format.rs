@@ -0,0 +1,60 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use fern::FormatCallback;
+use log::Record;
+use serde::Serialize;
+
+/// Whether the launcher log files are written as JSON lines, read on every record so the setting
+/// applies without restarting.
+static JSON_LOG_FILES: AtomicBool = AtomicBool::new(false);
+
+pub fn set_json_log_files(enabled: bool) {
+    JSON_LOG_FILES.store(enabled, Ordering::Relaxed);
+}
+
+/// A launcher log record as a single line of json.
+#[derive(Debug, Serialize)]
+struct JsonLogLine<'a> {
+    timestamp: String,
+    level: &'a str,
+    target: &'a str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<u32>,
+}
+
+/// `[file:line datetime][LEVEL] - message`
+pub fn format_plain(out: FormatCallback, message: &fmt::Arguments, record: &Record) {
+    out.finish(format_args!(
+        "[{}:{} {}][{}] - {}",
+        record.file().unwrap_or("unknown"),
+        record.line().unwrap_or(0),
+        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S"),
+        record.level(),
+        message
+    ))
+}
+
+/// Formats records for the log files, as plain text or json lines depending on the setting.
+pub fn format_log_file(out: FormatCallback, message: &fmt::Arguments, record: &Record) {
+    if !JSON_LOG_FILES.load(Ordering::Relaxed) {
+        return format_plain(out, message, record);
+    }
+    let line = JsonLogLine {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        level: record.level().as_str(),
+        target: record.target(),
+        message: message.to_string(),
+        file: record.file(),
+        line: record.line(),
+    };
+    match serde_json::to_string(&line) {
+        Ok(json) => out.finish(format_args!("{}", json)),
+        Err(_) => format_plain(out, message, record),
+    }
+}
@@ -6,73 +6,243 @@
 mod authentication;
 mod commands;
 mod consts;
+mod crash_incident;
+mod crash_restart;
+mod data_roots;
+mod deep_link;
+mod deobfuscation;
+mod error_catalog;
+mod instance_export;
+mod instance_sync;
+mod instance_template;
+mod jar_conflicts;
+mod jar_mods;
+mod keybind_profile;
+mod lan_share;
+mod log_format;
+mod mod_bisect;
+mod notifications;
+mod onboarding;
 mod option_parser;
+mod safe_mode;
+mod secondary_window;
+mod server_properties;
+mod shutdown;
+mod skin;
+mod startup_diagnostics;
 mod state;
+mod support_bundle;
+mod system_info;
 #[cfg(test)]
 mod tests;
+mod trash;
+mod update_check;
 mod web_services;
 use crate::state::ManagerFromAppHandle;
 use crate::{
-    authentication::validate_account,
+    authentication::{is_transient_error, validate_account, validate_accounts, AccountHealth},
     commands::{
-        get_account_skin, get_accounts, get_curseforge_categories, get_logs, get_screenshots,
-        import_zip, launch_instance, load_instances, obtain_manifests, obtain_version, open_folder,
-        poll_device_code_authentication, read_log_lines, search_curseforge,
-        start_authentication_flow,
+        add_offline_account, apply_keybind_profile, cancel_mod_bisect, complete_first_run,
+        copy_instance_options, delete_instance, delete_instance_template, delete_keybind_profile,
+        delete_world, evict_unused_versions, exit_launcher, export_instance,
+        export_keybind_profile, generate_support_bundle, get_account_profile, get_account_skin,
+        get_accounts, get_available_instance_name, get_crash_reports, get_curseforge_categories,
+        get_error_messages, get_instance_jar_mods, get_instance_lan_share, get_instance_templates,
+        get_keybind_profiles, get_last_install_report, get_launcher_logs, get_launcher_settings,
+        get_log_scopes, get_logs, get_memory_bounds, get_mod_bisect_status,
+        get_modpack_optional_files, get_modrinth_categories, get_onboarding_info, get_safe_mode,
+        get_screenshots, get_search_filters, get_server_properties, get_startup_deep_links,
+        get_startup_diagnostics, get_system_info, get_trash, get_update_notifications,
+        import_custom_version, import_instance_export, import_instance_lan_share,
+        import_keybind_profile, import_zip, install_deep_link, install_search_result,
+        launch_instance, launch_instance_safe_mode, list_incidents, load_instances,
+        move_instance_to_data_root, obtain_manifests, obtain_version, open_console_window,
+        open_folder, open_log_viewer_window, open_path, poll_device_code_authentication,
+        preview_launch_command, read_crash_report, read_forge_install_log, read_launcher_log_lines,
+        read_log_lines, refresh_version, remove_mod, report_mod_bisect_result, restore_from_trash,
+        save_instance_template, save_keybind_profile, scan_instance_conflicts, search_curseforge,
+        set_account_cape, set_active_account, set_instance_gpu_preference, set_instance_jar_mods,
+        set_instance_restart_policy, set_instance_sync, set_log_scope, start_authentication_flow,
+        start_instance_lan_share, start_mod_bisect, stop_instance_lan_share, sync_instance_now,
+        update_instance_memory, update_launcher_settings, update_server_properties,
+        verify_manifest_snapshot, watch_instance_updates,
     },
+    consts::LATEST_LAUNCHER_LOG,
+    deep_link::{handle_deep_link_urls, queue_startup_deep_links, DeepLinkState},
+    lan_share::LanShareState,
+    log_format::{format_log_file, format_plain},
+    safe_mode::{has_safe_mode_flag, SafeModeState},
+    shutdown::{request_exit, TaskState},
+    startup_diagnostics::{run_startup_diagnostics, StartupDiagnosticsState},
     state::{
-        account_manager::AccountManager, instance_manager::InstanceState,
-        resource_manager::ResourceState,
+        account_manager::{emit_profile_change, AccountManager},
+        instance_manager::{InstanceManager, InstanceState},
+        resource_manager::{ResourceManager, ResourceState},
+        settings_manager::{SettingsManager, SettingsState},
     },
+    trash::purge_expired_trash,
+    update_check::{
+        check_instance_updates, InstanceUpdates, UpdateNotificationsState, UPDATE_CHECK_INTERVAL,
+    },
+    web_services::install_report::record_request,
 };
-use autmc_authentication::AuthenticationError::{MicrosoftError, XboxError};
+use autmc_authentication::MinecraftAccount;
+use autmc_downloader::set_request_observer;
+use autmc_log::LogSubscriber;
 use log::{error, info, warn};
 use regex::Regex;
 use serde::ser::StdError;
 use state::{account_manager::AccountState, redirect};
 use std::{
+    collections::HashMap,
     fs::{self},
     path::{Path, PathBuf},
+    time::{Duration, SystemTime},
 };
-use tauri::{App, Emitter, Manager, Wry};
+use tauri::{App, AppHandle, Emitter, Manager, RunEvent, Wry};
+use tauri_plugin_deep_link::DeepLinkExt;
 
 const MAX_LOGS: usize = 20;
 fn main() {
     tauri::Builder::default()
+        // Must be registered first so a second launcher exits before touching any saved state.
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            info!("Another launcher was started with arguments: {:?}", argv);
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.set_focus();
+            }
+            // Deep links in `argv` are forwarded to the deep link plugin by the single instance plugin.
+            handle_cli_arguments(app, argv.into_iter().skip(1));
+        }))
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_opener::init())
         .setup(|app| {
             match setup(app) {
                 Ok(_) => {}
-                Err(e) => println!("Error: {:#?}", e),
+                Err(e) => eprintln!("Error: {:#?}", e),
             };
             Ok(())
         })
-        // .register_uri_scheme_protocol("autmc", autmc_uri_scheme)
         .on_window_event(|window, event| {
-            if let tauri::WindowEvent::CloseRequested { .. } = event {
-                info!("Closing");
+            // Closing the main window exits the launcher, once the running tasks are dealt with.
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if window.label() == "main" {
+                    api.prevent_close();
+                    request_exit(window.app_handle());
+                }
             }
         })
         .invoke_handler(tauri::generate_handler![
             start_authentication_flow,
             poll_device_code_authentication,
+            add_offline_account,
             obtain_manifests,
             obtain_version,
             load_instances,
             get_account_skin,
             launch_instance,
             get_accounts,
+            set_active_account,
             open_folder,
+            open_path,
+            open_console_window,
+            get_server_properties,
+            update_server_properties,
+            open_log_viewer_window,
             get_screenshots,
             get_logs,
             read_log_lines,
+            get_modpack_optional_files,
+            get_modrinth_categories,
+            get_search_filters,
             import_zip,
             search_curseforge,
             get_curseforge_categories,
+            get_startup_deep_links,
+            install_deep_link,
+            install_search_result,
+            get_launcher_settings,
+            update_launcher_settings,
+            get_log_scopes,
+            set_log_scope,
+            get_launcher_logs,
+            read_launcher_log_lines,
+            generate_support_bundle,
+            get_system_info,
+            read_forge_install_log,
+            get_startup_diagnostics,
+            get_onboarding_info,
+            get_error_messages,
+            get_safe_mode,
+            import_custom_version,
+            get_account_profile,
+            set_account_cape,
+            move_instance_to_data_root,
+            scan_instance_conflicts,
+            get_instance_jar_mods,
+            set_instance_jar_mods,
+            watch_instance_updates,
+            set_instance_restart_policy,
+            get_update_notifications,
+            save_keybind_profile,
+            get_keybind_profiles,
+            delete_keybind_profile,
+            apply_keybind_profile,
+            export_keybind_profile,
+            import_keybind_profile,
+            list_incidents,
+            delete_instance,
+            delete_world,
+            remove_mod,
+            get_trash,
+            restore_from_trash,
+            get_available_instance_name,
+            exit_launcher,
+            complete_first_run,
+            get_memory_bounds,
+            update_instance_memory,
+            set_instance_gpu_preference,
+            copy_instance_options,
+            save_instance_template,
+            get_instance_templates,
+            delete_instance_template,
+            get_crash_reports,
+            read_crash_report,
+            launch_instance_safe_mode,
+            preview_launch_command,
+            start_mod_bisect,
+            report_mod_bisect_result,
+            get_mod_bisect_status,
+            cancel_mod_bisect,
+            get_last_install_report,
+            refresh_version,
+            evict_unused_versions,
+            verify_manifest_snapshot,
+            start_instance_lan_share,
+            get_instance_lan_share,
+            stop_instance_lan_share,
+            import_instance_lan_share,
+            set_instance_sync,
+            sync_instance_now,
+            export_instance,
+            import_instance_export,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app_handle, event| {
+            // Quitting from the dock or menu bar. Exits asked for by the launcher have a code.
+            if let RunEvent::ExitRequested {
+                code: None, api, ..
+            } = event
+            {
+                api.prevent_exit();
+                request_exit(app_handle);
+            }
+        });
 }
 
 /// First thing called on application setup.
@@ -86,30 +256,94 @@ fn setup(app: &mut App<Wry>) -> Result<(), Box<(dyn StdError + 'static)>> {
     fs::create_dir_all(&log_dir)?;
     match init_logger(&log_dir) {
         Ok(_) => {}
-        Err(e) => println!("Error: {}", e),
+        Err(e) => eprintln!("Error: {}", e),
     }
     info!("Starting Autmc");
 
+    // Time downloads made during installs for the install reports.
+    set_request_observer(record_request);
+
+    // Instances are looked up in the data roots from the settings as well.
+    let settings_manager = SettingsManager::new(&app_dir);
+    let resource_manager = ResourceManager::new(&app_dir);
+    resource_manager.set_data_roots(settings_manager.get_settings().data_roots.clone());
+    let instances_dirs: Vec<_> = resource_manager
+        .all_instances_dirs()
+        .into_iter()
+        .map(|(_, instances_dir)| instances_dir)
+        .collect();
+
     // Attach the account manager to the app using 'AccountState'
     app.manage(AccountState::new(&app_dir));
-    app.manage(ResourceState::new(&app_dir));
-    app.manage(InstanceState::new(&app_dir));
+    app.manage(ResourceState(resource_manager.clone()));
+    app.manage(InstanceState::new(resource_manager));
+    app.manage(SettingsState::new(settings_manager));
+    let safe_mode = has_safe_mode_flag(std::env::args().skip(1));
+    if safe_mode {
+        info!("Starting in safe mode, accounts won't be refreshed and the network won't be used");
+    }
+    app.manage(SafeModeState(safe_mode));
+    app.manage(DeepLinkState::default());
+    app.manage(LanShareState::default());
+    app.manage(TaskState::default());
+    app.manage(UpdateNotificationsState::default());
+
+    // Reported once up front instead of as confusing errors when the broken files are used.
+    let diagnostics = run_startup_diagnostics(&app_dir, &instances_dirs);
+    if !diagnostics.is_empty() {
+        app.emit_to("main", "startup-diagnostics", &diagnostics)?;
+    }
+    app.manage(StartupDiagnosticsState(diagnostics));
+
+    let trash_handle = app.handle().clone();
+    tauri::async_runtime::spawn(async move {
+        let retention_days = SettingsManager::from_app_handle(&trash_handle)
+            .await
+            .get_settings()
+            .trash_retention_days;
+        let resource_manager = ResourceManager::from_app_handle(&trash_handle);
+        if let Err(e) = purge_expired_trash(
+            &resource_manager.trash_dir(),
+            retention_days,
+            chrono::Local::now(),
+        ) {
+            warn!("Could not empty the trash: {}", e);
+        }
+        // Downloaded modpacks are kept as long as the trash, they can be downloaded again.
+        let max_age = Duration::from_secs(u64::from(retention_days) * 24 * 60 * 60);
+        if let Err(e) = resource_manager.evict_expired_modpacks(max_age, SystemTime::now()) {
+            warn!("Could not clear old modpack downloads: {}", e);
+        }
+    });
+
+    // Register the `autmc://` scheme for one-click installs. macOS registers it through the bundle.
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    if let Err(error) = app.deep_link().register_all() {
+        warn!("Could not register deep link schemes: {}", error);
+    }
+    if let Some(urls) = app.deep_link().get_current()? {
+        queue_startup_deep_links(&app.state::<DeepLinkState>(), urls);
+    }
+    let deep_link_handle = app.handle().clone();
+    app.deep_link().on_open_url(move |event| {
+        handle_deep_link_urls(&deep_link_handle, event.urls());
+    });
+
     let app_handle = app.handle().clone();
 
-    // let cli_matches = match app.get_cli_matches() {
-    //     Ok(matches) => matches,
-    //     Err(e) => {
-    //         error!("Invalid CLI Arguments: {}", e);
-    //         app_handle.exit(1);
-    //         Matches::default()
-    //     }
-    // };
-
-    // info!("Arguments: {:#?}", cli_matches);
-    // let arguments = cli_matches.args.get("instance").unwrap();
-    // if let Value::String(value) = &arguments.value {
-    //     launch_instance(value.into(), app_handle.clone()).await;
-    // }
+    handle_cli_arguments(&app_handle, std::env::args().skip(1));
+    if safe_mode {
+        // Loaded as they were saved, without refreshing them, so instances can still be launched.
+        tauri::async_runtime::spawn(async move {
+            let mut account_manager = AccountManager::from_app_handle(&app_handle).await;
+            if let Err(error) = account_manager.deserialize_accounts() {
+                warn!("Could not load accounts in safe mode: {}", error);
+            }
+        });
+        return Ok(());
+    }
+
+    tauri::async_runtime::spawn(check_watched_instances(app_handle.clone()));
 
     // Spawn an async thread and use the app_handle to refresh active account.
     // TODO: Maybe emit event to display a toast telling the user what happened.
@@ -128,7 +362,7 @@ fn setup(app: &mut App<Wry>) -> Result<(), Box<(dyn StdError + 'static)>> {
                 return;
             }
         }
-        let deserialized_account = account_manager.get_active_account();
+        let deserialized_account = account_manager.get_active_account().cloned();
         // If there is some active account, retrieve it and attempt to refresh access tokens.
         match deserialized_account {
             Some(active_account) => {
@@ -158,14 +392,13 @@ fn setup(app: &mut App<Wry>) -> Result<(), Box<(dyn StdError + 'static)>> {
                             );
                         }
                     }
-                    Err(e) => match e {
-                        MicrosoftError { .. } | XboxError { .. } => {
-                            if let Err(error) = redirect(&app_handle, "login") {
-                                error!("{}", error.to_string());
-                            }
-                        }
-                        _ => error!("{}", e.to_string()),
-                    },
+                    // Keep the account so the launcher stays usable, it's only asked to sign in
+                    // again once it's needed.
+                    Err(e) if is_transient_error(&e) => error!("{}", e.to_string()),
+                    Err(e) => {
+                        error!("{}", e.to_string());
+                        account_manager.mark_needs_reauth(&active_account.uuid, &app_handle);
+                    }
                 }
             }
             None => {
@@ -174,11 +407,171 @@ fn setup(app: &mut App<Wry>) -> Result<(), Box<(dyn StdError + 'static)>> {
                 }
             }
         }
+        drop(account_manager);
+        validate_stored_accounts(app_handle).await;
     });
 
     Ok(())
 }
 
+/// Validates the stored accounts besides the active one, which is validated first on startup, and
+/// emits `accounts-validated` with the health of every account.
+async fn validate_stored_accounts(app_handle: AppHandle<Wry>) {
+    let accounts: Vec<MinecraftAccount> = {
+        let account_manager = AccountManager::from_app_handle(&app_handle).await;
+        let active_uuid = account_manager.get_active_uuid();
+        account_manager
+            .get_all_accounts()
+            .into_values()
+            // Accounts that were rejected before have to sign in again, refreshing won't help.
+            .filter(|account| {
+                active_uuid.as_ref() != Some(&account.uuid)
+                    && !account_manager.needs_reauth(&account.uuid)
+            })
+            .collect()
+    };
+    info!("Validating {} stored accounts", accounts.len());
+    let results = validate_accounts(accounts).await;
+
+    let mut account_manager = AccountManager::from_app_handle(&app_handle).await;
+    for (uuid, result) in results {
+        match result {
+            Ok(account) => {
+                if let Some(change) = account_manager.add_account(account) {
+                    emit_profile_change(&change, &app_handle);
+                }
+            }
+            Err(e) if is_transient_error(&e) => warn!("Could not refresh account {}: {}", uuid, e),
+            Err(e) => {
+                warn!("Account {} was rejected: {}", uuid, e);
+                account_manager.mark_needs_reauth(&uuid, &app_handle);
+            }
+        }
+    }
+    if let Err(error) = account_manager.serialize_accounts() {
+        warn!("Could not save the validated accounts: {}", error);
+    }
+
+    let health: HashMap<String, AccountHealth> = account_manager
+        .get_all_accounts()
+        .into_values()
+        .map(|account| {
+            (
+                account.uuid.clone(),
+                account_manager.get_account_health(&account),
+            )
+        })
+        .collect();
+    if let Err(error) = app_handle.emit_to("main", "accounts-validated", health) {
+        error!("{}", error);
+    }
+}
+
+/// Checks the instances the user watches for updates every [UPDATE_CHECK_INTERVAL] and emits
+/// `update-notifications` with them when they changed since the last check.
+async fn check_watched_instances(app_handle: AppHandle<Wry>) {
+    InstanceManager::from_app_handle(&app_handle)
+        .await
+        .deserialize_instances();
+    loop {
+        let watched: Vec<_> = {
+            let instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+            instance_manager
+                .get_instance_configurations()
+                .into_iter()
+                .filter(|config| config.update_watch.is_some())
+                .filter_map(|config| {
+                    let instance_dir = instance_manager.instance_dir(&config.instance_name)?;
+                    Some((config, instance_dir))
+                })
+                .collect()
+        };
+        info!("Checking {} watched instances for updates", watched.len());
+        let resource_manager = ResourceManager::from_app_handle(&app_handle);
+        let mut notifications: Vec<InstanceUpdates> = Vec::new();
+        for (config, instance_dir) in watched {
+            let updates = check_instance_updates(&config, &instance_dir, &resource_manager).await;
+            if !updates.is_empty() {
+                notifications.push(updates);
+            }
+        }
+        notifications.sort_by(|a, b| a.instance_name.cmp(&b.instance_name));
+
+        let changed = {
+            let state = app_handle.state::<UpdateNotificationsState>();
+            let mut current = state
+                .0
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let changed = *current != notifications;
+            *current = notifications.clone();
+            changed
+        };
+        if changed && !notifications.is_empty() {
+            if let Err(error) = app_handle.emit_to("main", "update-notifications", notifications) {
+                error!("{}", error);
+            }
+        }
+        tokio::time::sleep(UPDATE_CHECK_INTERVAL).await;
+    }
+}
+
+/// Handles the command line arguments the launcher was started with, or the ones forwarded from
+/// a second launcher process. Supports `--launch <instance name>`.
+fn handle_cli_arguments<I: Iterator<Item = String>>(app_handle: &AppHandle<Wry>, mut args: I) {
+    // Launching can download the version's files.
+    if app_handle.state::<SafeModeState>().0 {
+        if args.any(|arg| arg == "--launch") {
+            warn!("Ignoring --launch in safe mode");
+        }
+        return;
+    }
+    while let Some(arg) = args.next() {
+        if arg != "--launch" {
+            continue;
+        }
+        match args.next() {
+            Some(instance_name) => {
+                let app_handle = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    launch_from_cli(instance_name, app_handle).await;
+                });
+            }
+            None => warn!("Missing instance name after --launch"),
+        }
+    }
+}
+
+async fn launch_from_cli(instance_name: String, app_handle: AppHandle<Wry>) {
+    info!("Launching {} from the command line", instance_name);
+    let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    instance_manager.deserialize_instances();
+
+    let mut account_manager = AccountManager::from_app_handle(&app_handle).await;
+    // On startup this can run before the account refresh has loaded accounts.json.
+    if account_manager.get_active_account().is_none() {
+        if let Err(error) = account_manager.deserialize_accounts() {
+            warn!("Could not load accounts: {}", error);
+        }
+    }
+
+    let settings_manager = SettingsManager::from_app_handle(&app_handle).await;
+
+    match account_manager.get_active_account() {
+        Some(account) => {
+            if let Err(error) = instance_manager.launch_instance(
+                &instance_name,
+                account,
+                settings_manager.get_settings(),
+                app_handle.clone(),
+            ) {
+                error!("Could not launch {}: {}", instance_name, error);
+            }
+        }
+        None => warn!("Cannot launch {} without an active account", instance_name),
+    }
+}
+
 /// Sets up the logger and saves launcher logs to ${app_dir}/logs/launcher_log_${datetime}.log
 fn init_logger(log_dir: &PathBuf) -> Result<(), fern::InitError> {
     let datetime = chrono::Local::now().format("%Y-%m-%dT%H-%M-%S");
@@ -188,44 +581,46 @@ fn init_logger(log_dir: &PathBuf) -> Result<(), fern::InitError> {
     purge_old_logs(log_dir)?;
     let log_path = log_dir.join(format!("launcher_log_{}.log", datetime));
     // let log_path = log_dir.join("launcher_log.log");
-    println!("Log path: {:#?}", log_path);
-    let latest_log_path = log_dir.join("latest.log");
+    let latest_log_path = log_dir.join(LATEST_LAUNCHER_LOG);
     if latest_log_path.exists() {
         fs::remove_file(&latest_log_path)?;
     }
+    let reqwest_level = match std::env::var("REQWEST_DEBUG") {
+        Ok(var) if var == "1" => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Info,
+    };
     fern::Dispatch::new()
-        .format(|out, message, record| {
-            out.finish(format_args!(
-                "[{}:{} {}][{}] - {}",
-                record.file().unwrap_or("unknown"),
-                record.line().unwrap_or(0),
-                chrono::Local::now().format("%Y-%m-%dT%H:%M:%S"),
-                record.level(),
-                message
-            ))
-        })
         .level(match std::env::var("DEBUG") {
             Ok(var) if var == "1" => log::LevelFilter::Debug,
             _ => log::LevelFilter::Info,
         })
-        .level_for(
-            "reqwest",
-            match std::env::var("REQWEST_DEBUG") {
-                Ok(var) if var == "1" => log::LevelFilter::Debug,
-                _ => log::LevelFilter::Info,
-            },
+        .level_for("reqwest", reqwest_level)
+        // Reqwest's connections are traced by hyper and h2 now that tracing reaches the log.
+        .level_for("hyper", reqwest_level)
+        .level_for("h2", reqwest_level)
+        .chain(
+            fern::Dispatch::new()
+                .format(format_plain)
+                .chain(std::io::stdout()),
+        )
+        .chain(
+            fern::Dispatch::new()
+                .format(format_log_file)
+                .chain(fern::log_file(log_path.as_os_str())?)
+                .chain(fern::log_file(latest_log_path.as_os_str())?),
         )
-        .chain(std::io::stdout())
-        .chain(fern::log_file(log_path.as_os_str())?)
-        .chain(fern::log_file(latest_log_path.as_os_str())?)
         .apply()?;
+    // Spans and events from `tracing` go through the logger above.
+    if let Err(error) = tracing::subscriber::set_global_default(LogSubscriber::new()) {
+        warn!("Could not forward tracing to the launcher log: {}", error);
+    }
+    info!("Logging to {}", log_path.display());
     Ok(())
 }
 
 /// Removes `old` logs, keeping only the latest MAX_LOGS in the log directory.
 fn purge_old_logs(log_dir: &Path) -> Result<(), std::io::Error> {
     let file_paths = fs::read_dir(log_dir)?;
-    println!("{:#?}", file_paths);
 
     let regex = Regex::new("^launcher_log_[0-9]{4}-[0-9]{2}-[0-9]{2}T([0-9]{2}-){2}[0-9]{2}.log$");
     match regex {
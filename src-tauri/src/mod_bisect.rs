@@ -0,0 +1,282 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+/// File in the instance directory the bisect session is saved to between launches.
+const BISECT_SESSION_FILE: &str = "bisect.json";
+const DISABLED_EXTENSION: &str = "disabled";
+
+#[derive(Debug)]
+pub enum ModBisectError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    NotEnoughMods,
+    NoSession,
+}
+
+impl fmt::Display for ModBisectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModBisectError::Io(error) => write!(f, "{}", error),
+            ModBisectError::Json(error) => write!(f, "{}", error),
+            ModBisectError::NotEnoughMods => f.write_str("At least two mods are needed to bisect"),
+            ModBisectError::NoSession => f.write_str("There is no mod bisect in progress"),
+        }
+    }
+}
+
+impl From<io::Error> for ModBisectError {
+    fn from(error: io::Error) -> Self {
+        ModBisectError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ModBisectError {
+    fn from(error: serde_json::Error) -> Self {
+        ModBisectError::Json(error)
+    }
+}
+
+pub type ModBisectResult<T> = Result<T, ModBisectError>;
+
+/// A binary search for the mod causing a crash. Each step enables half of the remaining suspects
+/// and the user reports whether the game still crashed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BisectSession {
+    /// Every mod that was enabled when the bisect started, re-enabled once it is done.
+    all_mods: Vec<String>,
+    /// Mods that could still be the culprit.
+    suspects: Vec<String>,
+    /// Mods ruled out, these stay enabled.
+    cleared: Vec<String>,
+    iteration: u32,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BisectStatus {
+    iteration: u32,
+    remaining_suspects: usize,
+    enabled: Vec<String>,
+    disabled: Vec<String>,
+    /// Set once a single suspect is left.
+    culprit: Option<String>,
+}
+
+impl BisectSession {
+    pub fn new(mods: Vec<String>) -> ModBisectResult<Self> {
+        if mods.len() < 2 {
+            return Err(ModBisectError::NotEnoughMods);
+        }
+        Ok(Self {
+            suspects: mods.clone(),
+            all_mods: mods,
+            cleared: Vec::new(),
+            iteration: 1,
+        })
+    }
+
+    /// The suspects enabled for the current launch.
+    fn testing(&self) -> &[String] {
+        &self.suspects[..self.suspects.len().div_ceil(2)]
+    }
+
+    fn culprit(&self) -> Option<&String> {
+        match self.suspects.as_slice() {
+            [culprit] => Some(culprit),
+            _ => None,
+        }
+    }
+
+    fn is_enabled(&self, name: &String) -> bool {
+        self.culprit().is_some() || self.cleared.contains(name) || self.testing().contains(name)
+    }
+
+    /// Narrows the suspects down after a launch of the current step.
+    pub fn record(&mut self, crashed: bool) {
+        if self.culprit().is_some() {
+            return;
+        }
+        let split = self.testing().len();
+        let untested = self.suspects.split_off(split);
+        if crashed {
+            self.cleared.extend(untested);
+        } else {
+            self.cleared.append(&mut self.suspects);
+            self.suspects = untested;
+        }
+        self.iteration += 1;
+    }
+
+    pub fn status(&self) -> BisectStatus {
+        let (enabled, disabled) = self
+            .all_mods
+            .iter()
+            .cloned()
+            .partition(|name| self.is_enabled(name));
+        BisectStatus {
+            iteration: self.iteration,
+            remaining_suspects: self.suspects.len(),
+            enabled,
+            disabled,
+            culprit: self.culprit().cloned(),
+        }
+    }
+
+    /// Renames the mods in `mods_dir` so only the mods of the current step are loaded.
+    /// Once the culprit is found every mod is enabled again.
+    fn apply(&self, mods_dir: &Path) -> io::Result<()> {
+        for name in &self.all_mods {
+            set_mod_enabled(mods_dir, name, self.is_enabled(name))?;
+        }
+        Ok(())
+    }
+}
+
+fn disabled_path(mods_dir: &Path, name: &str) -> PathBuf {
+    mods_dir.join(format!("{}.{}", name, DISABLED_EXTENSION))
+}
+
+fn set_mod_enabled(mods_dir: &Path, name: &str, enabled: bool) -> io::Result<()> {
+    let enabled_path = mods_dir.join(name);
+    let disabled_path = disabled_path(mods_dir, name);
+    if enabled && disabled_path.exists() {
+        fs::rename(disabled_path, enabled_path)?;
+    } else if !enabled && enabled_path.exists() {
+        fs::rename(enabled_path, disabled_path)?;
+    }
+    Ok(())
+}
+
+/// File names of the enabled mods in `mods_dir`.
+fn enabled_mods(mods_dir: &Path) -> io::Result<Vec<String>> {
+    let mut mods: Vec<String> = fs::read_dir(mods_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "jar"))
+        .filter_map(|path| path.file_name().map(|name| name.to_string_lossy().into()))
+        .collect();
+    mods.sort();
+    Ok(mods)
+}
+
+fn session_path(instance_dir: &Path) -> PathBuf {
+    instance_dir.join(BISECT_SESSION_FILE)
+}
+
+pub fn load_session(instance_dir: &Path) -> ModBisectResult<Option<BisectSession>> {
+    let path = session_path(instance_dir);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let reader = BufReader::new(File::open(path)?);
+    Ok(Some(serde_json::from_reader(reader)?))
+}
+
+fn save_session(instance_dir: &Path, session: &BisectSession) -> ModBisectResult<()> {
+    let json = serde_json::to_string(session)?;
+    let mut file = File::create(session_path(instance_dir))?;
+    file.write_all(json.as_bytes())?;
+    Ok(())
+}
+
+/// Starts bisecting the enabled mods of an instance, replacing any bisect already in progress.
+pub fn start_bisect(instance_dir: &Path) -> ModBisectResult<BisectStatus> {
+    if let Some(session) = load_session(instance_dir)? {
+        session.apply(&instance_dir.join("mods"))?;
+    }
+    let mods_dir = instance_dir.join("mods");
+    let session = BisectSession::new(enabled_mods(&mods_dir)?)?;
+    info!(
+        "Starting mod bisect of {} mods in {}",
+        session.all_mods.len(),
+        instance_dir.display()
+    );
+    session.apply(&mods_dir)?;
+    save_session(instance_dir, &session)?;
+    Ok(session.status())
+}
+
+/// Records whether the last launch crashed and sets up the next step. The session is removed once
+/// the culprit has been found.
+pub fn record_bisect_result(instance_dir: &Path, crashed: bool) -> ModBisectResult<BisectStatus> {
+    let mut session = load_session(instance_dir)?.ok_or(ModBisectError::NoSession)?;
+    session.record(crashed);
+    debug!("Bisect step {}: {:?}", session.iteration, session.suspects);
+
+    session.apply(&instance_dir.join("mods"))?;
+    if session.culprit().is_some() {
+        fs::remove_file(session_path(instance_dir))?;
+    } else {
+        save_session(instance_dir, &session)?;
+    }
+    Ok(session.status())
+}
+
+/// Stops the bisect in progress and enables every mod again.
+pub fn cancel_bisect(instance_dir: &Path) -> ModBisectResult<()> {
+    let session = load_session(instance_dir)?.ok_or(ModBisectError::NoSession)?;
+    let mods_dir = instance_dir.join("mods");
+    for name in &session.all_mods {
+        set_mod_enabled(&mods_dir, name, true)?;
+    }
+    fs::remove_file(session_path(instance_dir))?;
+    Ok(())
+}
+
+#[test]
+fn test_bisect_session_finds_culprit() {
+    let mods: Vec<String> = ["a.jar", "b.jar", "c.jar", "d.jar", "e.jar"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    let culprit = "d.jar".to_string();
+    let mut session = BisectSession::new(mods).unwrap();
+
+    while session.status().culprit.is_none() {
+        let status = session.status();
+        session.record(status.enabled.contains(&culprit));
+    }
+    assert_eq!(session.status().culprit, Some(culprit));
+    assert!(session.status().disabled.is_empty());
+    assert!(session.iteration <= 4);
+}
+
+#[test]
+fn test_bisect_needs_two_mods() {
+    assert!(matches!(
+        BisectSession::new(vec!["a.jar".into()]),
+        Err(ModBisectError::NotEnoughMods)
+    ));
+}
+
+#[test]
+fn test_bisect_renames_mods() {
+    let tmp_dir = tempdir::TempDir::new("bisect").unwrap();
+    let instance_dir = tmp_dir.path();
+    let mods_dir = instance_dir.join("mods");
+    fs::create_dir_all(&mods_dir).unwrap();
+    for name in ["a.jar", "b.jar", "c.jar", "d.jar"] {
+        fs::write(mods_dir.join(name), "").unwrap();
+    }
+
+    let status = start_bisect(instance_dir).unwrap();
+    assert_eq!(status.enabled, vec!["a.jar", "b.jar"]);
+    assert!(mods_dir.join("c.jar.disabled").is_file());
+    assert!(load_session(instance_dir).unwrap().is_some());
+
+    // No crash with a and b, so the culprit is c or d.
+    let status = record_bisect_result(instance_dir, false).unwrap();
+    assert_eq!(status.disabled, vec!["d.jar"]);
+    assert!(mods_dir.join("c.jar").is_file());
+
+    cancel_bisect(instance_dir).unwrap();
+    assert!(mods_dir.join("d.jar").is_file());
+    assert!(load_session(instance_dir).unwrap().is_none());
+}
@@ -0,0 +1,36 @@
+use log::warn;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::state::{settings_manager::SettingsManager, ManagerFromAppHandle};
+
+/// Shows a desktop notification, unless they are turned off in the settings or the launcher's
+/// window is focused and the user already sees what happened.
+pub async fn notify(app_handle: &AppHandle<Wry>, title: &str, body: &str) {
+    let enabled = SettingsManager::from_app_handle(app_handle)
+        .await
+        .get_settings()
+        .desktop_notifications;
+    if !enabled || is_launcher_focused(app_handle) {
+        return;
+    }
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+    {
+        warn!("Could not show the notification `{}`: {}", title, e);
+    }
+}
+
+/// Whether the main window is open, not minimized and focused.
+fn is_launcher_focused(app_handle: &AppHandle<Wry>) -> bool {
+    match app_handle.get_webview_window("main") {
+        Some(window) => {
+            !window.is_minimized().unwrap_or(false) && window.is_focused().unwrap_or(false)
+        }
+        None => false,
+    }
+}
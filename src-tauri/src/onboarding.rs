@@ -0,0 +1,166 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::system_info::{disk_space, DiskSpace, MemoryBounds};
+
+/// What the first-run flow shows before the user has set anything up.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingInfo {
+    pub first_run_completed: bool,
+    /// Where instances, java and downloads are stored.
+    pub data_dir: PathBuf,
+    pub data_dir_space: Option<DiskSpace>,
+    /// `recommendedMb` is the default to preselect for new instances.
+    pub memory: MemoryBounds,
+    pub detected_launchers: Vec<DetectedLauncher>,
+}
+
+impl OnboardingInfo {
+    pub fn detect(
+        first_run_completed: bool,
+        app_dir: &Path,
+        home_dir: &Path,
+        user_data_dir: &Path,
+    ) -> Self {
+        Self {
+            first_run_completed,
+            data_dir: app_dir.into(),
+            data_dir_space: disk_space(&[app_dir]).pop(),
+            memory: MemoryBounds::detect(),
+            detected_launchers: detect_launchers(env::consts::OS, home_dir, user_data_dir),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LauncherKind {
+    Minecraft,
+    PrismLauncher,
+    Curseforge,
+}
+
+/// Another launcher's data found on this machine, that the user can be offered to import from.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedLauncher {
+    pub kind: LauncherKind,
+    pub path: PathBuf,
+    /// Folder names of the launcher's instances, empty for the official launcher which only has
+    /// the one `.minecraft`.
+    pub instances: Vec<String>,
+}
+
+/// Looks for the official launcher, Prism Launcher and CurseForge in their default locations.
+/// `user_data_dir` is `%APPDATA%`, `~/Library/Application Support` or `~/.local/share`.
+fn detect_launchers(os: &str, home_dir: &Path, user_data_dir: &Path) -> Vec<DetectedLauncher> {
+    let minecraft_dir = match os {
+        "linux" => home_dir.join(".minecraft"),
+        "macos" => user_data_dir.join("minecraft"),
+        _ => user_data_dir.join(".minecraft"),
+    };
+    let curseforge_dir = match os {
+        "macos" => home_dir.join("Documents").join("curseforge"),
+        _ => home_dir.join("curseforge"),
+    };
+    let candidates = [
+        (LauncherKind::Minecraft, minecraft_dir, None),
+        (
+            LauncherKind::PrismLauncher,
+            user_data_dir.join("PrismLauncher"),
+            Some(("instances", "instance.cfg")),
+        ),
+        (
+            LauncherKind::Curseforge,
+            curseforge_dir.join("minecraft"),
+            Some(("Instances", "minecraftinstance.json")),
+        ),
+    ];
+
+    let mut launchers = Vec::new();
+    for (kind, path, instances_layout) in candidates {
+        if !path.is_dir() {
+            continue;
+        }
+        let instances = match instances_layout {
+            Some((instances_dir, marker_file)) => {
+                let instances = list_instances(&path.join(instances_dir), marker_file);
+                // A launcher that was installed but never used has nothing to import.
+                if instances.is_empty() {
+                    continue;
+                }
+                instances
+            }
+            None => Vec::new(),
+        };
+        launchers.push(DetectedLauncher {
+            kind,
+            path,
+            instances,
+        });
+    }
+    launchers
+}
+
+/// Names of the folders in `instances_dir` that contain `marker_file`, sorted.
+fn list_instances(instances_dir: &Path, marker_file: &str) -> Vec<String> {
+    let mut instances: Vec<String> = fs::read_dir(instances_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join(marker_file).is_file())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    instances.sort();
+    instances
+}
+
+#[cfg(test)]
+use tempdir::TempDir;
+
+#[test]
+fn test_detect_launchers() {
+    let tmp_dir = TempDir::new("onboarding").unwrap();
+    let home_dir = tmp_dir.path();
+    let user_data_dir = home_dir.join(".local").join("share");
+    fs::create_dir_all(home_dir.join(".minecraft")).unwrap();
+    let prism_instances = user_data_dir.join("PrismLauncher").join("instances");
+    fs::create_dir_all(prism_instances.join("Fabulously Optimized")).unwrap();
+    fs::create_dir_all(prism_instances.join("_LAUNCHER_TEMP")).unwrap();
+    fs::write(
+        prism_instances
+            .join("Fabulously Optimized")
+            .join("instance.cfg"),
+        "",
+    )
+    .unwrap();
+    // Installed without any instances.
+    fs::create_dir_all(
+        home_dir
+            .join("curseforge")
+            .join("minecraft")
+            .join("Instances"),
+    )
+    .unwrap();
+
+    assert_eq!(
+        detect_launchers("linux", home_dir, &user_data_dir),
+        [
+            DetectedLauncher {
+                kind: LauncherKind::Minecraft,
+                path: home_dir.join(".minecraft"),
+                instances: Vec::new(),
+            },
+            DetectedLauncher {
+                kind: LauncherKind::PrismLauncher,
+                path: user_data_dir.join("PrismLauncher"),
+                instances: vec!["Fabulously Optimized".into()],
+            },
+        ]
+    );
+}
@@ -1,36 +1,134 @@
 use std::{
-    collections::HashMap,
     fs::File,
-    io::{self, BufRead, BufReader},
+    io::{self, BufRead, BufReader, BufWriter, Write},
     path::Path,
 };
 
-pub fn parse_options_txt(instance_dir: &Path) -> io::Result<HashMap<String, String>> {
+use indexmap::IndexMap;
+use serde::Deserialize;
+
+/// Keys set from the video settings screen, across versions.
+const VIDEO_OPTIONS: [&str; 31] = [
+    "anaglyph3d",
+    "ao",
+    "attackIndicator",
+    "biomeBlendRadius",
+    "bobView",
+    "chunkUpdates",
+    "damageTiltStrength",
+    "darknessEffectScale",
+    "enableVsync",
+    "entityDistanceScaling",
+    "entityShadows",
+    "fancyGraphics",
+    "fov",
+    "fovEffectScale",
+    "fullscreen",
+    "fullscreenResolution",
+    "gamma",
+    "glintSpeed",
+    "glintStrength",
+    "graphicsMode",
+    "guiScale",
+    "maxFps",
+    "menuBackgroundBlurriness",
+    "mipmapLevels",
+    "particles",
+    "prioritizeChunkUpdates",
+    "renderClouds",
+    "renderDistance",
+    "screenEffectScale",
+    "simulationDistance",
+    "useVbo",
+];
+const AUDIO_OPTIONS: [&str; 3] = ["directionalAudio", "showSubtitles", "soundDevice"];
+
+/// Groups of `options.txt` keys that can be copied between instances.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OptionCategory {
+    Keybinds,
+    Video,
+    Audio,
+}
+
+impl OptionCategory {
+    pub fn contains(&self, key: &str) -> bool {
+        match self {
+            OptionCategory::Keybinds => key.starts_with("key_"),
+            OptionCategory::Video => VIDEO_OPTIONS.contains(&key),
+            OptionCategory::Audio => {
+                key.starts_with("soundCategory_") || AUDIO_OPTIONS.contains(&key)
+            }
+        }
+    }
+}
+
+/// Reads the instance's `options.txt` in the order the keys are written.
+pub fn parse_options_txt(instance_dir: &Path) -> io::Result<IndexMap<String, String>> {
     let options_txt_path = instance_dir.join("options.txt");
     let file = File::open(options_txt_path)?;
     let reader = BufReader::new(file);
 
-    reader
-        .lines()
-        .into_iter()
-        .map(|line_res| {
-            let line = line_res?;
-            let splits: Vec<&str> = line.split(":").collect();
-            Ok((splits[0].to_owned(), splits[1].to_owned()))
-        })
-        .collect()
+    let mut options = IndexMap::new();
+    for line in reader.lines() {
+        let line = line?;
+        // Values can contain colons too, like `lastServer:localhost:25565`.
+        if let Some((key, value)) = line.split_once(':') {
+            options.insert(key.to_owned(), value.to_owned());
+        }
+    }
+    Ok(options)
+}
+
+fn write_options_txt(instance_dir: &Path, options: &IndexMap<String, String>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(instance_dir.join("options.txt"))?);
+    for (key, value) in options {
+        writeln!(writer, "{}:{}", key, value)?;
+    }
+    writer.flush()
+}
+
+/// The options in `category` from the `options.txt` of `instance_dir`.
+pub fn category_options(
+    instance_dir: &Path,
+    category: OptionCategory,
+) -> io::Result<IndexMap<String, String>> {
+    let mut options = parse_options_txt(instance_dir)?;
+    options.retain(|key, _| category.contains(key));
+    Ok(options)
 }
 
-fn copy_applicable_options(
-    src_options: HashMap<String, String>,
-    dst_keys: &[String],
-) -> HashMap<String, String> {
-    src_options
-        .into_iter()
-        .filter(|(key, _)| dst_keys.contains(key))
-        .collect()
+/// Sets `options` in the `options.txt` of `dst_dir`, keeping its other options. Returns how many
+/// options were set.
+pub fn merge_options(dst_dir: &Path, options: IndexMap<String, String>) -> io::Result<usize> {
+    // An instance that was never launched has no options yet.
+    let mut dst_options = match parse_options_txt(dst_dir) {
+        Ok(options) => options,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => IndexMap::new(),
+        Err(e) => return Err(e),
+    };
+    let merged = options.len();
+    dst_options.extend(options);
+    write_options_txt(dst_dir, &dst_options)?;
+    Ok(merged)
 }
 
+/// Copies the options in `categories` from the `options.txt` of `src_dir` to the one of
+/// `dst_dir`, keeping the destination's other options. Returns how many options were copied.
+pub fn copy_options(
+    src_dir: &Path,
+    dst_dir: &Path,
+    categories: &[OptionCategory],
+) -> io::Result<usize> {
+    let mut src_options = parse_options_txt(src_dir)?;
+    src_options.retain(|key, _| categories.iter().any(|category| category.contains(key)));
+    merge_options(dst_dir, src_options)
+}
+
+#[cfg(test)]
+use {std::fs, tempdir::TempDir};
+
 #[test]
 fn test_parse_options_txt() {
     let instance_path = Path::new(
@@ -38,3 +136,42 @@ fn test_parse_options_txt() {
     );
     println!("Options: {:#?}", parse_options_txt(&instance_path));
 }
+
+#[test]
+fn test_copy_options() {
+    let tmp_dir = TempDir::new("copy-options").unwrap();
+    let src_dir = tmp_dir.path().join("src");
+    let dst_dir = tmp_dir.path().join("dst");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::create_dir_all(&dst_dir).unwrap();
+    fs::write(
+        src_dir.join("options.txt"),
+        "version:3465\nrenderDistance:16\nkey_key.jump:key.keyboard.space\nsoundCategory_master:0.5\nlastServer:mc.example.com:25565\n",
+    )
+    .unwrap();
+    fs::write(
+        dst_dir.join("options.txt"),
+        "version:3337\nsoundCategory_master:1.0\nkey_key.jump:key.keyboard.j\nlastServer:localhost\n",
+    )
+    .unwrap();
+
+    let copied = copy_options(
+        &src_dir,
+        &dst_dir,
+        &[OptionCategory::Keybinds, OptionCategory::Audio],
+    )
+    .unwrap();
+    assert_eq!(copied, 2);
+    assert_eq!(
+        fs::read_to_string(dst_dir.join("options.txt")).unwrap(),
+        "version:3337\nsoundCategory_master:0.5\nkey_key.jump:key.keyboard.space\nlastServer:localhost\n"
+    );
+
+    // Copying into an instance that has no options.txt yet creates it.
+    fs::remove_file(dst_dir.join("options.txt")).unwrap();
+    assert_eq!(
+        copy_options(&src_dir, &dst_dir, &[OptionCategory::Video]).unwrap(),
+        1
+    );
+    assert_eq!(parse_options_txt(&dst_dir).unwrap()["renderDistance"], "16");
+}
@@ -0,0 +1,26 @@
+/// Starts the launcher without refreshing the active account or using the network, so it can be
+/// reached with a broken `accounts.json` or without a network connection. `--launch` is ignored.
+pub const SAFE_MODE_FLAG: &str = "--safe-mode";
+
+/// Whether the launcher was started with [SAFE_MODE_FLAG].
+pub struct SafeModeState(pub bool);
+
+/// Whether `args`, without the executable, contain [SAFE_MODE_FLAG].
+pub fn has_safe_mode_flag<I: IntoIterator<Item = String>>(args: I) -> bool {
+    args.into_iter().any(|arg| arg == SAFE_MODE_FLAG)
+}
+
+#[test]
+fn test_has_safe_mode_flag() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    assert!(has_safe_mode_flag(args(&[
+        "--launch",
+        "Vanilla",
+        "--safe-mode"
+    ])));
+    assert!(!has_safe_mode_flag(args(&[
+        "--launch",
+        "--safe-mode-please"
+    ])));
+    assert!(!has_safe_mode_flag(args(&[])));
+}
@@ -0,0 +1,114 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder, Wry};
+use url::form_urlencoded;
+
+/// Event sent to an instance's console window for each line the game prints.
+pub const CONSOLE_LINE_EVENT: &str = "console-line";
+/// Event sent to an instance's console window when the game exits, with its exit code.
+pub const CONSOLE_EXIT_EVENT: &str = "console-exit";
+
+/// A window opened next to the launcher's, to follow a game on another monitor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SecondaryWindow<'a> {
+    /// The live output of a running instance.
+    Console { instance_name: &'a str },
+    /// One of an instance's log files.
+    LogViewer {
+        instance_name: &'a str,
+        log_name: &'a str,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsoleLine {
+    pub line: String,
+    /// Whether the game printed the line to stderr.
+    pub error: bool,
+}
+
+/// Window labels can only contain letters, numbers and `-/:_`, instance names are hex encoded.
+fn hex(name: &str) -> String {
+    name.bytes().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl SecondaryWindow<'_> {
+    /// Unique per instance, or per log file, so opening it again focuses the existing window.
+    pub fn label(&self) -> String {
+        match self {
+            SecondaryWindow::Console { instance_name } => format!("console-{}", hex(instance_name)),
+            SecondaryWindow::LogViewer {
+                instance_name,
+                log_name,
+            } => format!("log-viewer-{}-{}", hex(instance_name), hex(log_name)),
+        }
+    }
+
+    fn url(&self) -> String {
+        let (route, params) = match self {
+            SecondaryWindow::Console { instance_name } => {
+                ("console", vec![("instance", *instance_name)])
+            }
+            SecondaryWindow::LogViewer {
+                instance_name,
+                log_name,
+            } => (
+                "log-viewer",
+                vec![("instance", *instance_name), ("log", *log_name)],
+            ),
+        };
+        let query = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(params)
+            .finish();
+        format!("{}?{}", route, query)
+    }
+
+    fn title(&self) -> String {
+        match self {
+            SecondaryWindow::Console { instance_name } => format!("{} - Console", instance_name),
+            SecondaryWindow::LogViewer {
+                instance_name,
+                log_name,
+            } => format!("{} - {}", instance_name, log_name),
+        }
+    }
+
+    /// Opens the window, or focuses it if it is already open.
+    pub fn open(&self, app_handle: &AppHandle<Wry>) -> tauri::Result<()> {
+        if let Some(window) = app_handle.get_webview_window(&self.label()) {
+            window.unminimize()?;
+            return window.set_focus();
+        }
+        WebviewWindowBuilder::new(app_handle, self.label(), WebviewUrl::App(self.url().into()))
+            .title(self.title())
+            .inner_size(900.0, 600.0)
+            .build()?;
+        Ok(())
+    }
+
+    /// Sends `event` to this window only. Does nothing if it isn't open.
+    pub fn emit<S: Serialize + Clone>(
+        &self,
+        app_handle: &AppHandle<Wry>,
+        event: &str,
+        payload: S,
+    ) -> tauri::Result<()> {
+        app_handle.emit_to(self.label().as_str(), event, payload)
+    }
+}
+
+#[test]
+fn test_secondary_window_labels() {
+    let console = SecondaryWindow::Console {
+        instance_name: "Sky Block: 2/3",
+    };
+    assert_eq!(console.label(), "console-536b7920426c6f636b3a20322f33");
+    assert_eq!(console.url(), "console?instance=Sky+Block%3A+2%2F3");
+
+    let log_viewer = SecondaryWindow::LogViewer {
+        instance_name: "a",
+        log_name: "latest.log",
+    };
+    assert_eq!(log_viewer.label(), "log-viewer-61-6c61746573742e6c6f67");
+    assert_eq!(log_viewer.url(), "log-viewer?instance=a&log=latest.log");
+}
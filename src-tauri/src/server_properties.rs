@@ -0,0 +1,325 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+/// Where a server stores its settings, in the folder it runs in.
+const SERVER_PROPERTIES_FILE: &str = "server.properties";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Difficulty::Peaceful => "peaceful",
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    /// Servers before 1.14 store the difficulty as a number.
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "peaceful" | "0" => Some(Difficulty::Peaceful),
+            "easy" | "1" => Some(Difficulty::Easy),
+            "normal" | "2" => Some(Difficulty::Normal),
+            "hard" | "3" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+/// The properties of a server's `server.properties` that can be edited from the launcher, the
+/// others are kept as they are.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerProperties {
+    pub server_port: u16,
+    pub motd: String,
+    pub difficulty: Difficulty,
+    pub white_list: bool,
+    pub online_mode: bool,
+}
+
+/// What a vanilla server writes when it creates the file.
+impl Default for ServerProperties {
+    fn default() -> Self {
+        Self {
+            server_port: 25565,
+            motd: "A Minecraft Server".into(),
+            difficulty: Difficulty::Easy,
+            white_list: false,
+            online_mode: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ServerPropertiesError {
+    /// The server wouldn't accept `value` for the property `key`.
+    InvalidValue {
+        key: String,
+        value: String,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for ServerPropertiesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerPropertiesError::InvalidValue { key, value } => {
+                write!(f, "`{}` isn't a valid value for {}", value, key)
+            }
+            ServerPropertiesError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<io::Error> for ServerPropertiesError {
+    fn from(error: io::Error) -> Self {
+        ServerPropertiesError::Io(error)
+    }
+}
+
+fn invalid_value(key: &str, value: &str) -> ServerPropertiesError {
+    ServerPropertiesError::InvalidValue {
+        key: key.into(),
+        value: value.into(),
+    }
+}
+
+fn server_properties_path(instance_dir: &Path) -> PathBuf {
+    instance_dir.join(SERVER_PROPERTIES_FILE)
+}
+
+/// The key and raw value of a `key=value` line, `None` for comments and blank lines. Properties
+/// continued on the next line aren't supported, servers never write them.
+fn split_property(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    if line.is_empty() || line.starts_with(['#', '!']) {
+        return None;
+    }
+    let (key, value) = line.split_once(['=', ':']).unwrap_or((line, ""));
+    Some((key.trim_end(), value.trim_start()))
+}
+
+/// Undoes the escapes java writes, like `\\` and `\u00e9`.
+fn unescape(value: &str) -> String {
+    let mut units: Vec<u16> = Vec::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next() {
+                Some('t') => '\t',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('f') => '\u{c}',
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    if let Ok(unit) = u16::from_str_radix(&hex, 16) {
+                        // Characters outside the BMP are written as two escaped surrogates.
+                        units.push(unit);
+                        continue;
+                    }
+                    units.extend("\\u".encode_utf16().chain(hex.encode_utf16()));
+                    continue;
+                }
+                Some(c) => c,
+                None => break,
+            },
+            c => c,
+        };
+        let mut buffer = [0; 2];
+        units.extend_from_slice(c.encode_utf16(&mut buffer));
+    }
+    String::from_utf16_lossy(&units)
+}
+
+/// Escapes `value` like java does, older servers read the file as latin-1.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_ascii() => escaped.push(c),
+            c => {
+                let mut buffer = [0; 2];
+                for unit in c.encode_utf16(&mut buffer) {
+                    escaped.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+        }
+    }
+    escaped
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, ServerPropertiesError> {
+    value.parse().map_err(|_| invalid_value(key, value))
+}
+
+fn parse_properties(contents: &str) -> Result<ServerProperties, ServerPropertiesError> {
+    let mut properties = ServerProperties::default();
+    for (key, value) in contents.lines().filter_map(split_property) {
+        let value = unescape(value);
+        match key {
+            "server-port" => {
+                properties.server_port = value
+                    .parse()
+                    .ok()
+                    .filter(|port| *port != 0)
+                    .ok_or_else(|| invalid_value(key, &value))?;
+            }
+            "motd" => properties.motd = value,
+            "difficulty" => {
+                properties.difficulty =
+                    Difficulty::parse(&value).ok_or_else(|| invalid_value(key, &value))?;
+            }
+            "white-list" => properties.white_list = parse_bool(key, &value)?,
+            "online-mode" => properties.online_mode = parse_bool(key, &value)?,
+            _ => {}
+        }
+    }
+    Ok(properties)
+}
+
+fn validate(properties: &ServerProperties) -> Result<(), ServerPropertiesError> {
+    if properties.server_port == 0 {
+        return Err(invalid_value("server-port", "0"));
+    }
+    // Each property is a single line.
+    if properties.motd.contains(['\n', '\r']) {
+        return Err(invalid_value("motd", &properties.motd));
+    }
+    Ok(())
+}
+
+/// `contents` with the values of `properties` set, keeping comments, the order of the lines and
+/// the other properties. Properties that aren't in it yet are added at the end.
+fn set_properties(contents: &str, properties: &ServerProperties) -> String {
+    let mut values = vec![
+        ("server-port", properties.server_port.to_string()),
+        ("motd", escape(&properties.motd)),
+        ("difficulty", properties.difficulty.as_str().to_owned()),
+        ("white-list", properties.white_list.to_string()),
+        ("online-mode", properties.online_mode.to_string()),
+    ];
+    let mut lines: Vec<String> = contents
+        .lines()
+        .map(|line| {
+            let index = split_property(line)
+                .and_then(|(key, _)| values.iter().position(|(name, _)| *name == key));
+            match index {
+                Some(index) => {
+                    let (key, value) = values.remove(index);
+                    format!("{}={}", key, value)
+                }
+                None => line.to_owned(),
+            }
+        })
+        .collect();
+    lines.extend(
+        values
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, value)),
+    );
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    contents
+}
+
+/// The server properties of the instance at `instance_dir`, the defaults if the server hasn't
+/// created the file yet.
+pub fn read_server_properties(
+    instance_dir: &Path,
+) -> Result<ServerProperties, ServerPropertiesError> {
+    match fs::read_to_string(server_properties_path(instance_dir)) {
+        Ok(contents) => parse_properties(&contents),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(ServerProperties::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Validates `properties` and saves them to the `server.properties` of the instance at
+/// `instance_dir`, creating it if needed. The server reads them when it starts.
+pub fn write_server_properties(
+    instance_dir: &Path,
+    properties: &ServerProperties,
+) -> Result<(), ServerPropertiesError> {
+    validate(properties)?;
+    let path = server_properties_path(instance_dir);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    fs::write(&path, set_properties(&contents, properties))?;
+    info!("Saved {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+use tempdir::TempDir;
+
+#[test]
+fn test_server_properties() {
+    let contents = "#Minecraft server properties\n#Sat Jan 06 12:00:00 CET 2024\nspawn-protection=16\ndifficulty=1\nmotd=Caf\\u00e9 \\\\ friends\nserver-port=25566\nonline-mode=false\n";
+    let properties = parse_properties(contents).unwrap();
+    assert_eq!(
+        properties,
+        ServerProperties {
+            server_port: 25566,
+            motd: "Café \\ friends".into(),
+            difficulty: Difficulty::Easy,
+            white_list: false,
+            online_mode: false,
+        }
+    );
+
+    let properties = ServerProperties {
+        difficulty: Difficulty::Hard,
+        white_list: true,
+        ..properties
+    };
+    assert_eq!(
+        set_properties(contents, &properties),
+        "#Minecraft server properties\n#Sat Jan 06 12:00:00 CET 2024\nspawn-protection=16\ndifficulty=hard\nmotd=Caf\\u00e9 \\\\ friends\nserver-port=25566\nonline-mode=false\nwhite-list=true\n"
+    );
+
+    assert!(parse_properties("server-port=70000\n").is_err());
+    assert!(parse_properties("difficulty=extreme\n").is_err());
+    assert_eq!(unescape("\\ud83d\\ude00"), "😀");
+    assert_eq!(escape("😀"), "\\ud83d\\ude00");
+}
+
+#[test]
+fn test_write_server_properties() {
+    let tmp_dir = TempDir::new("server-properties").unwrap();
+    assert_eq!(
+        read_server_properties(tmp_dir.path()).unwrap(),
+        ServerProperties::default()
+    );
+
+    let properties = ServerProperties {
+        motd: "Two\nlines".into(),
+        ..Default::default()
+    };
+    assert!(write_server_properties(tmp_dir.path(), &properties).is_err());
+
+    let properties = ServerProperties {
+        server_port: 25570,
+        ..Default::default()
+    };
+    write_server_properties(tmp_dir.path(), &properties).unwrap();
+    assert_eq!(read_server_properties(tmp_dir.path()).unwrap(), properties);
+}
@@ -0,0 +1,186 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use log::{error, info, warn};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use tokio::sync::{watch, Notify};
+
+use crate::state::{
+    account_manager::AccountManager, settings_manager::SettingsManager, ManagerFromAppHandle,
+};
+
+/// How long cancelled tasks get to stop before the launcher exits anyway.
+const CANCEL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Work the launcher shouldn't exit in the middle of, like installs.
+pub struct TaskState(pub TaskTracker);
+
+impl Default for TaskState {
+    fn default() -> Self {
+        Self(TaskTracker::new())
+    }
+}
+
+pub struct TaskTracker {
+    // <Task id, description shown to the user>
+    tasks: Mutex<Vec<(u64, String)>>,
+    next_id: AtomicU64,
+    finished: Notify,
+    cancelled: watch::Sender<bool>,
+}
+
+/// Removes its task from the tracker when the task finishes or is dropped.
+struct TaskGuard<'a> {
+    tracker: &'a TaskTracker,
+    id: u64,
+}
+
+impl Drop for TaskGuard<'_> {
+    fn drop(&mut self) {
+        self.tracker
+            .tasks
+            .lock()
+            .unwrap()
+            .retain(|(id, _)| *id != self.id);
+        self.tracker.finished.notify_waiters();
+    }
+}
+
+impl TaskTracker {
+    fn new() -> Self {
+        Self {
+            tasks: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(0),
+            finished: Notify::new(),
+            cancelled: watch::Sender::new(false),
+        }
+    }
+
+    /// Descriptions of the running tasks, oldest first.
+    pub fn active_tasks(&self) -> Vec<String> {
+        let tasks = self.tasks.lock().unwrap();
+        tasks.iter().map(|(_, name)| name.clone()).collect()
+    }
+
+    /// Runs `future` as a task described by `name`. Returns `None` if it was cancelled before it
+    /// finished, or wasn't started because the launcher is exiting.
+    pub async fn run<F: Future>(&self, name: &str, future: F) -> Option<F::Output> {
+        let mut cancelled = self.cancelled.subscribe();
+        if *cancelled.borrow() {
+            return None;
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.tasks.lock().unwrap().push((id, name.into()));
+        let _guard = TaskGuard { tracker: self, id };
+        tokio::select! {
+            output = future => Some(output),
+            _ = cancelled.wait_for(|cancelled| *cancelled) => {
+                info!("Cancelled `{}`", name);
+                None
+            }
+        }
+    }
+
+    /// Cancels every running task and any started after.
+    pub fn cancel_all(&self) {
+        self.cancelled.send_replace(true);
+    }
+
+    /// Waits until no task is running.
+    pub async fn wait_idle(&self) {
+        loop {
+            // Created before checking so a task finishing in between still wakes it.
+            let finished = self.finished.notified();
+            if self.tasks.lock().unwrap().is_empty() {
+                return;
+            }
+            finished.await;
+        }
+    }
+}
+
+/// Called instead of closing the launcher. Exits right away if nothing is running, otherwise
+/// emits `exit-requested` with the running tasks so the frontend can ask whether to cancel them
+/// or wait, then call the `exit_launcher` command.
+pub fn request_exit(app_handle: &AppHandle<Wry>) {
+    let tasks = app_handle.state::<TaskState>().0.active_tasks();
+    let app_handle = app_handle.clone();
+    if tasks.is_empty() {
+        tauri::async_runtime::spawn(async move { exit_after_tasks(&app_handle, false).await });
+        return;
+    }
+    info!("Asking before exiting, {} tasks are running", tasks.len());
+    if let Err(e) = app_handle.emit_to("main", "exit-requested", &tasks) {
+        // Nobody can be asked, so don't leave the launcher stuck open.
+        error!("Could not ask to exit: {}", e);
+        tauri::async_runtime::spawn(async move { exit_after_tasks(&app_handle, true).await });
+    }
+}
+
+/// Cancels or waits for the running tasks, saves the launcher's state and exits.
+pub async fn exit_after_tasks(app_handle: &AppHandle<Wry>, cancel_tasks: bool) {
+    let tasks = &app_handle.state::<TaskState>().0;
+    if cancel_tasks {
+        tasks.cancel_all();
+        if tokio::time::timeout(CANCEL_TIMEOUT, tasks.wait_idle())
+            .await
+            .is_err()
+        {
+            warn!(
+                "Exiting with tasks that didn't stop: {:?}",
+                tasks.active_tasks()
+            );
+        }
+    } else {
+        tasks.wait_idle().await;
+    }
+
+    let account_manager = AccountManager::from_app_handle(app_handle).await;
+    // Nothing to save if nobody has logged in yet.
+    if !account_manager.get_all_accounts().is_empty() {
+        if let Err(e) = account_manager.serialize_accounts() {
+            error!("Could not save accounts before exiting: {}", e);
+        }
+    }
+    drop(account_manager);
+    if let Err(e) = SettingsManager::from_app_handle(app_handle)
+        .await
+        .serialize_settings()
+    {
+        error!("Could not save settings before exiting: {}", e);
+    }
+    info!("Exiting");
+    app_handle.exit(0);
+}
+
+#[cfg(test)]
+use tauri::async_runtime::block_on;
+
+#[test]
+fn test_cancelled_tasks_stop() {
+    let tracker = TaskTracker::new();
+    let output = block_on(tracker.run("Installing Ready", async { 1 }));
+    assert_eq!(output, Some(1));
+    assert!(tracker.active_tasks().is_empty());
+
+    block_on(async {
+        let pending = tracker.run("Installing Forever", std::future::pending::<()>());
+        let cancel = async {
+            tokio::task::yield_now().await;
+            assert_eq!(tracker.active_tasks(), ["Installing Forever"]);
+            tracker.cancel_all();
+        };
+        let (output, _) = tokio::join!(pending, cancel);
+        assert_eq!(output, None);
+    });
+    assert!(tracker.active_tasks().is_empty());
+    block_on(tracker.wait_idle());
+    // Nothing new starts once the launcher is exiting.
+    assert_eq!(block_on(tracker.run("Installing Late", async { 1 })), None);
+}
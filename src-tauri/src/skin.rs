@@ -0,0 +1,208 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
+use serde::Serialize;
+
+/// Sizes in pixels the account avatars are rendered at.
+pub const AVATAR_SIZES: [u32; 4] = [16, 32, 64, 128];
+
+/// The account's skin with its face rendered at each of [AVATAR_SIZES].
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountSkin {
+    pub skin_url: String,
+    /// PNG data urls of the face with the hat layer, keyed by their size.
+    pub heads: BTreeMap<u32, String>,
+}
+
+#[derive(Debug)]
+pub enum SkinError {
+    Decode(png::DecodingError),
+    Encode(png::EncodingError),
+    /// Skins are 64 pixels wide, or a multiple of it, and 32 or 64 pixels high.
+    InvalidSize(u32, u32),
+}
+
+impl fmt::Display for SkinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkinError::Decode(error) => write!(f, "Could not read the skin: {}", error),
+            SkinError::Encode(error) => write!(f, "Could not write the avatar: {}", error),
+            SkinError::InvalidSize(width, height) => {
+                write!(f, "A {}x{} image is not a skin", width, height)
+            }
+        }
+    }
+}
+
+impl From<png::DecodingError> for SkinError {
+    fn from(error: png::DecodingError) -> Self {
+        SkinError::Decode(error)
+    }
+}
+
+impl From<png::EncodingError> for SkinError {
+    fn from(error: png::EncodingError) -> Self {
+        SkinError::Encode(error)
+    }
+}
+
+/// An RGBA image.
+struct Rgba {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Rgba {
+    fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * self.width + x) * 4) as usize;
+        self.pixels[index..index + 4].try_into().unwrap()
+    }
+}
+
+/// Where the skin at `skin_url` is cached in `skins_dir`. Skin urls end in the texture's hash, so a
+/// changed skin gets a new file.
+pub fn cached_skin_path(skins_dir: &Path, skin_url: &str) -> PathBuf {
+    let name: String = skin_url
+        .rsplit('/')
+        .next()
+        .unwrap_or(skin_url)
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+    skins_dir.join(format!("{}.png", name))
+}
+
+fn decode_rgba(png_bytes: &[u8]) -> Result<Rgba, SkinError> {
+    let mut decoder = Decoder::new(png_bytes);
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+    let pixels = match info.color_type {
+        ColorType::Rgba => buffer,
+        ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect(),
+        ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+            .collect(),
+        ColorType::Grayscale | ColorType::Indexed => {
+            buffer.iter().flat_map(|&g| [g, g, g, 255]).collect()
+        }
+    };
+    Ok(Rgba {
+        width: info.width,
+        height: info.height,
+        pixels,
+    })
+}
+
+fn encode_rgba(image: &Rgba) -> Result<Vec<u8>, SkinError> {
+    let mut bytes = Vec::new();
+    let mut encoder = Encoder::new(&mut bytes, image.width, image.height);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&image.pixels)?;
+    Ok(bytes)
+}
+
+/// Renders the face of the skin in `skin_png` with the hat layer on top as a `size`x`size` PNG.
+pub fn render_head(skin_png: &[u8], size: u32) -> Result<Vec<u8>, SkinError> {
+    let skin = decode_rgba(skin_png)?;
+    let valid_size = skin.width >= 64
+        && skin.width % 64 == 0
+        && (skin.height == skin.width || skin.height * 2 == skin.width);
+    if !valid_size {
+        return Err(SkinError::InvalidSize(skin.width, skin.height));
+    }
+    // HD skins are larger multiples of the 64 pixel wide layout.
+    let scale = skin.width / 64;
+    let face_size = 8 * scale;
+
+    let mut head = Rgba {
+        width: size,
+        height: size,
+        pixels: Vec::with_capacity((size * size * 4) as usize),
+    };
+    for y in 0..size {
+        for x in 0..size {
+            let face_x = x * face_size / size;
+            let face_y = y * face_size / size;
+            let face = skin.pixel(face_size + face_x, face_size + face_y);
+            let hat = skin.pixel(5 * face_size + face_x, face_size + face_y);
+            head.pixels.extend(blend(face, hat));
+        }
+    }
+    encode_rgba(&head)
+}
+
+/// `top` drawn over the opaque `bottom`.
+fn blend(bottom: [u8; 4], top: [u8; 4]) -> [u8; 4] {
+    let alpha = top[3] as u32;
+    let mix = |b: u8, t: u8| ((t as u32 * alpha + b as u32 * (255 - alpha)) / 255) as u8;
+    [
+        mix(bottom[0], top[0]),
+        mix(bottom[1], top[1]),
+        mix(bottom[2], top[2]),
+        255,
+    ]
+}
+
+/// The face of the skin in `skin_png` at each of [AVATAR_SIZES], as PNG data urls.
+pub fn render_heads(skin_png: &[u8]) -> Result<BTreeMap<u32, String>, SkinError> {
+    AVATAR_SIZES
+        .iter()
+        .map(|&size| {
+            let png = render_head(skin_png, size)?;
+            Ok((
+                size,
+                format!("data:image/png;base64,{}", STANDARD.encode(png)),
+            ))
+        })
+        .collect()
+}
+
+#[test]
+fn test_render_head() {
+    let mut skin = Rgba {
+        width: 64,
+        height: 64,
+        pixels: vec![0; 64 * 64 * 4],
+    };
+    let mut set = |x: u32, y: u32, rgba: [u8; 4]| {
+        let index = ((y * 64 + x) * 4) as usize;
+        skin.pixels[index..index + 4].copy_from_slice(&rgba);
+    };
+    for y in 8..16 {
+        for x in 8..16 {
+            set(x, y, [200, 0, 0, 255]);
+        }
+    }
+    // A hat pixel over the top left of the face and a half transparent one next to it.
+    set(40, 8, [0, 0, 200, 255]);
+    set(41, 8, [0, 0, 200, 128]);
+
+    let head = decode_rgba(&render_head(&encode_rgba(&skin).unwrap(), 16).unwrap()).unwrap();
+    assert_eq!((head.width, head.height), (16, 16));
+    assert_eq!(head.pixel(0, 0), [0, 0, 200, 255]);
+    assert_eq!(head.pixel(1, 1), [0, 0, 200, 255]);
+    assert_eq!(head.pixel(2, 0), [99, 0, 100, 255]);
+    assert_eq!(head.pixel(15, 15), [200, 0, 0, 255]);
+
+    skin.height = 16;
+    skin.pixels.truncate(64 * 16 * 4);
+    assert!(matches!(
+        render_head(&encode_rgba(&skin).unwrap(), 16),
+        Err(SkinError::InvalidSize(64, 16))
+    ));
+}
@@ -0,0 +1,164 @@
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+use serde::Serialize;
+
+use crate::state::{account_manager::AccountManager, instance_manager::InstanceConfiguration};
+
+/// Problems found with the launcher's files on startup, kept until the frontend asks for them.
+#[derive(Default)]
+pub struct StartupDiagnosticsState(pub Vec<Diagnostic>);
+
+/// What the user can do about a [Diagnostic].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiagnosticAction {
+    /// Reinstall the instance's missing files.
+    Repair,
+    /// Remove the instance, it can't be loaded.
+    Remove,
+    /// Log in again, the saved accounts can't be read.
+    Relogin,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub message: String,
+    pub action: DiagnosticAction,
+    pub instance_name: Option<String>,
+}
+
+impl Diagnostic {
+    fn instance(instance_name: &str, message: String, action: DiagnosticAction) -> Self {
+        Self {
+            message,
+            action,
+            instance_name: Some(instance_name.into()),
+        }
+    }
+}
+
+/// Checks that every instance in `instances_dirs` has a readable `config.json` whose java still
+/// exists, and that the saved accounts in `app_dir` can be read.
+pub fn run_startup_diagnostics(app_dir: &Path, instances_dirs: &[PathBuf]) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = instances_dirs
+        .iter()
+        .flat_map(|instances_dir| check_instances(instances_dir))
+        .collect();
+    diagnostics.sort_by(|a, b| a.instance_name.cmp(&b.instance_name));
+
+    // No accounts file just means nobody has logged in yet.
+    let accounts_path = app_dir.join("accounts.json");
+    if accounts_path.is_file() {
+        let accounts = File::open(&accounts_path)
+            .map_err(serde_json::Error::io)
+            .and_then(|file| serde_json::from_reader::<_, AccountManager>(BufReader::new(file)));
+        if let Err(e) = accounts {
+            diagnostics.push(Diagnostic {
+                message: format!("The saved accounts can't be read: {}", e),
+                action: DiagnosticAction::Relogin,
+                instance_name: None,
+            });
+        }
+    }
+
+    for diagnostic in &diagnostics {
+        warn!("Startup diagnostic: {}", diagnostic.message);
+    }
+    diagnostics
+}
+
+fn check_instances(instances_dir: &Path) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let entries = match fs::read_dir(instances_dir) {
+        Ok(entries) => entries,
+        Err(_) => return diagnostics,
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let config = match fs::read(path.join("config.json")) {
+            Ok(config) => config,
+            Err(_) => {
+                diagnostics.push(Diagnostic::instance(
+                    &name,
+                    format!("{} has no config.json", name),
+                    DiagnosticAction::Remove,
+                ));
+                continue;
+            }
+        };
+        match serde_json::from_slice::<InstanceConfiguration>(&config) {
+            // The folder can be named differently from the instance.
+            Ok(config) if !config.jvm_path.is_file() => {
+                diagnostics.push(Diagnostic::instance(
+                    &config.instance_name,
+                    format!(
+                        "The java used by {} is missing from {}",
+                        config.instance_name,
+                        config.jvm_path.display()
+                    ),
+                    DiagnosticAction::Repair,
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => diagnostics.push(Diagnostic::instance(
+                &name,
+                format!("The config.json of {} can't be read: {}", name, e),
+                DiagnosticAction::Remove,
+            )),
+        }
+    }
+    diagnostics.sort_by(|a, b| a.instance_name.cmp(&b.instance_name));
+    diagnostics
+}
+
+#[cfg(test)]
+use tempdir::TempDir;
+
+#[test]
+fn test_startup_diagnostics() {
+    let app_dir = TempDir::new("diagnostics").unwrap();
+    let instances_dir = app_dir.path().join("instances");
+    for name in ["Broken", "Empty", "Missing Java"] {
+        fs::create_dir_all(instances_dir.join(name)).unwrap();
+    }
+    fs::write(instances_dir.join("Broken").join("config.json"), "{").unwrap();
+    fs::write(
+        instances_dir.join("Missing Java").join("config.json"),
+        r#"{"instance_name":"Missing Java","jvm_path":"/missing/bin/java","arguments":[],
+            "modloader_type":"None","modloader_version":"1.20.1","author":"You",
+            "instance_icon":null,"playtime":0,"resolution_width":null,
+            "resolution_height":null,"fullscreen":null,"vanilla_version":"1.20.1","sync":null,
+            "max_memory_mb":null}"#,
+    )
+    .unwrap();
+    fs::write(app_dir.path().join("accounts.json"), "not json").unwrap();
+
+    let archive_dir = app_dir.path().join("archive").join("instances");
+    fs::create_dir_all(archive_dir.join("Archived")).unwrap();
+
+    let diagnostics = run_startup_diagnostics(app_dir.path(), &[instances_dir, archive_dir]);
+    let summary: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.instance_name.as_deref(), diagnostic.action))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (Some("Archived"), DiagnosticAction::Remove),
+            (Some("Broken"), DiagnosticAction::Remove),
+            (Some("Empty"), DiagnosticAction::Remove),
+            (Some("Missing Java"), DiagnosticAction::Repair),
+            (None, DiagnosticAction::Relogin),
+        ]
+    );
+}
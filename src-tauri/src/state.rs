@@ -8,6 +8,7 @@ use tokio::sync::{Mutex, OwnedMutexGuard};
 pub mod account_manager;
 pub mod instance_manager;
 pub mod resource_manager;
+pub mod settings_manager;
 
 /// Attempts to redirect the main window to the specified endpoint
 /// Specify endpoint without a leading `/`.  
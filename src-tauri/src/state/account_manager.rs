@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::File,
     io::{BufReader, Error, Write},
     path::{Path, PathBuf},
@@ -8,11 +8,16 @@ use std::{
 };
 
 use autmc_authentication::{refresh_access_tokens, MinecraftAccount, OAuthRefreshMode};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use tauri::{async_runtime::Mutex, AppHandle, Wry};
+use tauri::{
+    async_runtime::{JoinHandle, Mutex},
+    AppHandle, Emitter, Wry,
+};
 use tokio::time::sleep;
 
+use crate::authentication::{account_health, AccountHealth};
+
 use super::{InnerState, ManagerFromAppHandle};
 
 #[derive(Debug)]
@@ -34,12 +39,40 @@ impl AccountState {
     }
 }
 
+/// How a stored account's profile changed since it was saved, emitted as `account-profile-changed`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileChange {
+    pub old_uuid: String,
+    pub uuid: String,
+    pub old_name: String,
+    pub name: String,
+}
+
+/// Lets the frontend know an account was renamed so it can refresh what it shows.
+pub fn emit_profile_change(change: &ProfileChange, app_handle: &AppHandle<Wry>) {
+    info!(
+        "Account {} ({}) is now {} ({})",
+        change.old_name, change.old_uuid, change.name, change.uuid
+    );
+    if let Err(e) = app_handle.emit_to("main", "account-profile-changed", change) {
+        error!("{}", e);
+    }
+}
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct AccountManager {
     #[serde(skip)]
     path: PathBuf,
     active: Option<String>,
     accounts: HashMap<String, MinecraftAccount>,
+    /// Uuids of the accounts whose tokens couldn't be refreshed, kept until they sign in again.
+    #[serde(default)]
+    needs_reauth: HashSet<String>,
+    /// Refreshes the active account's tokens once they expire, aborted when another account is
+    /// activated.
+    #[serde(skip)]
+    refresh_task: Option<JoinHandle<()>>,
 }
 
 // FIXME: Storing tokens in plaintext is bad... store them in the platform keystore using keyring-rs
@@ -51,6 +84,8 @@ impl AccountManager {
             path: app_dir.into(),
             active: Default::default(),
             accounts: Default::default(),
+            needs_reauth: Default::default(),
+            refresh_task: None,
         }
     }
 
@@ -63,6 +98,7 @@ impl AccountManager {
             serde_json::from_reader::<BufReader<File>, AccountManager>(reader)?;
         self.active = deserialized_account_manager.active;
         self.accounts = deserialized_account_manager.accounts;
+        self.needs_reauth = deserialized_account_manager.needs_reauth;
         Ok(())
     }
 
@@ -89,6 +125,14 @@ impl AccountManager {
         }
     }
 
+    /// Get the account with `uuid`, or the active account if it is `None`.
+    pub fn get_account_or_active(&self, uuid: Option<&str>) -> Option<&MinecraftAccount> {
+        match uuid {
+            Some(uuid) => self.get_account(uuid),
+            None => self.get_active_account(),
+        }
+    }
+
     /// Get the active account's uuid
     pub fn get_active_uuid(&self) -> Option<String> {
         self.active.clone()
@@ -116,7 +160,9 @@ impl AccountManager {
             account.minecraft_access_token_expiry,
             account.microsoft_access_token_expiry
         );
-        self.add_account(account);
+        if let Some(change) = self.add_account(account) {
+            emit_profile_change(&change, &app_handle);
+        }
         self.activate_account(uuid, app_handle);
         info!(
             "Added and activated account: {}",
@@ -127,17 +173,24 @@ impl AccountManager {
     // Activate the account associated with uuid
     pub fn activate_account(&mut self, uuid: &str, app_handle: AppHandle<Wry>) {
         self.active = Some(uuid.to_owned());
+        if let Some(refresh_task) = self.refresh_task.take() {
+            refresh_task.abort();
+        }
         // Can unwrap here since we just set `self.active`
         let account = self.get_active_account().unwrap().clone();
+        let account_uuid = account.uuid.clone();
+        if account.offline {
+            return;
+        }
         // Spawn a thread to refresh access tokens once they expire.
-        tauri::async_runtime::spawn(async move {
+        self.refresh_task = Some(tauri::async_runtime::spawn(async move {
             // Assumes SystemTime is after UNIX_EPOCH
             let now = chrono::Local::now().timestamp() as u64;
             let refresh_mode =
                 if account.minecraft_access_token_expiry < account.microsoft_access_token_expiry {
                     // Minecraft
-                    let secs_until_expire = account.minecraft_access_token_expiry - now;
-                    sleep(Duration::from_secs(secs_until_expire)).await;
+                    let secs_until_expire = account.minecraft_access_token_expiry.checked_sub(now);
+                    sleep(Duration::from_secs(secs_until_expire.unwrap_or(0))).await;
                     info!("Refreshing minecraft access token");
                     OAuthRefreshMode::Minecraft {
                         token: account.into(),
@@ -151,20 +204,148 @@ impl AccountManager {
                         token: account.into(),
                     }
                 };
-            let mut account_manager = AccountManager::from_app_handle(&app_handle).await;
 
             let account_res = refresh_access_tokens(refresh_mode).await;
+            let mut account_manager = AccountManager::from_app_handle(&app_handle).await;
             match account_res {
-                Ok(account) => {
+                // The user may have switched accounts while it was refreshing.
+                Ok(account) if account_manager.active.as_deref() == Some(&account_uuid) => {
                     account_manager.add_and_activate_account(account, app_handle.clone())
                 }
-                Err(e) => error!("Issue re-authenticating with microsoft: {}", e.to_string()),
+                Ok(account) => {
+                    if let Some(change) = account_manager.add_account(account) {
+                        emit_profile_change(&change, &app_handle);
+                    }
+                }
+                Err(e) => {
+                    error!("Issue re-authenticating with microsoft: {}", e.to_string());
+                    account_manager.mark_needs_reauth(&account_uuid, &app_handle);
+                }
             }
-        });
+        }));
     }
 
-    /// Adds an account, overwriting any existing accounts with the same uuid.
-    pub fn add_account(&mut self, account: MinecraftAccount) {
+    /// Adds an account, overwriting any existing accounts with the same uuid. Returns the change if
+    /// the account was stored under another name.
+    pub fn add_account(&mut self, account: MinecraftAccount) -> Option<ProfileChange> {
+        self.needs_reauth.remove(&account.uuid);
+        let change = self
+            .accounts
+            .get(&account.uuid)
+            .filter(|old| old.name != account.name)
+            .map(|old| ProfileChange {
+                old_uuid: old.uuid.clone(),
+                uuid: account.uuid.clone(),
+                old_name: old.name.clone(),
+                name: account.name.clone(),
+            });
         self.accounts.insert(account.uuid.clone(), account);
+        change
     }
+
+    /// Updates the stored account `uuid` with the uuid, name and skin of its freshly fetched
+    /// profile, moving it to the new uuid if that changed. Returns what changed besides the skin.
+    pub fn apply_profile(
+        &mut self,
+        uuid: &str,
+        profile_uuid: &str,
+        name: &str,
+        skin_url: Option<&str>,
+    ) -> Option<ProfileChange> {
+        let mut account = self.accounts.remove(uuid)?;
+        if let Some(skin_url) = skin_url {
+            account.skin_url = skin_url.to_owned();
+        }
+        let change =
+            (account.uuid != profile_uuid || account.name != name).then(|| ProfileChange {
+                old_uuid: account.uuid.clone(),
+                uuid: profile_uuid.to_owned(),
+                old_name: account.name.clone(),
+                name: name.to_owned(),
+            });
+        if account.uuid != profile_uuid {
+            if self.active.as_deref() == Some(uuid) {
+                self.active = Some(profile_uuid.to_owned());
+            }
+            if self.needs_reauth.remove(uuid) {
+                self.needs_reauth.insert(profile_uuid.to_owned());
+            }
+        }
+        account.uuid = profile_uuid.to_owned();
+        account.name = name.to_owned();
+        self.accounts.insert(account.uuid.clone(), account);
+        change
+    }
+
+    /// The health of a stored account.
+    pub fn get_account_health(&self, account: &MinecraftAccount) -> AccountHealth {
+        let now = chrono::Local::now().timestamp() as u64;
+        account_health(account, self.needs_reauth(&account.uuid), now)
+    }
+
+    /// Whether the account's tokens couldn't be refreshed and it has to sign in again to play
+    /// online.
+    pub fn needs_reauth(&self, uuid: &str) -> bool {
+        self.needs_reauth.contains(uuid)
+    }
+
+    /// Marks the account as needing to sign in again after refreshing its tokens failed. It stays
+    /// stored and active so singleplayer and everything that doesn't need a valid token keeps
+    /// working. Emits `account-needs-reauth` with the uuid so the user can be asked to sign in.
+    pub fn mark_needs_reauth(&mut self, uuid: &str, app_handle: &AppHandle<Wry>) {
+        if !self.accounts.contains_key(uuid) {
+            return;
+        }
+        info!("Account {} needs to sign in again", uuid);
+        self.needs_reauth.insert(uuid.to_owned());
+        if let Err(e) = self.serialize_accounts() {
+            warn!("Could not save that {} needs to sign in again: {}", uuid, e);
+        }
+        if let Err(e) = app_handle.emit_to("main", "account-needs-reauth", uuid) {
+            error!("{}", e);
+        }
+    }
+}
+
+#[test]
+fn test_apply_profile() {
+    let mut account_manager = AccountManager::default();
+    let account = MinecraftAccount {
+        uuid: "old-uuid".into(),
+        name: "OldName".into(),
+        ..Default::default()
+    };
+    assert_eq!(account_manager.add_account(account.clone()), None);
+    account_manager.active = Some("old-uuid".into());
+    assert_eq!(
+        account_manager.apply_profile("old-uuid", "old-uuid", "OldName", None),
+        None
+    );
+
+    assert_eq!(
+        account_manager.apply_profile("old-uuid", "new-uuid", "NewName", Some("skin")),
+        Some(ProfileChange {
+            old_uuid: "old-uuid".into(),
+            uuid: "new-uuid".into(),
+            old_name: "OldName".into(),
+            name: "NewName".into(),
+        })
+    );
+    assert!(account_manager.get_account("old-uuid").is_none());
+    let renamed = account_manager.get_active_account().unwrap();
+    assert_eq!(
+        (
+            renamed.uuid.as_str(),
+            renamed.name.as_str(),
+            renamed.skin_url.as_str()
+        ),
+        ("new-uuid", "NewName", "skin")
+    );
+
+    let change = account_manager.add_account(MinecraftAccount {
+        uuid: "new-uuid".into(),
+        name: "Renamed".into(),
+        ..Default::default()
+    });
+    assert_eq!(change.map(|change| change.old_name), Some("NewName".into()));
 }
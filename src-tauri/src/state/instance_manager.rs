@@ -1,23 +1,50 @@
 use autmc_authentication::MinecraftAccount;
+use autmc_log::debug_if;
 use log::{debug, error, warn};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::{
     collections::HashMap,
+    fmt::Display,
     fs::{self, File},
     io::{self, BufReader, Write},
     path::{Path, PathBuf},
     process::Stdio,
     sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
 };
 use tauri::{
-    async_runtime::{JoinHandle, Mutex}, AppHandle, Emitter, Manager, Wry
+    async_runtime::{JoinHandle, Mutex},
+    AppHandle, Emitter, Manager, Wry,
 };
 use tokio::io::{AsyncBufReadExt, BufReader as AsyncBufReader};
 use tokio::process::{Child, Command};
 
-use crate::web_services::resources::{substitute_account_specific_arguments, ModloaderType};
+use crate::{
+    consts::CLASSPATH_ARGFILE,
+    crash_incident::{record_exit, InstanceSnapshot},
+    crash_restart::{restart_crashed_instance, RestartPolicy},
+    instance_sync::{sync_instance, InstanceSync},
+    notifications::notify,
+    secondary_window::{ConsoleLine, SecondaryWindow, CONSOLE_EXIT_EVENT, CONSOLE_LINE_EVENT},
+    system_info::{discrete_gpu_env, java_major_version, total_memory_mb, DiscreteGpuPreferences},
+    update_check::UpdateWatch,
+    web_services::resources::{
+        substitute_account_specific_arguments, ModloaderType, PinnedAssetIndex,
+    },
+};
+
+use super::{
+    resource_manager::ResourceManager, settings_manager::LauncherSettings, InnerState,
+    ManagerFromAppHandle,
+};
 
-use super::{InnerState, ManagerFromAppHandle};
+/// Where the mods folder is moved to while an instance runs in safe mode.
+const SAFE_MODE_MODS_DIR: &str = "mods-safe-mode";
+/// Windows can't start processes with a command line over 32767 characters, this leaves room for
+/// the java path and quoting.
+const MAX_COMMAND_LINE_LENGTH: usize = 30_000;
+/// Shown instead of the access token in previewed launch commands.
+const REDACTED_ACCESS_TOKEN: &str = "<redacted>";
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct InstanceConfiguration {
@@ -29,6 +56,176 @@ pub struct InstanceConfiguration {
     pub author: String,
     pub instance_icon: Option<PathBuf>,
     pub playtime: u32,
+    // Window overrides, `None` uses the launcher's default settings.
+    pub resolution_width: Option<u32>,
+    pub resolution_height: Option<u32>,
+    pub fullscreen: Option<bool>,
+    // Only set for instances created after the vanilla version was tracked.
+    pub vanilla_version: Option<String>,
+    // Synced before launching and after the game exits, `None` if the instance isn't synced.
+    pub sync: Option<InstanceSync>,
+    // Passed as `-Xmx`, `None` leaves the heap size to the JVM or the additional jvm arguments.
+    pub max_memory_mb: Option<u64>,
+    // Hybrid graphics machines otherwise often run the game on the integrated GPU.
+    #[serde(default)]
+    pub prefer_discrete_gpu: bool,
+    // Already part of `arguments`, kept separately so it can be saved in templates.
+    #[serde(default, deserialize_with = "deserialize_jvm_arguments")]
+    pub additional_jvm_arguments: Vec<String>,
+    // Checked before launching, `None` for instances created before it was saved.
+    #[serde(default)]
+    pub asset_index: Option<PinnedAssetIndex>,
+    // The folder in `instances` the instance is stored in, which can differ from its name. Empty for
+    // instances created before it was saved, which are stored under their name.
+    #[serde(default)]
+    pub directory_name: String,
+    // The data root the instance is stored in, `None` for the launcher's own folder.
+    #[serde(default)]
+    pub data_root: Option<String>,
+    // Assets and native jars are stored in the instance folder instead of the shared ones, for
+    // snapshots whose asset indexes and natives shouldn't be mixed with the other versions'.
+    #[serde(default)]
+    pub isolated_resources: bool,
+    // Checked for new loader builds, pack versions and mod updates, `None` if it isn't watched.
+    #[serde(default)]
+    pub update_watch: Option<UpdateWatch>,
+    // Launched again when it crashes, `None` if it isn't. Meant for servers, but there are no
+    // server instances yet so any instance can opt in.
+    #[serde(default)]
+    pub restart_on_crash: Option<RestartPolicy>,
+}
+
+impl InstanceConfiguration {
+    /// Replaces `from` with `to` in the launch arguments, like the path of the game jar in the
+    /// classpath.
+    pub fn replace_in_arguments(&mut self, from: &str, to: &str) {
+        for argument in self.arguments.iter_mut() {
+            if argument.contains(from) {
+                *argument = argument.replace(from, to);
+            }
+        }
+    }
+
+    pub fn directory_name(&self) -> &str {
+        if self.directory_name.is_empty() {
+            &self.instance_name
+        } else {
+            &self.directory_name
+        }
+    }
+
+    /// The vanilla version the instance was created from, inferred from the modloader version or
+    /// the launch arguments for older instances when possible.
+    pub fn vanilla_version(&self) -> Option<&str> {
+        if let Some(version) = &self.vanilla_version {
+            return Some(version);
+        }
+        match self.modloader_type {
+            ModloaderType::None => Some(&self.modloader_version),
+            // Forge versions are stored as `<minecraft version>-<forge version>`.
+            ModloaderType::Forge => self
+                .modloader_version
+                .split_once('-')
+                .map(|(version, _)| version),
+            // The launch arguments name the vanilla version the game is started as.
+            ModloaderType::Fabric => self
+                .arguments
+                .iter()
+                .position(|argument| argument == "--version")
+                .and_then(|index| self.arguments.get(index + 1))
+                .map(String::as_str),
+        }
+    }
+
+    /// Instances created before the additional jvm arguments were tokenized have them as a single
+    /// argument, which java reads as one malformed flag. Splits it back into separate arguments.
+    /// They were always the first argument. One that is a path with spaces, like the natives
+    /// folder, doesn't split into options and is left alone.
+    fn split_legacy_jvm_arguments(&mut self) {
+        let legacy_arguments = match self.arguments.first() {
+            // A quoted argument the user entered with spaces in it.
+            Some(first) if self.additional_jvm_arguments.first() == Some(first) => return,
+            Some(first) if first.starts_with('-') && first.contains(char::is_whitespace) => {
+                match parse_jvm_arguments(first) {
+                    Ok(arguments) if arguments.len() > 1 => arguments,
+                    _ => return,
+                }
+            }
+            _ => return,
+        };
+        if self.additional_jvm_arguments.is_empty() {
+            self.additional_jvm_arguments = legacy_arguments.clone();
+        }
+        self.arguments.splice(0..1, legacy_arguments);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum JvmArgumentError {
+    UnterminatedQuote,
+    NotAnOption(String),
+}
+
+impl Display for JvmArgumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JvmArgumentError::UnterminatedQuote => {
+                write!(f, "The jvm arguments have an unterminated quote")
+            }
+            JvmArgumentError::NotAnOption(token) => {
+                write!(f, "`{}` is not a jvm option, they start with `-`", token)
+            }
+        }
+    }
+}
+
+/// Splits the jvm arguments entered by the user into separate arguments like a shell would. Single
+/// or double quotes keep whitespace in an argument, backslashes are kept as is since they are part
+/// of Windows paths.
+pub fn parse_jvm_arguments(line: &str) -> Result<Vec<String>, JvmArgumentError> {
+    let mut arguments = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.get_or_insert_with(String::new).push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                // Makes `""` an empty argument rather than nothing.
+                current.get_or_insert_with(String::new);
+            }
+            None if c.is_whitespace() => arguments.extend(current.take()),
+            None => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(JvmArgumentError::UnterminatedQuote);
+    }
+    arguments.extend(current);
+    if let Some(argument) = arguments.iter().find(|argument| !argument.starts_with('-')) {
+        return Err(JvmArgumentError::NotAnOption(argument.clone()));
+    }
+    Ok(arguments)
+}
+
+/// Older configs and templates stored the additional jvm arguments as the line the user entered.
+pub fn deserialize_jvm_arguments<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StoredArguments {
+        Line(String),
+        Arguments(Vec<String>),
+    }
+    Ok(match StoredArguments::deserialize(deserializer)? {
+        // They were never validated, so anything that doesn't parse is split on whitespace.
+        StoredArguments::Line(line) => parse_jvm_arguments(&line)
+            .unwrap_or_else(|_| line.split_whitespace().map(str::to_owned).collect()),
+        StoredArguments::Arguments(arguments) => arguments,
+    })
 }
 
 pub struct InstanceState(pub Arc<Mutex<InstanceManager>>);
@@ -44,51 +241,113 @@ impl ManagerFromAppHandle for InstanceManager {
 }
 
 impl InstanceState {
-    pub fn new(app_dir: &Path) -> Self {
-        Self(Arc::new(Mutex::new(InstanceManager::new(app_dir))))
+    pub fn new(resources: ResourceManager) -> Self {
+        Self(Arc::new(Mutex::new(InstanceManager::new(resources))))
+    }
+}
+
+/// Folders the game keeps its files in inside an instance's folder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InstanceSubdir {
+    Screenshots,
+    Logs,
+    CrashReports,
+    Saves,
+    Mods,
+    ResourcePacks,
+}
+
+impl InstanceSubdir {
+    pub fn dir_name(self) -> &'static str {
+        match self {
+            InstanceSubdir::Screenshots => "screenshots",
+            InstanceSubdir::Logs => "logs",
+            InstanceSubdir::CrashReports => "crash-reports",
+            InstanceSubdir::Saves => "saves",
+            InstanceSubdir::Mods => "mods",
+            InstanceSubdir::ResourcePacks => "resourcepacks",
+        }
+    }
+
+    /// This folder in the instance folder `instance_dir`.
+    pub fn path_in(self, instance_dir: &Path) -> PathBuf {
+        instance_dir.join(self.dir_name())
     }
 }
 
 // TODO: Maybe "double fork" to keep the Minecraft instance once the launcher is closed.
 // Would be an option in the launcher settings.
 pub struct InstanceManager {
-    app_dir: PathBuf,
+    // Resolves the data roots instances are stored in.
+    resources: ResourceManager,
     instance_map: HashMap<String, InstanceConfiguration>,
     // <Instance name, child process>
     children: HashMap<String, Arc<Mutex<Child>>>,
     logging_threads: HashMap<String, JoinHandle<()>>,
+    // <Instance name, (when they were computed, last computed stats)>
+    stats_cache: HashMap<String, (SystemTime, InstanceStats)>,
+    gpu_preferences: DiscreteGpuPreferences,
 }
 
 impl InstanceManager {
-    pub fn new(app_dir: &Path) -> Self {
+    pub fn new(resources: ResourceManager) -> Self {
         Self {
-            app_dir: app_dir.into(),
+            resources,
             instance_map: HashMap::new(),
             children: HashMap::new(),
             logging_threads: HashMap::new(),
+            stats_cache: HashMap::new(),
+            gpu_preferences: DiscreteGpuPreferences::default(),
         }
     }
 
+    /// The instances folder of the launcher's own data root.
     pub fn instances_dir(&self) -> PathBuf {
-        self.app_dir.join("instances")
+        self.resources.instances_dir()
+    }
+
+    /// The folder `config` is stored in. Falls back to the launcher's own data root if the
+    /// instance's data root was removed since it was loaded.
+    fn config_dir(&self, config: &InstanceConfiguration) -> PathBuf {
+        self.resources
+            .instances_dir_in(config.data_root.as_deref())
+            .unwrap_or_else(|| self.instances_dir())
+            .join(config.directory_name())
     }
 
     /// Add the config.json to an instance folder. Used to relaunch the instance again.
     pub fn add_instance(&self, config: InstanceConfiguration) -> Result<(), io::Error> {
-        let path = self
-            .instances_dir()
-            .join(&config.instance_name)
-            .join("config.json");
+        let path = self.config_dir(&config).join("config.json");
         let mut file = File::create(path)?;
         let json = serde_json::to_string(&config)?;
         file.write_all(json.as_bytes())?;
         Ok(())
     }
 
+    /// Loads the instances of every data root, forgetting the ones in data roots that were removed.
     pub fn deserialize_instances(&mut self) {
-        let paths = fs::read_dir(self.instances_dir());
+        let instances_dirs = self.resources.all_instances_dirs();
+        self.instance_map.retain(|_, config| {
+            instances_dirs
+                .iter()
+                .any(|(data_root, _)| *data_root == config.data_root)
+        });
+        for (data_root, instances_dir) in instances_dirs {
+            self.deserialize_instances_in(data_root, &instances_dir);
+        }
+    }
+
+    fn deserialize_instances_in(&mut self, data_root: Option<String>, instances_dir: &Path) {
+        let paths = fs::read_dir(instances_dir);
         if let Err(e) = paths {
-            error!("Error loading instances from disk: {}", e);
+            // Data roots only get an instances folder once something is moved to them.
+            if data_root.is_none() || e.kind() != io::ErrorKind::NotFound {
+                error!(
+                    "Error loading instances from {}: {}",
+                    instances_dir.display(),
+                    e
+                );
+            }
             return;
         }
         for path in paths.unwrap().filter_map(|path| path.ok()) {
@@ -118,7 +377,50 @@ impl InstanceManager {
                 );
                 continue;
             }
-            let conf = instance.unwrap();
+            let mut conf = instance.unwrap();
+            conf.split_legacy_jvm_arguments();
+            // Instances are looked up by the folder they are in, which configs only store since
+            // the name of an instance could differ from its folder.
+            let directory_name = match path.file_name().into_string() {
+                Ok(directory_name) => directory_name,
+                Err(_) => {
+                    warn!(
+                        "Skipping instance at {}, its folder name isn't valid unicode",
+                        path.path().display()
+                    );
+                    continue;
+                }
+            };
+            let is_duplicate = self
+                .instance_map
+                .get(&conf.instance_name)
+                .is_some_and(|loaded| {
+                    loaded.directory_name() != directory_name || loaded.data_root != data_root
+                });
+            if is_duplicate {
+                warn!(
+                    "Skipping instance at {}, another instance is named {}",
+                    path.path().display(),
+                    conf.instance_name
+                );
+                continue;
+            }
+            if conf.directory_name != directory_name || conf.data_root != data_root {
+                conf.directory_name = directory_name;
+                conf.data_root = data_root.clone();
+                if let Err(e) = self.add_instance(conf.clone()) {
+                    warn!("Could not save the folder of {}: {}", conf.instance_name, e);
+                }
+            }
+            // Restore mods left disabled if the launcher closed during a safe mode launch.
+            if !self.children.contains_key(&conf.instance_name) {
+                if let Err(e) = restore_safe_mode_mods(&path.path()) {
+                    warn!(
+                        "Could not restore mods for {} after safe mode: {}",
+                        conf.instance_name, e
+                    );
+                }
+            }
             self.instance_map.insert(conf.instance_name.clone(), conf);
         }
     }
@@ -130,58 +432,417 @@ impl InstanceManager {
             .collect()
     }
 
-    pub fn get_instance_names(&self) -> Vec<String> {
+    /// Every instance's name with the folder it is stored in.
+    pub fn get_instance_dirs(&self) -> Vec<(String, PathBuf)> {
         self.instance_map
-            .keys()
-            .map(|instance_name| instance_name.into())
+            .iter()
+            .map(|(name, config)| (name.clone(), self.config_dir(config)))
             .collect()
     }
 
+    /// Returns the last computed stats for an instance, if they have been computed yet.
+    pub fn get_cached_stats(&self, instance_name: &str) -> Option<InstanceStats> {
+        self.stats_cache
+            .get(instance_name)
+            .map(|(_, stats)| stats.clone())
+    }
+
+    /// Recomputes the stats of the instances that changed since they were cached, in the background,
+    /// caching them and emitting `instance-stats` for each instance once it is done.
+    pub fn refresh_instance_stats(&self, app_handle: AppHandle<Wry>) {
+        let instance_dirs: Vec<_> = self
+            .get_instance_dirs()
+            .into_iter()
+            .map(|(instance_name, instance_dir)| {
+                let computed_at = self
+                    .stats_cache
+                    .get(&instance_name)
+                    .map(|(computed_at, _)| *computed_at);
+                (instance_name, instance_dir, computed_at)
+            })
+            .collect();
+
+        tauri::async_runtime::spawn(async move {
+            #[derive(Serialize, Clone)]
+            #[serde(rename_all = "camelCase")]
+            struct StatsEvent {
+                instance_name: String,
+                stats: InstanceStats,
+            }
+
+            for (instance_name, instance_dir, computed_at) in instance_dirs {
+                let (computed_at, stats) = match tauri::async_runtime::spawn_blocking(move || {
+                    if computed_at.is_some_and(|computed_at| {
+                        !InstanceStats::changed_since(&instance_dir, computed_at)
+                    }) {
+                        return None;
+                    }
+                    // Taken before counting so changes made meanwhile are picked up next time.
+                    let computed_at = SystemTime::now();
+                    Some((computed_at, InstanceStats::compute(&instance_dir)))
+                })
+                .await
+                {
+                    Ok(Some(computed)) => computed,
+                    Ok(None) => continue,
+                    Err(error) => {
+                        warn!("Could not compute stats for {}: {}", instance_name, error);
+                        continue;
+                    }
+                };
+
+                let mut instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+                instance_manager
+                    .stats_cache
+                    .insert(instance_name.clone(), (computed_at, stats.clone()));
+                drop(instance_manager);
+
+                if let Err(error) = app_handle.emit(
+                    "instance-stats",
+                    StatsEvent {
+                        instance_name,
+                        stats,
+                    },
+                ) {
+                    error!("{}", error.to_string());
+                }
+            }
+        });
+    }
+
+    /// Forgets the instance, after its folder was moved or deleted.
+    pub fn remove_instance(&mut self, instance_name: &str) -> Option<InstanceConfiguration> {
+        self.stats_cache.remove(instance_name);
+        self.release_gpu_preference(instance_name);
+        self.instance_map.remove(instance_name)
+    }
+
+    /// Whether the game of the instance is still running.
+    pub fn is_running(&self, instance_name: &str) -> bool {
+        // The child stays locked until the game exits.
+        self.children
+            .get(instance_name)
+            .is_some_and(|child| match child.try_lock() {
+                Ok(mut child) => matches!(child.try_wait(), Ok(None)),
+                Err(_) => true,
+            })
+    }
+
+    pub fn has_instance(&self, instance_name: &str) -> bool {
+        self.instance_map.contains_key(instance_name)
+    }
+
+    /// The instance named `slug`, or else the one stored in the folder named `slug`. Instances
+    /// created before folder names were sanitized, or renamed since, are stored under another name.
+    fn find_instance(&self, slug: &str) -> Option<&InstanceConfiguration> {
+        self.instance_map.get(slug).or_else(|| {
+            self.instance_map
+                .values()
+                .find(|config| config.directory_name() == slug)
+        })
+    }
+
+    /// The folder the instance is stored in, `None` if there is no instance named or stored in
+    /// `slug`.
+    pub fn instance_dir(&self, slug: &str) -> Option<PathBuf> {
+        let config = self.find_instance(slug)?;
+        Some(self.config_dir(config))
+    }
+
+    /// The `subdir` folder of the instance named or stored in `slug`, which may not exist yet.
+    pub fn instance_subdir(&self, slug: &str, subdir: InstanceSubdir) -> Option<PathBuf> {
+        Some(subdir.path_in(&self.instance_dir(slug)?))
+    }
+
+    /// Every instance's name with its `subdir` folder.
+    pub fn instance_subdirs(&self, subdir: InstanceSubdir) -> Vec<(String, PathBuf)> {
+        self.get_instance_dirs()
+            .into_iter()
+            .map(|(name, instance_dir)| (name, subdir.path_in(&instance_dir)))
+            .collect()
+    }
+
+    /// `instance_name`, or the first of `instance_name (2)`, `instance_name (3)`... that isn't the
+    /// name of an instance yet.
+    pub fn available_instance_name(&self, instance_name: &str) -> String {
+        unique_instance_name(instance_name, |name| self.has_instance(name))
+    }
+
+    pub fn get_instance(&self, instance_name: &str) -> Option<&InstanceConfiguration> {
+        self.instance_map.get(instance_name)
+    }
+
+    pub fn get_instance_sync(&self, instance_name: &str) -> Option<InstanceSync> {
+        self.instance_map.get(instance_name)?.sync.clone()
+    }
+
+    /// Sets the instance's heap size and saves its `config.json`.
+    pub fn set_instance_memory(
+        &mut self,
+        instance_name: &str,
+        max_memory_mb: Option<u64>,
+    ) -> io::Result<()> {
+        if let Some(config) = self.instance_map.get_mut(instance_name) {
+            config.max_memory_mb = max_memory_mb;
+            let config = config.clone();
+            self.add_instance(config)?;
+        }
+        Ok(())
+    }
+
+    /// Sets whether the instance runs on the discrete GPU and saves its `config.json`.
+    pub fn set_instance_gpu_preference(
+        &mut self,
+        instance_name: &str,
+        prefer_discrete_gpu: bool,
+    ) -> io::Result<()> {
+        if let Some(config) = self.instance_map.get_mut(instance_name) {
+            config.prefer_discrete_gpu = prefer_discrete_gpu;
+            let config = config.clone();
+            self.add_instance(config)?;
+        }
+        if !prefer_discrete_gpu {
+            self.release_gpu_preference(instance_name);
+        }
+        Ok(())
+    }
+
+    /// Puts back the GPU preference registered for the instance's java on Windows.
+    fn release_gpu_preference(&mut self, instance_name: &str) {
+        if let Err(e) = self.gpu_preferences.release(instance_name) {
+            warn!("{}", e);
+        }
+    }
+
+    /// Sets whether and how the instance is watched for updates and saves its `config.json`.
+    pub fn set_instance_update_watch(
+        &mut self,
+        instance_name: &str,
+        update_watch: Option<UpdateWatch>,
+    ) -> io::Result<()> {
+        if let Some(config) = self.instance_map.get_mut(instance_name) {
+            config.update_watch = update_watch;
+            let config = config.clone();
+            self.add_instance(config)?;
+        }
+        Ok(())
+    }
+
+    /// Sets whether and how the instance is restarted when it crashes and saves its `config.json`.
+    pub fn set_instance_restart_policy(
+        &mut self,
+        instance_name: &str,
+        restart_on_crash: Option<RestartPolicy>,
+    ) -> io::Result<()> {
+        if let Some(config) = self.instance_map.get_mut(instance_name) {
+            config.restart_on_crash = restart_on_crash;
+            let config = config.clone();
+            self.add_instance(config)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces `from` with `to` in the instance's launch arguments, like the path of the game jar
+    /// in its classpath, and saves its `config.json`.
+    pub fn replace_in_arguments(
+        &mut self,
+        instance_name: &str,
+        from: &str,
+        to: &str,
+    ) -> io::Result<()> {
+        if let Some(config) = self.instance_map.get_mut(instance_name) {
+            config.replace_in_arguments(from, to);
+            let config = config.clone();
+            self.add_instance(config)?;
+        }
+        Ok(())
+    }
+
+    /// Sets which folder the instance is synced with and saves its `config.json`.
+    pub fn set_instance_sync(
+        &mut self,
+        instance_name: &str,
+        sync: Option<InstanceSync>,
+    ) -> io::Result<()> {
+        if let Some(config) = self.instance_map.get_mut(instance_name) {
+            config.sync = sync;
+            let config = config.clone();
+            self.add_instance(config)?;
+        }
+        Ok(())
+    }
+
     pub fn launch_instance(
         &mut self,
         instance_name: &str,
         active_account: &MinecraftAccount,
+        launcher_settings: &LauncherSettings,
         app_handle: AppHandle<Wry>,
-    ) {
-        debug!("Instance Name: {}", instance_name);
+    ) -> io::Result<()> {
+        self.spawn_instance(
+            instance_name,
+            active_account,
+            launcher_settings,
+            app_handle,
+            0,
+        )
+    }
+
+    /// Launches an instance that crashed again, `restarts` is how many times it was restarted since
+    /// the user launched it, including this one.
+    pub fn relaunch_crashed_instance(
+        &mut self,
+        instance_name: &str,
+        active_account: &MinecraftAccount,
+        launcher_settings: &LauncherSettings,
+        app_handle: AppHandle<Wry>,
+        restarts: u32,
+    ) -> io::Result<()> {
+        self.spawn_instance(
+            instance_name,
+            active_account,
+            launcher_settings,
+            app_handle,
+            restarts,
+        )
+    }
+
+    fn spawn_instance(
+        &mut self,
+        instance_name: &str,
+        active_account: &MinecraftAccount,
+        launcher_settings: &LauncherSettings,
+        app_handle: AppHandle<Wry>,
+        restarts: u32,
+    ) -> io::Result<()> {
+        debug_if!("LAUNCH", "Launching instance {}", instance_name);
         let instance_config = self.instance_map.get(instance_name);
         match instance_config {
             Some(instance) => {
-                let working_dir = self.instances_dir().join(instance_name);
-                let mut args: Vec<String> = Vec::new();
-                for argument in &instance.arguments {
-                    args.push(
-                        match substitute_account_specific_arguments(argument, active_account) {
-                            Some(arg) => arg,
-                            None => argument.into(),
-                        },
-                    );
+                let working_dir = self.config_dir(instance);
+                if let Some(sync) = &instance.sync {
+                    // Pull in changes made on other machines, a failed sync shouldn't stop the launch.
+                    if let Err(e) = sync_instance(&working_dir, instance_name, sync) {
+                        error!("Could not sync {} before launching: {}", instance_name, e);
+                    }
+                }
+                let mut args =
+                    launch_arguments(instance_name, instance, active_account, launcher_settings);
+                if command_line_length(&args) > MAX_COMMAND_LINE_LENGTH {
+                    // Argfiles were added in java 9.
+                    match java_major_version(&instance.jvm_path) {
+                        Some(major) if major >= 9 => {
+                            move_classpath_to_argfile(&mut args, &working_dir.join(CLASSPATH_ARGFILE))?;
+                        }
+                        _ => warn!(
+                            "The command line of {} is too long for some systems and its java can't read the classpath from a file",
+                            instance_name
+                        ),
+                    }
                 }
                 let mut command = Command::new(&instance.jvm_path);
                 command
-                    .current_dir(working_dir)
+                    .current_dir(&working_dir)
                     .args(args)
                     .stdout(Stdio::piped())
                     .stderr(Stdio::piped());
-                debug!("Command: {:#?}", command);
-                let child = command.spawn().expect("Could not spawn instance.");
+                if instance.prefer_discrete_gpu {
+                    command.envs(discrete_gpu_env());
+                    if let Err(e) = self
+                        .gpu_preferences
+                        .register(&instance.jvm_path, instance_name)
+                    {
+                        warn!("{}", e);
+                    }
+                }
+                debug_if!("LAUNCH", "Command: {:#?}", command);
+                let child = command.spawn()?;
 
                 let child_handle = Arc::new(Mutex::new(child));
-                self.tick_instance(instance_name.into(), child_handle.clone(), app_handle);
+                self.tick_instance(
+                    instance_name.into(),
+                    working_dir,
+                    child_handle.clone(),
+                    app_handle,
+                    restarts,
+                );
                 self.children.insert(instance_name.into(), child_handle);
-                debug!("After instance launch");
             }
             None => error!("Unknown instance name: {}", instance_name),
         }
+        Ok(())
+    }
+
+    /// The command `launch_instance` would start the instance with, with the account's access token
+    /// redacted. A classpath too long for the command line is left in place instead of being moved
+    /// to an argfile.
+    pub fn preview_launch_command(
+        &self,
+        instance_name: &str,
+        active_account: &MinecraftAccount,
+        launcher_settings: &LauncherSettings,
+    ) -> Option<LaunchCommand> {
+        let instance = self.instance_map.get(instance_name)?;
+        let redacted_account = MinecraftAccount {
+            minecraft_access_token: REDACTED_ACCESS_TOKEN.into(),
+            ..active_account.clone()
+        };
+        let arguments = launch_arguments(
+            instance_name,
+            instance,
+            &redacted_account,
+            launcher_settings,
+        );
+        Some(LaunchCommand {
+            working_dir: self.config_dir(instance),
+            command_line: quoted_command_line(&instance.jvm_path, &arguments),
+            jvm_path: instance.jvm_path.clone(),
+            arguments,
+        })
+    }
+
+    /// Launches the instance with its mods folder moved aside, restoring it once the game exits.
+    pub fn launch_instance_safe_mode(
+        &mut self,
+        instance_name: &str,
+        active_account: &MinecraftAccount,
+        launcher_settings: &LauncherSettings,
+        app_handle: AppHandle<Wry>,
+    ) -> io::Result<()> {
+        let instance_dir = self.instance_dir(instance_name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Unknown instance name: {}", instance_name),
+            )
+        })?;
+        disable_mods_for_safe_mode(&instance_dir)?;
+
+        let result =
+            self.launch_instance(instance_name, active_account, launcher_settings, app_handle);
+        if result.is_err() {
+            restore_safe_mode_mods(&instance_dir)?;
+        }
+        result
     }
 
     fn tick_instance(
         &mut self,
         instance_name: String,
+        instance_dir: PathBuf,
         child_handle: Arc<Mutex<Child>>,
         app_handle: AppHandle<Wry>,
+        restarts: u32,
     ) {
         let name = instance_name.clone();
+        let sync = self.get_instance_sync(&instance_name);
+        let restart_policy = self
+            .get_instance(&instance_name)
+            .and_then(|config| config.restart_on_crash.clone());
+        // Taken before launching, safe mode has already moved the mods aside by now.
+        let mut snapshot = self
+            .get_instance(&instance_name)
+            .map(|config| InstanceSnapshot::capture(config, &instance_dir));
+        let launched_at = SystemTime::now();
         let handle = tauri::async_runtime::spawn(async move {
             let mut child = child_handle.lock().await;
             let stdout = child
@@ -203,12 +864,21 @@ impl InstanceManager {
                 line: String,
             }
 
+            let mut crashed = false;
+            // Also sent to the instance's detached console, if it is open.
+            let console = SecondaryWindow::Console {
+                instance_name: &instance_name,
+            };
+
             // TODO: Emit an event to the screenshot store when a screenshot is taken. use notifier crate.
             loop {
                 tokio::select! {
                     result = stdout_reader.next_line() => {
                         match result {
                             Ok(Some(line)) => {
+                                if let Err(e) = console.emit(&app_handle, CONSOLE_LINE_EVENT, ConsoleLine { line: line.clone(), error: false }) {
+                                    error!("{}", e);
+                                }
                                 app_handle.emit("instance-logging", Logging { instance_name: instance_name.clone(), category: "Running".into(), line }).unwrap();
                             },
                             Err(_) => break,
@@ -217,7 +887,12 @@ impl InstanceManager {
                     }
                     result = stderr_reader.next_line() => {
                         match result {
-                            Ok(Some(line)) => debug!("Emit stderr line: {}", line),
+                            Ok(Some(line)) => {
+                                debug_if!("LAUNCH", "Emit stderr line: {}", line);
+                                if let Err(e) = console.emit(&app_handle, CONSOLE_LINE_EVENT, ConsoleLine { line, error: true }) {
+                                    error!("{}", e);
+                                }
+                            },
                             Err(_) => break,
                             _ => (),
                         }
@@ -226,12 +901,34 @@ impl InstanceManager {
                         match result {
                             Ok(exit_status) => {
                                 debug!("Child exited with exit code: {}", exit_status);
+                                if let Some(snapshot) = snapshot.take() {
+                                    match record_exit(&instance_dir, &instance_name, snapshot, exit_status.code(), launched_at) {
+                                        Ok(Some(_)) => {
+                                            notify(&app_handle, "Game crashed", &format!("{} crashed", instance_name)).await;
+                                            // Safe mode is for finding the cause of a crash, restarting it would hide it.
+                                            crashed = !instance_dir.join(SAFE_MODE_MODS_DIR).is_dir();
+                                        }
+                                        Ok(None) => {}
+                                        Err(e) => error!("Could not record how {} exited: {}", instance_name, e),
+                                    }
+                                }
+                                if let Err(e) = restore_safe_mode_mods(&instance_dir) {
+                                    error!("Could not restore mods after safe mode: {}", e);
+                                }
+                                if let Some(sync) = &sync {
+                                    if let Err(e) = sync_instance(&instance_dir, &instance_name, sync) {
+                                        error!("Could not sync {} after it exited: {}", instance_name, e);
+                                    }
+                                }
                                 #[derive(Serialize, Clone)]
                                 #[serde(rename_all = "camelCase")]
                                 struct ExitCode {
                                     instance_name: String,
                                     code: Option<i32>
                                 }
+                                if let Err(e) = console.emit(&app_handle, CONSOLE_EXIT_EVENT, exit_status.code()) {
+                                    error!("{}", e);
+                                }
                                 app_handle.emit("instance-exit", ExitCode {instance_name: instance_name.clone(), code: exit_status.code()}).unwrap();
                                 break;
                             },
@@ -240,7 +937,882 @@ impl InstanceManager {
                     }
                 };
             }
+            // Unlocked first so the restarted game can be seen as not running anymore.
+            drop(child);
+            InstanceManager::from_app_handle(&app_handle)
+                .await
+                .release_gpu_preference(&instance_name);
+            if let (true, Some(policy)) = (crashed, restart_policy) {
+                restart_crashed_instance(app_handle, instance_name, policy, restarts).await;
+            }
         });
         self.logging_threads.insert(name, handle);
     }
 }
+
+fn disable_mods_for_safe_mode(instance_dir: &Path) -> io::Result<()> {
+    let mods_dir = instance_dir.join("mods");
+    let disabled_dir = instance_dir.join(SAFE_MODE_MODS_DIR);
+    if mods_dir.is_dir() && !disabled_dir.exists() {
+        fs::rename(mods_dir, disabled_dir)?;
+    }
+    Ok(())
+}
+
+/// Moves the mods disabled by safe mode back, does nothing if the instance wasn't in safe mode.
+fn restore_safe_mode_mods(instance_dir: &Path) -> io::Result<()> {
+    let mods_dir = instance_dir.join("mods");
+    let disabled_dir = instance_dir.join(SAFE_MODE_MODS_DIR);
+    if !disabled_dir.is_dir() {
+        return Ok(());
+    }
+    if !mods_dir.exists() {
+        return fs::rename(disabled_dir, mods_dir);
+    }
+
+    // Modloaders create an empty mods folder when it's missing, merge back into it.
+    for entry in fs::read_dir(&disabled_dir)? {
+        let entry = entry?;
+        let target = mods_dir.join(entry.file_name());
+        if target.exists() {
+            warn!(
+                "Not restoring {}, it already exists in the mods folder",
+                entry.path().display()
+            );
+            continue;
+        }
+        fs::rename(entry.path(), target)?;
+    }
+    // Only removed once empty so nothing is lost if a file couldn't be moved back.
+    if let Err(e) = fs::remove_dir(&disabled_dir) {
+        warn!("Could not remove {}: {}", disabled_dir.display(), e);
+    }
+    Ok(())
+}
+
+/// The java command an instance is started with.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LaunchCommand {
+    pub working_dir: PathBuf,
+    pub jvm_path: PathBuf,
+    pub arguments: Vec<String>,
+    /// The java path and arguments as one line, quoted to be pasted into a shell.
+    pub command_line: String,
+}
+
+/// Cheap to display information about an instance's directory.
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+pub struct InstanceStats {
+    pub mod_count: usize,
+    pub resource_pack_count: usize,
+    pub shader_pack_count: usize,
+    pub world_count: usize,
+    pub size_on_disk: u64,
+    /// Seconds since the unix epoch the instance was last played, taken from `logs/latest.log`.
+    pub last_played: Option<u64>,
+}
+
+impl InstanceStats {
+    pub fn compute(instance_dir: &Path) -> Self {
+        Self {
+            mod_count: count_entries(&InstanceSubdir::Mods.path_in(instance_dir), |path| {
+                path.extension().is_some_and(|extension| extension == "jar")
+            }),
+            resource_pack_count: count_entries(
+                &InstanceSubdir::ResourcePacks.path_in(instance_dir),
+                |_| true,
+            ),
+            shader_pack_count: count_entries(&instance_dir.join("shaderpacks"), |_| true),
+            world_count: count_entries(&InstanceSubdir::Saves.path_in(instance_dir), |path| {
+                path.is_dir()
+            }),
+            size_on_disk: directory_size(instance_dir),
+            last_played: fs::metadata(
+                InstanceSubdir::Logs
+                    .path_in(instance_dir)
+                    .join("latest.log"),
+            )
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs()),
+        }
+    }
+
+    /// Whether the instance or one of the folders its stats are counted from was modified after
+    /// `computed_at`. Files changed deeper in the instance, like a world being played, aren't seen.
+    pub fn changed_since(instance_dir: &Path, computed_at: SystemTime) -> bool {
+        let dirs = [
+            instance_dir.to_path_buf(),
+            InstanceSubdir::Mods.path_in(instance_dir),
+            InstanceSubdir::ResourcePacks.path_in(instance_dir),
+            instance_dir.join("shaderpacks"),
+            InstanceSubdir::Saves.path_in(instance_dir),
+            InstanceSubdir::Logs.path_in(instance_dir),
+        ];
+        let modified = dirs
+            .iter()
+            .filter_map(|dir| {
+                fs::metadata(dir)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+            })
+            .max();
+        modified.map_or(true, |modified| modified > computed_at)
+    }
+}
+
+/// Counts the entries in `dir` matching `filter`, a missing directory has no entries.
+fn count_entries(dir: &Path, filter: impl Fn(&Path) -> bool) -> usize {
+    match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| filter(&entry.path()))
+            .count(),
+        Err(_) => 0,
+    }
+}
+
+/// Total size of all the files in `dir`, without following symlinks.
+fn directory_size(dir: &Path) -> u64 {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let metadata = entry.path().symlink_metadata().ok()?;
+            Some(if metadata.is_dir() {
+                directory_size(&entry.path())
+            } else {
+                metadata.len()
+            })
+        })
+        .sum()
+}
+
+/// The arguments java is started with for the instance, with the account and launcher settings
+/// substituted in.
+fn launch_arguments(
+    instance_name: &str,
+    instance: &InstanceConfiguration,
+    active_account: &MinecraftAccount,
+    launcher_settings: &LauncherSettings,
+) -> Vec<String> {
+    let mut args: Vec<String> = Vec::new();
+    if let Some(max_memory_mb) = instance.max_memory_mb {
+        args.push(format!("-Xmx{}M", max_memory_mb));
+    }
+    for argument in &instance.arguments {
+        args.push(
+            match substitute_account_specific_arguments(argument, active_account) {
+                Some(arg) => arg,
+                None => argument.into(),
+            },
+        );
+    }
+    args.append(&mut window_arguments(instance, launcher_settings));
+    // Instances created before the user type was substituted at launch have `mojang`.
+    set_argument_value(&mut args, "--userType", active_account.user_type());
+    if active_account.offline {
+        remove_argument(&mut args, "--xuid");
+        remove_argument(&mut args, "--clientId");
+    }
+    if launcher_settings.show_instance_name_in_game {
+        // Minecraft shows the version type after the version unless it is `release`.
+        set_argument_value(&mut args, "--versionType", instance_name);
+    }
+    let total_memory = total_memory_mb();
+    if let Some(requested) = clamp_max_heap(&mut args, total_memory) {
+        warn!(
+            "{} asks for {}MB of memory but only {}MB is installed, lowering it",
+            instance_name, requested, total_memory
+        );
+    }
+    args
+}
+
+/// `jvm_path` followed by `args`, quoting the ones a shell would split up.
+fn quoted_command_line(jvm_path: &Path, args: &[String]) -> String {
+    let quote = |arg: &str| {
+        if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || c == '"') {
+            format!("\"{}\"", arg.replace('"', "\\\""))
+        } else {
+            arg.into()
+        }
+    };
+    std::iter::once(quote(&jvm_path.to_string_lossy()))
+        .chain(args.iter().map(|arg| quote(arg)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Game arguments for the window size and fullscreen, falling back to the launcher defaults.
+fn window_arguments(instance: &InstanceConfiguration, settings: &LauncherSettings) -> Vec<String> {
+    let width = instance
+        .resolution_width
+        .unwrap_or(settings.default_resolution_width);
+    let height = instance
+        .resolution_height
+        .unwrap_or(settings.default_resolution_height);
+    let mut args = vec![
+        "--width".into(),
+        width.to_string(),
+        "--height".into(),
+        height.to_string(),
+    ];
+    if instance.fullscreen.unwrap_or(settings.default_fullscreen) {
+        args.push("--fullscreen".into());
+    }
+    args
+}
+
+/// Characters Windows doesn't allow in file names, the strictest of the supported systems.
+const RESERVED_CHARACTERS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+/// Device names Windows doesn't allow as file names, even with an extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// A folder name for the instance `instance_name` that is valid on every supported system and
+/// stays inside the instances folder.
+pub fn directory_slug(instance_name: &str) -> String {
+    let slug: String = instance_name
+        .chars()
+        .map(|c| {
+            if c.is_control() || RESERVED_CHARACTERS.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    // Windows drops trailing dots and spaces, leading dots would hide the folder or make it `..`.
+    let slug = slug.trim_matches(|c| c == '.' || c == ' ');
+    if slug.is_empty() {
+        return "instance".into();
+    }
+    let stem = slug.split('.').next().unwrap_or(slug);
+    if RESERVED_NAMES
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(stem))
+    {
+        return format!("_{}", slug);
+    }
+    slug.into()
+}
+
+/// The folder in `instances_dir` to store a new instance named `instance_name` in. Suffixed with
+/// ` (2)`, ` (3)`... if another instance is stored in it.
+pub fn available_directory_name(instances_dir: &Path, instance_name: &str) -> String {
+    unique_instance_name(&directory_slug(instance_name), |name| {
+        instances_dir.join(name).exists()
+    })
+}
+
+fn unique_instance_name(instance_name: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    if !is_taken(instance_name) {
+        return instance_name.into();
+    }
+    (2..)
+        .map(|n| format!("{} ({})", instance_name, n))
+        .find(|name| !is_taken(name))
+        .unwrap()
+}
+
+/// Replaces the value following `flag` in `args`. Versions that don't have the argument are left
+/// alone.
+fn set_argument_value(args: &mut [String], flag: &str, value: &str) {
+    if let Some(index) = args.iter().position(|arg| arg == flag) {
+        if let Some(argument) = args.get_mut(index + 1) {
+            *argument = value.into();
+        }
+    }
+}
+
+/// Removes `flag` and the value following it from `args`.
+fn remove_argument(args: &mut Vec<String>, flag: &str) {
+    if let Some(index) = args.iter().position(|arg| arg == flag) {
+        args.drain(index..(index + 2).min(args.len()));
+    }
+}
+
+fn command_line_length(args: &[String]) -> usize {
+    args.iter().map(|arg| arg.len() + 1).sum()
+}
+
+/// Writes the classpath following `-cp` in `args` to `argfile_path` and passes it to java as
+/// `@argfile` instead. Returns whether `args` had a classpath.
+fn move_classpath_to_argfile(args: &mut Vec<String>, argfile_path: &Path) -> io::Result<bool> {
+    let index = match args
+        .iter()
+        .position(|arg| arg == "-cp" || arg == "-classpath")
+    {
+        Some(index) if index + 1 < args.len() => index,
+        _ => return Ok(false),
+    };
+    let classpath = args.remove(index + 1);
+    // Backslashes escape inside the quotes of an argfile, so windows paths need them doubled.
+    let escaped = classpath.replace('\\', "\\\\").replace('"', "\\\"");
+    fs::write(argfile_path, format!("{} \"{}\"\n", args[index], escaped))?;
+    args[index] = format!("@{}", argfile_path.display());
+    Ok(true)
+}
+
+/// Parses a JVM memory size like `4G`, `512m` or `1048576k` into megabytes.
+fn parse_memory_mb(size: &str) -> Option<u64> {
+    let (number, unit) = match size.char_indices().last()? {
+        (index, unit) if unit.is_ascii_alphabetic() => (&size[..index], unit),
+        _ => (size, 'b'),
+    };
+    let number: u64 = number.parse().ok()?;
+    match unit.to_ascii_lowercase() {
+        'b' => Some(number / (1024 * 1024)),
+        'k' => Some(number / 1024),
+        'm' => Some(number),
+        'g' => Some(number * 1024),
+        't' => Some(number * 1024 * 1024),
+        _ => None,
+    }
+}
+
+/// Lowers the `-Xmx` in `args` to `limit_mb` if it asks for more, returning the amount it asked
+/// for. Does nothing if the limit is unknown, i.e. `0`.
+fn clamp_max_heap(args: &mut [String], limit_mb: u64) -> Option<u64> {
+    if limit_mb == 0 {
+        return None;
+    }
+    let mut requested = None;
+    for arg in args.iter_mut() {
+        // Older instances passed their additional jvm arguments as a single argument.
+        let tokens: Vec<String> = arg
+            .split_whitespace()
+            .map(
+                |token| match token.strip_prefix("-Xmx").and_then(parse_memory_mb) {
+                    Some(size) if size > limit_mb => {
+                        requested = Some(size);
+                        format!("-Xmx{}M", limit_mb)
+                    }
+                    _ => token.into(),
+                },
+            )
+            .collect();
+        if requested.is_some() {
+            *arg = tokens.join(" ");
+            break;
+        }
+    }
+    requested
+}
+
+#[test]
+fn test_window_arguments_fall_back_to_defaults() {
+    let mut instance = InstanceConfiguration {
+        instance_name: "Test".into(),
+        jvm_path: PathBuf::from("java"),
+        arguments: Vec::new(),
+        modloader_type: ModloaderType::None,
+        modloader_version: "1.20.1".into(),
+        author: "You".into(),
+        instance_icon: None,
+        playtime: 0,
+        resolution_width: None,
+        resolution_height: None,
+        fullscreen: None,
+        vanilla_version: None,
+        sync: None,
+        max_memory_mb: None,
+        prefer_discrete_gpu: false,
+        additional_jvm_arguments: Vec::new(),
+        asset_index: None,
+        directory_name: String::new(),
+        data_root: None,
+        isolated_resources: false,
+        update_watch: None,
+        restart_on_crash: None,
+    };
+    let settings = LauncherSettings {
+        default_resolution_width: 1280,
+        default_resolution_height: 720,
+        default_fullscreen: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        window_arguments(&instance, &settings),
+        vec!["--width", "1280", "--height", "720", "--fullscreen"]
+    );
+
+    instance.resolution_width = Some(1920);
+    instance.resolution_height = Some(1080);
+    instance.fullscreen = Some(false);
+    assert_eq!(
+        window_arguments(&instance, &settings),
+        vec!["--width", "1920", "--height", "1080"]
+    );
+}
+
+#[test]
+fn test_set_argument_value() {
+    let mut args: Vec<String> = ["--version", "1.20.1", "--versionType", "release", "--width"]
+        .map(String::from)
+        .to_vec();
+    set_argument_value(&mut args, "--versionType", "Fabulously Optimized");
+    assert_eq!(
+        args,
+        [
+            "--version",
+            "1.20.1",
+            "--versionType",
+            "Fabulously Optimized",
+            "--width"
+        ]
+    );
+
+    let mut args = vec!["--versionType".to_owned()];
+    set_argument_value(&mut args, "--versionType", "Fabulously Optimized");
+    assert_eq!(args, ["--versionType"]);
+}
+
+#[test]
+fn test_unique_instance_name() {
+    let taken = ["MyPack", "MyPack (2)", "Other (2)"];
+    let is_taken = |name: &str| taken.contains(&name);
+    assert_eq!(unique_instance_name("MyPack", is_taken), "MyPack (3)");
+    assert_eq!(unique_instance_name("Other", is_taken), "Other");
+    assert_eq!(unique_instance_name("New", is_taken), "New");
+}
+
+#[test]
+fn test_directory_slug() {
+    assert_eq!(directory_slug("Fabric 1.20.1"), "Fabric 1.20.1");
+    assert_eq!(directory_slug("Mods: A/B?"), "Mods_ A_B_");
+    assert_eq!(directory_slug("../.."), "_");
+    assert_eq!(directory_slug(".."), "instance");
+    assert_eq!(directory_slug(" Pack. "), "Pack");
+    assert_eq!(directory_slug("con"), "_con");
+    assert_eq!(directory_slug("Aux.old"), "_Aux.old");
+    assert_eq!(directory_slug("Console"), "Console");
+}
+
+#[test]
+fn test_legacy_instances_keep_their_folder() {
+    let tmp_dir = tempdir::TempDir::new("instances").unwrap();
+    let instance_dir = tmp_dir.path().join("instances").join("Renamed Folder");
+    fs::create_dir_all(&instance_dir).unwrap();
+    fs::write(
+        instance_dir.join("config.json"),
+        r#"{"instance_name":"Pack","jvm_path":"java","arguments":[],"modloader_type":"None",
+            "modloader_version":"1.20.1","author":"You","instance_icon":null,"playtime":0,
+            "resolution_width":null,"resolution_height":null,"fullscreen":null,
+            "vanilla_version":"1.20.1","sync":null,"max_memory_mb":null}"#,
+    )
+    .unwrap();
+
+    let mut instance_manager = InstanceManager::new(ResourceManager::new(tmp_dir.path()));
+    instance_manager.deserialize_instances();
+    assert_eq!(
+        instance_manager.instance_dir("Pack"),
+        Some(instance_dir.clone())
+    );
+    // It can be looked up by its folder too.
+    assert_eq!(
+        instance_manager.instance_subdir("Renamed Folder", InstanceSubdir::Logs),
+        Some(instance_dir.join("logs"))
+    );
+    // The folder is saved so the config doesn't have to be migrated again.
+    let config: InstanceConfiguration =
+        serde_json::from_slice(&fs::read(instance_dir.join("config.json")).unwrap()).unwrap();
+    assert_eq!(config.directory_name, "Renamed Folder");
+}
+
+#[test]
+fn test_vanilla_version_of_older_instances() {
+    let mut config: InstanceConfiguration = serde_json::from_str(
+        r#"{"instance_name":"Pack","jvm_path":"java","arguments":["--version","1.20.1"],
+            "modloader_type":"Fabric","modloader_version":"0.14.21","author":"You",
+            "instance_icon":null,"playtime":0,"resolution_width":null,"resolution_height":null,
+            "fullscreen":null,"sync":null,"max_memory_mb":null}"#,
+    )
+    .unwrap();
+    assert_eq!(config.vanilla_version(), Some("1.20.1"));
+    config.arguments.clear();
+    assert_eq!(config.vanilla_version(), None);
+
+    config.modloader_type = ModloaderType::Forge;
+    config.modloader_version = "1.12.2-14.23.5.2860".into();
+    assert_eq!(config.vanilla_version(), Some("1.12.2"));
+}
+
+#[test]
+fn test_instances_in_data_roots() {
+    let tmp_dir = tempdir::TempDir::new("data_roots").unwrap();
+    let archive_dir = tmp_dir.path().join("archive");
+    let instance_dir = archive_dir.join("instances").join("Old");
+    fs::create_dir_all(&instance_dir).unwrap();
+    fs::write(
+        instance_dir.join("config.json"),
+        r#"{"instance_name":"Old","jvm_path":"java","arguments":[],"modloader_type":"None",
+            "modloader_version":"1.7.10","author":"You","instance_icon":null,"playtime":0,
+            "resolution_width":null,"resolution_height":null,"fullscreen":null,
+            "vanilla_version":"1.7.10","sync":null,"max_memory_mb":null,"directory_name":"Old"}"#,
+    )
+    .unwrap();
+
+    let resources = ResourceManager::new(&tmp_dir.path().join("app"));
+    let mut instance_manager = InstanceManager::new(resources.clone());
+    resources.set_data_roots(vec![crate::data_roots::DataRoot {
+        name: "HDD archive".into(),
+        path: archive_dir,
+    }]);
+    instance_manager.deserialize_instances();
+    assert_eq!(instance_manager.instance_dir("Old"), Some(instance_dir));
+    assert_eq!(
+        instance_manager
+            .get_instance("Old")
+            .unwrap()
+            .data_root
+            .as_deref(),
+        Some("HDD archive")
+    );
+
+    resources.set_data_roots(Vec::new());
+    instance_manager.deserialize_instances();
+    assert!(!instance_manager.has_instance("Old"));
+}
+
+#[test]
+fn test_moved_instance_launches_from_its_new_folder() {
+    let tmp_dir = tempdir::TempDir::new("move_instance").unwrap();
+    let archive_dir = tmp_dir.path().join("archive");
+    let resources = ResourceManager::new(&tmp_dir.path().join("app"));
+    let mut instance_manager = InstanceManager::new(resources.clone());
+    resources.set_data_roots(vec![crate::data_roots::DataRoot {
+        name: "HDD archive".into(),
+        path: archive_dir.clone(),
+    }]);
+
+    let from = resources.instances_dir().join("Pack");
+    let to = archive_dir.join("instances").join("Pack");
+    fs::create_dir_all(&from).unwrap();
+    let mut config = InstanceConfiguration {
+        instance_name: "Pack".into(),
+        jvm_path: PathBuf::from("java"),
+        arguments: vec![
+            format!("-Djava.library.path={}", from.join("natives").display()),
+            "-cp".into(),
+            format!(
+                "{}{}{}",
+                resources.libraries_dir().join("lwjgl.jar").display(),
+                if cfg!(windows) { ";" } else { ":" },
+                from.join("bin").join("minecraft.jar").display()
+            ),
+            "--gameDir".into(),
+            from.display().to_string(),
+        ],
+        modloader_type: ModloaderType::None,
+        modloader_version: "1.20.1".into(),
+        author: "You".into(),
+        instance_icon: None,
+        playtime: 0,
+        resolution_width: None,
+        resolution_height: None,
+        fullscreen: None,
+        vanilla_version: Some("1.20.1".into()),
+        sync: None,
+        max_memory_mb: None,
+        prefer_discrete_gpu: false,
+        additional_jvm_arguments: Vec::new(),
+        asset_index: None,
+        directory_name: "Pack".into(),
+        data_root: None,
+        isolated_resources: false,
+        update_watch: None,
+        restart_on_crash: None,
+    };
+    instance_manager.add_instance(config.clone()).unwrap();
+
+    crate::data_roots::move_dir_with_progress(&from, &to, |_| {}).unwrap();
+    config.replace_in_arguments(&from.to_string_lossy(), &to.to_string_lossy());
+    config.data_root = Some("HDD archive".into());
+    instance_manager.add_instance(config).unwrap();
+    instance_manager.deserialize_instances();
+
+    let args = launch_arguments(
+        "Pack",
+        instance_manager.get_instance("Pack").unwrap(),
+        &MinecraftAccount::default(),
+        &LauncherSettings::default(),
+    );
+    assert!(args.contains(&format!(
+        "-Djava.library.path={}",
+        to.join("natives").display()
+    )));
+    assert!(args.contains(&to.display().to_string()));
+    assert!(args
+        .iter()
+        .any(|arg| arg.contains(&resources.libraries_dir().display().to_string())));
+    let from = from.display().to_string();
+    assert!(!args.iter().any(|arg| arg.contains(&from)));
+}
+
+#[test]
+fn test_preview_launch_command_redacts_the_access_token() {
+    let tmp_dir = tempdir::TempDir::new("preview_launch").unwrap();
+    let instance_manager = InstanceManager::new(ResourceManager::new(tmp_dir.path()));
+    instance_manager
+        .add_instance(InstanceConfiguration {
+            instance_name: "Vanilla".into(),
+            jvm_path: PathBuf::from("java"),
+            arguments: vec![
+                "net.minecraft.client.main.Main".into(),
+                "--username".into(),
+                "${auth_player_name}".into(),
+                "--accessToken".into(),
+                "${auth_access_token}".into(),
+            ],
+            modloader_type: ModloaderType::None,
+            modloader_version: "1.20.1".into(),
+            author: "You".into(),
+            instance_icon: None,
+            playtime: 0,
+            resolution_width: None,
+            resolution_height: None,
+            fullscreen: None,
+            vanilla_version: Some("1.20.1".into()),
+            sync: None,
+            max_memory_mb: None,
+            prefer_discrete_gpu: false,
+            additional_jvm_arguments: Vec::new(),
+            asset_index: None,
+            directory_name: "Vanilla".into(),
+            data_root: None,
+            isolated_resources: false,
+            update_watch: None,
+            restart_on_crash: None,
+        })
+        .unwrap();
+    let account = MinecraftAccount {
+        name: "Player".into(),
+        minecraft_access_token: "eyJhbGciOiJIUzI1NiJ9.secret".into(),
+        ..Default::default()
+    };
+
+    let command = instance_manager
+        .preview_launch_command("Vanilla", &account, &LauncherSettings::default())
+        .unwrap();
+    assert!(command.arguments.contains(&"Player".to_owned()));
+    assert!(command
+        .arguments
+        .contains(&REDACTED_ACCESS_TOKEN.to_owned()));
+    assert!(!command
+        .arguments
+        .iter()
+        .any(|arg| arg.contains("eyJhbGciOiJIUzI1NiJ9")));
+    assert!(!command.command_line.contains("eyJhbGciOiJIUzI1NiJ9"));
+}
+
+#[test]
+fn test_instance_stats() {
+    let tmp_dir = tempdir::TempDir::new("stats").unwrap();
+    let instance_dir = tmp_dir.path();
+    fs::create_dir_all(instance_dir.join("mods")).unwrap();
+    fs::write(instance_dir.join("mods").join("a.jar"), [0; 10]).unwrap();
+    fs::write(instance_dir.join("mods").join("b.jar.disabled"), [0; 5]).unwrap();
+    fs::create_dir_all(instance_dir.join("saves").join("New World")).unwrap();
+    fs::write(instance_dir.join("saves").join("level.dat_old"), [0; 1]).unwrap();
+    fs::create_dir_all(instance_dir.join("logs")).unwrap();
+    fs::write(instance_dir.join("logs").join("latest.log"), "").unwrap();
+
+    let stats = InstanceStats::compute(instance_dir);
+    assert_eq!(stats.mod_count, 1);
+    assert_eq!(stats.world_count, 1);
+    assert_eq!(stats.resource_pack_count, 0);
+    assert_eq!(stats.shader_pack_count, 0);
+    assert_eq!(stats.size_on_disk, 16);
+    assert!(stats.last_played.is_some());
+
+    assert!(InstanceStats::changed_since(instance_dir, UNIX_EPOCH));
+    let later = SystemTime::now() + std::time::Duration::from_secs(60);
+    assert!(!InstanceStats::changed_since(instance_dir, later));
+    assert!(InstanceStats::changed_since(
+        &instance_dir.join("missing"),
+        later
+    ));
+}
+
+#[test]
+fn test_safe_mode_mods_are_restored() {
+    let tmp_dir = tempdir::TempDir::new("safe_mode").unwrap();
+    let instance_dir = tmp_dir.path();
+    fs::create_dir_all(instance_dir.join("mods")).unwrap();
+    fs::write(instance_dir.join("mods").join("a.jar"), "").unwrap();
+
+    disable_mods_for_safe_mode(instance_dir).unwrap();
+    assert!(!instance_dir.join("mods").exists());
+    assert!(instance_dir
+        .join(SAFE_MODE_MODS_DIR)
+        .join("a.jar")
+        .is_file());
+
+    // The modloader recreated an empty mods folder while running.
+    fs::create_dir_all(instance_dir.join("mods")).unwrap();
+    restore_safe_mode_mods(instance_dir).unwrap();
+    assert!(instance_dir.join("mods").join("a.jar").is_file());
+    assert!(!instance_dir.join(SAFE_MODE_MODS_DIR).exists());
+
+    // Restoring an instance that isn't in safe mode does nothing.
+    restore_safe_mode_mods(instance_dir).unwrap();
+    assert!(instance_dir.join("mods").join("a.jar").is_file());
+}
+
+#[test]
+fn test_move_classpath_to_argfile() {
+    let tmp_dir = tempdir::TempDir::new("argfile").unwrap();
+    let argfile_path = tmp_dir.path().join(CLASSPATH_ARGFILE);
+    let mut args: Vec<String> = [
+        "-Xmx4G",
+        "-cp",
+        r"C:\libraries\a.jar;C:\libraries\b.jar",
+        "Main",
+    ]
+    .map(String::from)
+    .to_vec();
+    assert!(move_classpath_to_argfile(&mut args, &argfile_path).unwrap());
+    assert_eq!(
+        args,
+        [
+            "-Xmx4G".into(),
+            format!("@{}", argfile_path.display()),
+            "Main".into()
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(&argfile_path).unwrap(),
+        "-cp \"C:\\\\libraries\\\\a.jar;C:\\\\libraries\\\\b.jar\"\n"
+    );
+
+    let mut args = vec!["Main".to_owned()];
+    assert!(!move_classpath_to_argfile(&mut args, &argfile_path).unwrap());
+}
+
+#[test]
+fn test_clamp_max_heap() {
+    assert_eq!(parse_memory_mb("4G"), Some(4096));
+    assert_eq!(parse_memory_mb("512m"), Some(512));
+    assert_eq!(parse_memory_mb("1048576k"), Some(1024));
+    assert_eq!(parse_memory_mb("2147483648"), Some(2048));
+    assert_eq!(parse_memory_mb("4X"), None);
+
+    let mut args = vec!["-Xms1G -Xmx16G -XX:+UseG1GC".to_string(), "-cp".into()];
+    assert_eq!(clamp_max_heap(&mut args, 8192), Some(16384));
+    assert_eq!(args[0], "-Xms1G -Xmx8192M -XX:+UseG1GC");
+
+    let mut args = vec!["-Xmx4G".to_string()];
+    assert_eq!(clamp_max_heap(&mut args, 8192), None);
+    assert_eq!(clamp_max_heap(&mut args, 0), None);
+    assert_eq!(args[0], "-Xmx4G");
+}
+
+#[test]
+fn test_parse_jvm_arguments() {
+    assert_eq!(
+        parse_jvm_arguments("  -XX:+UseG1GC   -Dfoo=\"a b\" '-Dpath=C:\\Program Files\\x' ")
+            .unwrap(),
+        ["-XX:+UseG1GC", "-Dfoo=a b", r"-Dpath=C:\Program Files\x"]
+    );
+    assert!(parse_jvm_arguments("").unwrap().is_empty());
+    assert_eq!(
+        parse_jvm_arguments("-Dfoo=\"a b"),
+        Err(JvmArgumentError::UnterminatedQuote)
+    );
+    assert_eq!(
+        parse_jvm_arguments("-Xmx4G UseG1GC"),
+        Err(JvmArgumentError::NotAnOption("UseG1GC".into()))
+    );
+    assert_eq!(
+        parse_jvm_arguments("-Dfoo= \"\""),
+        Err(JvmArgumentError::NotAnOption(String::new()))
+    );
+}
+
+#[test]
+fn test_legacy_jvm_arguments_are_split() {
+    let mut config: InstanceConfiguration = serde_json::from_str(
+        r#"{"instance_name":"Old","jvm_path":"java",
+            "arguments":["-Xmx2G -Dfoo=\"a b\"","-cp","a.jar","Main"],
+            "modloader_type":"None","modloader_version":"1.20.1","author":"You",
+            "instance_icon":null,"playtime":0,"resolution_width":null,
+            "resolution_height":null,"fullscreen":null,"vanilla_version":"1.20.1","sync":null,
+            "max_memory_mb":null,"additional_jvm_arguments":"-Xmx2G -Dfoo=\"a b\""}"#,
+    )
+    .unwrap();
+    assert_eq!(config.additional_jvm_arguments, ["-Xmx2G", "-Dfoo=a b"]);
+    config.split_legacy_jvm_arguments();
+    assert_eq!(
+        config.arguments,
+        ["-Xmx2G", "-Dfoo=a b", "-cp", "a.jar", "Main"]
+    );
+
+    // Already split arguments are left alone.
+    config.split_legacy_jvm_arguments();
+    assert_eq!(config.arguments.len(), 5);
+
+    // Configs from before the additional jvm arguments were saved on their own.
+    let mut config: InstanceConfiguration = serde_json::from_str(
+        r#"{"instance_name":"Old","jvm_path":"java",
+            "arguments":["-Xmx4G -XX:+UseG1GC","-Djava.library.path=/My Pack/natives","-cp",
+                "a.jar","Main"],
+            "modloader_type":"None","modloader_version":"1.20.1","author":"You",
+            "instance_icon":null,"playtime":0}"#,
+    )
+    .unwrap();
+    config.split_legacy_jvm_arguments();
+    assert_eq!(
+        config.arguments,
+        [
+            "-Xmx4G",
+            "-XX:+UseG1GC",
+            "-Djava.library.path=/My Pack/natives",
+            "-cp",
+            "a.jar",
+            "Main"
+        ]
+    );
+    assert_eq!(config.additional_jvm_arguments, ["-Xmx4G", "-XX:+UseG1GC"]);
+
+    let mut config: InstanceConfiguration = serde_json::from_str(
+        r#"{"instance_name":"Old","jvm_path":"java",
+            "arguments":["-Djava.library.path=/My Pack/natives","-cp","a.jar","Main"],
+            "modloader_type":"None","modloader_version":"1.20.1","author":"You",
+            "instance_icon":null,"playtime":0}"#,
+    )
+    .unwrap();
+    config.split_legacy_jvm_arguments();
+    assert_eq!(config.arguments.len(), 4);
+}
+
+#[test]
+fn test_quoted_command_line() {
+    let args: Vec<String> = ["-Xmx4G", "--gameDir", "/home/me/My Instance", ""]
+        .map(String::from)
+        .to_vec();
+    assert_eq!(
+        quoted_command_line(Path::new("/usr/bin/java"), &args),
+        r#"/usr/bin/java -Xmx4G --gameDir "/home/me/My Instance" """#
+    );
+}
+
+#[test]
+fn test_remove_argument() {
+    let mut args: Vec<String> = ["--xuid", "", "--clientId", "", "--version", "1.20.1"]
+        .map(String::from)
+        .to_vec();
+    remove_argument(&mut args, "--xuid");
+    remove_argument(&mut args, "--demo");
+    assert_eq!(args, ["--clientId", "", "--version", "1.20.1"]);
+    args.truncate(1);
+    remove_argument(&mut args, "--clientId");
+    assert!(args.is_empty());
+}
@@ -1,34 +1,43 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    fmt,
     fs::{self, File},
     io::{self, BufReader, Write},
     path::{Path, PathBuf},
     string::FromUtf8Error,
-    sync::Arc,
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::{Duration, SystemTime},
 };
 
-use bytes::Bytes;
-use log::info;
-use serde::Serialize;
-use tauri::async_runtime::Mutex;
+use autmc_core::{consts::FABRIC_BASE_URL, CoreError};
+use autmc_downloader::{
+    download_bytes_from_url, download_validated_file, send_with_retry, DownloadError,
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Wry};
+use tempdir::TempDir;
+use tokio::sync::{Mutex, MutexGuard};
 use zip::result::ZipError;
 
 use crate::{
     commands::VersionEntry,
-    consts::{FABRIC_BASE_URL, FORGE_MANIFEST_URL, VANILLA_MANIFEST_URL},
+    consts::{FORGE_MANIFEST_URL, VANILLA_MANIFEST_URL},
+    data_roots::DataRoot,
+    system_info::JavaCheckError,
     web_services::{
-        downloader::{
-            download_bytes_from_url, validate_file_hash, validate_hash_sha1, DownloadError,
-        },
+        custom_version::read_custom_version,
         manifest::{
             fabric::FabricLoaderManifest,
-            forge::ForgeManifest,
-            vanilla::{VanillaManifest, VanillaManifestVersion, VanillaVersion},
+            forge::{ForgeManifest, ForgePatchError},
+            patches::{PatchTarget, VersionPatches},
+            vanilla::{JarType, VanillaManifest, VanillaManifestVersion, VanillaVersion},
         },
+        mod_cache::ModCache,
     },
 };
 
-use super::{InnerState, ManagerFromAppHandle};
+use super::instance_manager::JvmArgumentError;
 
 pub type ManifestResult<T> = Result<T, ManifestError>;
 
@@ -42,6 +51,31 @@ pub enum ManifestError {
     ResourceError(String),
     MismatchedFileHash(String),
     FileExtractionError(ZipError),
+    ForgePatchError(ForgePatchError),
+    InvalidJvmArguments(JvmArgumentError),
+    InstanceExists(String),
+    BrokenJava(JavaCheckError),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::HttpError(error) => write!(f, "{}", error),
+            ManifestError::SerializationFilesystemError(error) => write!(f, "{}", error),
+            ManifestError::Utf8DeserializationError(error) => write!(f, "{}", error),
+            ManifestError::JsonSerializationError(error) => write!(f, "{}", error),
+            ManifestError::VersionRetrievalError(error) => f.write_str(error),
+            ManifestError::ResourceError(error) => f.write_str(error),
+            ManifestError::MismatchedFileHash(error) => f.write_str(error),
+            ManifestError::FileExtractionError(error) => write!(f, "{}", error),
+            ManifestError::ForgePatchError(error) => write!(f, "{}", error),
+            ManifestError::InvalidJvmArguments(error) => write!(f, "{}", error),
+            ManifestError::InstanceExists(instance_name) => {
+                write!(f, "There already is an instance named {}", instance_name)
+            }
+            ManifestError::BrokenJava(error) => write!(f, "{}", error),
+        }
+    }
 }
 
 impl Serialize for ManifestError {
@@ -49,24 +83,7 @@ impl Serialize for ManifestError {
     where
         S: serde::Serializer,
     {
-        match &self {
-            ManifestError::HttpError(error) => serializer.serialize_str(&error.to_string()),
-            ManifestError::SerializationFilesystemError(error) => {
-                serializer.serialize_str(&error.to_string())
-            }
-            ManifestError::Utf8DeserializationError(error) => {
-                serializer.serialize_str(&error.to_string())
-            }
-            ManifestError::JsonSerializationError(error) => {
-                serializer.serialize_str(&error.to_string())
-            }
-            ManifestError::VersionRetrievalError(error) => serializer.serialize_str(error),
-            ManifestError::ResourceError(error) => serializer.serialize_str(error),
-            ManifestError::MismatchedFileHash(error) => serializer.serialize_str(error),
-            ManifestError::FileExtractionError(error) => {
-                serializer.serialize_str(&error.to_string())
-            }
-        }
+        serializer.serialize_str(&self.to_string())
     }
 }
 
@@ -104,49 +121,162 @@ impl From<DownloadError> for ManifestError {
     }
 }
 
+impl From<CoreError> for ManifestError {
+    fn from(error: CoreError) -> Self {
+        match error {
+            CoreError::Download(e) => e.into(),
+            CoreError::Io(e) => ManifestError::SerializationFilesystemError(e),
+            CoreError::Utf8(e) => ManifestError::Utf8DeserializationError(e),
+            CoreError::Json(e) => ManifestError::JsonSerializationError(e),
+            CoreError::Zip(e) => ManifestError::FileExtractionError(e),
+            CoreError::MismatchedFileHash(e) => ManifestError::MismatchedFileHash(e),
+        }
+    }
+}
+
+impl From<ForgePatchError> for ManifestError {
+    fn from(error: ForgePatchError) -> Self {
+        ManifestError::ForgePatchError(error)
+    }
+}
+
+impl From<JvmArgumentError> for ManifestError {
+    fn from(error: JvmArgumentError) -> Self {
+        ManifestError::InvalidJvmArguments(error)
+    }
+}
+
+impl From<JavaCheckError> for ManifestError {
+    fn from(error: JavaCheckError) -> Self {
+        ManifestError::BrokenJava(error)
+    }
+}
+
 impl From<ZipError> for ManifestError {
     fn from(error: ZipError) -> Self {
         ManifestError::FileExtractionError(error)
     }
 }
 
-pub struct ResourceState(pub Arc<Mutex<ResourceManager>>);
+/// File in the instance directory the [ManifestSnapshot] is saved to.
+const MANIFEST_SNAPSHOT_FILE: &str = "manifest_snapshot.json";
+
+/// The manifest entries an instance was created from. Repairing or verifying the instance later
+/// uses these instead of the current upstream manifests, which can change.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestSnapshot {
+    pub created_at: String,
+    pub vanilla: PinnedVanillaVersion,
+    pub modloader: Option<PinnedModloader>,
+}
 
-impl InnerState<Arc<Mutex<ResourceManager>>> for ResourceState {
-    fn inner_state(&self) -> Arc<Mutex<ResourceManager>> {
-        self.0.clone()
-    }
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinnedVanillaVersion {
+    pub id: String,
+    pub url: String,
+    pub sha1: String,
+    pub release_time: String,
 }
 
-impl ManagerFromAppHandle for ResourceManager {
-    type State = ResourceState;
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PinnedModloader {
+    #[serde(rename_all = "camelCase")]
+    Fabric { loader_version: String },
+    /// Forge publishes md5 hashes for its installers.
+    #[serde(rename_all = "camelCase")]
+    Forge {
+        forge_version: String,
+        installer_md5: String,
+    },
 }
 
-impl ResourceState {
-    pub fn new(app_dir: &Path) -> Self {
-        Self(Arc::new(Mutex::new(ResourceManager::new(app_dir))))
+impl ManifestSnapshot {
+    pub fn load(instance_dir: &Path) -> ManifestResult<Option<Self>> {
+        let path = instance_dir.join(MANIFEST_SNAPSHOT_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let reader = BufReader::new(File::open(path)?);
+        Ok(Some(serde_json::from_reader(reader)?))
+    }
+
+    pub fn save(&self, instance_dir: &Path) -> ManifestResult<()> {
+        let json = serde_json::to_string(self)?;
+        let mut file = File::create(instance_dir.join(MANIFEST_SNAPSHOT_FILE))?;
+        file.write_all(json.as_bytes())?;
+        Ok(())
     }
 }
 
-#[derive(Debug)]
+/// Unlike the other states, the resource manager isn't behind a lock. Installs hold on to it for
+/// minutes, so each command gets its own handle sharing the cached manifests.
+pub struct ResourceState(pub ResourceManager);
+
+/// The manifests are only written when they are downloaded, everything else reads them.
+#[derive(Debug, Default)]
+struct Manifests {
+    vanilla: Option<Arc<VanillaManifest>>,
+    /// Release versions of `vanilla`, newest first.
+    vanilla_releases: Option<Arc<Vec<String>>>,
+    forge: Option<Arc<ForgeManifest>>,
+    fabric: Option<Arc<FabricLoaderManifest>>,
+}
+
+#[derive(Debug, Clone)]
 pub struct ResourceManager {
     app_dir: PathBuf,
-    // FIXME: On instantiation of the resource manager, get all manifests so theres no options.
-    vanilla_manifest: Option<VanillaManifest>,
-    forge_manifest: Option<ForgeManifest>,
-    fabric_manifest: Option<FabricLoaderManifest>,
+    manifests: Arc<RwLock<Manifests>>,
+    data_roots: Arc<RwLock<Vec<DataRoot>>>,
+    /// Held for a whole install. Installs write to the shared libraries, assets and versions
+    /// folders, where a file being downloaded already looks present to another install.
+    installs: Arc<Mutex<()>>,
 }
 
 impl ResourceManager {
     pub fn new(app_dir: &Path) -> Self {
+        // Nothing is using them yet, these were left behind by a launcher that was closed mid
+        // install.
+        if let Err(e) = remove_dir_contents(&app_dir.join("tmp")) {
+            warn!("Could not remove old temporary files: {}", e);
+        }
         Self {
             app_dir: app_dir.into(),
-            vanilla_manifest: None,
-            forge_manifest: None,
-            fabric_manifest: None,
+            manifests: Arc::default(),
+            data_roots: Arc::default(),
+            installs: Arc::default(),
         }
     }
 
+    pub fn from_app_handle(app_handle: &AppHandle<Wry>) -> Self {
+        let state: tauri::State<ResourceState> = app_handle
+            .try_state()
+            .expect("This state should already be managed.");
+        state.0.clone()
+    }
+
+    // The manifests are only ever replaced as a whole, so a panic while holding the lock can't
+    // leave them half written.
+    fn read_manifests(&self) -> RwLockReadGuard<'_, Manifests> {
+        self.manifests
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_manifests(&self) -> RwLockWriteGuard<'_, Manifests> {
+        self.manifests
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Waits for the running install to finish, if any, and keeps others from starting until the
+    /// guard is dropped.
+    pub async fn lock_installs(&self) -> MutexGuard<'_, ()> {
+        self.installs.lock().await
+    }
+
     /// Returns the version directory at ${app_dir}/versions
     pub fn version_dir(&self) -> PathBuf {
         self.app_dir.join("versions")
@@ -162,9 +292,24 @@ impl ResourceManager {
         self.app_dir.join("assets")
     }
 
-    /// Returns the objects directory at ${app_dir}/assets/objects
-    pub fn asset_objects_dir(&self) -> PathBuf {
-        self.assets_dir().join("objects")
+    /// Returns the assets directory of an instance, ${instance_dir}/assets if it keeps its resources
+    /// isolated from the other instances.
+    pub fn instance_assets_dir(&self, instance_dir: &Path, isolated_resources: bool) -> PathBuf {
+        if isolated_resources {
+            instance_dir.join("assets")
+        } else {
+            self.assets_dir()
+        }
+    }
+
+    /// Returns the directory the native jars of an instance are downloaded to,
+    /// ${instance_dir}/libraries if it keeps its resources isolated from the other instances.
+    pub fn instance_natives_dir(&self, instance_dir: &Path, isolated_resources: bool) -> PathBuf {
+        if isolated_resources {
+            instance_dir.join("libraries")
+        } else {
+            self.libraries_dir()
+        }
     }
 
     /// Returns the java directory at ${app_dir}/java
@@ -172,159 +317,615 @@ impl ResourceManager {
         self.app_dir.join("java")
     }
 
-    /// Returns the java directory at ${app_dir}/instances
+    /// Returns the temporary files directory at ${app_dir}/tmp
+    pub fn tmp_dir(&self) -> PathBuf {
+        self.app_dir.join("tmp")
+    }
+
+    /// Creates a temporary directory in [ResourceManager::tmp_dir], removed when dropped. Installers
+    /// and modpacks can be too big for the system's temp dir, which is often in memory.
+    pub fn create_tmp_dir(&self, prefix: &str) -> io::Result<TempDir> {
+        fs::create_dir_all(self.tmp_dir())?;
+        TempDir::new_in(self.tmp_dir(), prefix)
+    }
+
+    /// Returns the instances directory at ${app_dir}/instances
     pub fn instances_dir(&self) -> PathBuf {
         self.app_dir.join("instances")
     }
 
-    async fn download_fabric_manifest(&mut self) -> reqwest::Result<()> {
+    /// Replaces the data roots instances can be stored in besides ${app_dir}.
+    pub fn set_data_roots(&self, data_roots: Vec<DataRoot>) {
+        *self
+            .data_roots
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = data_roots;
+    }
+
+    /// Returns the instances directory of the data root named `data_root`, or
+    /// [ResourceManager::instances_dir] for `None`. `None` if there is no such data root.
+    pub fn instances_dir_in(&self, data_root: Option<&str>) -> Option<PathBuf> {
+        let name = match data_root {
+            Some(name) => name,
+            None => return Some(self.instances_dir()),
+        };
+        self.data_roots
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .find(|root| root.name == name)
+            .map(|root| root.path.join("instances"))
+    }
+
+    /// Returns the instances directory of every data root, starting with ${app_dir}/instances under
+    /// `None`.
+    pub fn all_instances_dirs(&self) -> Vec<(Option<String>, PathBuf)> {
+        let data_roots = self
+            .data_roots
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        std::iter::once((None, self.instances_dir()))
+            .chain(
+                data_roots
+                    .iter()
+                    .map(|root| (Some(root.name.clone()), root.path.join("instances"))),
+            )
+            .collect()
+    }
+
+    /// Returns the templates directory at ${app_dir}/templates
+    pub fn templates_dir(&self) -> PathBuf {
+        self.app_dir.join("templates")
+    }
+
+    /// Returns the keybind profiles directory at ${app_dir}/keybind_profiles
+    pub fn keybind_profiles_dir(&self) -> PathBuf {
+        self.app_dir.join("keybind_profiles")
+    }
+
+    /// Returns the directory of the json overlays applied to version jsons at ${app_dir}/patches
+    pub fn patches_dir(&self) -> PathBuf {
+        self.app_dir.join("patches")
+    }
+
+    /// Reads the version patches in [ResourceManager::patches_dir].
+    pub fn version_patches(&self) -> VersionPatches {
+        VersionPatches::load(&self.patches_dir())
+    }
+
+    /// Returns the directory downloaded account skins are cached in at ${app_dir}/skins
+    pub fn skins_dir(&self) -> PathBuf {
+        self.app_dir.join("skins")
+    }
+
+    /// Returns the directory deleted instances, worlds and mods are moved to at ${app_dir}/trash
+    pub fn trash_dir(&self) -> PathBuf {
+        self.app_dir.join("trash")
+    }
+
+    /// Returns the directory modpacks downloaded from curseforge and modrinth are kept in at
+    /// ${app_dir}/downloads/modpacks, so reinstalling a pack doesn't download it again.
+    pub fn modpack_cache_dir(&self) -> PathBuf {
+        self.app_dir.join("downloads").join("modpacks")
+    }
+
+    /// Returns the directory mods are cached in by their hash at ${app_dir}/downloads/mods
+    pub fn mod_cache(&self) -> ModCache {
+        ModCache::new(&self.app_dir.join("downloads").join("mods"))
+    }
+
+    async fn download_fabric_manifest(&self) -> reqwest::Result<Arc<FabricLoaderManifest>> {
         info!("Downloading fabric manifest");
         let client = reqwest::Client::new();
         let fabric_manifest_url = format!("{}/{}", FABRIC_BASE_URL, "versions/loader");
-        let fabric_response = client.get(fabric_manifest_url).send().await?;
-        let fabric_manifest = fabric_response.json::<FabricLoaderManifest>().await?;
-        self.fabric_manifest = Some(fabric_manifest);
-        Ok(())
+        let fabric_response = send_with_retry(client.get(fabric_manifest_url)).await?;
+        let fabric_manifest = Arc::new(fabric_response.json::<FabricLoaderManifest>().await?);
+        self.write_manifests().fabric = Some(fabric_manifest.clone());
+        Ok(fabric_manifest)
     }
 
-    async fn download_forge_manifest(&mut self) -> reqwest::Result<()> {
+    async fn download_forge_manifest(&self) -> reqwest::Result<Arc<ForgeManifest>> {
         info!("Downloading forge manifest");
         let client = reqwest::Client::new();
-        let forge_response = client.get(FORGE_MANIFEST_URL).send().await?;
-        let forge_manifest = forge_response.json::<ForgeManifest>().await?;
-        self.forge_manifest = Some(forge_manifest);
-        Ok(())
+        let forge_response = send_with_retry(client.get(FORGE_MANIFEST_URL)).await?;
+        let forge_manifest = Arc::new(forge_response.json::<ForgeManifest>().await?);
+        self.write_manifests().forge = Some(forge_manifest.clone());
+        Ok(forge_manifest)
     }
 
-    async fn download_vanilla_manifest(&mut self) -> reqwest::Result<()> {
+    async fn download_vanilla_manifest(&self) -> reqwest::Result<Arc<VanillaManifest>> {
         info!("Downloading vanilla manifest");
         let client = reqwest::Client::new();
-        let vanilla_response = client.get(VANILLA_MANIFEST_URL).send().await?;
-        let vanilla_manifest = vanilla_response.json::<VanillaManifest>().await?;
-        self.vanilla_manifest = Some(vanilla_manifest);
-        Ok(())
+        let vanilla_response = send_with_retry(client.get(VANILLA_MANIFEST_URL)).await?;
+        let vanilla_manifest = Arc::new(vanilla_response.json::<VanillaManifest>().await?);
+        let releases = vanilla_manifest
+            .versions()
+            .iter()
+            .filter(|(_, version_info)| version_info.version_type() == "release")
+            .map(|(version, _)| version.clone())
+            .collect();
+        let mut manifests = self.write_manifests();
+        manifests.vanilla = Some(vanilla_manifest.clone());
+        manifests.vanilla_releases = Some(Arc::new(releases));
+        Ok(vanilla_manifest)
     }
 
-    /// Gets a list of all vanilla versions
-    pub async fn get_vanilla_version_list(&mut self) -> reqwest::Result<Vec<VersionEntry>> {
-        let mut result: Vec<VersionEntry> = Vec::new();
-        if self.vanilla_manifest.is_none() {
-            self.download_vanilla_manifest().await?;
+    /// The vanilla manifest, downloaded the first time it is needed.
+    async fn vanilla_manifest(&self) -> reqwest::Result<Arc<VanillaManifest>> {
+        let cached = self.read_manifests().vanilla.clone();
+        match cached {
+            Some(manifest) => Ok(manifest),
+            None => self.download_vanilla_manifest().await,
         }
-        if let Some(manifest) = &self.vanilla_manifest {
-            for (version, version_info) in &manifest.versions {
-                result.push(VersionEntry::new(version, version_info));
+    }
+
+    /// The release versions of the vanilla manifest, newest first, without the custom versions.
+    pub async fn get_vanilla_releases(&self) -> reqwest::Result<Arc<Vec<String>>> {
+        let cached = self.read_manifests().vanilla_releases.clone();
+        match cached {
+            Some(releases) => Ok(releases),
+            None => {
+                self.download_vanilla_manifest().await?;
+                Ok(self
+                    .read_manifests()
+                    .vanilla_releases
+                    .clone()
+                    .unwrap_or_default())
             }
         }
-        Ok(result)
     }
 
-    pub async fn get_fabric_version_list(&mut self) -> reqwest::Result<Vec<String>> {
-        let mut result = Vec::new();
-        if self.fabric_manifest.is_none() {
-            self.download_fabric_manifest().await?;
+    /// Gets a list of all vanilla versions, after the imported custom versions.
+    pub async fn get_vanilla_version_list(&self) -> reqwest::Result<Vec<VersionEntry>> {
+        let manifest = self.vanilla_manifest().await?;
+        let custom_versions = self.custom_versions();
+        Ok(custom_versions
+            .iter()
+            .map(|version| (version.id(), version))
+            .chain(
+                manifest
+                    .versions()
+                    .iter()
+                    .map(|(version, version_info)| (version.as_str(), version_info)),
+            )
+            .map(|(version, version_info)| VersionEntry::new(version, version_info))
+            .collect())
+    }
+
+    /// Returns the list of versions imported with [ResourceManager::import_custom_version] at
+    /// ${app_dir}/versions/custom_versions.json
+    fn custom_versions_path(&self) -> PathBuf {
+        self.version_dir().join("custom_versions.json")
+    }
+
+    /// Versions imported from a version json or zip, stand-ins for their vanilla manifest entries.
+    pub fn custom_versions(&self) -> Vec<VanillaManifestVersion> {
+        let file = match File::open(self.custom_versions_path()) {
+            Ok(file) => file,
+            Err(_) => return Vec::new(),
+        };
+        serde_json::from_reader(BufReader::new(file)).unwrap_or_else(|e| {
+            warn!("Could not read the custom versions: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// Imports a version that isn't in the vanilla manifest from a version json or zip, by url or
+    /// local path. Instances can then be created with it like any vanilla version.
+    pub async fn import_custom_version(
+        &self,
+        source: &str,
+    ) -> ManifestResult<VanillaManifestVersion> {
+        let bytes = if source.starts_with("https://") || source.starts_with("http://") {
+            download_bytes_from_url(source).await?.to_vec()
+        } else {
+            fs::read(source)?
+        };
+        let version = read_custom_version(&bytes, source)?;
+        let id = version.entry.id().to_owned();
+        let in_manifest = self
+            .read_manifests()
+            .vanilla
+            .as_ref()
+            .is_some_and(|manifest| manifest.versions().contains_key(&id));
+        if in_manifest {
+            return Err(ManifestError::ResourceError(format!(
+                "{} is already a vanilla version",
+                id
+            )));
         }
-        if let Some(manifest) = &self.fabric_manifest {
-            let FabricLoaderManifest(vec) = manifest;
-            for entry in vec {
-                result.push(entry.version.to_owned());
-            }
+
+        let json_path = self.version_json_path(&id);
+        fs::create_dir_all(json_path.parent().unwrap())?;
+        fs::write(&json_path, &version.json)?;
+        if let Some(jar) = &version.client_jar {
+            let jar_dir = self.version_dir().join(&id).join(JarType::Client.side());
+            fs::create_dir_all(&jar_dir)?;
+            fs::write(jar_dir.join(format!("{}.jar", id)), jar)?;
         }
-        Ok(result)
+        let mut custom_versions = self.custom_versions();
+        custom_versions.retain(|custom_version| custom_version.id() != id);
+        custom_versions.insert(0, version.entry.clone());
+        serde_json::to_writer(File::create(self.custom_versions_path())?, &custom_versions)?;
+        info!("Imported custom version `{}` from {}", id, source);
+        Ok(version.entry)
     }
 
-    pub async fn get_forge_version_list(
-        &mut self,
-    ) -> reqwest::Result<HashMap<String, Vec<String>>> {
-        if self.forge_manifest.is_none() {
-            self.download_forge_manifest().await?;
-        }
-        Ok(if let Some(manifest) = &self.forge_manifest {
-            manifest.0.to_owned()
-        } else {
-            HashMap::new()
-        })
+    pub async fn get_fabric_version_list(&self) -> reqwest::Result<Vec<String>> {
+        let cached = self.read_manifests().fabric.clone();
+        let manifest = match cached {
+            Some(manifest) => manifest,
+            None => self.download_fabric_manifest().await?,
+        };
+        Ok(manifest
+            .versions()
+            .iter()
+            .map(|entry| entry.version().to_owned())
+            .collect())
+    }
+
+    /// The fabric loader versions marked stable, newest first.
+    pub async fn get_stable_fabric_versions(&self) -> reqwest::Result<Vec<String>> {
+        let cached = self.read_manifests().fabric.clone();
+        let manifest = match cached {
+            Some(manifest) => manifest,
+            None => self.download_fabric_manifest().await?,
+        };
+        Ok(manifest
+            .versions()
+            .iter()
+            .filter(|entry| entry.stable())
+            .map(|entry| entry.version().to_owned())
+            .collect())
+    }
+
+    pub async fn get_forge_version_list(&self) -> reqwest::Result<HashMap<String, Vec<String>>> {
+        let cached = self.read_manifests().forge.clone();
+        let manifest = match cached {
+            Some(manifest) => manifest,
+            None => self.download_forge_manifest().await?,
+        };
+        Ok(manifest.versions().to_owned())
     }
 
-    /// Get the vanilla manifest for a given mc_version. Returns None if mc_version is invalid.
+    /// Get the vanilla manifest for a given mc_version, or its custom version. Returns None if
+    /// mc_version is invalid.
     pub fn get_vanilla_manifest_from_version(
         &self,
         mc_version: &str,
-    ) -> Option<&VanillaManifestVersion> {
-        if let Some(manifest) = &self.vanilla_manifest {
-            manifest.versions.get(mc_version)
-        } else {
-            None
-        }
+    ) -> Option<VanillaManifestVersion> {
+        self.read_manifests()
+            .vanilla
+            .as_ref()
+            .and_then(|manifest| manifest.versions().get(mc_version).cloned())
+            .or_else(|| {
+                self.custom_versions()
+                    .into_iter()
+                    .find(|version| version.id() == mc_version)
+            })
     }
 
+    /// Loads a vanilla version json, using the cached copy in `versions/<id>/<id>.json` unless its
+    /// hash no longer matches the manifest's.
     pub async fn download_vanilla_version(
         &self,
         version_id: &str,
     ) -> ManifestResult<VanillaVersion> {
-        if let Some(manifest) = &self.vanilla_manifest {
-            if let Some(manifest_version) = manifest.versions.get(version_id) {
-                // If there is a version json cached and its hash matches the manifest hash, load it.
-                if validate_file_hash(
-                    &self.get_version_file_path(version_id),
-                    &manifest_version.sha1,
-                ) {
-                    info!("Loading vanilla version `{}` from disk.", version_id);
-                    self.deserialize_cached_vanilla_version(version_id)
-                } else {
-                    info!("Requesting vanilla version from {}", &manifest_version.url);
-                    let bytes = download_bytes_from_url(&manifest_version.url).await?;
-                    validate_hash_sha1(&bytes, "");
-
-                    self.serialize_version(version_id, &bytes)?;
-
-                    let vanilla_version =
-                        serde_json::from_slice::<VanillaVersion>(&bytes.to_vec())?;
-                    info!("Finished downloading version `{}`", version_id);
-                    Ok(vanilla_version)
-                }
-            } else {
-                Err(ManifestError::VersionRetrievalError(format!(
+        if self.read_manifests().vanilla.is_none() {
+            return Err(ManifestError::ResourceError(
+                "Trying to access vanilla manifest but it is not downloaded yet.".into(),
+            ));
+        }
+        let manifest_version = self
+            .get_vanilla_manifest_from_version(version_id)
+            .ok_or_else(|| {
+                ManifestError::VersionRetrievalError(format!(
                     "Cannot find version with id: {}",
                     version_id
-                )))
+                ))
+            })?;
+
+        let bytes = download_validated_file(
+            &self.version_json_path(version_id),
+            manifest_version.url(),
+            manifest_version.sha1(),
+        )
+        .await?;
+        Ok(self
+            .version_patches()
+            .read(PatchTarget::Vanilla, version_id, &bytes)?)
+    }
+
+    /// Fetches the vanilla manifest again and replaces the cached json of `version_id`, for when
+    /// Mojang republishes a version.
+    pub async fn refresh_version(&self, version_id: &str) -> ManifestResult<VanillaVersion> {
+        if self.is_custom_version(version_id) {
+            return Err(ManifestError::ResourceError(format!(
+                "{} is a custom version, import it again instead",
+                version_id
+            )));
+        }
+        self.download_vanilla_manifest().await?;
+        let path = self.version_json_path(version_id);
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        info!("Refreshing vanilla version `{}`", version_id);
+        self.download_vanilla_version(version_id).await
+    }
+
+    /// Removes cached version jsons whose version isn't in `referenced`. Jars are kept since they
+    /// are part of the instances' launch arguments. Returns the evicted version ids.
+    pub fn evict_version_jsons(&self, referenced: &HashSet<String>) -> io::Result<Vec<String>> {
+        let mut evicted = Vec::new();
+        // Custom versions can't always be downloaded again.
+        let custom_versions = self.custom_versions();
+        let entries = match fs::read_dir(self.version_dir()) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(evicted),
+            Err(error) => return Err(error),
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let version_id = entry.file_name().to_string_lossy().to_string();
+            let path = self.version_json_path(&version_id);
+            let is_custom = custom_versions
+                .iter()
+                .any(|version| version.id() == version_id);
+            if !referenced.contains(&version_id) && !is_custom && path.is_file() {
+                fs::remove_file(path)?;
+                evicted.push(version_id);
             }
-        } else {
-            Err(ManifestError::ResourceError(
-                "Trying to access vanilla manifest but it is not downloaded yet.".into(),
-            ))
         }
+        info!("Evicted {} unreferenced version jsons", evicted.len());
+        Ok(evicted)
     }
 
-    /// Gets the path to a version json given a `version_id`
-    fn get_version_file_path(&self, version_id: &str) -> PathBuf {
-        self.version_dir().join(format!("{}.json", version_id))
+    /// Removes the modpacks downloaded more than `max_age` before `now` from the modpack cache, they
+    /// are downloaded again if they are installed later. Returns the folders of the removed packs.
+    pub fn evict_expired_modpacks(
+        &self,
+        max_age: Duration,
+        now: SystemTime,
+    ) -> io::Result<Vec<PathBuf>> {
+        let mut evicted = Vec::new();
+        // Packs are cached in `<source>/<file or version id>/`.
+        for source in ["curseforge", "modrinth"] {
+            let entries = match fs::read_dir(self.modpack_cache_dir().join(source)) {
+                Ok(entries) => entries,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error),
+            };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let metadata = entry.metadata()?;
+                let expired = now
+                    .duration_since(metadata.modified()?)
+                    .is_ok_and(|age| age > max_age);
+                if metadata.is_dir() && expired {
+                    fs::remove_dir_all(entry.path())?;
+                    evicted.push(entry.path());
+                }
+            }
+        }
+        if !evicted.is_empty() {
+            info!("Evicted {} expired modpacks", evicted.len());
+        }
+        Ok(evicted)
     }
 
-    /// Deserialize a cached vanilla version json from disk.
-    fn deserialize_cached_vanilla_version(
+    /// Pins the current vanilla manifest entry of `version_id` along with the modloader used.
+    pub fn snapshot_manifests(
         &self,
         version_id: &str,
+        modloader: Option<PinnedModloader>,
+    ) -> ManifestResult<ManifestSnapshot> {
+        let version = self
+            .get_vanilla_manifest_from_version(version_id)
+            .ok_or_else(|| {
+                ManifestError::VersionRetrievalError(format!(
+                    "Cannot find version with id: {}",
+                    version_id
+                ))
+            })?;
+        Ok(ManifestSnapshot {
+            created_at: chrono::Local::now().to_rfc3339(),
+            vanilla: PinnedVanillaVersion {
+                id: version.id().to_owned(),
+                url: version.url().to_owned(),
+                sha1: version.sha1().to_owned(),
+                release_time: version.release_time().to_owned(),
+            },
+            modloader,
+        })
+    }
+
+    /// Loads the version json pinned in `snapshot`, even if the manifest now lists a different one.
+    pub async fn download_pinned_vanilla_version(
+        &self,
+        snapshot: &ManifestSnapshot,
     ) -> ManifestResult<VanillaVersion> {
-        let path = self.version_dir().join(format!("{}.json", version_id));
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let version = serde_json::from_reader::<BufReader<File>, VanillaVersion>(reader)?;
-        Ok(version)
-    }
-
-    /// Seralize a vanilla version from bytes to disk.
-    fn serialize_version(&self, version_id: &str, bytes: &Bytes) -> Result<(), io::Error> {
-        info!("REMOVEME: Serializing version json.");
-        if !&self.version_dir().exists() {
-            fs::create_dir(self.version_dir())?;
+        let pinned = &snapshot.vanilla;
+        let bytes = download_validated_file(
+            &self.version_json_path(&pinned.id),
+            &pinned.url,
+            &pinned.sha1,
+        )
+        .await?;
+        Ok(self
+            .version_patches()
+            .read(PatchTarget::Vanilla, &pinned.id, &bytes)?)
+    }
+
+    /// Whether the upstream vanilla manifest no longer matches the version pinned in `snapshot`.
+    pub async fn is_snapshot_outdated(&self, snapshot: &ManifestSnapshot) -> ManifestResult<bool> {
+        self.vanilla_manifest().await?;
+        Ok(self
+            .get_vanilla_manifest_from_version(&snapshot.vanilla.id)
+            .map_or(true, |version| version.sha1() != snapshot.vanilla.sha1))
+    }
+
+    fn is_custom_version(&self, version_id: &str) -> bool {
+        self.custom_versions()
+            .iter()
+            .any(|version| version.id() == version_id)
+    }
+
+    /// Gets the path to a version json given a `version_id`
+    fn version_json_path(&self, version_id: &str) -> PathBuf {
+        self.version_dir()
+            .join(version_id)
+            .join(format!("{}.json", version_id))
+    }
+}
+
+fn remove_dir_contents(dir: &Path) -> io::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else {
+            fs::remove_file(path)?;
         }
-        let dir_path = &self.version_dir().join(version_id);
-        fs::create_dir_all(dir_path)?;
+    }
+    Ok(())
+}
 
-        let path = &dir_path.join(format!("{}.json", version_id));
-        let mut file = File::create(path)?;
-        file.write_all(bytes)?;
-        Ok(())
+#[test]
+fn test_evict_version_jsons() {
+    let tmp_dir = tempdir::TempDir::new("versions").unwrap();
+    let resource_manager = ResourceManager::new(tmp_dir.path());
+    for version in ["1.20.1", "1.19.2"] {
+        let path = resource_manager.version_json_path(version);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "{}").unwrap();
     }
+    let jar_dir = resource_manager.version_dir().join("1.19.2").join("client");
+    fs::create_dir_all(&jar_dir).unwrap();
+    fs::write(jar_dir.join("1.19.2.jar"), "").unwrap();
+
+    let referenced = HashSet::from(["1.20.1".to_string()]);
+    let evicted = resource_manager.evict_version_jsons(&referenced).unwrap();
+    assert_eq!(evicted, vec!["1.19.2"]);
+    assert!(resource_manager.version_json_path("1.20.1").is_file());
+    assert!(!resource_manager.version_json_path("1.19.2").exists());
+    assert!(jar_dir.join("1.19.2.jar").is_file());
+}
+
+#[test]
+fn test_evict_expired_modpacks() {
+    let tmp_dir = tempdir::TempDir::new("modpacks").unwrap();
+    let resource_manager = ResourceManager::new(tmp_dir.path());
+    let pack_dir = resource_manager
+        .modpack_cache_dir()
+        .join("modrinth")
+        .join("fixture-version");
+    fs::create_dir_all(&pack_dir).unwrap();
+    fs::write(pack_dir.join("Fixture.mrpack"), "").unwrap();
+
+    let max_age = Duration::from_secs(30 * 24 * 60 * 60);
+    let evicted = resource_manager
+        .evict_expired_modpacks(max_age, SystemTime::now())
+        .unwrap();
+    assert!(evicted.is_empty());
+    assert!(pack_dir.is_dir());
+
+    let later = SystemTime::now() + max_age + Duration::from_secs(60);
+    let evicted = resource_manager
+        .evict_expired_modpacks(max_age, later)
+        .unwrap();
+    assert_eq!(evicted, vec![pack_dir.clone()]);
+    assert!(!pack_dir.exists());
+}
+
+#[test]
+fn test_manifest_snapshot_round_trip() {
+    let tmp_dir = tempdir::TempDir::new("snapshot").unwrap();
+    assert!(ManifestSnapshot::load(tmp_dir.path()).unwrap().is_none());
+
+    let snapshot = ManifestSnapshot {
+        created_at: "2023-06-01T12:00:00+00:00".into(),
+        vanilla: PinnedVanillaVersion {
+            id: "1.19.2".into(),
+            url: "https://piston-meta.mojang.com/v1/packages/abc/1.19.2.json".into(),
+            sha1: "abc".into(),
+            release_time: "2022-08-05T11:57:05+00:00".into(),
+        },
+        modloader: Some(PinnedModloader::Forge {
+            forge_version: "1.19.2-43.2.0".into(),
+            installer_md5: "def".into(),
+        }),
+    };
+    snapshot.save(tmp_dir.path()).unwrap();
+    assert_eq!(
+        ManifestSnapshot::load(tmp_dir.path()).unwrap(),
+        Some(snapshot)
+    );
+}
+
+#[test]
+fn test_stale_tmp_dirs_are_removed() {
+    let app_dir = TempDir::new("app").unwrap();
+    let resource_manager = ResourceManager::new(app_dir.path());
+    let stale_dir = resource_manager.create_tmp_dir("temp").unwrap().into_path();
+    assert!(stale_dir.starts_with(resource_manager.tmp_dir()));
+    fs::write(stale_dir.join("forge-installer.jar"), "").unwrap();
+
+    ResourceManager::new(app_dir.path());
+    assert!(!stale_dir.exists());
+    assert!(resource_manager.tmp_dir().is_dir());
+}
+
+#[test]
+fn test_data_root_instances_dirs() {
+    let tmp_dir = TempDir::new("app").unwrap();
+    let app_dir = tmp_dir.path();
+    let resource_manager = ResourceManager::new(app_dir);
+    resource_manager.set_data_roots(vec![DataRoot {
+        name: "HDD archive".into(),
+        path: PathBuf::from("archive"),
+    }]);
+    assert_eq!(
+        resource_manager.instances_dir_in(None),
+        Some(app_dir.join("instances"))
+    );
+    assert_eq!(
+        resource_manager.instances_dir_in(Some("HDD archive")),
+        Some(Path::new("archive").join("instances"))
+    );
+    assert_eq!(resource_manager.instances_dir_in(Some("SSD")), None);
+    assert_eq!(
+        resource_manager.all_instances_dirs(),
+        [
+            (None, app_dir.join("instances")),
+            (
+                Some("HDD archive".into()),
+                Path::new("archive").join("instances")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_isolated_instance_resources() {
+    let tmp_dir = TempDir::new("app").unwrap();
+    let resource_manager = ResourceManager::new(tmp_dir.path());
+    let instance_dir = tmp_dir.path().join("instances").join("Snapshot");
+    assert_eq!(
+        resource_manager.instance_assets_dir(&instance_dir, false),
+        resource_manager.assets_dir()
+    );
+    assert_eq!(
+        resource_manager.instance_assets_dir(&instance_dir, true),
+        instance_dir.join("assets")
+    );
+    assert_eq!(
+        resource_manager.instance_natives_dir(&instance_dir, false),
+        resource_manager.libraries_dir()
+    );
+    assert_eq!(
+        resource_manager.instance_natives_dir(&instance_dir, true),
+        instance_dir.join("libraries")
+    );
 }
@@ -0,0 +1,146 @@
+use std::{
+    fs::File,
+    io::{BufReader, Error, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use autmc_log::{enable_scopes_from_env, set_enabled_scopes};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::Mutex;
+
+use crate::{data_roots::DataRoot, log_format::set_json_log_files};
+
+use super::{InnerState, ManagerFromAppHandle};
+
+pub struct SettingsState(pub Arc<Mutex<SettingsManager>>);
+
+impl InnerState<Arc<Mutex<SettingsManager>>> for SettingsState {
+    fn inner_state(&self) -> Arc<Mutex<SettingsManager>> {
+        self.0.clone()
+    }
+}
+
+impl ManagerFromAppHandle for SettingsManager {
+    type State = SettingsState;
+}
+
+impl SettingsState {
+    pub fn new(settings_manager: SettingsManager) -> Self {
+        Self(Arc::new(Mutex::new(settings_manager)))
+    }
+}
+
+/// Launcher wide settings, used whenever an instance doesn't override them.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LauncherSettings {
+    pub default_resolution_width: u32,
+    pub default_resolution_height: u32,
+    pub default_fullscreen: bool,
+    /// Also download the client's obfuscation mappings when creating instances.
+    pub download_client_mappings: bool,
+    /// Let mod dependencies resolve to beta files, not only releases.
+    pub allow_beta_dependencies: bool,
+    /// Log scopes, from `autmc_log::LOG_SCOPES`, with verbose logging turned on.
+    pub verbose_log_scopes: Vec<String>,
+    /// Write the launcher's log files as json lines instead of plain text.
+    pub json_launcher_logs: bool,
+    /// The first-run flow has been finished or skipped.
+    pub first_run_completed: bool,
+    /// Pass the instance name as the game's version type, so it is shown next to the version on
+    /// the title screen and in the debug screen of every running instance.
+    pub show_instance_name_in_game: bool,
+    /// Download java from Adoptium when Mojang has no runtime for this system, or the one it has
+    /// doesn't run.
+    pub adoptium_java_fallback: bool,
+    /// Days deleted instances, worlds and mods are kept in the trash before they are gone for good.
+    pub trash_retention_days: u32,
+    /// Folders instances can be stored in besides the launcher's own, looked up by name.
+    pub data_roots: Vec<DataRoot>,
+    /// Show a desktop notification when an install finishes or a game crashes while the
+    /// launcher's window isn't focused.
+    pub desktop_notifications: bool,
+}
+
+impl Default for LauncherSettings {
+    fn default() -> Self {
+        // Minecraft's own default window size.
+        Self {
+            default_resolution_width: 854,
+            default_resolution_height: 480,
+            default_fullscreen: false,
+            download_client_mappings: false,
+            allow_beta_dependencies: false,
+            verbose_log_scopes: Vec::new(),
+            json_launcher_logs: false,
+            first_run_completed: false,
+            show_instance_name_in_game: true,
+            adoptium_java_fallback: true,
+            trash_retention_days: 30,
+            data_roots: Vec::new(),
+            desktop_notifications: true,
+        }
+    }
+}
+
+pub struct SettingsManager {
+    path: PathBuf,
+    settings: LauncherSettings,
+}
+
+impl SettingsManager {
+    /// Call on app setup. Loads `app_dir/settings.json` if it exists.
+    pub fn new(app_dir: &Path) -> Self {
+        let path = app_dir.join("settings.json");
+        let settings = match Self::deserialize_settings(&path) {
+            Ok(settings) => settings,
+            Err(error) => {
+                if path.exists() {
+                    warn!("Could not load settings, using defaults: {}", error);
+                }
+                LauncherSettings::default()
+            }
+        };
+        apply_log_settings(&settings);
+        // Scopes can still be turned on for a single run with their environment variables.
+        enable_scopes_from_env();
+        Self { path, settings }
+    }
+
+    fn deserialize_settings(path: &Path) -> Result<LauncherSettings, Error> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Serialize the launcher settings into `app_dir/settings.json`
+    pub fn serialize_settings(&self) -> Result<(), Error> {
+        let json = serde_json::to_string(&self.settings)?;
+        let mut file = File::create(&self.path)?;
+        info!("Serialized launcher settings.");
+        file.write_all(json.as_bytes())
+    }
+
+    pub fn get_settings(&self) -> &LauncherSettings {
+        &self.settings
+    }
+
+    pub fn complete_first_run(&mut self) {
+        self.settings.first_run_completed = true;
+    }
+
+    pub fn set_settings(&mut self, settings: LauncherSettings) {
+        apply_log_settings(&settings);
+        self.settings = settings;
+    }
+}
+
+/// Applies the logging settings to the already running logger.
+fn apply_log_settings(settings: &LauncherSettings) {
+    set_json_log_files(settings.json_launcher_logs);
+    for scope in set_enabled_scopes(&settings.verbose_log_scopes) {
+        warn!("Ignoring unknown log scope {}", scope);
+    }
+}
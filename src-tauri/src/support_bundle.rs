@@ -0,0 +1,213 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use log::info;
+use regex::Regex;
+use serde::Serialize;
+use zip::{result::ZipError, write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    consts::LATEST_LAUNCHER_LOG,
+    state::instance_manager::InstanceConfiguration,
+    system_info::{java_version, SystemInfo},
+};
+
+#[derive(Debug)]
+pub enum SupportBundleError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Zip(ZipError),
+}
+
+impl fmt::Display for SupportBundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SupportBundleError::Io(error) => write!(f, "{}", error),
+            SupportBundleError::Json(error) => write!(f, "{}", error),
+            SupportBundleError::Zip(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<io::Error> for SupportBundleError {
+    fn from(error: io::Error) -> Self {
+        SupportBundleError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for SupportBundleError {
+    fn from(error: serde_json::Error) -> Self {
+        SupportBundleError::Json(error)
+    }
+}
+
+impl From<ZipError> for SupportBundleError {
+    fn from(error: ZipError) -> Self {
+        SupportBundleError::Zip(error)
+    }
+}
+
+pub type SupportBundleResult<T> = Result<T, SupportBundleError>;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleSystemInfo {
+    #[serde(flatten)]
+    system: SystemInfo,
+    java_version: Option<String>,
+}
+
+/// Hides what identifies the user in logs and configs: their home directory, which usually
+/// contains their username, and the access token passed to the game.
+pub fn redact(text: &str, home_dir: Option<&Path>) -> String {
+    let access_token = Regex::new(r"(--accessToken\W+)[^\s\x22,]+").unwrap();
+    let text = access_token.replace_all(text, "${1}<redacted>");
+    match home_dir.and_then(|home_dir| home_dir.to_str()) {
+        Some(home_dir) if !home_dir.is_empty() => text.replace(home_dir, "~"),
+        _ => text.into_owned(),
+    }
+}
+
+/// The most recently modified file in `dir`, if there is one.
+fn newest_file(dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .max_by_key(|entry| entry.metadata().and_then(|meta| meta.modified()).ok())
+        .map(|entry| entry.path())
+}
+
+/// Writes a zip for bug reports to `bundle_path` with `system_info`, the launcher's latest log
+/// and, if given, the instance's config, latest log and newest crash report. Everything text
+/// is redacted, missing logs are skipped.
+pub fn write_support_bundle(
+    bundle_path: &Path,
+    system_info: SystemInfo,
+    launcher_log_dir: &Path,
+    instance_dir: Option<&Path>,
+    home_dir: Option<&Path>,
+) -> SupportBundleResult<()> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(File::create(bundle_path)?);
+    let mut add_text_file = |name: &str, path: &Path| -> SupportBundleResult<()> {
+        if !path.is_file() {
+            return Ok(());
+        }
+        // Logs can contain invalid utf-8 from the game's output.
+        let text = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+        zip.start_file(name, options)?;
+        zip.write_all(redact(&text, home_dir).as_bytes())?;
+        Ok(())
+    };
+
+    add_text_file(
+        "launcher/latest.log",
+        &launcher_log_dir.join(LATEST_LAUNCHER_LOG),
+    )?;
+
+    let mut jvm_path = None;
+    if let Some(instance_dir) = instance_dir {
+        let config_path = instance_dir.join("config.json");
+        add_text_file("instance/config.json", &config_path)?;
+        if let Ok(config) = fs::read(&config_path) {
+            jvm_path = serde_json::from_slice::<InstanceConfiguration>(&config)
+                .ok()
+                .map(|config| config.jvm_path);
+        }
+        add_text_file(
+            "instance/latest.log",
+            &instance_dir.join("logs").join("latest.log"),
+        )?;
+        if let Some(report) = newest_file(&instance_dir.join("crash-reports")) {
+            let name = report.file_name().unwrap_or_default().to_string_lossy();
+            add_text_file(&format!("instance/crash-reports/{}", name), &report)?;
+        }
+    }
+
+    let system_info = BundleSystemInfo {
+        system: system_info,
+        java_version: jvm_path.as_deref().and_then(java_version),
+    };
+    zip.start_file("system_info.json", options)?;
+    zip.write_all(redact(&serde_json::to_string_pretty(&system_info)?, home_dir).as_bytes())?;
+    zip.finish()?;
+
+    info!("Wrote support bundle to {}", bundle_path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+use {std::io::Read, tempdir::TempDir, zip::ZipArchive};
+
+#[test]
+fn test_redact() {
+    let home_dir = Path::new("/home/steve");
+    let text = "Using /home/steve/.autmc/java\n\"--accessToken\",\n    \"eyJhbGc.abc\",\n--accessToken eyJ --version 1.20.1";
+    assert_eq!(
+        redact(text, Some(home_dir)),
+        "Using ~/.autmc/java\n\"--accessToken\",\n    \"<redacted>\",\n--accessToken <redacted> --version 1.20.1"
+    );
+}
+
+#[test]
+fn test_write_support_bundle() {
+    let tmp_dir = TempDir::new("support-bundle").unwrap();
+    let home_dir = tmp_dir.path();
+    let log_dir = home_dir.join("logs");
+    let instance_dir = home_dir.join("instances").join("Test");
+    fs::create_dir_all(&log_dir).unwrap();
+    fs::create_dir_all(instance_dir.join("logs")).unwrap();
+    fs::create_dir_all(instance_dir.join("crash-reports")).unwrap();
+    fs::write(log_dir.join(LATEST_LAUNCHER_LOG), "--accessToken secret").unwrap();
+    fs::write(
+        instance_dir.join("logs").join("latest.log"),
+        format!("Loading from {}", instance_dir.display()),
+    )
+    .unwrap();
+    fs::write(
+        instance_dir
+            .join("crash-reports")
+            .join("crash-2024-01-01.txt"),
+        "Crash",
+    )
+    .unwrap();
+
+    let bundle_path = home_dir.join("bundle.zip");
+    write_support_bundle(
+        &bundle_path,
+        SystemInfo::detect(&[home_dir]),
+        &log_dir,
+        Some(&instance_dir),
+        Some(home_dir),
+    )
+    .unwrap();
+
+    let mut archive = ZipArchive::new(File::open(&bundle_path).unwrap()).unwrap();
+    let mut names: Vec<&str> = archive.file_names().collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "instance/crash-reports/crash-2024-01-01.txt",
+            "instance/latest.log",
+            "launcher/latest.log",
+            "system_info.json",
+        ]
+    );
+    let mut read = |name: &str| {
+        let mut contents = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    };
+    assert_eq!(read("launcher/latest.log"), "--accessToken <redacted>");
+    assert_eq!(read("instance/latest.log"), "Loading from ~/instances/Test");
+}
@@ -0,0 +1,485 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fmt, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::Serialize;
+use sysinfo::{Disks, System};
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+/// The smallest heap an instance can be given.
+const MIN_HEAP_MB: u64 = 512;
+/// Memory left to the OS and other programs when giving an instance as much as possible.
+const RESERVED_MEMORY_MB: u64 = 2048;
+/// Enough for most modpacks, more mostly makes garbage collection pauses longer.
+const RECOMMENDED_HEAP_MB: u64 = 4096;
+
+/// A best effort description of the machine the launcher is running on, anything that can't be
+/// detected is left empty.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    pub os: String,
+    pub os_version: Option<String>,
+    pub arch: String,
+    pub total_memory_mb: u64,
+    pub gpus: Vec<String>,
+    pub disks: Vec<DiskSpace>,
+}
+
+/// Space left on a drive the launcher stores files on.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskSpace {
+    pub mount_point: PathBuf,
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+}
+
+impl SystemInfo {
+    /// Detects the system's info, with the free space of each drive holding one of `paths`.
+    pub fn detect(paths: &[&Path]) -> Self {
+        Self {
+            os: env::consts::OS.into(),
+            os_version: System::long_os_version(),
+            arch: env::consts::ARCH.into(),
+            total_memory_mb: total_memory_mb(),
+            gpus: gpus(),
+            disks: disk_space(paths),
+        }
+    }
+}
+
+/// The heap sizes an instance can be given on this machine, in megabytes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryBounds {
+    pub min_mb: u64,
+    pub recommended_mb: u64,
+    pub max_mb: u64,
+}
+
+impl MemoryBounds {
+    pub fn detect() -> Self {
+        Self::from_total_memory(total_memory_mb())
+    }
+
+    fn from_total_memory(total_mb: u64) -> Self {
+        let max_mb = total_mb.saturating_sub(RESERVED_MEMORY_MB).max(MIN_HEAP_MB);
+        Self {
+            min_mb: MIN_HEAP_MB,
+            recommended_mb: (total_mb / 2)
+                .clamp(MIN_HEAP_MB, RECOMMENDED_HEAP_MB)
+                .min(max_mb),
+            max_mb,
+        }
+    }
+
+    pub fn contains(&self, heap_mb: u64) -> bool {
+        (self.min_mb..=self.max_mb).contains(&heap_mb)
+    }
+
+    pub fn clamp(&self, heap_mb: u64) -> u64 {
+        heap_mb.clamp(self.min_mb, self.max_mb)
+    }
+}
+
+/// Physical memory of the machine in megabytes.
+pub fn total_memory_mb() -> u64 {
+    let mut system = System::new();
+    system.refresh_memory();
+    system.total_memory() / BYTES_PER_MB
+}
+
+/// The drives holding `paths`, each listed once.
+pub fn disk_space(paths: &[&Path]) -> Vec<DiskSpace> {
+    let disks = Disks::new_with_refreshed_list();
+    let mut result: Vec<DiskSpace> = Vec::new();
+    for path in paths {
+        // The drive a path is on is the one with the longest mount point containing it.
+        let disk = disks
+            .iter()
+            .filter(|disk| path.starts_with(disk.mount_point()))
+            .max_by_key(|disk| disk.mount_point().as_os_str().len());
+        if let Some(disk) = disk {
+            if result
+                .iter()
+                .all(|space| space.mount_point != disk.mount_point())
+            {
+                result.push(DiskSpace {
+                    mount_point: disk.mount_point().into(),
+                    available_bytes: disk.available_space(),
+                    total_bytes: disk.total_space(),
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Runs `program` and returns its stdout if it exited successfully.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn gpus() -> Vec<String> {
+    match env::consts::OS {
+        "linux" => command_output("lspci", &[])
+            .map(|output| {
+                output
+                    .lines()
+                    .filter(|line| line.contains("VGA") || line.contains("3D controller"))
+                    // Skip the bus address and device class, `00:02.0 VGA compatible controller: `
+                    .filter_map(|line| line.split_once(": "))
+                    .map(|(_, name)| name.to_owned())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        "windows" => command_output("wmic", &["path", "win32_VideoController", "get", "name"])
+            .map(|output| {
+                output
+                    .lines()
+                    .skip(1)
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        "macos" => command_output("system_profiler", &["SPDisplaysDataType"])
+            .map(|output| {
+                output
+                    .lines()
+                    .filter_map(|line| line.trim().strip_prefix("Chipset Model: "))
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Environment variables that make the game render on the discrete GPU of a hybrid graphics
+/// machine, only needed on Linux.
+pub fn discrete_gpu_env() -> Vec<(&'static str, &'static str)> {
+    if env::consts::OS != "linux" {
+        return Vec::new();
+    }
+    prime_render_offload_env(&gpus())
+}
+
+fn prime_render_offload_env(gpus: &[String]) -> Vec<(&'static str, &'static str)> {
+    // Picks the secondary GPU with mesa drivers.
+    let mut env = vec![("DRI_PRIME", "1")];
+    // The nvidia GLX vendor fails to load without the proprietary driver, only set it when there
+    // is an NVIDIA GPU.
+    if gpus.iter().any(|gpu| gpu.contains("NVIDIA")) {
+        env.push(("__NV_PRIME_RENDER_OFFLOAD", "1"));
+        env.push(("__GLX_VENDOR_LIBRARY_NAME", "nvidia"));
+    }
+    env
+}
+
+/// Where Windows keeps the GPU each program runs on, the same as picking it under Settings >
+/// Display > Graphics.
+const GPU_PREFERENCES_KEY: &str = r"HKCU\Software\Microsoft\DirectX\UserGpuPreferences";
+const HIGH_PERFORMANCE_GPU_PREFERENCE: &str = "GpuPreference=2;";
+
+/// Windows stores GPU preferences per program, not per instance, and instances share their java.
+/// The high performance GPU is only registered for a java while instances preferring it run with it,
+/// then the preference it had before is put back. Does nothing on other systems.
+#[derive(Debug, Default)]
+pub struct DiscreteGpuPreferences {
+    // <java path, (preference before it was registered, instances running with it)>
+    registered: HashMap<PathBuf, (Option<String>, HashSet<String>)>,
+}
+
+impl DiscreteGpuPreferences {
+    /// Registers `program` to run on the high performance GPU while `instance_name` runs.
+    pub fn register(&mut self, program: &Path, instance_name: &str) -> io::Result<()> {
+        if env::consts::OS != "windows" {
+            return Ok(());
+        }
+        if let Some((_, instances)) = self.registered.get_mut(program) {
+            instances.insert(instance_name.to_owned());
+            return Ok(());
+        }
+        let previous = gpu_preference(program);
+        set_gpu_preference(program, HIGH_PERFORMANCE_GPU_PREFERENCE)?;
+        self.registered.insert(
+            program.to_path_buf(),
+            (previous, HashSet::from([instance_name.to_owned()])),
+        );
+        Ok(())
+    }
+
+    /// Puts back the preference of the java `instance_name` registered, once no other instance
+    /// needs it.
+    pub fn release(&mut self, instance_name: &str) -> io::Result<()> {
+        let mut released = Vec::new();
+        for (program, (_, instances)) in self.registered.iter_mut() {
+            if instances.remove(instance_name) && instances.is_empty() {
+                released.push(program.clone());
+            }
+        }
+        for program in released {
+            if let Some((previous, _)) = self.registered.remove(&program) {
+                match previous {
+                    Some(previous) => set_gpu_preference(&program, &previous)?,
+                    None => remove_gpu_preference(&program)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The GPU preference stored for `program`, `None` if it has none.
+fn gpu_preference(program: &Path) -> Option<String> {
+    let program = program.to_string_lossy();
+    let output = command_output("reg", &["query", GPU_PREFERENCES_KEY, "/v", &program])?;
+    parse_gpu_preference(&output, &program)
+}
+
+/// The value of `program` in the output of `reg query`, where each value is listed as
+/// `<name>    REG_SZ    <data>`.
+fn parse_gpu_preference(output: &str, program: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (_, data) = line.trim().strip_prefix(program)?.split_once("REG_SZ")?;
+        Some(data.trim().to_owned())
+    })
+}
+
+fn set_gpu_preference(program: &Path, preference: &str) -> io::Result<()> {
+    let status = Command::new("reg")
+        .args(["add", GPU_PREFERENCES_KEY, "/v"])
+        .arg(program)
+        .args(["/t", "REG_SZ", "/d", preference, "/f"])
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "Could not set the GPU preference of {}, reg exited with {}",
+            program.display(),
+            status
+        )));
+    }
+    Ok(())
+}
+
+fn remove_gpu_preference(program: &Path) -> io::Result<()> {
+    let status = Command::new("reg")
+        .args(["delete", GPU_PREFERENCES_KEY, "/v"])
+        .arg(program)
+        .arg("/f")
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "Could not remove the GPU preference of {}, reg exited with {}",
+            program.display(),
+            status
+        )));
+    }
+    Ok(())
+}
+
+/// The version line printed by `<jvm_path> -version`, like `openjdk version "17.0.8" 2023-07-18`.
+pub fn java_version(jvm_path: &Path) -> Option<String> {
+    let output = Command::new(jvm_path).arg("-version").output().ok()?;
+    // `-version` prints to stderr.
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_owned())
+}
+
+/// The major version of the java at `jvm_path`, `None` if it couldn't be run.
+pub fn java_major_version(jvm_path: &Path) -> Option<u32> {
+    parse_java_major_version(&java_version(jvm_path)?)
+}
+
+/// Why a java runtime failed its `-version` check.
+#[derive(Debug)]
+pub enum JavaCheckError {
+    /// It couldn't be started, like a binary built for another architecture.
+    Spawn(PathBuf, io::Error),
+    /// It started but exited with an error, with what it printed.
+    Exited(PathBuf, String),
+    /// It isn't the expected major version, `None` if its version couldn't be read.
+    WrongVersion(PathBuf, u32, Option<u32>),
+}
+
+impl fmt::Display for JavaCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JavaCheckError::Spawn(path, error) => {
+                write!(f, "Could not run the java at {}: {}", path.display(), error)
+            }
+            JavaCheckError::Exited(path, output) => {
+                write!(f, "The java at {} failed: {}", path.display(), output)
+            }
+            JavaCheckError::WrongVersion(path, expected, Some(found)) => write!(
+                f,
+                "The java at {} is java {} instead of java {}",
+                path.display(),
+                found,
+                expected
+            ),
+            JavaCheckError::WrongVersion(path, expected, None) => write!(
+                f,
+                "Could not tell if the java at {} is java {}",
+                path.display(),
+                expected
+            ),
+        }
+    }
+}
+
+/// Runs `<jvm_path> -version` to check that a freshly installed runtime works and is java
+/// `expected_major`, so a broken install fails now instead of when the game is launched. Returns
+/// its version line.
+pub fn check_java_runtime(jvm_path: &Path, expected_major: u32) -> Result<String, JavaCheckError> {
+    let output = Command::new(jvm_path)
+        .arg("-version")
+        .output()
+        .map_err(|e| JavaCheckError::Spawn(jvm_path.into(), e))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(JavaCheckError::Exited(
+            jvm_path.into(),
+            stderr.trim().to_owned(),
+        ));
+    }
+    check_version_output(jvm_path, &stderr, expected_major)
+}
+
+fn check_version_output(
+    jvm_path: &Path,
+    output: &str,
+    expected_major: u32,
+) -> Result<String, JavaCheckError> {
+    // Options from `JAVA_TOOL_OPTIONS` are printed before the version.
+    let version_line = output
+        .lines()
+        .map(str::trim)
+        .find(|line| line.contains(" version \""));
+    match version_line.and_then(parse_java_major_version) {
+        Some(major) if major == expected_major => Ok(version_line.unwrap().to_owned()),
+        major => Err(JavaCheckError::WrongVersion(
+            jvm_path.into(),
+            expected_major,
+            major,
+        )),
+    }
+}
+
+/// Parses the major version from a `-version` line. Java 8 and older are versioned `1.<major>`.
+fn parse_java_major_version(version_line: &str) -> Option<u32> {
+    let version = version_line.split('"').nth(1)?;
+    let version = version.strip_prefix("1.").unwrap_or(version);
+    let major: String = version.chars().take_while(char::is_ascii_digit).collect();
+    major.parse().ok()
+}
+
+#[test]
+fn test_parse_java_major_version() {
+    assert_eq!(
+        parse_java_major_version(r#"java version "1.8.0_392""#),
+        Some(8)
+    );
+    assert_eq!(
+        parse_java_major_version(r#"openjdk version "17.0.8" 2023-07-18"#),
+        Some(17)
+    );
+    assert_eq!(
+        parse_java_major_version(r#"openjdk version "21-ea" 2023-09-19"#),
+        Some(21)
+    );
+    assert_eq!(parse_java_major_version("Error: Could not find"), None);
+}
+
+#[test]
+fn test_check_version_output() {
+    let path = Path::new("java");
+    let output = "Picked up JAVA_TOOL_OPTIONS: -Xss2m\nopenjdk version \"17.0.8\" 2023-07-18\nOpenJDK Runtime Environment";
+    assert_eq!(
+        check_version_output(path, output, 17).unwrap(),
+        r#"openjdk version "17.0.8" 2023-07-18"#
+    );
+    assert!(matches!(
+        check_version_output(path, output, 21),
+        Err(JavaCheckError::WrongVersion(_, 21, Some(17)))
+    ));
+    assert!(matches!(
+        check_version_output(path, "", 17),
+        Err(JavaCheckError::WrongVersion(_, 17, None))
+    ));
+    assert!(matches!(
+        check_java_runtime(Path::new("/missing/bin/java"), 17),
+        Err(JavaCheckError::Spawn(_, _))
+    ));
+}
+
+#[test]
+fn test_memory_bounds() {
+    assert_eq!(
+        MemoryBounds::from_total_memory(16384),
+        MemoryBounds {
+            min_mb: 512,
+            recommended_mb: 4096,
+            max_mb: 14336
+        }
+    );
+    assert_eq!(
+        MemoryBounds::from_total_memory(4096),
+        MemoryBounds {
+            min_mb: 512,
+            recommended_mb: 2048,
+            max_mb: 2048
+        }
+    );
+    // Machines with less than the reserved memory can still give an instance the minimum.
+    let bounds = MemoryBounds::from_total_memory(1024);
+    assert_eq!((bounds.recommended_mb, bounds.max_mb), (512, 512));
+    assert!(bounds.contains(512));
+    assert!(!bounds.contains(511));
+    assert!(!bounds.contains(513));
+    assert_eq!(bounds.clamp(8192), 512);
+}
+
+#[test]
+fn test_prime_render_offload_env() {
+    let intel = "Intel Corporation UHD Graphics 620 (rev 07)".to_string();
+    let nvidia = "NVIDIA Corporation GP108M [GeForce MX150] (rev a1)".to_string();
+    assert_eq!(
+        prime_render_offload_env(std::slice::from_ref(&intel)),
+        [("DRI_PRIME", "1")]
+    );
+    assert_eq!(
+        prime_render_offload_env(&[intel, nvidia]),
+        [
+            ("DRI_PRIME", "1"),
+            ("__NV_PRIME_RENDER_OFFLOAD", "1"),
+            ("__GLX_VENDOR_LIBRARY_NAME", "nvidia")
+        ]
+    );
+}
+
+#[test]
+fn test_parse_gpu_preference() {
+    let program = r"C:\Users\Steve\AppData\Roaming\autmc\java\java-runtime-gamma\bin\javaw.exe";
+    let output = format!(
+        "\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\DirectX\\UserGpuPreferences\r\n    {}    REG_SZ    GpuPreference=1;\r\n\r\n",
+        program
+    );
+    assert_eq!(
+        parse_gpu_preference(&output, program),
+        Some("GpuPreference=1;".into())
+    );
+    assert_eq!(parse_gpu_preference("", program), None);
+}
@@ -1,3 +1,172 @@
+pub mod fixtures;
+
+use std::fs::{self, File};
+
+use autmc_downloader::mock_http::start_mock_server;
+use serde_json::json;
+use tauri::async_runtime::block_on;
+use tempdir::TempDir;
+use zip::ZipArchive;
+
+use crate::{
+    state::{resource_manager::ResourceManager, settings_manager::LauncherSettings},
+    web_services::{
+        mod_cache::ModCache,
+        modpack::{
+            curseforge::{download_mods_from_curseforge, CurseforgeFile, CurseforgeManifestInfo},
+            modrinth::{download_modrinth_modpack, read_modrinth_manifest},
+        },
+        resources::{download_instance, InstanceSettings, ModloaderType},
+    },
+};
+
+#[test]
+fn test_refresh_version_from_recorded_manifest() {
+    start_mock_server();
+    let tmp_dir = TempDir::new("mock_http").unwrap();
+    let resource_manager = ResourceManager::new(tmp_dir.path());
+
+    let versions = block_on(resource_manager.get_vanilla_version_list()).unwrap();
+    assert_eq!(versions.len(), 2);
+    let releases = block_on(resource_manager.get_vanilla_releases()).unwrap();
+    assert_eq!(*releases, vec!["1.20.1".to_string()]);
+
+    let version = block_on(resource_manager.refresh_version("1.20.1")).unwrap();
+    assert_eq!(version.id(), "1.20.1");
+    assert_eq!(version.main_class(), "net.minecraft.client.main.Main");
+    // The version json is cached next to the version's jar.
+    assert!(tmp_dir
+        .path()
+        .join("versions")
+        .join("1.20.1")
+        .join("1.20.1.json")
+        .is_file());
+}
+
+#[test]
+fn test_download_vanilla_instance() {
+    start_mock_server();
+    let tmp_dir = TempDir::new("mock_http").unwrap();
+    let resource_manager = ResourceManager::new(tmp_dir.path());
+    block_on(resource_manager.get_vanilla_version_list()).unwrap();
+    // Java isn't mocked, the override is never run while installing.
+    let mut settings: InstanceSettings = serde_json::from_value(json!({
+        "instanceName": "Fixture",
+        "vanillaVersion": "1.20.1",
+        "modloaderType": "None",
+        "modloaderVersion": "",
+        "instanceIcon": null,
+        "additionalJvmArguments": "",
+        "javaPathOverride": "/fixture/bin/java",
+        "resolutionWidth": "",
+        "resolutionHeight": "",
+        "startWindowMaximized": false,
+        "recordPlaytime": true,
+        "showRecordedPlaytime": true,
+        "overrideOptionsTxt": false,
+        "overrideServersDat": false,
+        "ignoreJavaRequirement": true,
+    }))
+    .unwrap();
+    settings.directory_name = "Fixture".into();
+
+    let instance = block_on(download_instance(
+        settings,
+        &resource_manager,
+        &LauncherSettings::default(),
+        None,
+    ))
+    .unwrap();
+    assert_eq!(instance.instance_name, "Fixture");
+    assert_eq!(instance.vanilla_version.as_deref(), Some("1.20.1"));
+    assert!(instance
+        .arguments
+        .contains(&"net.minecraft.client.main.Main".to_string()));
+
+    let libraries_dir = resource_manager.libraries_dir();
+    let library = libraries_dir.join("com/mojang/logging/1.1.1/logging-1.1.1.jar");
+    assert!(library.is_file());
+    assert!(instance
+        .arguments
+        .iter()
+        .any(|argument| argument.contains(&*library.to_string_lossy())));
+    let jar = resource_manager
+        .version_dir()
+        .join("1.20.1/client/1.20.1.jar");
+    assert!(jar.is_file());
+    let assets_dir = tmp_dir.path().join("assets");
+    assert!(assets_dir.join("indexes").join("5.json").is_file());
+    assert!(assets_dir
+        .join("objects/86/864bc7a7042bb0dac6ec23337ed5ab623f3730d5")
+        .is_file());
+    // The pinned manifests are saved in the instance.
+    assert!(resource_manager
+        .instances_dir()
+        .join("Fixture")
+        .join("manifest_snapshot.json")
+        .is_file());
+}
+
+#[test]
+fn test_download_modrinth_modpack_from_recorded_api() {
+    start_mock_server();
+    let tmp_dir = TempDir::new("mock_http").unwrap();
+
+    let path = block_on(download_modrinth_modpack("fixture-version", tmp_dir.path())).unwrap();
+    assert_eq!(
+        path,
+        tmp_dir
+            .path()
+            .join("modrinth")
+            .join("fixture-version")
+            .join("Fixture.mrpack")
+    );
+    // Installing the pack again uses the cached archive.
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    block_on(download_modrinth_modpack("fixture-version", tmp_dir.path())).unwrap();
+    assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+
+    let pack = File::open(&path).unwrap();
+    let mut archive = ZipArchive::new(&pack).unwrap();
+    read_modrinth_manifest(&mut archive).unwrap();
+}
+
+#[test]
+fn test_download_curseforge_mods_with_dependencies() {
+    start_mock_server();
+    let tmp_dir = TempDir::new("mock_http").unwrap();
+    let files: Vec<CurseforgeFile> =
+        serde_json::from_value(json!([{ "projectID": 100, "fileID": 4600001, "required": true }]))
+            .unwrap();
+    let info = CurseforgeManifestInfo {
+        game_version: "1.20.1".into(),
+        modloader_type: ModloaderType::Fabric,
+        allow_beta: false,
+    };
+
+    let instance_dir = tmp_dir.path().join("Pack");
+    let mod_cache = ModCache::new(&tmp_dir.path().join("cache"));
+    block_on(download_mods_from_curseforge(
+        &files,
+        &instance_dir,
+        info,
+        &mod_cache,
+    ))
+    .unwrap();
+
+    // The recorded library depends on the mod again, which must not be downloaded twice.
+    let mods_dir = instance_dir.join("mods");
+    let mut mods: Vec<_> = fs::read_dir(mods_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    mods.sort();
+    assert_eq!(
+        mods,
+        vec!["fixture-library-2.0.0.jar", "fixture-mod-1.0.0.jar"]
+    );
+}
+
 // use crate::{
 //     purge_old_logs,
 //     web_services::{downloader::download_all},
@@ -0,0 +1,17 @@
+//! Synthetic api responses in `fixtures/json`, shaped like the real ones but trimmed down to a few
+//! entries with made up hashes and sizes, for checking that the launcher still deserializes every
+//! shape of them. See `fixtures/README.md`.
+use std::{fs, path::PathBuf};
+
+use serde::de::DeserializeOwned;
+
+pub fn read_fixture<T: DeserializeOwned>(name: &str) -> T {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join("json")
+        .join(name);
+    let bytes = fs::read(&path)
+        .unwrap_or_else(|error| panic!("Missing fixture {}: {}", path.display(), error));
+    serde_json::from_slice(&bytes)
+        .unwrap_or_else(|error| panic!("Fixture {} no longer deserializes: {}", name, error))
+}
@@ -0,0 +1,252 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Duration, Local};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::web_services::resources::copy_recursively;
+
+/// Describes the trashed file or folder, stored next to it.
+const ENTRY_FILE: &str = "entry.json";
+/// What the trashed file or folder is renamed to in its entry's folder.
+const CONTENTS: &str = "contents";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrashKind {
+    Instance,
+    World,
+    Mod,
+}
+
+/// Something deleted from the launcher, kept until the trash retention period is over.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    pub id: String,
+    pub kind: TrashKind,
+    /// The instance's folder, the world's folder in `saves` or the mod's file name in `mods`.
+    pub name: String,
+    /// The instance it was deleted from, or the deleted instance.
+    pub instance_name: String,
+    /// RFC 3339 time it was deleted at.
+    pub trashed_at: String,
+}
+
+#[derive(Debug)]
+pub enum TrashError {
+    UnknownEntry(String),
+    /// Restoring would overwrite something created since it was deleted.
+    TargetExists(PathBuf),
+    Io(io::Error),
+}
+
+impl fmt::Display for TrashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrashError::UnknownEntry(id) => write!(f, "Nothing in the trash has the id `{}`", id),
+            TrashError::TargetExists(path) => {
+                write!(
+                    f,
+                    "Can't restore over {}, it already exists",
+                    path.display()
+                )
+            }
+            TrashError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<io::Error> for TrashError {
+    fn from(error: io::Error) -> Self {
+        TrashError::Io(error)
+    }
+}
+
+/// `name` in `dir`, as long as it is a plain file or folder name that can't point outside of it.
+pub fn child_path(dir: &Path, name: &str) -> io::Result<PathBuf> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("`{}` is not a valid file name", name),
+        ));
+    }
+    Ok(dir.join(name))
+}
+
+/// Moves the file or folder at `path` into the trash.
+pub fn move_to_trash(
+    trash_dir: &Path,
+    path: &Path,
+    kind: TrashKind,
+    instance_name: &str,
+) -> io::Result<TrashEntry> {
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} doesn't exist", path.display()),
+        ));
+    }
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let trashed_at = Local::now();
+    let base_id = trashed_at.format("%Y%m%d%H%M%S%3f").to_string();
+    let mut id = base_id.clone();
+    let mut suffix = 1;
+    while trash_dir.join(&id).exists() {
+        suffix += 1;
+        id = format!("{}-{}", base_id, suffix);
+    }
+    let entry_dir = trash_dir.join(&id);
+    fs::create_dir_all(&entry_dir)?;
+
+    let entry = TrashEntry {
+        id,
+        kind,
+        name,
+        instance_name: instance_name.into(),
+        trashed_at: trashed_at.to_rfc3339(),
+    };
+    serde_json::to_writer(File::create(entry_dir.join(ENTRY_FILE))?, &entry)?;
+    if let Err(e) = move_path(path, &entry_dir.join(CONTENTS)) {
+        // Don't leave an entry behind for something that wasn't trashed.
+        fs::remove_dir_all(&entry_dir)?;
+        return Err(e);
+    }
+    info!("Moved {} to the trash as {}", path.display(), entry.id);
+    Ok(entry)
+}
+
+/// The trashed entry with `id`.
+pub fn get_trash_entry(trash_dir: &Path, id: &str) -> Result<TrashEntry, TrashError> {
+    let path = child_path(trash_dir, id)
+        .map_err(|_| TrashError::UnknownEntry(id.into()))?
+        .join(ENTRY_FILE);
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(TrashError::UnknownEntry(id.into()))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    Ok(serde_json::from_reader(BufReader::new(file)).map_err(io::Error::from)?)
+}
+
+/// Everything in the trash, most recently deleted first. Entries that can't be read are skipped.
+pub fn list_trash(trash_dir: &Path) -> io::Result<Vec<TrashEntry>> {
+    if !trash_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for dir_entry in fs::read_dir(trash_dir)? {
+        let id = dir_entry?.file_name().to_string_lossy().into_owned();
+        match get_trash_entry(trash_dir, &id) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => warn!("Skipping trash entry {}: {}", id, e),
+        }
+    }
+    entries.sort_by(|a, b| b.trashed_at.cmp(&a.trashed_at));
+    Ok(entries)
+}
+
+/// Moves the entry `id` back to `target` and removes it from the trash.
+pub fn restore_trash_entry(
+    trash_dir: &Path,
+    id: &str,
+    target: &Path,
+) -> Result<TrashEntry, TrashError> {
+    let entry = get_trash_entry(trash_dir, id)?;
+    if target.exists() {
+        return Err(TrashError::TargetExists(target.into()));
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let entry_dir = trash_dir.join(&entry.id);
+    move_path(&entry_dir.join(CONTENTS), target)?;
+    fs::remove_dir_all(entry_dir)?;
+    info!("Restored {} from the trash to {}", id, target.display());
+    Ok(entry)
+}
+
+/// Permanently deletes the entries trashed more than `retention_days` days before `now`.
+/// Returns the deleted entries.
+pub fn purge_expired_trash(
+    trash_dir: &Path,
+    retention_days: u32,
+    now: DateTime<Local>,
+) -> io::Result<Vec<TrashEntry>> {
+    let cutoff = now - Duration::days(retention_days.into());
+    let mut purged = Vec::new();
+    for entry in list_trash(trash_dir)? {
+        let expired = DateTime::parse_from_rfc3339(&entry.trashed_at)
+            .map_or(true, |trashed_at| trashed_at < cutoff);
+        if expired {
+            fs::remove_dir_all(trash_dir.join(&entry.id))?;
+            purged.push(entry);
+        }
+    }
+    if !purged.is_empty() {
+        info!("Emptied {} expired entries from the trash", purged.len());
+    }
+    Ok(purged)
+}
+
+/// Renames `from` to `to`, copying and removing it if they are on different drives.
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_recursively(from, to)?;
+    if from.is_dir() {
+        fs::remove_dir_all(from)
+    } else {
+        fs::remove_file(from)
+    }
+}
+
+#[cfg(test)]
+use tempdir::TempDir;
+
+#[test]
+fn test_trash() {
+    let tmp_dir = TempDir::new("trash").unwrap();
+    let trash_dir = tmp_dir.path().join("trash");
+    let world_dir = tmp_dir.path().join("Vanilla").join("saves").join("World");
+    fs::create_dir_all(&world_dir).unwrap();
+    fs::write(world_dir.join("level.dat"), "level").unwrap();
+
+    let entry = move_to_trash(&trash_dir, &world_dir, TrashKind::World, "Vanilla").unwrap();
+    assert!(!world_dir.exists());
+    assert_eq!(entry.name, "World");
+    assert_eq!(list_trash(&trash_dir).unwrap(), [entry.clone()]);
+
+    fs::create_dir_all(&world_dir).unwrap();
+    assert!(matches!(
+        restore_trash_entry(&trash_dir, &entry.id, &world_dir),
+        Err(TrashError::TargetExists(_))
+    ));
+    fs::remove_dir(&world_dir).unwrap();
+    restore_trash_entry(&trash_dir, &entry.id, &world_dir).unwrap();
+    assert_eq!(fs::read(world_dir.join("level.dat")).unwrap(), b"level");
+    assert!(list_trash(&trash_dir).unwrap().is_empty());
+    assert!(matches!(
+        restore_trash_entry(&trash_dir, "../Vanilla", &world_dir),
+        Err(TrashError::UnknownEntry(_))
+    ));
+
+    let entry = move_to_trash(&trash_dir, &world_dir, TrashKind::World, "Vanilla").unwrap();
+    assert!(purge_expired_trash(&trash_dir, 30, Local::now())
+        .unwrap()
+        .is_empty());
+    let purged = purge_expired_trash(&trash_dir, 30, Local::now() + Duration::days(31)).unwrap();
+    assert_eq!(purged, [entry]);
+    assert!(!trash_dir.join(&purged[0].id).exists());
+}
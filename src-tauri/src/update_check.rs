@@ -0,0 +1,320 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
+    sync::Mutex,
+    time::Duration,
+};
+
+use autmc_downloader::{download_json_object_from_url, hash_bytes_sha1, send_with_retry};
+use bytes::Bytes;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    consts::MODRINTH_API_URL,
+    deep_link::DeepLinkRequest,
+    state::{
+        instance_manager::{InstanceConfiguration, InstanceSubdir},
+        resource_manager::ResourceManager,
+    },
+    web_services::{modpack::curseforge::download_main_file_id, resources::ModloaderType},
+};
+
+/// How often the watched instances are checked for updates while the launcher is open.
+pub const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Set on instances the user watches for updates.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateWatch {
+    /// The pack version the instance was installed from, `None` if it wasn't installed from a
+    /// pack or it isn't known.
+    pub pack: Option<DeepLinkRequest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionUpdate {
+    pub current: String,
+    pub latest: String,
+}
+
+/// A mod in the instance's mods folder with a newer version on modrinth.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModUpdate {
+    pub file_name: String,
+    pub latest: String,
+}
+
+/// The updates found for a watched instance.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceUpdates {
+    pub instance_name: String,
+    pub loader: Option<VersionUpdate>,
+    pub pack: Option<VersionUpdate>,
+    pub mods: Vec<ModUpdate>,
+}
+
+impl InstanceUpdates {
+    pub fn is_empty(&self) -> bool {
+        self.loader.is_none() && self.pack.is_none() && self.mods.is_empty()
+    }
+}
+
+/// The updates found by the last check, only for instances that have any.
+#[derive(Debug, Default)]
+pub struct UpdateNotificationsState(pub Mutex<Vec<InstanceUpdates>>);
+
+#[derive(Debug, Deserialize)]
+struct ModrinthFileHashes {
+    sha1: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModrinthFile {
+    hashes: ModrinthFileHashes,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModrinthVersion {
+    id: String,
+    version_number: String,
+    files: Vec<ModrinthFile>,
+}
+
+/// The numbers in `version`, `1.20.1-47.2.0` is `[1, 20, 1, 47, 2, 0]`.
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+/// The newest of `versions`, if it is newer than `current`.
+fn newer_version<'a>(
+    current: &str,
+    versions: impl IntoIterator<Item = &'a String>,
+) -> Option<VersionUpdate> {
+    let latest = versions
+        .into_iter()
+        .max_by_key(|version| version_key(version))?;
+    (version_key(latest) > version_key(current)).then(|| VersionUpdate {
+        current: current.into(),
+        latest: latest.clone(),
+    })
+}
+
+/// The mods in `mod_files`, by their sha1, whose newest compatible version in `latest_versions` is
+/// a different file.
+fn outdated_mods(
+    mod_files: &BTreeMap<String, String>,
+    latest_versions: &HashMap<String, ModrinthVersion>,
+) -> Vec<ModUpdate> {
+    mod_files
+        .iter()
+        .filter_map(|(sha1, file_name)| {
+            let latest = latest_versions.get(sha1)?;
+            if latest.files.iter().any(|file| &file.hashes.sha1 == sha1) {
+                return None;
+            }
+            Some(ModUpdate {
+                file_name: file_name.clone(),
+                latest: latest.version_number.clone(),
+            })
+        })
+        .collect()
+}
+
+/// The enabled jars in `mods_dir` by their sha1.
+fn mod_files(mods_dir: &Path) -> io::Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    if !mods_dir.is_dir() {
+        return Ok(files);
+    }
+    for entry in fs::read_dir(mods_dir)? {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().map_or(true, |ext| ext != "jar") {
+            continue;
+        }
+        let sha1 = hash_bytes_sha1(&Bytes::from(fs::read(&path)?));
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        files.insert(sha1, file_name.into_owned());
+    }
+    Ok(files)
+}
+
+async fn check_loader(
+    config: &InstanceConfiguration,
+    resource_manager: &ResourceManager,
+) -> reqwest::Result<Option<VersionUpdate>> {
+    let current = &config.modloader_version;
+    Ok(match config.modloader_type {
+        ModloaderType::Fabric => newer_version(
+            current,
+            &resource_manager.get_stable_fabric_versions().await?,
+        ),
+        ModloaderType::Forge => {
+            let forge_versions = resource_manager.get_forge_version_list().await?;
+            config
+                .vanilla_version()
+                .and_then(|version| forge_versions.get(version))
+                .and_then(|versions| newer_version(current, versions))
+        }
+        ModloaderType::None => None,
+    })
+}
+
+async fn check_pack(pack: &DeepLinkRequest) -> reqwest::Result<Option<VersionUpdate>> {
+    match pack {
+        DeepLinkRequest::Curseforge {
+            project_id,
+            file_id,
+        } => {
+            let main_file_id = download_main_file_id(*project_id).await?;
+            Ok((main_file_id != *file_id).then(|| VersionUpdate {
+                current: file_id.to_string(),
+                latest: main_file_id.to_string(),
+            }))
+        }
+        DeepLinkRequest::Modrinth {
+            project_id,
+            version_id,
+        } => {
+            // Newest first.
+            let url = format!("{}/project/{}/version", MODRINTH_API_URL, project_id);
+            let versions: Vec<ModrinthVersion> = download_json_object_from_url(&url).await?;
+            let latest = match versions.first() {
+                Some(latest) if &latest.id != version_id => latest,
+                _ => return Ok(None),
+            };
+            let current = versions
+                .iter()
+                .find(|version| &version.id == version_id)
+                .map_or(version_id, |version| &version.version_number);
+            Ok(Some(VersionUpdate {
+                current: current.clone(),
+                latest: latest.version_number.clone(),
+            }))
+        }
+    }
+}
+
+/// Looks up the mods in `mods_dir` on modrinth by their hash. Mods that aren't on modrinth are
+/// skipped.
+async fn check_mods(
+    mods_dir: &Path,
+    modloader_type: &ModloaderType,
+    game_version: &str,
+) -> Result<Vec<ModUpdate>, Box<dyn std::error::Error + Send + Sync>> {
+    let files = mod_files(mods_dir)?;
+    if files.is_empty() {
+        return Ok(Vec::new());
+    }
+    let body = json!({
+        "hashes": files.keys().collect::<Vec<_>>(),
+        "algorithm": "sha1",
+        "loaders": [modloader_type.to_string()],
+        "game_versions": [game_version],
+    });
+    let request = reqwest::Client::new()
+        .post(format!("{}/version_files/update", MODRINTH_API_URL))
+        .json(&body);
+    let latest_versions: HashMap<String, ModrinthVersion> = send_with_retry(request)
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(outdated_mods(&files, &latest_versions))
+}
+
+/// Checks the instance stored in `instance_dir` for a newer loader build, pack version and mod
+/// versions. Parts that can't be checked are logged and left out.
+pub async fn check_instance_updates(
+    config: &InstanceConfiguration,
+    instance_dir: &Path,
+    resource_manager: &ResourceManager,
+) -> InstanceUpdates {
+    let name = &config.instance_name;
+    let loader = check_loader(config, resource_manager)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Could not check {} for loader updates: {}", name, e);
+            None
+        });
+    let pack = match config
+        .update_watch
+        .as_ref()
+        .and_then(|watch| watch.pack.as_ref())
+    {
+        Some(pack) => check_pack(pack).await.unwrap_or_else(|e| {
+            warn!("Could not check {} for pack updates: {}", name, e);
+            None
+        }),
+        None => None,
+    };
+    let mods = match (&config.modloader_type, config.vanilla_version()) {
+        (ModloaderType::None, _) | (_, None) => Vec::new(),
+        (modloader_type, Some(game_version)) => {
+            let mods_dir = InstanceSubdir::Mods.path_in(instance_dir);
+            check_mods(&mods_dir, modloader_type, game_version)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Could not check {} for mod updates: {}", name, e);
+                    Vec::new()
+                })
+        }
+    };
+    InstanceUpdates {
+        instance_name: name.clone(),
+        loader,
+        pack,
+        mods,
+    }
+}
+
+#[test]
+fn test_newer_version() {
+    let forge: Vec<String> = vec!["1.20.1-47.0.3".into(), "1.20.1-47.2.0".into()];
+    assert_eq!(
+        newer_version("1.20.1-47.1.0", &forge),
+        Some(VersionUpdate {
+            current: "1.20.1-47.1.0".into(),
+            latest: "1.20.1-47.2.0".into(),
+        })
+    );
+    assert_eq!(newer_version("1.20.1-47.2.0", &forge), None);
+    let fabric: Vec<String> = vec!["0.14.21".into(), "0.14.9".into()];
+    assert_eq!(newer_version("0.14.21", &fabric), None);
+    assert_eq!(newer_version("0.14.21", &Vec::new()), None);
+}
+
+#[test]
+fn test_outdated_mods() {
+    let mod_files = BTreeMap::from([
+        ("aaa".to_string(), "sodium-0.4.jar".to_string()),
+        ("bbb".to_string(), "lithium.jar".to_string()),
+        ("ccc".to_string(), "private.jar".to_string()),
+    ]);
+    let version = |sha1: &str, version_number: &str| ModrinthVersion {
+        id: version_number.into(),
+        version_number: version_number.into(),
+        files: vec![ModrinthFile {
+            hashes: ModrinthFileHashes { sha1: sha1.into() },
+        }],
+    };
+    let latest_versions = HashMap::from([
+        ("aaa".to_string(), version("ddd", "0.5.0")),
+        ("bbb".to_string(), version("bbb", "0.11.2")),
+    ]);
+    assert_eq!(
+        outdated_mods(&mod_files, &latest_versions),
+        [ModUpdate {
+            file_name: "sodium-0.4.jar".into(),
+            latest: "0.5.0".into(),
+        }]
+    );
+}
@@ -1,4 +1,8 @@
-pub mod downloader;
-pub mod manifest;
+pub mod api_cache;
+pub mod custom_version;
+pub mod install_report;
+pub mod mod_cache;
 pub mod modpack;
 pub mod resources;
+
+pub use autmc_core::manifest;
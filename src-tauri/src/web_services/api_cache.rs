@@ -0,0 +1,134 @@
+use std::{
+    fmt, fs,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use log::{info, warn};
+use serde::{de::DeserializeOwned, Serialize};
+use tauri::{AppHandle, Manager, Wry};
+
+/// Api responses are cached in this directory of the launcher's cache directory.
+const API_CACHE_DIR: &str = "api";
+
+#[derive(Debug)]
+pub enum ApiCacheError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Http(reqwest::Error),
+}
+
+impl fmt::Display for ApiCacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiCacheError::Io(error) => write!(f, "{}", error),
+            ApiCacheError::Json(error) => write!(f, "{}", error),
+            ApiCacheError::Http(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<io::Error> for ApiCacheError {
+    fn from(error: io::Error) -> Self {
+        ApiCacheError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ApiCacheError {
+    fn from(error: serde_json::Error) -> Self {
+        ApiCacheError::Json(error)
+    }
+}
+
+impl From<reqwest::Error> for ApiCacheError {
+    fn from(error: reqwest::Error) -> Self {
+        ApiCacheError::Http(error)
+    }
+}
+
+pub type ApiCacheResult<T> = Result<T, ApiCacheError>;
+
+/// Path of the cached response named `name`.
+pub fn api_cache_path(app_handle: &AppHandle<Wry>, name: &str) -> tauri::Result<PathBuf> {
+    Ok(app_handle
+        .path()
+        .app_cache_dir()?
+        .join(API_CACHE_DIR)
+        .join(format!("{}.json", name)))
+}
+
+/// Returns the response cached at `path` if it's younger than `ttl`, otherwise `fetch`es and caches
+/// a new one. When fetching fails the stale response is returned instead, so the launcher keeps
+/// working offline.
+pub async fn cached_response<T, F>(path: &Path, ttl: Duration, fetch: F) -> ApiCacheResult<T>
+where
+    T: Serialize + DeserializeOwned,
+    F: Future<Output = reqwest::Result<T>>,
+{
+    let age = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(|modified| {
+            SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default()
+        });
+    if age.map_or(false, |age| age < ttl) {
+        match read_cached(path) {
+            Ok(response) => return Ok(response),
+            Err(error) => warn!("Ignoring unreadable cache {}: {}", path.display(), error),
+        }
+    }
+
+    match fetch.await {
+        Ok(response) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, serde_json::to_vec(&response)?)?;
+            Ok(response)
+        }
+        Err(error) if age.is_some() => {
+            info!(
+                "Using stale cache {} after the request failed: {}",
+                path.display(),
+                error
+            );
+            read_cached(path)
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn read_cached<T: DeserializeOwned>(path: &Path) -> ApiCacheResult<T> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+#[test]
+fn test_cached_response() {
+    use tauri::async_runtime::block_on;
+
+    let tmp_dir = tempdir::TempDir::new("api_cache").unwrap();
+    let path = tmp_dir.path().join("api").join("categories.json");
+    let fetched = |value: u32| async move { Ok::<_, reqwest::Error>(vec![value]) };
+
+    let response = block_on(cached_response(&path, Duration::from_secs(60), fetched(1)));
+    assert_eq!(response.unwrap(), vec![1]);
+    // Still fresh, the cached response is used.
+    let response = block_on(cached_response(&path, Duration::from_secs(60), fetched(2)));
+    assert_eq!(response.unwrap(), vec![1]);
+    // Expired, the new response replaces it.
+    let response = block_on(cached_response(&path, Duration::ZERO, fetched(3)));
+    assert_eq!(response.unwrap(), vec![3]);
+
+    // Failed requests fall back to the stale response.
+    let failed = async {
+        reqwest::get("http://127.0.0.1:0")
+            .await
+            .map(|_| Vec::<u32>::new())
+    };
+    let response = block_on(cached_response(&path, Duration::ZERO, failed));
+    assert_eq!(response.unwrap(), vec![3]);
+}
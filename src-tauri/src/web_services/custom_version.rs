@@ -0,0 +1,141 @@
+use std::io::{Cursor, Read};
+
+use autmc_downloader::hash_bytes_sha1;
+use bytes::Bytes;
+use serde_json::{json, Value};
+use zip::ZipArchive;
+
+use crate::{
+    consts::ZIP_SIGNATURE,
+    state::resource_manager::{ManifestError, ManifestResult},
+    web_services::manifest::vanilla::{VanillaManifestVersion, VanillaVersion},
+};
+
+/// A version that isn't in the vanilla manifest, like the experimental snapshots Mojang publishes
+/// as standalone zips, read from its version json or the zip containing it.
+#[derive(Debug)]
+pub struct CustomVersion {
+    /// Stands in for the version's vanilla manifest entry.
+    pub entry: VanillaManifestVersion,
+    pub json: Vec<u8>,
+    /// The client jar, if it came in a zip with the json.
+    pub client_jar: Option<Vec<u8>>,
+}
+
+/// Reads a version json, or a zip containing one and optionally its client jar, downloaded from
+/// or found at `source`.
+pub fn read_custom_version(bytes: &[u8], source: &str) -> ManifestResult<CustomVersion> {
+    let (json_bytes, client_jar) = if bytes.starts_with(&ZIP_SIGNATURE) {
+        read_version_zip(bytes)?
+    } else {
+        (bytes.to_vec(), None)
+    };
+    let mut json: Value = serde_json::from_slice(&json_bytes)?;
+    let id = json["id"]
+        .as_str()
+        .filter(|id| is_valid_version_id(id))
+        .ok_or_else(|| ManifestError::ResourceError(format!("{} has no valid version id", source)))?
+        .to_owned();
+    // Jsons shipped next to their jar don't always say where to download it from. The jar is
+    // cached before installing, so it is never downloaded.
+    if let Some(jar) = &client_jar {
+        if json.pointer("/downloads/client").is_none() {
+            json["downloads"]["client"] = json!({
+                "sha1": hash_bytes_sha1(&Bytes::copy_from_slice(jar)),
+                "size": jar.len(),
+                "url": source,
+            });
+        }
+    }
+    let version_type = json["type"].as_str().unwrap_or("snapshot").to_owned();
+    let release_time = json["releaseTime"].as_str().unwrap_or_default().to_owned();
+    let json = serde_json::to_vec(&json)?;
+    // Fail on import rather than when creating an instance with it.
+    serde_json::from_slice::<VanillaVersion>(&json)?;
+
+    let sha1 = hash_bytes_sha1(&Bytes::copy_from_slice(&json));
+    Ok(CustomVersion {
+        entry: VanillaManifestVersion::new(id, version_type, source.into(), release_time, sha1),
+        json,
+        client_jar,
+    })
+}
+
+/// The first json and jar in the zip.
+fn read_version_zip(bytes: &[u8]) -> ManifestResult<(Vec<u8>, Option<Vec<u8>>)> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let mut json = None;
+    let mut jar = None;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let name = file.name().to_owned();
+        let target = if name.ends_with(".json") && json.is_none() {
+            &mut json
+        } else if name.ends_with(".jar") && jar.is_none() {
+            &mut jar
+        } else {
+            continue;
+        };
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        *target = Some(contents);
+    }
+    let json = json.ok_or_else(|| {
+        ManifestError::ResourceError("The zip doesn't contain a version json".into())
+    })?;
+    Ok((json, jar))
+}
+
+/// Version ids name folders in `versions`, so they can't contain path separators.
+fn is_valid_version_id(id: &str) -> bool {
+    !id.is_empty() && id != "." && id != ".." && !id.contains(['/', '\\'])
+}
+
+#[cfg(test)]
+use {std::io::Write, zip::write::SimpleFileOptions};
+
+#[test]
+fn test_read_custom_version() {
+    let json = json!({
+        "id": "1.14_combat-212796",
+        "type": "snapshot",
+        "releaseTime": "2019-11-29T09:00:00+00:00",
+        "minecraftArguments": "--username ${auth_player_name}",
+        "assetIndex": {"id": "1.14", "sha1": "a", "size": 1, "url": "https://example.com/1.14.json"},
+        "libraries": [],
+        "mainClass": "net.minecraft.client.main.Main",
+    });
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file(
+        "1_14_combat-212796/1.14_combat-212796.json",
+        SimpleFileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(json.to_string().as_bytes()).unwrap();
+    zip.start_file(
+        "1_14_combat-212796/1.14_combat-212796.jar",
+        SimpleFileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(b"jar").unwrap();
+    let bytes = zip.finish().unwrap().into_inner();
+
+    let source = "https://launcher.mojang.com/experiments/combat/1_14_combat-212796.zip";
+    let version = read_custom_version(&bytes, source).unwrap();
+    assert_eq!(version.entry.id(), "1.14_combat-212796");
+    assert_eq!(version.entry.version_type(), "snapshot");
+    assert_eq!(version.entry.url(), source);
+    assert_eq!(version.client_jar.as_deref(), Some(&b"jar"[..]));
+    let json: Value = serde_json::from_slice(&version.json).unwrap();
+    assert_eq!(json["downloads"]["client"]["url"], source);
+    assert_eq!(json["downloads"]["client"]["size"], 3);
+
+    // Without the jar the json has to say where to download it from.
+    assert!(read_custom_version(json.to_string().as_bytes(), source).is_ok());
+    let mut without_downloads = json.clone();
+    without_downloads["downloads"] = Value::Null;
+    assert!(read_custom_version(without_downloads.to_string().as_bytes(), source).is_err());
+    let mut bad_id = json;
+    bad_id["id"] = "../escape".into();
+    assert!(read_custom_version(bad_id.to_string().as_bytes(), source).is_err());
+}
@@ -0,0 +1,323 @@
+use std::{
+    fs::{self, File},
+    future::Future,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use autmc_downloader::DownloadProgress;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use url::Url;
+
+use crate::shutdown::TaskState;
+
+/// Install reports are appended to this file in the launcher's log directory, one json object per line.
+const INSTALL_REPORTS_FILE: &str = "install_reports.jsonl";
+/// Only the most recent reports are kept.
+const MAX_INSTALL_REPORTS: usize = 100;
+
+tokio::task_local! {
+    static CURRENT_INSTALL: Arc<Mutex<InstallReport>>;
+    /// Where the progress of the install running on this task is emitted to.
+    static PROGRESS_TARGET: (AppHandle<Wry>, String);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallPhase {
+    Manifest,
+    Java,
+    Libraries,
+    Assets,
+    Mods,
+    Patching,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PhaseTiming {
+    phase: InstallPhase,
+    millis: u64,
+}
+
+/// Requests made to a single host. `millis` is summed over every request, downloads running in
+/// parallel are counted separately.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HostTiming {
+    host: String,
+    requests: u32,
+    bytes: u64,
+    millis: u64,
+}
+
+/// How long each phase of an instance installation took and which hosts were downloaded from.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallReport {
+    instance_name: String,
+    started_at: String,
+    total_millis: u64,
+    succeeded: bool,
+    phases: Vec<PhaseTiming>,
+    hosts: Vec<HostTiming>,
+}
+
+impl InstallReport {
+    fn new(instance_name: &str) -> Self {
+        Self {
+            instance_name: instance_name.into(),
+            started_at: chrono::Local::now().to_rfc3339(),
+            total_millis: 0,
+            succeeded: false,
+            phases: Vec::new(),
+            hosts: Vec::new(),
+        }
+    }
+
+    pub fn instance_name(&self) -> &str {
+        &self.instance_name
+    }
+
+    fn add_phase(&mut self, phase: InstallPhase, elapsed: Duration) {
+        let millis = elapsed.as_millis() as u64;
+        match self.phases.iter_mut().find(|timing| timing.phase == phase) {
+            Some(timing) => timing.millis += millis,
+            None => self.phases.push(PhaseTiming { phase, millis }),
+        }
+    }
+
+    fn add_request(&mut self, host: &str, bytes: u64, elapsed: Duration) {
+        let millis = elapsed.as_millis() as u64;
+        match self.hosts.iter_mut().find(|timing| timing.host == host) {
+            Some(timing) => {
+                timing.requests += 1;
+                timing.bytes += bytes;
+                timing.millis += millis;
+            }
+            None => self.hosts.push(HostTiming {
+                host: host.into(),
+                requests: 1,
+                bytes,
+                millis,
+            }),
+        }
+    }
+}
+
+/// Emitted as `install-progress` while an install downloads files. Only counts are sent so the
+/// frontend can show determinate progress bars, estimate the time left and describe it however it
+/// likes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallProgress {
+    instance_name: String,
+    phase: InstallPhase,
+    #[serde(flatten)]
+    progress: DownloadProgress,
+}
+
+/// Emits the download progress of `phase` of the install running on this task, if there is one.
+pub fn report_progress(phase: InstallPhase, progress: DownloadProgress) {
+    let _ = PROGRESS_TARGET.try_with(|(app_handle, instance_name)| {
+        let payload = InstallProgress {
+            instance_name: instance_name.clone(),
+            phase,
+            progress,
+        };
+        if let Err(error) = app_handle.emit_to("main", "install-progress", payload) {
+            error!("Could not emit install progress: {}", error);
+        }
+    });
+}
+
+/// Records the time since `started` against `phase` of the install running on this task.
+pub fn record_phase(phase: InstallPhase, started: Instant) {
+    let _ = CURRENT_INSTALL.try_with(|report| {
+        report.lock().unwrap().add_phase(phase, started.elapsed());
+    });
+}
+
+/// Records a finished request against its host if an install is running on this task.
+pub fn record_request(url: &str, bytes: u64, started: Instant) {
+    let _ = CURRENT_INSTALL.try_with(|report| {
+        if let Some(host) = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(String::from))
+        {
+            report
+                .lock()
+                .unwrap()
+                .add_request(&host, bytes, started.elapsed());
+        }
+    });
+}
+
+async fn run_tracked<F, T, E>(instance_name: &str, future: F) -> (InstallReport, Result<T, E>)
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let report = Arc::new(Mutex::new(InstallReport::new(instance_name)));
+    let start = Instant::now();
+    let result = CURRENT_INSTALL.scope(report.clone(), future).await;
+
+    let mut report = report.lock().unwrap().clone();
+    report.total_millis = start.elapsed().as_millis() as u64;
+    report.succeeded = result.is_ok();
+    report
+        .hosts
+        .sort_by(|a, b| b.millis.cmp(&a.millis).then(a.host.cmp(&b.host)));
+    (report, result)
+}
+
+/// Runs an instance installation, timing its phases and requests, then saves the report to the
+/// launcher's log directory. Installs nested in an already tracked install are part of its report.
+/// Cancelling the install when the launcher exits removes the instance if it didn't exist before.
+pub async fn track_install<F, T, E>(
+    app_handle: &AppHandle<Wry>,
+    instance_name: &str,
+    instance_dir: &Path,
+    future: F,
+) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    E: From<io::Error>,
+{
+    if CURRENT_INSTALL.try_with(|_| ()).is_ok() {
+        return future.await;
+    }
+
+    let is_new_instance = !instance_dir.exists();
+    let progress_target = (app_handle.clone(), instance_name.to_owned());
+    let tracked = PROGRESS_TARGET.scope(progress_target, run_tracked(instance_name, future));
+    let task_name = format!("Installing {}", instance_name);
+    let (report, result) = match app_handle
+        .state::<TaskState>()
+        .0
+        .run(&task_name, tracked)
+        .await
+    {
+        Some(output) => output,
+        None => {
+            if is_new_instance && instance_dir.exists() {
+                fs::remove_dir_all(instance_dir)?;
+            }
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                format!("The install of {} was cancelled", instance_name),
+            )
+            .into());
+        }
+    };
+    info!(
+        "Install of {} took {}ms: {:?}",
+        instance_name, report.total_millis, report.phases
+    );
+    match install_reports_path(app_handle) {
+        Ok(path) => {
+            if let Err(error) = append_install_report(&path, &report) {
+                error!("Could not save install report: {}", error);
+            }
+        }
+        Err(error) => error!("{}", error),
+    }
+    result
+}
+
+pub fn install_reports_path(app_handle: &AppHandle<Wry>) -> tauri::Result<PathBuf> {
+    Ok(app_handle.path().app_log_dir()?.join(INSTALL_REPORTS_FILE))
+}
+
+fn read_install_reports(path: &Path) -> io::Result<Vec<InstallReport>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let reader = BufReader::new(File::open(path)?);
+    let mut reports = Vec::new();
+    for line in reader.lines() {
+        // Skip lines that can't be read instead of losing every report.
+        if let Ok(report) = serde_json::from_str(&line?) {
+            reports.push(report);
+        }
+    }
+    Ok(reports)
+}
+
+fn append_install_report(path: &Path, report: &InstallReport) -> io::Result<()> {
+    let mut reports = read_install_reports(path)?;
+    reports.push(report.clone());
+    let skip = reports.len().saturating_sub(MAX_INSTALL_REPORTS);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = File::create(path)?;
+    for report in &reports[skip..] {
+        writeln!(file, "{}", serde_json::to_string(report)?)?;
+    }
+    Ok(())
+}
+
+/// The most recent install report, optionally only considering installs of `instance_name`.
+pub fn last_install_report(
+    path: &Path,
+    instance_name: Option<&str>,
+) -> io::Result<Option<InstallReport>> {
+    Ok(read_install_reports(path)?
+        .into_iter()
+        .rev()
+        .find(|report| instance_name.map_or(true, |name| report.instance_name == name)))
+}
+
+#[test]
+fn test_install_report_timings() {
+    let (report, result) = futures::executor::block_on(run_tracked("Test", async {
+        let started = Instant::now() - Duration::from_millis(20);
+        record_phase(InstallPhase::Libraries, started);
+        record_phase(InstallPhase::Libraries, started);
+        record_request("https://libraries.minecraft.net/a.jar", 10, started);
+        record_request(
+            "https://resources.download.minecraft.net/ab/abc",
+            5,
+            Instant::now(),
+        );
+        record_request("https://libraries.minecraft.net/b.jar", 20, started);
+        Ok::<_, io::Error>(())
+    }));
+    assert!(result.is_ok());
+    assert!(report.succeeded);
+    assert_eq!(report.phases.len(), 1);
+    assert!(report.phases[0].millis >= 40);
+
+    // Slowest host first.
+    assert_eq!(report.hosts[0].host, "libraries.minecraft.net");
+    assert_eq!(report.hosts[0].requests, 2);
+    assert_eq!(report.hosts[0].bytes, 30);
+    assert_eq!(report.hosts[1].host, "resources.download.minecraft.net");
+}
+
+#[test]
+fn test_install_report_file() {
+    let tmp_dir = tempdir::TempDir::new("reports").unwrap();
+    let path = tmp_dir.path().join(INSTALL_REPORTS_FILE);
+    assert_eq!(last_install_report(&path, None).unwrap(), None);
+
+    for name in ["A", "B", "A"] {
+        let mut report = InstallReport::new(name);
+        report.total_millis = name.len() as u64;
+        append_install_report(&path, &report).unwrap();
+    }
+    assert_eq!(read_install_reports(&path).unwrap().len(), 3);
+    let last = last_install_report(&path, Some("B")).unwrap().unwrap();
+    assert_eq!(last.instance_name(), "B");
+    assert!(last_install_report(&path, Some("C")).unwrap().is_none());
+
+    for _ in 0..MAX_INSTALL_REPORTS {
+        append_install_report(&path, &InstallReport::new("C")).unwrap();
+    }
+    let reports = read_install_reports(&path).unwrap();
+    assert_eq!(reports.len(), MAX_INSTALL_REPORTS);
+    assert!(reports.iter().all(|report| report.instance_name == "C"));
+}
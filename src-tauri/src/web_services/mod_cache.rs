@@ -0,0 +1,95 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use autmc_downloader::hash_bytes_sha1;
+use bytes::Bytes;
+use log::{debug, warn};
+
+/// Mods downloaded for any instance, stored by their SHA1 so a mod shared by several instances or
+/// packs is only downloaded once. Cached files are hard linked into instances, or copied where
+/// that isn't possible, like across drives.
+#[derive(Debug, Clone)]
+pub struct ModCache {
+    dir: PathBuf,
+}
+
+impl ModCache {
+    pub fn new(dir: &Path) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn object_path(&self, sha1: &str) -> PathBuf {
+        // Split by the first two letters of the hash like asset objects, so no folder gets huge.
+        self.dir.join(sha1.get(..2).unwrap_or(sha1)).join(sha1)
+    }
+
+    /// Links the cached file with the hash `sha1` to `path`. Returns false if it isn't cached, or
+    /// the cached file doesn't match its hash anymore and was removed.
+    pub fn install(&self, sha1: &str, path: &Path) -> io::Result<bool> {
+        let object_path = self.object_path(sha1);
+        let bytes = match fs::read(&object_path) {
+            Ok(bytes) => Bytes::from(bytes),
+            Err(_) => return Ok(false),
+        };
+        // A hard linked file changes with every instance it is linked into.
+        if hash_bytes_sha1(&bytes) != sha1 {
+            warn!("Cached mod {} was modified, removing it", sha1);
+            fs::remove_file(&object_path)?;
+            return Ok(false);
+        }
+        debug!("Using cached mod for {}", path.display());
+        link_file(&object_path, path)?;
+        Ok(true)
+    }
+
+    /// Caches the downloaded `bytes`, which must hash to `sha1`, and links them to `path`.
+    pub fn store(&self, sha1: &str, bytes: &[u8], path: &Path) -> io::Result<()> {
+        let object_path = self.object_path(sha1);
+        if let Some(parent) = object_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&object_path, bytes)?;
+        link_file(&object_path, path)
+    }
+}
+
+fn link_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Writing over the old file would change the cached copy it may be linked to.
+    if to.exists() {
+        fs::remove_file(to)?;
+    }
+    if let Err(e) = fs::hard_link(from, to) {
+        debug!("Could not hard link {}, copying it: {}", to.display(), e);
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+use tempdir::TempDir;
+
+#[test]
+fn test_mod_cache() {
+    let tmp_dir = TempDir::new("mod_cache").unwrap();
+    let cache = ModCache::new(&tmp_dir.path().join("cache"));
+    let bytes = Bytes::from_static(b"mod");
+    let sha1 = hash_bytes_sha1(&bytes);
+    let first = tmp_dir.path().join("First").join("mods").join("a.jar");
+    let second = tmp_dir.path().join("Second").join("mods").join("a.jar");
+
+    assert!(!cache.install(&sha1, &second).unwrap());
+    cache.store(&sha1, &bytes, &first).unwrap();
+    assert!(cache.install(&sha1, &second).unwrap());
+    assert_eq!(fs::read(&second).unwrap(), b"mod");
+
+    // A linked file edited in an instance isn't handed out again.
+    fs::remove_file(&second).unwrap();
+    fs::write(cache.object_path(&sha1), "edited").unwrap();
+    assert!(!cache.install(&sha1, &second).unwrap());
+    assert!(!cache.object_path(&sha1).exists());
+}
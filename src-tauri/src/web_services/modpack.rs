@@ -1,2 +1,76 @@
+use std::{fmt, fs::File};
+
+use zip::{result::ZipError, ZipArchive};
+
+use super::manifest::bytes_from_zip_file;
+
 pub mod curseforge;
 pub mod modrinth;
+
+pub const CURSEFORGE_MANIFEST_FILE: &str = "manifest.json";
+pub const MODRINTH_INDEX_FILE: &str = "modrinth.index.json";
+
+/// Why a modpack archive can't be imported, reported before anything is installed.
+#[derive(Debug)]
+pub enum PackFormatError {
+    Zip(ZipError),
+    MissingManifest(&'static str),
+    InvalidManifest(serde_json::Error),
+    UnsupportedManifestVersion(u32),
+    UnsupportedGame(String),
+    /// A `.mrpack` that was renamed to `.zip`.
+    MisnamedModrinthPack,
+    UnsupportedExtension(String),
+}
+
+impl fmt::Display for PackFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackFormatError::Zip(error) => write!(f, "Not a valid modpack archive: {}", error),
+            PackFormatError::MissingManifest(manifest) => {
+                write!(f, "The modpack is missing its `{}`", manifest)
+            }
+            PackFormatError::InvalidManifest(error) => {
+                write!(f, "The modpack's manifest is malformed: {}", error)
+            }
+            PackFormatError::UnsupportedManifestVersion(version) => {
+                write!(f, "Unsupported modpack manifest version {}", version)
+            }
+            PackFormatError::UnsupportedGame(game) => {
+                write!(f, "The modpack is made for `{}`, not Minecraft", game)
+            }
+            PackFormatError::MisnamedModrinthPack => f.write_str(
+                "This is a Modrinth modpack, rename it to end in `.mrpack` to import it",
+            ),
+            PackFormatError::UnsupportedExtension(extension) => {
+                write!(f, "Unsupported modpack file type `{}`", extension)
+            }
+        }
+    }
+}
+
+impl From<ZipError> for PackFormatError {
+    fn from(error: ZipError) -> Self {
+        PackFormatError::Zip(error)
+    }
+}
+
+impl From<serde_json::Error> for PackFormatError {
+    fn from(error: serde_json::Error) -> Self {
+        PackFormatError::InvalidManifest(error)
+    }
+}
+
+pub type PackFormatResult<T> = Result<T, PackFormatError>;
+
+/// The bytes of `manifest` in the archive, or which manifest is missing.
+fn read_manifest_bytes(
+    archive: &mut ZipArchive<&File>,
+    manifest: &'static str,
+) -> PackFormatResult<Vec<u8>> {
+    match archive.by_name(manifest) {
+        Ok(file) => Ok(bytes_from_zip_file(file)),
+        Err(ZipError::FileNotFound) => Err(PackFormatError::MissingManifest(manifest)),
+        Err(error) => Err(error.into()),
+    }
+}
@@ -1,13 +1,22 @@
 use crate::state::ManagerFromAppHandle;
-use log::{debug, error, info};
+#[cfg(test)]
+use crate::tests::fixtures::read_fixture;
+use autmc_downloader::{
+    buffered_download_stream, download_json_object, download_validated_file, send_with_retry,
+    validate_hash_sha1, DownloadError, DownloadResult, Downloadable,
+};
+use chrono::DateTime;
+use log::{debug, error, info, warn};
 use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::{
-    collections::VecDeque,
+    cmp::Reverse,
+    collections::{HashSet, VecDeque},
+    fmt,
     fs::{self, File},
     io::{self, Write},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     time::Instant,
 };
 #[cfg(test)]
@@ -18,15 +27,22 @@ use zip::ZipArchive;
 use crate::{
     consts::{
         CURSEFORGE_API_URL, CURSEFORGE_FORGECDN_URL, CURSEFORGE_MODPACK_CLASS_ID,
-        CURSEFORGE_PAGE_SIZE,
+        CURSEFORGE_MODS_CLASS_ID, CURSEFORGE_PAGE_SIZE, CURSEFORGE_RESOURCE_PACK_CLASS_ID,
+        CURSEFORGE_SHADER_CLASS_ID,
+    },
+    state::{
+        instance_manager::{available_directory_name, InstanceManager, InstanceState},
+        resource_manager::{ManifestError, ManifestResult, ResourceManager},
+        settings_manager::SettingsManager,
     },
-    state::instance_manager::{InstanceManager, InstanceState},
     web_services::{
-        downloader::{
-            buffered_download_stream, download_json_object, validate_hash_sha1, DownloadError,
-            DownloadResult, Downloadable,
-        },
+        install_report::{record_phase, report_progress, track_install, InstallPhase},
         manifest::bytes_from_zip_file,
+        mod_cache::ModCache,
+        modpack::{
+            read_manifest_bytes, PackFormatError, PackFormatResult, CURSEFORGE_MANIFEST_FILE,
+            MODRINTH_INDEX_FILE,
+        },
         resources::{create_instance, InstanceSettings, ModloaderType},
     },
 };
@@ -39,7 +55,7 @@ use crate::{
 pub struct CurseforgeManifest {
     minecraft: CurseforgeGameInformation,
     // manifest_type: String,
-    // manifest_version: u32,
+    manifest_version: u32,
     name: String,
     // version: String,
     author: String,
@@ -56,6 +72,11 @@ impl CurseforgeManifest {
         &self.minecraft.modloaders
     }
 
+    /// The heap size the pack's author recommends, in megabytes.
+    pub fn recommended_memory_mb(&self) -> Option<u64> {
+        self.minecraft.recommended_ram
+    }
+
     pub fn modpack_name(&self) -> &str {
         &self.name
     }
@@ -74,6 +95,9 @@ pub struct CurseforgeGameInformation {
     version: String,
     #[serde(rename = "modLoaders")]
     modloaders: Vec<Modloader>,
+    /// Only in manifests exported by newer versions of the curseforge app.
+    #[serde(rename = "recommendedRam")]
+    recommended_ram: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -91,14 +115,30 @@ pub struct CurseforgeFile {
     // required: bool,
 }
 
+/// The only `manifestVersion` curseforge has released.
+const CURSEFORGE_MANIFEST_VERSION: u32 = 1;
+
 /// Extract the manifest from the curseforge zip.
 pub fn extract_manifest_from_curseforge_zip(
     archive: &mut ZipArchive<&File>,
-) -> io::Result<CurseforgeManifest> {
+) -> PackFormatResult<CurseforgeManifest> {
     info!("Extracting manifest from curseforge modpack zip");
-    let manifest_bytes = bytes_from_zip_file(archive.by_name("manifest.json")?);
+    let manifest_bytes = match read_manifest_bytes(archive, CURSEFORGE_MANIFEST_FILE) {
+        Err(PackFormatError::MissingManifest(_))
+            if archive.index_for_name(MODRINTH_INDEX_FILE).is_some() =>
+        {
+            return Err(PackFormatError::MisnamedModrinthPack)
+        }
+        result => result?,
+    };
 
-    Ok(serde_json::from_slice(&manifest_bytes)?)
+    let manifest: CurseforgeManifest = serde_json::from_slice(&manifest_bytes)?;
+    if manifest.manifest_version != CURSEFORGE_MANIFEST_VERSION {
+        return Err(PackFormatError::UnsupportedManifestVersion(
+            manifest.manifest_version,
+        ));
+    }
+    Ok(manifest)
 }
 
 /// Extract overrides into the instance's directory
@@ -110,11 +150,28 @@ pub fn extract_overrides(
     info!("Extracting overrides into {:#?}", instance_path);
     for i in 0..archive.len() {
         let zip_file = archive.by_index(i)?;
-        let name = zip_file.enclosed_name().unwrap().to_path_buf();
+        let name = match zip_file.enclosed_name() {
+            Some(name) => name.to_path_buf(),
+            None => {
+                warn!(
+                    "Skipping {}, it is outside of the instance",
+                    zip_file.name()
+                );
+                continue;
+            }
+        };
         if name.starts_with(overrides) && zip_file.is_file() {
             let timer = Instant::now();
 
             let base_path = name.strip_prefix(overrides).unwrap();
+            // `overrides/../file` is enclosed in the zip but not in the instance.
+            if !base_path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+            {
+                warn!("Skipping {}, it is outside of the instance", name.display());
+                continue;
+            }
             let path = instance_path.join(base_path);
             let bytes = bytes_from_zip_file(zip_file);
 
@@ -172,9 +229,10 @@ struct CurseforgeDependency {
 
 #[derive(Debug, Deserialize)]
 pub struct CurseforgeManifestInfo {
-    pub instance_name: String,
     pub game_version: String,
     pub modloader_type: ModloaderType,
+    /// Let dependencies resolve to beta files, not only releases.
+    pub allow_beta: bool,
 }
 
 // -----------------------------
@@ -188,9 +246,10 @@ pub struct CurseforgeManifestInfo {
 /// Download all mods from `files` into the instance's `mods` directory.
 pub async fn download_mods_from_curseforge(
     files: &[CurseforgeFile],
-    instances_dir: &Path,
+    instance_dir: &Path,
     info: CurseforgeManifestInfo,
-) -> DownloadResult<()> {
+    mod_cache: &ModCache,
+) -> DependencyResult<()> {
     info!("Requesting curseforge files");
     // Send request with headers and body content.
     let mut header_map = HeaderMap::new();
@@ -208,12 +267,11 @@ pub async fn download_mods_from_curseforge(
 
     let url = format!("{}/mods/files", CURSEFORGE_API_URL);
     let client = reqwest::Client::new();
-    let response = client
+    let request = client
         .post(url)
         .headers(header_map)
-        .body(json!({ "fileIds": file_ids }).to_string())
-        .send()
-        .await?;
+        .body(json!({ "fileIds": file_ids }).to_string());
+    let response = send_with_retry(request).await?;
 
     let response = response.json::<CurseforgeFilesResponse>().await?;
     // Files to download.
@@ -242,49 +300,206 @@ pub async fn download_mods_from_curseforge(
         download_vec.push(files_data);
     }
 
+    // Every mod is resolved at most once, which also stops dependency cycles.
+    let mut visited: HashSet<u32> = existing_modids.into_iter().collect();
     info!("Gathering modids from {} dependencies", dependencies.len());
     for dependency_modid in dependencies {
         download_vec.extend(
-            download_dependencies_recursively(
-                &info.game_version,
-                &info.modloader_type,
-                dependency_modid,
-            )
-            .await?,
+            download_dependencies_recursively(&info, dependency_modid, &mut visited, &mut vec![])
+                .await?,
         );
     }
 
-    let mods_dir = instances_dir.join(info.instance_name).join("mods");
+    // Manifests can list the same file more than once.
+    let mut file_ids = HashSet::new();
+    download_vec.retain(|file_data| file_ids.insert(file_data.id));
+
+    let mods_dir = instance_dir.join("mods");
 
     info!("Downloading {} mods from curseforge", download_vec.len());
-    // Download all the files
-    buffered_download_stream(&download_vec, &mods_dir, |bytes, file_data| {
-        if !validate_hash_sha1(bytes, file_data.hash()) {
-            let err = format!("Error downloading {}, invalid hash.", file_data.url());
-            error!("{}", err);
-            return Err(DownloadError::InvalidFileHash(err));
+    download_curseforge_files(&download_vec, &mods_dir, mod_cache).await?;
+    Ok(())
+}
+
+/// Download `files` into `dir`, checking each file's hash. Files in `mod_cache` aren't downloaded
+/// again.
+async fn download_curseforge_files(
+    files: &[CurseforgeFilesData],
+    dir: &Path,
+    mod_cache: &ModCache,
+) -> DownloadResult<()> {
+    let mut missing = Vec::new();
+    for file_data in files {
+        if !mod_cache.install(file_data.hash(), &file_data.path(dir))? {
+            missing.push(file_data);
         }
-        debug!("Downloading mod: {}", file_data.name());
-        let path = file_data.path(&mods_dir);
-        let mut file = File::create(path)?;
-        file.write_all(bytes)?;
-        Ok(())
-    })
-    .await?;
+    }
+    info!(
+        "{} of {} files are cached",
+        files.len() - missing.len(),
+        files.len()
+    );
+    buffered_download_stream(
+        &missing,
+        dir,
+        |bytes, file_data| {
+            if !validate_hash_sha1(bytes, file_data.hash()) {
+                let err = format!("Error downloading {}, invalid hash.", file_data.url());
+                error!("{}", err);
+                return Err(DownloadError::InvalidFileHash(err));
+            }
+            debug!("Downloading mod: {}", file_data.name());
+            mod_cache.store(file_data.hash(), bytes, &file_data.path(dir))?;
+            Ok(())
+        },
+        |progress| report_progress(InstallPhase::Mods, progress),
+    )
+    .await
+}
+
+/// Where files of the curseforge class `class_id` are installed in an instance. None for classes
+/// that can't be added to an existing instance, like modpacks.
+pub fn class_install_dir(class_id: u32) -> Option<&'static str> {
+    match class_id {
+        CURSEFORGE_MODS_CLASS_ID => Some("mods"),
+        CURSEFORGE_RESOURCE_PACK_CLASS_ID => Some("resourcepacks"),
+        CURSEFORGE_SHADER_CLASS_ID => Some("shaderpacks"),
+        _ => None,
+    }
+}
 
+/// Installs `file_id` of the curseforge project `project_id` into `install_dir`, or the newest
+/// file compatible with the instance `info` describes. Mods also get their required dependencies.
+pub async fn install_curseforge_project(
+    project_id: u32,
+    class_id: u32,
+    file_id: Option<u32>,
+    install_dir: &Path,
+    info: &CurseforgeManifestInfo,
+    mod_cache: &ModCache,
+) -> DependencyResult<()> {
+    let is_mod = class_id == CURSEFORGE_MODS_CLASS_ID;
+    let file_data = match file_id {
+        Some(file_id) => download_file_data(project_id, file_id).await?,
+        None => {
+            // Only mods depend on the modloader.
+            let modloader_type = if is_mod {
+                &info.modloader_type
+            } else {
+                &ModloaderType::None
+            };
+            download_mod_from_modid(
+                &info.game_version,
+                modloader_type,
+                info.allow_beta,
+                project_id,
+            )
+            .await?
+            .ok_or(DependencyError::NoCompatibleFile(project_id))?
+        }
+    };
+
+    let mut visited = HashSet::from([project_id]);
+    let mut files = Vec::new();
+    if is_mod {
+        for dependency in &file_data.dependencies {
+            if dependency.relation_type == 3 {
+                files.extend(
+                    download_dependencies_recursively(
+                        info,
+                        dependency.mod_id,
+                        &mut visited,
+                        &mut vec![project_id],
+                    )
+                    .await?,
+                );
+            }
+        }
+    }
+    files.push(file_data);
+
+    info!(
+        "Installing {} files from curseforge into {}",
+        files.len(),
+        install_dir.display()
+    );
+    download_curseforge_files(&files, install_dir, mod_cache).await?;
     Ok(())
 }
 
-/// Resursively download a mod and its dependencies at `modid`, filtered by `game_version` and `modloader_type`
+/// Dependency chains longer than this are assumed to be broken metadata.
+const MAX_DEPENDENCY_DEPTH: usize = 16;
+
+#[derive(Debug)]
+pub enum DependencyError {
+    Download(DownloadError),
+    /// The chain of modids that exceeded [MAX_DEPENDENCY_DEPTH].
+    TooDeep(Vec<u32>),
+    /// No file of the modid matches the instance's version and modloader.
+    NoCompatibleFile(u32),
+}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyError::Download(error) => write!(f, "{:?}", error),
+            DependencyError::TooDeep(chain) => write!(
+                f,
+                "Dependencies nest more than {} levels deep: {}",
+                MAX_DEPENDENCY_DEPTH,
+                chain
+                    .iter()
+                    .map(|modid| modid.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            ),
+            DependencyError::NoCompatibleFile(modid) => {
+                write!(f, "Mod {} has no file compatible with the instance", modid)
+            }
+        }
+    }
+}
+
+impl From<DownloadError> for DependencyError {
+    fn from(error: DownloadError) -> Self {
+        DependencyError::Download(error)
+    }
+}
+
+impl From<reqwest::Error> for DependencyError {
+    fn from(error: reqwest::Error) -> Self {
+        DependencyError::Download(error.into())
+    }
+}
+
+pub type DependencyResult<T> = Result<T, DependencyError>;
+
+/// Resursively download a mod and its dependencies at `modid`, filtered by the game version and modloader of `info`.
+/// Mods in `visited` are skipped, `chain` holds the modids that depend on `modid`.
 #[async_recursion::async_recursion]
 async fn download_dependencies_recursively(
-    game_version: &str,
-    modloader_type: &ModloaderType,
+    info: &CurseforgeManifestInfo,
     modid: u32,
-) -> reqwest::Result<Vec<CurseforgeFilesData>> {
+    visited: &mut HashSet<u32>,
+    chain: &mut Vec<u32>,
+) -> DependencyResult<Vec<CurseforgeFilesData>> {
     let mut dependencies = Vec::new();
+    if !visited.insert(modid) {
+        debug!("Skipping modid {}, it's already resolved", modid);
+        return Ok(dependencies);
+    }
+    chain.push(modid);
+    if chain.len() > MAX_DEPENDENCY_DEPTH {
+        return Err(DependencyError::TooDeep(chain.clone()));
+    }
 
-    let search_entry = download_mod_from_modid(game_version, modloader_type, modid).await?;
+    let search_entry = download_mod_from_modid(
+        &info.game_version,
+        &info.modloader_type,
+        info.allow_beta,
+        modid,
+    )
+    .await?;
 
     // If there is no entry response, then the modid doesn't exist or there is no file that matches the
     // `game_version` and `modloader_version` filters.
@@ -299,9 +514,10 @@ async fn download_dependencies_recursively(
             for required_dependency in required_dependencies {
                 dependencies.extend(
                     download_dependencies_recursively(
-                        game_version,
-                        modloader_type,
+                        info,
                         required_dependency.mod_id,
+                        visited,
+                        chain,
                     )
                     .await?,
                 );
@@ -313,12 +529,13 @@ async fn download_dependencies_recursively(
             error!("File with modid {} could not be found", modid);
             debug!(
                 "Filtering by game_version: {} and modloader_type: {}",
-                game_version,
-                modloader_type.to_string()
+                info.game_version,
+                info.modloader_type.to_string()
             );
         }
     }
 
+    chain.pop();
     Ok(dependencies)
 }
 
@@ -327,6 +544,7 @@ async fn download_dependencies_recursively(
 async fn download_mod_from_modid(
     game_version: &str,
     modloader_type: &ModloaderType,
+    allow_beta: bool,
     modid: u32,
 ) -> reqwest::Result<Option<CurseforgeFilesData>> {
     info!("Downloading mod file");
@@ -342,7 +560,7 @@ async fn download_mod_from_modid(
     header_map.insert("Accept", "application/json".parse().unwrap());
 
     // Download a curseforge files response with files filtered to `game_version` and `modloader_version`
-    let mut response: CurseforgeFilesResponse = download_json_object(
+    let response: CurseforgeFilesResponse = download_json_object(
         &url,
         Some(header_map),
         Some(&[
@@ -357,9 +575,58 @@ async fn download_mod_from_modid(
     )
     .await?;
 
-    // TODO: Sort by date?
-    // Take the first element from data since they are already ordered by date and filtered during the request.
-    Ok(response.data.pop_front())
+    Ok(select_newest_file(
+        response.data.into(),
+        game_version,
+        allow_beta,
+    ))
+}
+
+/// Curseforge's `releaseType` values.
+const RELEASE_TYPE_RELEASE: u8 = 1;
+const RELEASE_TYPE_BETA: u8 = 2;
+
+/// Picks the file to install out of `files`, independent of the order the api returned them in.
+/// Releases, and betas when `allow_beta` is set, are always picked over other files. Among those,
+/// files made for exactly `game_version` win, then the newest file.
+fn select_newest_file(
+    mut files: Vec<CurseforgeFilesData>,
+    game_version: &str,
+    allow_beta: bool,
+) -> Option<CurseforgeFilesData> {
+    let allowed_release_type = if allow_beta {
+        RELEASE_TYPE_BETA
+    } else {
+        RELEASE_TYPE_RELEASE
+    };
+    files.sort_by_cached_key(|file| {
+        // Files that aren't allowed fall back to betas before alphas.
+        let release_tier = if file.release_type <= allowed_release_type {
+            0
+        } else {
+            file.release_type
+        };
+        let exact_version = file
+            .game_versions
+            .iter()
+            .any(|version| version == game_version);
+        let file_date = DateTime::parse_from_rfc3339(&file.file_date).ok();
+        (
+            release_tier,
+            Reverse(exact_version),
+            Reverse(file_date),
+            file.release_type,
+        )
+    });
+
+    let file = files.into_iter().next()?;
+    if file.release_type > allowed_release_type {
+        warn!(
+            "{} has no release files, using {}",
+            file.mod_id, file.display_name
+        );
+    }
+    Some(file)
 }
 
 /// Convert a [ModloaderType] to the `modLoaderVersion` query parameter
@@ -379,12 +646,69 @@ fn test_download_mod_from_modid() {
     let x = block_on(download_mod_from_modid(
         "1.19.2",
         &ModloaderType::Forge,
+        false,
         320926,
     ))
     .unwrap();
     println!("Here: {:#?}", x);
 }
 
+#[cfg(test)]
+fn test_file(
+    id: u32,
+    release_type: u8,
+    file_date: &str,
+    game_versions: &[&str],
+) -> CurseforgeFilesData {
+    serde_json::from_value(json!({
+        "id": id,
+        "gameId": 432,
+        "modId": 1,
+        "isAvailable": true,
+        "displayName": format!("file-{}", id),
+        "fileName": format!("file-{}.jar", id),
+        "releaseType": release_type,
+        "fileStatus": 4,
+        "hashes": [],
+        "fileDate": file_date,
+        "fileLength": 0,
+        "downloadCount": 0,
+        "downloadUrl": null,
+        "gameVersions": game_versions,
+        "sortableGameVersions": [],
+        "dependencies": [],
+        "alternateFileId": 0,
+        "isServerPack": false,
+        "fileFingerprint": 0,
+        "modules": []
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_select_newest_file() {
+    let files = || {
+        vec![
+            test_file(1, 1, "2023-01-01T00:00:00Z", &["1.20", "1.20.1"]),
+            test_file(2, 1, "2023-03-01T00:00:00.5Z", &["1.20", "1.20.1"]),
+            test_file(3, 2, "2023-06-01T00:00:00Z", &["1.20.1"]),
+            test_file(4, 1, "2023-09-01T00:00:00Z", &["1.20"]),
+        ]
+    };
+    let selected = |files, allow_beta| select_newest_file(files, "1.20.1", allow_beta).unwrap().id;
+
+    // The newest release for the exact version, not the newest file or the beta.
+    assert_eq!(selected(files(), false), 2);
+    assert_eq!(selected(files(), true), 3);
+    // Betas are only a fallback without any releases.
+    let betas = files()
+        .into_iter()
+        .filter(|file| file.release_type != RELEASE_TYPE_RELEASE)
+        .collect();
+    assert_eq!(selected(betas, false), 3);
+    assert!(select_newest_file(Vec::new(), "1.20.1", true).is_none());
+}
+
 #[derive(Debug, Deserialize)]
 struct CurseforgeFilesResponse {
     data: VecDeque<CurseforgeFilesData>,
@@ -456,15 +780,101 @@ struct CurseforgeModule {
     fingerprint: u32,
 }
 
+/// Download the file data of `file_id` of the curseforge project `project_id`.
+async fn download_file_data(project_id: u32, file_id: u32) -> reqwest::Result<CurseforgeFilesData> {
+    let mut header_map = HeaderMap::new();
+    header_map.insert(
+        "X-API-KEY",
+        "$2a$10$5BgCleD8.rLQ5Ix17Xm2lOjgfoeTJV26a1BXmmpwrOemgI517.nuC"
+            .parse()
+            .unwrap(),
+    );
+    header_map.insert("Accept", "application/json".parse().unwrap());
+
+    #[derive(Deserialize)]
+    struct FileResponse {
+        data: CurseforgeFilesData,
+    }
+
+    let url = format!(
+        "{}/mods/{}/files/{}",
+        CURSEFORGE_API_URL, project_id, file_id
+    );
+    Ok(
+        download_json_object::<FileResponse, ()>(&url, Some(header_map), None)
+            .await?
+            .data,
+    )
+}
+
+/// The id of the file curseforge shows as the project's main download.
+pub async fn download_main_file_id(project_id: u32) -> reqwest::Result<u32> {
+    let mut header_map = HeaderMap::new();
+    header_map.insert(
+        "X-API-KEY",
+        "$2a$10$5BgCleD8.rLQ5Ix17Xm2lOjgfoeTJV26a1BXmmpwrOemgI517.nuC"
+            .parse()
+            .unwrap(),
+    );
+    header_map.insert("Accept", "application/json".parse().unwrap());
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Project {
+        main_file_id: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct ProjectResponse {
+        data: Project,
+    }
+
+    let url = format!("{}/mods/{}", CURSEFORGE_API_URL, project_id);
+    Ok(
+        download_json_object::<ProjectResponse, ()>(&url, Some(header_map), None)
+            .await?
+            .data
+            .main_file_id,
+    )
+}
+
+/// Download the modpack zip for `file_id` of the curseforge project `project_id` into `cache_dir`,
+/// unless it is already there with the published hash. Returns the path to the downloaded zip.
+pub async fn download_curseforge_modpack(
+    project_id: u32,
+    file_id: u32,
+    cache_dir: &Path,
+) -> DownloadResult<PathBuf> {
+    info!(
+        "Downloading curseforge modpack {} with file id {}",
+        project_id, file_id
+    );
+    let file_data = download_file_data(project_id, file_id).await?;
+
+    let path = file_data.path(&cache_dir.join("curseforge").join(file_id.to_string()));
+    download_validated_file(&path, &file_data.url(), file_data.hash()).await?;
+    Ok(path)
+}
+
+/// Imports the pack `curseforge_manifest` was extracted from.
 pub async fn import_curseforge_zip(
+    curseforge_manifest: CurseforgeManifest,
     mut archive: &mut ZipArchive<&File>,
     app_handle: &AppHandle<Wry>,
-) -> io::Result<()> {
-    // Pull out the manifest.json from the zip
-    let curseforge_manifest = extract_manifest_from_curseforge_zip(&mut archive)?;
-
+) -> ManifestResult<()> {
+    let allow_beta = SettingsManager::from_app_handle(app_handle)
+        .await
+        .get_settings()
+        .allow_beta_dependencies;
     let vanilla_version = curseforge_manifest.vanilla_version();
-    let instance_name = curseforge_manifest.modpack_name();
+    // Importing a pack twice installs it next to the first copy instead of over it.
+    let (instance_name, instances_dir) = {
+        let instance_manager = InstanceManager::from_app_handle(app_handle).await;
+        (
+            instance_manager.available_instance_name(curseforge_manifest.modpack_name()),
+            instance_manager.instances_dir(),
+        )
+    };
 
     // Get the modloader with 'primary: true'
     let primary_modloader = curseforge_manifest
@@ -485,39 +895,43 @@ pub async fn import_curseforge_zip(
     // Create corrected modloader version string for instance creation
     let full_modloader_version = format!("{}-{}", vanilla_version, modloader_version);
 
-    let settings = InstanceSettings::new(
-        instance_name.into(),
+    let mut settings = InstanceSettings::new(
+        instance_name.clone(),
         vanilla_version.into(),
         modloader_type.into(),
         full_modloader_version,
         None,
     );
-
-    create_instance(settings, &app_handle, Some(&curseforge_manifest.author))
-        .await
-        .unwrap();
-
-    let instance_manager = InstanceManager::from_app_handle(&app_handle).await;
-
-    let instances_dir = instance_manager.instances_dir();
-
-    let info = CurseforgeManifestInfo {
-        instance_name: instance_name.into(),
-        game_version: curseforge_manifest.vanilla_version().into(),
-        modloader_type: modloader_type.into(),
-    };
-
-    // After instance is created, download the mods from curseforge
-    download_mods_from_curseforge(curseforge_manifest.files(), &instances_dir, info)
-        .await
-        .unwrap();
-
-    // Finally extract overrides into the instance dir
-    extract_overrides(
-        &instances_dir.join(instance_name),
-        &mut archive,
-        curseforge_manifest.overrides(),
-    )?;
+    // Large packs run out of memory with the JVM's default heap size.
+    if let Some(memory_mb) = curseforge_manifest.recommended_memory_mb() {
+        settings.set_recommended_memory(memory_mb);
+    }
+    settings.directory_name = available_directory_name(&instances_dir, &instance_name);
+    let instance_dir = instances_dir.join(&settings.directory_name);
+    let mod_cache = ResourceManager::from_app_handle(app_handle).mod_cache();
+
+    // Track the whole import so mod downloads are part of the instance's install report.
+    track_install(app_handle, &instance_name, &instance_dir, async {
+        create_instance(settings, &app_handle, Some(&curseforge_manifest.author)).await?;
+
+        let info = CurseforgeManifestInfo {
+            game_version: curseforge_manifest.vanilla_version().into(),
+            modloader_type: modloader_type.into(),
+            allow_beta,
+        };
+
+        // After instance is created, download the mods from curseforge
+        let phase_start = Instant::now();
+        download_mods_from_curseforge(curseforge_manifest.files(), &instance_dir, info, &mod_cache)
+            .await
+            .map_err(|error| io::Error::new(io::ErrorKind::Other, error.to_string()))?;
+        record_phase(InstallPhase::Mods, phase_start);
+
+        // Finally extract overrides into the instance dir
+        extract_overrides(&instance_dir, &mut archive, curseforge_manifest.overrides())?;
+        Ok::<_, ManifestError>(())
+    })
+    .await?;
     info!(
         "Succcessfully imported curseforge modpack {}",
         instance_name
@@ -553,15 +967,59 @@ pub struct CurseforgeSearchEntry {
     is_featured: bool,
     primary_category_id: u32,
     categories: Vec<CurseforgeSearchCategory>,
-    class_id: u32,
+    pub class_id: u32,
     pub authors: Vec<CurseforgeSearchAuthors>,
     pub logo: CurseforgeSearchImage,
     screenshots: Vec<CurseforgeSearchImage>,
-    main_file_id: u32,
+    pub main_file_id: u32,
     latest_files: Vec<CurseforgeSearchLatestFiles>,
+    #[serde(default)]
+    latest_files_indexes: Vec<CurseforgeFileIndex>,
+}
+
+/// The newest file of a project for one game version and modloader.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseforgeFileIndex {
+    game_version: String,
+    // file_id: u32,
+    mod_loader: Option<u32>,
+}
+
+/// Whether a search result has files for an existing instance's game version and modloader.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchCompatibility {
+    game_version: bool,
+    modloader: bool,
 }
 
 impl CurseforgeSearchEntry {
+    /// Compatibility with an instance of `game_version` and `modloader_type`. None for results
+    /// that can't be added to an instance.
+    pub fn compatibility(
+        &self,
+        game_version: &str,
+        modloader_type: &ModloaderType,
+    ) -> Option<SearchCompatibility> {
+        class_install_dir(self.class_id)?;
+        let modloader_id = modloader_id_from_version(modloader_type);
+        let version_indexes = self
+            .latest_files_indexes
+            .iter()
+            .filter(|index| index.game_version == game_version);
+        Some(SearchCompatibility {
+            game_version: version_indexes.clone().next().is_some(),
+            // Only mods depend on the modloader.
+            modloader: self.class_id != CURSEFORGE_MODS_CLASS_ID
+                || version_indexes.clone().any(|index| {
+                    index
+                        .mod_loader
+                        .map_or(true, |mod_loader| mod_loader.to_string() == modloader_id)
+                }),
+        })
+    }
+
     pub fn get_basic_categories(&self) -> Vec<CurseforgeCategory> {
         self.categories
             .iter()
@@ -697,10 +1155,17 @@ impl CurseforgeSortField {
     }
 }
 
-pub async fn search_curseforge_modpacks(
+/// Game version the UI selects to search every version.
+pub const ALL_VERSIONS: &str = "All Versions";
+
+/// Searches projects of the curseforge class `class_id`, an empty `selected_version` and
+/// `ModloaderType::None` match any version or modloader.
+pub async fn search_curseforge_projects(
+    class_id: u32,
     page: u32,
     search_filter: &str,
     selected_version: &str,
+    selected_modloader: &ModloaderType,
     selected_category: u32,
     selected_sort: CurseforgeSortField,
 ) -> reqwest::Result<CurseforgeSearchResponse> {
@@ -714,31 +1179,36 @@ pub async fn search_curseforge_modpacks(
     header_map.insert("Content-Type", "application/json".parse().unwrap());
 
     let client = reqwest::Client::new();
-    let response = client
+    let request = client
         .get(format!("{}/mods/search", CURSEFORGE_API_URL))
         .headers(header_map)
         .query(&[
             ("gameId", "432"),
-            ("classId", &CURSEFORGE_MODPACK_CLASS_ID.to_string()),
+            ("classId", &class_id.to_string()),
             ("categoryId", selected_category.to_string().as_str()),
             ("gameVersion", selected_version),
+            (
+                "modLoaderType",
+                modloader_id_from_version(selected_modloader),
+            ),
             ("searchFilter", search_filter),
             ("sortField", &selected_sort.as_number_str()),
             ("sortOrder", "desc"),
             ("index", &(page * CURSEFORGE_PAGE_SIZE).to_string()),
             ("pageSize", &CURSEFORGE_PAGE_SIZE.to_string()),
-        ])
-        .send()
-        .await?;
+        ]);
+    let response = send_with_retry(request).await?;
     response.json::<CurseforgeSearchResponse>().await
 }
 
 #[test]
 fn test_curseforge_search() {
-    let x = block_on(search_curseforge_modpacks(
+    let x = block_on(search_curseforge_projects(
+        CURSEFORGE_MODPACK_CLASS_ID,
         1,
         "",
         "",
+        &ModloaderType::None,
         4475,
         CurseforgeSortField::Popularity,
     ))
@@ -777,15 +1247,14 @@ pub async fn retrieve_curseforge_categories() -> reqwest::Result<Vec<CurseforgeC
     }
 
     let client = reqwest::Client::new();
-    let response = client
+    let request = client
         .get(format!("{}/categories", CURSEFORGE_API_URL))
         .headers(header_map)
         .query(&[
             ("gameId", "432"),
             ("classId", &CURSEFORGE_MODPACK_CLASS_ID.to_string()),
-        ])
-        .send()
-        .await?;
+        ]);
+    let response = send_with_retry(request).await?;
     Ok(response.json::<Categories>().await?.data)
 }
 
@@ -798,3 +1267,124 @@ fn test_curseforge_categories() {
 // -----------------------------------------
 // END: Curseforge API Mod/Modpack Search
 // -----------------------------------------
+
+#[cfg(test)]
+fn test_pack(dir: &Path, entries: &[(&str, &str)]) -> File {
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    let path = dir.join("pack.zip");
+    let mut zip = ZipWriter::new(File::create(&path).unwrap());
+    for (name, contents) in entries {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+    File::open(path).unwrap()
+}
+
+#[test]
+fn test_curseforge_manifest_validation() {
+    let tmp_dir = tempdir::TempDir::new("pack").unwrap();
+
+    let pack = test_pack(tmp_dir.path(), &[("overrides/options.txt", "")]);
+    assert!(matches!(
+        extract_manifest_from_curseforge_zip(&mut ZipArchive::new(&pack).unwrap()),
+        Err(PackFormatError::MissingManifest(CURSEFORGE_MANIFEST_FILE))
+    ));
+
+    let pack = test_pack(tmp_dir.path(), &[(MODRINTH_INDEX_FILE, "{}")]);
+    assert!(matches!(
+        extract_manifest_from_curseforge_zip(&mut ZipArchive::new(&pack).unwrap()),
+        Err(PackFormatError::MisnamedModrinthPack)
+    ));
+
+    let manifest = r#"{
+        "minecraft": { "version": "1.20.1", "modLoaders": [] },
+        "manifestVersion": 2,
+        "name": "Pack",
+        "author": "You",
+        "files": [],
+        "overrides": "overrides"
+    }"#;
+    let pack = test_pack(tmp_dir.path(), &[(CURSEFORGE_MANIFEST_FILE, manifest)]);
+    assert!(matches!(
+        extract_manifest_from_curseforge_zip(&mut ZipArchive::new(&pack).unwrap()),
+        Err(PackFormatError::UnsupportedManifestVersion(2))
+    ));
+
+    let pack = test_pack(
+        tmp_dir.path(),
+        &[(
+            CURSEFORGE_MANIFEST_FILE,
+            &manifest.replace("\"manifestVersion\": 2", "\"manifestVersion\": 1"),
+        )],
+    );
+    let manifest = extract_manifest_from_curseforge_zip(&mut ZipArchive::new(&pack).unwrap());
+    assert_eq!(manifest.unwrap().modpack_name(), "Pack");
+}
+
+#[test]
+fn test_overrides_outside_the_instance_are_skipped() {
+    let tmp_dir = tempdir::TempDir::new("pack").unwrap();
+    let instance_dir = tmp_dir.path().join("instance");
+    let pack = test_pack(
+        tmp_dir.path(),
+        &[
+            ("overrides/options.txt", "options"),
+            ("overrides/../escaped.txt", ""),
+            ("overrides/../../escaped.txt", ""),
+            ("/absolute.txt", ""),
+        ],
+    );
+    extract_overrides(
+        &instance_dir,
+        &mut ZipArchive::new(&pack).unwrap(),
+        "overrides",
+    )
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(instance_dir.join("options.txt")).unwrap(),
+        "options"
+    );
+    assert!(!tmp_dir.path().join("escaped.txt").exists());
+}
+
+#[test]
+fn test_curseforge_fixtures() {
+    let manifest: CurseforgeManifest = read_fixture("curseforge/manifest.json");
+    assert_eq!(manifest.modpack_name(), "All the Mods 8");
+    assert_eq!(manifest.vanilla_version(), "1.19.2");
+    assert_eq!(manifest.modloaders()[0].id, "forge-43.2.0");
+    assert!(manifest.modloaders()[0].primary);
+    assert_eq!(manifest.recommended_memory_mb(), Some(8192));
+    let file_ids: Vec<u32> = manifest.files().iter().map(|file| file.file_id).collect();
+    assert_eq!(file_ids, [4371807, 4393374]);
+
+    let search: CurseforgeSearchResponse = read_fixture("curseforge/search.json");
+    assert_eq!(search.pagination.total_count, 10000);
+    let entry = &search.data[0];
+    assert_eq!(entry.name, "Just Enough Items (JEI)");
+    assert_eq!(entry.class_id, CURSEFORGE_MODS_CLASS_ID);
+    assert_eq!(entry.get_basic_categories()[0].name, "API and Library");
+    let latest = &entry.latest_files[0];
+    assert_eq!(latest.sortable_game_versions[1].game_version_name, "Forge");
+    assert!(latest.server_pack_file_id.is_none());
+    // An index without a modloader is compatible with any of them.
+    let compatibility = entry
+        .compatibility("1.12.2", &ModloaderType::Fabric)
+        .unwrap();
+    assert!(compatibility.game_version && compatibility.modloader);
+    let compatibility = entry
+        .compatibility("1.20.1", &ModloaderType::Forge)
+        .unwrap();
+    assert!(!compatibility.game_version);
+
+    // Files of mods that don't allow third party downloads have no download url.
+    let files: CurseforgeFilesResponse = read_fixture("curseforge/files.json");
+    let file = &files.data[0];
+    assert_eq!(
+        file.url(),
+        format!("{}/4600/001/fixture-mod-1.0.0.jar", CURSEFORGE_FORGECDN_URL)
+    );
+    assert_eq!(file.dependencies[0].mod_id, 200);
+}
@@ -1,26 +1,43 @@
 use std::{
     fs::{self, File},
     io::{self, Write},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     time::Instant,
 };
 
 use crate::state::ManagerFromAppHandle;
 use crate::{
-    state::instance_manager::{InstanceManager, InstanceState},
+    consts::MODRINTH_API_URL,
+    state::{
+        instance_manager::{available_directory_name, InstanceManager, InstanceState},
+        resource_manager::{ManifestError, ManifestResult, ResourceManager},
+    },
     web_services::{
-        downloader::{buffered_download_stream, validate_hash_sha1, DownloadError, Downloadable},
+        install_report::{record_phase, report_progress, track_install, InstallPhase},
         manifest::bytes_from_zip_file,
+        mod_cache::ModCache,
+        modpack::{read_manifest_bytes, PackFormatError, PackFormatResult, MODRINTH_INDEX_FILE},
         resources::{create_instance, InstanceSettings, ModloaderType},
     },
 };
-use log::{debug, error, info};
-use serde::Deserialize;
+use autmc_downloader::{
+    buffered_download_stream, download_json_object_from_url, download_validated_file,
+    send_with_retry, validate_hash_sha1, validate_hash_sha512, DownloadError, DownloadResult,
+    Downloadable,
+};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Manager, State, Wry};
 use zip::ZipArchive;
 
+/// The only `formatVersion` modrinth has released.
+const MODRINTH_FORMAT_VERSION: u32 = 1;
+/// Extracted first, then `client-overrides` on top of it.
+const OVERRIDES: &str = "overrides";
+const CLIENT_OVERRIDES: &str = "client-overrides";
+
 #[derive(Debug, Deserialize)]
-struct ModrinthManifest {
+pub struct ModrinthManifest {
     #[serde(rename = "formatVersion")]
     format_version: u32,
     game: String,
@@ -32,6 +49,12 @@ struct ModrinthManifest {
     dependencies: ModrinthDependencies,
 }
 
+impl ModrinthManifest {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ModrinthFile {
     path: String,
@@ -40,7 +63,23 @@ struct ModrinthFile {
     // Vec of all possible downloads, if one fails fallback to others
     downloads: Vec<String>,
     #[serde(rename = "fileSize")]
-    file_size: u32,
+    file_size: u64,
+}
+
+impl ModrinthFile {
+    /// Files without `env` are required on both sides.
+    fn client_support(&self) -> EnvSupport {
+        self.env
+            .as_ref()
+            .map_or(EnvSupport::Required, |env| env.client)
+    }
+
+    /// Whether `path` stays inside the instance directory.
+    fn has_safe_path(&self) -> bool {
+        Path::new(&self.path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    }
 }
 
 impl Downloadable for ModrinthFile {
@@ -49,9 +88,7 @@ impl Downloadable for ModrinthFile {
     }
 
     fn url(&self) -> String {
-        // TODO: Fallback to alternate downloads when/if first one fails.
-        // Assumes there is always 1 download.
-        self.downloads.first().unwrap().into()
+        self.downloads.first().cloned().unwrap_or_default()
     }
 
     fn hash(&self) -> &str {
@@ -61,6 +98,10 @@ impl Downloadable for ModrinthFile {
     fn path(&self, base_dir: &Path) -> PathBuf {
         base_dir.join(&self.path)
     }
+
+    fn fallback_urls(&self) -> Vec<String> {
+        self.downloads.iter().skip(1).cloned().collect()
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -69,101 +110,295 @@ struct ModrinthHashes {
     sha512: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum EnvSupport {
+    Required,
+    Optional,
+    Unsupported,
+}
+
 #[derive(Debug, Deserialize)]
 struct ModrinthEnv {
-    client: String,
-    server: String,
+    client: EnvSupport,
+    server: EnvSupport,
 }
 
 #[derive(Debug, Deserialize)]
 struct ModrinthDependencies {
     minecraft: String,
-    #[serde(flatten)]
-    modloader_dependency: ModrinthModloaderDependency,
+    forge: Option<String>,
+    neoforge: Option<String>,
+    #[serde(rename = "fabric-loader")]
+    fabric_loader: Option<String>,
+    #[serde(rename = "quilt-loader")]
+    quilt_loader: Option<String>,
+}
+
+impl ModrinthDependencies {
+    /// The modloader to create the instance with and its version, as instance creation expects it.
+    fn modloader(&self) -> io::Result<(ModloaderType, String)> {
+        if self.neoforge.is_some() || self.quilt_loader.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "NeoForge and Quilt modpacks are not supported",
+            ));
+        }
+        Ok(match (&self.forge, &self.fabric_loader) {
+            (Some(forge), _) => (
+                ModloaderType::Forge,
+                format!("{}-{}", self.minecraft, forge),
+            ),
+            (None, Some(fabric)) => (ModloaderType::Fabric, fabric.clone()),
+            (None, None) => (ModloaderType::None, String::new()),
+        })
+    }
+}
+
+/// A file the pack marks as optional on the client, only installed if the user picks it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionalPackFile {
+    path: String,
+    file_size: u64,
 }
 
 #[derive(Debug, Deserialize)]
-enum ModrinthModloaderDependency {
-    #[serde(rename = "fabric-loader")]
-    Fabric(String),
-    #[serde(rename = "forge")]
-    Forge(String),
+struct ModrinthVersionFile {
+    hashes: ModrinthHashes,
+    url: String,
+    filename: String,
+    primary: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModrinthVersion {
+    files: Vec<ModrinthVersionFile>,
+}
+
+/// Download the `.mrpack` for the modrinth version `version_id` into `cache_dir`, unless it is
+/// already there with the published hash. Returns the path to the downloaded pack.
+pub async fn download_modrinth_modpack(
+    version_id: &str,
+    cache_dir: &Path,
+) -> DownloadResult<PathBuf> {
+    info!("Downloading modrinth modpack version {}", version_id);
+    let url = format!("{}/version/{}", MODRINTH_API_URL, version_id);
+    let version: ModrinthVersion = download_json_object_from_url(&url).await?;
+
+    // Prefer the primary file, falling back to the first file if none are marked primary.
+    let pack_file = match version.files.iter().find(|file| file.primary) {
+        Some(file) => file,
+        None => version.files.first().ok_or_else(|| {
+            DownloadError::FileWrite(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Modrinth version {} has no files", version_id),
+            ))
+        })?,
+    };
+
+    // Versions can have files with the same name, like every version of a pack named `pack.mrpack`.
+    let path = cache_dir
+        .join("modrinth")
+        .join(version_id)
+        .join(&pack_file.filename);
+    download_validated_file(&path, &pack_file.url, &pack_file.hashes.sha1).await?;
+    Ok(path)
+}
+
+/// A category modrinth projects can be tagged with. `icon` is an svg.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModrinthCategory {
+    icon: String,
+    pub name: String,
+    project_type: String,
+    header: String,
+}
+
+/// Retrieves the categories modpacks can be tagged with.
+pub async fn retrieve_modrinth_categories() -> reqwest::Result<Vec<ModrinthCategory>> {
+    let client = reqwest::Client::new();
+    let request = client.get(format!("{}/tag/category", MODRINTH_API_URL));
+    let response = send_with_retry(request).await?;
+    let categories: Vec<ModrinthCategory> = response.json().await?;
+    Ok(categories
+        .into_iter()
+        .filter(|category| category.project_type == "modpack")
+        .collect())
+}
+
+/// Extract the `modrinth.index.json` from the modrinth pack.
+pub fn read_modrinth_manifest(
+    archive: &mut ZipArchive<&File>,
+) -> PackFormatResult<ModrinthManifest> {
+    let manifest_bytes = read_manifest_bytes(archive, MODRINTH_INDEX_FILE)?;
+    let manifest: ModrinthManifest = serde_json::from_slice(&manifest_bytes)?;
+    if manifest.format_version != MODRINTH_FORMAT_VERSION {
+        return Err(PackFormatError::UnsupportedManifestVersion(
+            manifest.format_version,
+        ));
+    }
+    if manifest.game != "minecraft" {
+        return Err(PackFormatError::UnsupportedGame(manifest.game));
+    }
+    Ok(manifest)
 }
 
+/// The files of the pack the user can choose to install.
+pub fn modrinth_optional_files(manifest: &ModrinthManifest) -> Vec<OptionalPackFile> {
+    manifest
+        .files
+        .iter()
+        .filter(|file| file.client_support() == EnvSupport::Optional)
+        .map(|file| OptionalPackFile {
+            path: file.path.clone(),
+            file_size: file.file_size,
+        })
+        .collect()
+}
+
+/// Imports the `.mrpack` `manifest` was read from. Optional client files are skipped unless their
+/// path is in `optional_files`, files the client doesn't support are always skipped.
 pub async fn import_modrinth_zip(
+    manifest: ModrinthManifest,
     archive: &mut ZipArchive<&File>,
+    optional_files: &[String],
     app_handle: &AppHandle<Wry>,
-) -> io::Result<()> {
+) -> ManifestResult<()> {
     info!("Importing modrinth zip...");
-    let manifest_bytes = bytes_from_zip_file(archive.by_name("modrinth.index.json").unwrap());
-    let manifest: ModrinthManifest = serde_json::from_slice(&manifest_bytes)?;
     debug!("Manifset: {:#?}", manifest);
 
-    let (modloader_version, modloader_type) = match manifest.dependencies.modloader_dependency {
-        ModrinthModloaderDependency::Fabric(version) => (version, ModloaderType::Fabric),
-        ModrinthModloaderDependency::Forge(version) => (
-            format!("{}-{}", manifest.dependencies.minecraft, version),
-            ModloaderType::Forge,
-        ),
+    let (modloader_type, modloader_version) = manifest.dependencies.modloader()?;
+    // Importing a pack twice installs it next to the first copy instead of over it.
+    let (instance_name, instances_dir) = {
+        let instance_manager = InstanceManager::from_app_handle(app_handle).await;
+        (
+            instance_manager.available_instance_name(&manifest.name),
+            instance_manager.instances_dir(),
+        )
     };
-
-    let settings = InstanceSettings::new(
-        manifest.name.clone(),
-        manifest.dependencies.minecraft,
+    let mut settings = InstanceSettings::new(
+        instance_name.clone(),
+        manifest.dependencies.minecraft.clone(),
         modloader_type,
         modloader_version,
         None,
     );
+    settings.directory_name = available_directory_name(&instances_dir, &instance_name);
+    let instance_dir = instances_dir.join(&settings.directory_name);
+    let mod_cache = ResourceManager::from_app_handle(app_handle).mod_cache();
 
-    create_instance(settings, app_handle, Some("Modrinth"))
-        .await
-        .unwrap();
-
-    let instance_manager = InstanceManager::from_app_handle(&app_handle).await;
+    let files: Vec<ModrinthFile> = manifest
+        .files
+        .into_iter()
+        .filter(|file| {
+            if !file.has_safe_path() {
+                warn!("Skipping {}, it is outside of the instance", file.path);
+                return false;
+            }
+            match file.client_support() {
+                EnvSupport::Required => true,
+                EnvSupport::Optional => optional_files.contains(&file.path),
+                EnvSupport::Unsupported => false,
+            }
+        })
+        .collect();
 
-    let instances_dir = instance_manager.instances_dir();
-    let instance_dir = instances_dir.join(&manifest.name);
+    // Track the whole import so mod downloads are part of the instance's install report.
+    track_install(app_handle, &instance_name, &instance_dir, async {
+        create_instance(settings, app_handle, Some("Modrinth")).await?;
 
-    download_mods_from_modrinth(manifest.files, &instance_dir).await?;
+        let phase_start = Instant::now();
+        download_mods_from_modrinth(&files, &instance_dir, &mod_cache).await?;
+        record_phase(InstallPhase::Mods, phase_start);
 
-    extract_overrides(&instance_dir, archive)?;
-    info!("Succcessfully imported modrinth modpack {}", manifest.name);
+        extract_overrides(&instance_dir, archive, OVERRIDES)?;
+        extract_overrides(&instance_dir, archive, CLIENT_OVERRIDES)?;
+        Ok::<_, ManifestError>(())
+    })
+    .await?;
+    info!("Succcessfully imported modrinth modpack {}", instance_name);
     Ok(())
 }
 
+/// Downloads `files` into the instance, checking both hashes of every file. Fails if any file
+/// couldn't be downloaded from any of its urls.
 async fn download_mods_from_modrinth(
-    files: Vec<ModrinthFile>,
+    files: &[ModrinthFile],
     instance_dir: &Path,
+    mod_cache: &ModCache,
 ) -> io::Result<()> {
     fs::create_dir_all(&instance_dir)?;
 
-    let x = buffered_download_stream(&files, &instance_dir, |bytes, file| {
-        if !validate_hash_sha1(bytes, file.hash()) {
-            let err = format!("Error downloading {}, invalid hash.", file.url());
-            error!("{}", err);
-            return Err(DownloadError::InvalidFileHash(err));
+    let mut missing = Vec::new();
+    for file in files {
+        if !mod_cache.install(&file.hashes.sha1, &file.path(instance_dir))? {
+            missing.push(file);
         }
-        debug!("Downloading mod: {}", file.name());
-        let path = file.path(&instance_dir);
-        let mut file = File::create(path)?;
-        file.write_all(bytes)?;
-        Ok(())
-    })
-    .await;
+    }
+    info!(
+        "{} of {} files are cached",
+        files.len() - missing.len(),
+        files.len()
+    );
+    buffered_download_stream(
+        &missing,
+        &instance_dir,
+        |bytes, file| {
+            if !validate_hash_sha1(bytes, &file.hashes.sha1)
+                || !validate_hash_sha512(bytes, &file.hashes.sha512)
+            {
+                let err = format!("Error downloading {}, invalid hash.", file.name());
+                error!("{}", err);
+                return Err(DownloadError::InvalidFileHash(err));
+            }
+            debug!("Downloading mod: {}", file.name());
+            mod_cache.store(&file.hashes.sha1, bytes, &file.path(instance_dir))?;
+            Ok(())
+        },
+        |progress| report_progress(InstallPhase::Mods, progress),
+    )
+    .await
+    .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{:?}", err)))?;
 
+    let missing: Vec<&str> = files
+        .iter()
+        .filter(|file| !file.path(instance_dir).is_file())
+        .map(|file| file.name())
+        .collect();
+    if !missing.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("Could not download {}", missing.join(", ")),
+        ));
+    }
     Ok(())
 }
 
-fn extract_overrides(instance_dir: &Path, archive: &mut ZipArchive<&File>) -> io::Result<()> {
-    info!("Extracting overrides into {:#?}", instance_dir);
-    const OVERRIDES: &str = "overrides";
+fn extract_overrides(
+    instance_dir: &Path,
+    archive: &mut ZipArchive<&File>,
+    overrides: &str,
+) -> io::Result<()> {
+    info!("Extracting {} into {:#?}", overrides, instance_dir);
     for i in 0..archive.len() {
         let zip_file = archive.by_index(i)?;
-        let name = zip_file.enclosed_name().unwrap().to_path_buf();
-        if name.starts_with(OVERRIDES) && zip_file.is_file() {
+        let name = match zip_file.enclosed_name() {
+            Some(name) => name.to_path_buf(),
+            None => continue,
+        };
+        if name.starts_with(overrides) && zip_file.is_file() {
             let timer = Instant::now();
 
-            let base_path = name.strip_prefix(OVERRIDES).unwrap();
+            let base_path = name.strip_prefix(overrides).unwrap();
+            // `overrides/../file` is enclosed in the zip but not in the instance.
+            if !base_path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+            {
+                warn!("Skipping {}, it is outside of the instance", name.display());
+                continue;
+            }
             let path = instance_dir.join(base_path);
             let bytes = bytes_from_zip_file(zip_file);
 
@@ -187,3 +422,54 @@ fn extract_overrides(instance_dir: &Path, archive: &mut ZipArchive<&File>) -> io
 
     Ok(())
 }
+
+#[test]
+fn test_modrinth_manifest_env_and_dependencies() {
+    let manifest: ModrinthManifest = serde_json::from_str(
+        r#"{
+            "formatVersion": 1,
+            "game": "minecraft",
+            "versionId": "1.0.0",
+            "name": "Pack",
+            "files": [
+                {
+                    "path": "mods/required.jar",
+                    "hashes": { "sha1": "a", "sha512": "b" },
+                    "downloads": ["https://cdn.modrinth.com/a.jar", "https://mirror.example/a.jar"],
+                    "fileSize": 10
+                },
+                {
+                    "path": "mods/optional.jar",
+                    "hashes": { "sha1": "a", "sha512": "b" },
+                    "env": { "client": "optional", "server": "required" },
+                    "downloads": ["https://cdn.modrinth.com/b.jar"],
+                    "fileSize": 20
+                },
+                {
+                    "path": "../escape.jar",
+                    "hashes": { "sha1": "a", "sha512": "b" },
+                    "env": { "client": "unsupported", "server": "required" },
+                    "downloads": [],
+                    "fileSize": 30
+                }
+            ],
+            "dependencies": { "minecraft": "1.20.1", "forge": "47.2.0" }
+        }"#,
+    )
+    .unwrap();
+
+    let files = &manifest.files;
+    assert_eq!(files[0].client_support(), EnvSupport::Required);
+    assert_eq!(
+        files[0].fallback_urls(),
+        vec!["https://mirror.example/a.jar"]
+    );
+    assert_eq!(files[1].client_support(), EnvSupport::Optional);
+    assert_eq!(files[2].client_support(), EnvSupport::Unsupported);
+    assert!(files[0].has_safe_path());
+    assert!(!files[2].has_safe_path());
+    assert_eq!(
+        manifest.dependencies.modloader().unwrap(),
+        (ModloaderType::Forge, "1.20.1-47.2.0".into())
+    );
+}
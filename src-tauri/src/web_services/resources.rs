@@ -1,120 +1,70 @@
-use crate::state::{resource_manager::ResourceManager, ManagerFromAppHandle};
-use autmc_authentication::MinecraftAccount;
+use crate::state::{
+    resource_manager::ResourceManager,
+    settings_manager::{LauncherSettings, SettingsManager},
+    ManagerFromAppHandle,
+};
+use autmc_authentication::{client_id, MinecraftAccount};
+use autmc_core::arguments::{
+    construct_arguments, rules_match, substitute_placeholders, LaunchArgumentPaths,
+    VersionArguments,
+};
+pub use autmc_core::manifest::ModloaderType;
+use autmc_downloader::{
+    boxed_buffered_download_stream, buffered_download_stream, download_bytes_from_url,
+    download_json_object_from_url, download_validated_file, hash_bytes_sha1, validate_hash_sha1,
+    validate_hash_sha256, DownloadError, Downloadable,
+};
 use bytes::Bytes;
-use futures::future::BoxFuture;
+use flate2::read::GzDecoder;
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Deserializer, Serialize};
 use std::{
     collections::{HashMap, HashSet},
-    env,
+    env, fmt,
     fs::{self, File},
     io::{self, Write},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     time::Instant,
 };
 use tauri::{AppHandle, Emitter, Manager, State, Wry};
+#[cfg(test)]
 use tempdir::TempDir;
+use tracing::{debug_span, info_span, Instrument};
 use xmltree::{Element, XMLNode};
 use zip::ZipArchive;
 
 use crate::{
-    consts::{JAVA_VERSION_MANIFEST_URL, LAUNCHER_NAME, LAUNCHER_VERSION},
+    consts::{ADOPTIUM_API_URL, FORGE_INSTALL_LOG, JAVA_VERSION_MANIFEST_URL},
+    instance_template::{copy_template_files, load_template, InstanceTemplate},
     state::{
-        instance_manager::{self, InstanceConfiguration, InstanceManager, InstanceState},
-        resource_manager::{ManifestError, ManifestResult, ResourceState},
+        instance_manager::{
+            available_directory_name, parse_jvm_arguments, InstanceConfiguration, InstanceManager,
+        },
+        resource_manager::{ManifestError, ManifestResult, PinnedModloader},
     },
+    system_info::{check_java_runtime, java_major_version, MemoryBounds},
     web_services::{
-        downloader::{
-            boxed_buffered_download_stream, buffered_download_stream, download_bytes_from_url,
-            download_json_object_from_url, validate_hash_sha1, DownloadError, Downloadable,
-        },
+        install_report::{record_phase, report_progress, track_install, InstallPhase},
         manifest::{
             fabric::{download_fabric_profile, obtain_fabric_library_hashes},
             forge::{
-                download_forge_hashes, download_forge_version, patch_forge, ForgeInstallerProfile,
-                InstallerArgumentPaths,
+                download_forge_hashes, download_forge_version, forge_library_name, patch_forge,
+                ForgeInstallerProfile, ForgePatchError, InstallerArgumentPaths,
             },
-            get_classpath_separator, path_to_utf8_str,
+            library_overrides::{needs_library_overrides, override_lwjgl_for_arm64},
             vanilla::{
-                Argument, AssetObject, DownloadableClassifier, JavaRuntimeFile,
-                JavaRuntimeManifest, JavaRuntimeType, VanillaVersion,
+                Asset, AssetObject, DownloadableClassifier, JavaRuntimeFile, JavaRuntimeManifest,
+                JavaRuntimeType, VanillaVersion,
             },
         },
     },
 };
 
-use super::{
-    downloader::{hash_bytes_sha1, validate_file_hash},
-    manifest::vanilla::{
-        AssetIndex, DownloadMetadata, JarType, JavaManifest, JavaRuntime, JavaVersion,
-        LaunchArguments, LaunchArguments113, Library, Logging, Rule, RuleType,
-        VanillaManifestVersion,
-    },
+use super::manifest::vanilla::{
+    AssetIndex, DownloadMetadata, JarType, JavaManifest, JavaRuntime, JavaVersion, LaunchArguments,
+    Library, Logging,
 };
 
-/// Checks if a single rule matches every case.
-/// Returns true when an allow rule matches or a disallow rule does not match.
-fn rule_matches(rule: &Rule) -> bool {
-    let rule_type = &rule.rule_type;
-    if rule_type.is_none() {
-        return match rule.action.as_str() {
-            "allow" => true,
-            "disallow" => false,
-            _ => unimplemented!("Unknwon rule action: {}", rule.action),
-        };
-    }
-    match rule_type.as_ref().unwrap() {
-        RuleType::Features(_feature_rules) => {
-            error!(
-                "Implement feature rules for arguments: {:#?}",
-                _feature_rules
-            );
-            // FIXME: Currently just skipping these
-            false
-        }
-        RuleType::OperatingSystem(os_rules) => {
-            // Check if all the rules match the current system.
-            let mut rule_matches = false;
-            for (key, value) in os_rules {
-                match key.as_str() {
-                    "name" => {
-                        let os_type = env::consts::OS;
-                        if value == os_type || (os_type == "macos" && value == "osx") {
-                            rule_matches = true;
-                        }
-                    }
-                    "arch" => {
-                        let os_arch = env::consts::ARCH;
-                        if value == os_arch || (value == "x86" && os_arch == "x86_64") {
-                            rule_matches = true;
-                        }
-                    }
-                    "version" => { /*TODO: Check version of os to make sure it matches*/ }
-                    _ => unimplemented!("Unknown rule map key: {}", key),
-                }
-            }
-            // Check if we allow or disallow this downloadable
-            match rule.action.as_str() {
-                "allow" => rule_matches,
-                "disallow" => !rule_matches,
-                _ => unimplemented!("Unknwon rule action: {}", rule.action),
-            }
-        }
-    }
-}
-
-fn rules_match(rules: &[Rule]) -> bool {
-    let mut result = false;
-    for rule in rules {
-        if rule_matches(rule) {
-            result = true;
-        } else {
-            return false;
-        }
-    }
-    result
-}
-
 fn determine_key_for_java_manifest<'a>(
     java_version_manifest_map: &HashMap<String, JavaManifest>,
 ) -> &'a str {
@@ -157,365 +107,60 @@ fn determine_key_for_java_manifest<'a>(
         }
     }
 }
-struct LaunchArgumentPaths {
-    // logging configurations are optional since they dont exist in versions 1.6.4 and older
-    logging: Option<(String, PathBuf)>,
-    library_paths: Vec<PathBuf>,
-    instance_path: PathBuf,
-    jar_path: PathBuf,
-    asset_dir_path: PathBuf,
-    library_directory: PathBuf,
-}
-
-// TODO: Add -Xmx and -Xms arguments for memory
-fn construct_jvm_arguments113(
-    arguments: &LaunchArguments113,
-    argument_paths: &LaunchArgumentPaths,
-    mc_version: &str,
-) -> Vec<String> {
-    let mut formatted_arguments = Vec::new();
-
-    if arguments.jvm.is_none() {
-        return formatted_arguments;
-    }
-
-    for jvm_arg in arguments.jvm.as_ref().unwrap().iter() {
-        match jvm_arg {
-            // For normal arguments, check if it has something that should be replaced and replace it
-            Argument::Arg(value) => {
-                let sub_arg = substitute_jvm_arguments(value, mc_version, argument_paths);
-                formatted_arguments.push(match sub_arg {
-                    Some(argument) => argument,
-                    None => value.into(),
-                });
-            }
-            // For conditional args, check their rules before adding to formatted_arguments vec
-            Argument::ConditionalArg { rules, values } => {
-                if !rules_match(rules) {
-                    continue;
-                }
-                for value in values {
-                    let sub_arg = substitute_jvm_arguments(value, mc_version, argument_paths);
-                    formatted_arguments.push(match sub_arg {
-                        Some(argument) => argument,
-                        None => value.into(),
-                    });
-                }
-            }
-        }
-    }
-    formatted_arguments
-}
-
-// TODO: Add -Xmx and -Xms arguments for memory
-fn construct_jvm_arguments112(
-    mc_version: &str,
-    argument_paths: &LaunchArgumentPaths,
-) -> Vec<String> {
-    vec![
-        substitute_jvm_arguments(
-            "-Djava.library.path=${natives_directory}",
-            mc_version,
-            argument_paths,
-        )
-        .unwrap(),
-        "-cp".to_string(),
-        substitute_jvm_arguments("${classpath}", mc_version, argument_paths).unwrap(),
-    ]
-}
-
-fn construct_arguments(
-    main_class: String,
-    additional_arguments: String,
-    // (Width, Height)
-    resolution: (String, String),
-    arguments: &LaunchArguments,
-    modloader_arguments: Option<LaunchArguments>,
-    modloader_type: &ModloaderType,
-    mc_version: &VanillaManifestVersion,
-    asset_index: &str,
-    argument_paths: LaunchArgumentPaths,
-) -> Vec<String> {
-    // IDEA: Vec could be 'with_capacity' if we calculate capacity first.
-    let mut formatted_arguments: Vec<String> = Vec::new();
-    let mut game_args: Vec<Argument> = Vec::new();
-
-    // Empty strings will screw up the jvm arguments
-    if !additional_arguments.is_empty() {
-        formatted_arguments.push(additional_arguments);
-    }
-
-    // Create game arguments from the launch arguments.
-    game_args.append(&mut match arguments {
-        // Substitute values in for placeholders in the jvm arguments.
-
-        // Versions <= 1.12  use a string of game arguments and do not provide any jvm arguments.
-        LaunchArguments::LaunchArguments112(game_args) => {
-            formatted_arguments.append(&mut construct_jvm_arguments112(
-                &mc_version.id,
-                &argument_paths,
-            ));
-
-            // If the modloader is forge and 1.12.2 or older, then ignore vanilla arguments since they
-            // are already provided by the forge arguments.
-            match modloader_arguments {
-                // If we have some arguments and the modloader type is forge
-                Some(_) if *modloader_type == ModloaderType::Forge => Vec::new(),
-                _ => {
-                    // Split game arg string on whitespace to get individual args
-                    game_args
-                        .split_ascii_whitespace()
-                        .map(|split| Argument::Arg(split.into()))
-                        .collect::<Vec<Argument>>()
-                }
-            }
-        }
-        // Versions >= 1.13 provide the game and jvm arguments.
-        LaunchArguments::LaunchArguments113(arguments) => {
-            formatted_arguments.append(&mut construct_jvm_arguments113(
-                arguments,
-                &argument_paths,
-                &mc_version.id,
-            ));
-            arguments.game.to_vec()
-        }
-    });
-
-    // Append modloader arguments if a modloader is selected.
-    if let Some(args) = modloader_arguments {
-        game_args.append(&mut match args {
-            LaunchArguments::LaunchArguments112(game_args) => {
-                formatted_arguments.append(&mut construct_jvm_arguments112(
-                    &mc_version.id,
-                    &argument_paths,
-                ));
-                // Split game arg string on whitespace to get individual args
-                game_args
-                    .split_ascii_whitespace()
-                    .map(|split| Argument::Arg(split.into()))
-                    .collect::<Vec<Argument>>()
-            }
-            // Versions >= 1.13 provide the game and jvm arguments.
-            LaunchArguments::LaunchArguments113(arguments) => {
-                formatted_arguments.append(&mut construct_jvm_arguments113(
-                    &arguments,
-                    &argument_paths,
-                    &mc_version.id,
-                ));
-                arguments.game.to_vec()
-            }
-        });
-    }
-
-    if let Some((arg, path)) = &argument_paths.logging {
-        // Construct the logging configuration argument
-        if let Some(substr) = get_arg_substring(arg) {
-            formatted_arguments.push(arg.replace(substr, path_to_utf8_str(path)));
-        }
-    }
-
-    // Add main class
-    formatted_arguments.push(main_class);
-
-    // Substitute values in for placeholders in the game arguments, skipping account-specific arguments.
-    for game_arg in game_args.iter() {
-        match game_arg {
-            // For normal arguments, check if it has something that should be replaced and replace it
-            Argument::Arg(value) => {
-                let sub_arg = substitute_game_arguments(
-                    value,
-                    &resolution,
-                    mc_version,
-                    asset_index,
-                    &argument_paths,
-                );
-                formatted_arguments.push(match sub_arg {
-                    Some(argument) => argument,
-                    None => value.into(),
-                });
-            }
-            // For conditional args, check their rules before adding to formatted_arguments vec
-            Argument::ConditionalArg { rules, values } => {
-                if !rules_match(rules) {
-                    continue;
-                }
-                for value in values {
-                    let sub_arg = substitute_game_arguments(
-                        value,
-                        &resolution,
-                        mc_version,
-                        asset_index,
-                        &argument_paths,
-                    );
-                    formatted_arguments.push(match sub_arg {
-                        Some(argument) => argument,
-                        None => value.into(),
-                    });
-                }
-            }
-        }
-    }
-    println!("HERE: {:#?}", formatted_arguments);
-    formatted_arguments
-}
-
-// Returns the substring inside the argument if it exists, otherwise None
-fn get_arg_substring(arg: &str) -> Option<&str> {
-    let substr_start = arg.chars().position(|c| c == '$');
-    let substr_end = arg.chars().position(|c| c == '}');
-
-    if let (Some(start), Some(end)) = (substr_start, substr_end) {
-        debug!("get_arg_substring: {}", &arg[start..=end]);
-        Some(&arg[start..=end])
-    } else {
-        None
-    }
-}
-
-// Returns a string with the substituted value in the jvm argument or None if it doesn't apply.
-// mc_version is only needed here for one forge specific launch option
-fn substitute_jvm_arguments(
-    arg: &str,
-    mc_version: &str,
-    argument_paths: &LaunchArgumentPaths,
-) -> Option<String> {
-    debug!("substitute_jvm_arguments: {}", arg);
-    let classpath_strs: Vec<&str> = argument_paths
-        .library_paths
-        .iter()
-        .map(|path| path_to_utf8_str(path))
-        .collect();
-
-    let mut formatted_argument: Option<String> = None;
-    // Iterate here since some arguments(forge) can have multiple substitutions in them
-    loop {
-        let arg_to_replace = if let Some(argument) = &formatted_argument {
-            argument.as_str()
-        } else {
-            arg
-        };
-
-        let substring = get_arg_substring(arg_to_replace);
-
-        if let Some(substr) = substring {
-            info!("Substituting {} for jvm arguments", &substr);
-            formatted_argument = match substr {
-                "${natives_directory}" => Some(arg_to_replace.replace(
-                    substr,
-                    path_to_utf8_str(&argument_paths.instance_path.join("natives")),
-                )),
-                "${launcher_name}" => Some(arg_to_replace.replace(substr, LAUNCHER_NAME)),
-                "${launcher_version}" => Some(arg_to_replace.replace(substr, LAUNCHER_VERSION)),
-                "${classpath}" => {
-                    debug!("Vec: {:#?}", classpath_strs);
-                    debug!(
-                        "Classpath: {} ",
-                        classpath_strs.join(&get_classpath_separator())
-                    );
-                    Some(arg_to_replace.replace(
-                        substr,
-                        &format!(
-                            "{}{}{}",
-                            classpath_strs.join(&get_classpath_separator()),
-                            get_classpath_separator(),
-                            path_to_utf8_str(&argument_paths.jar_path)
-                        ),
-                    ))
-                }
-                // Forge specific jvm arguments
-                "${library_directory}" => Some(
-                    arg_to_replace
-                        .replace(substr, path_to_utf8_str(&argument_paths.library_directory)),
-                ),
-                "${classpath_separator}" => {
-                    Some(arg_to_replace.replace(substr, &get_classpath_separator()))
-                }
-                "${version_name}" => Some(arg_to_replace.replace(substr, mc_version)),
-                _ => formatted_argument,
-            };
-            debug!("Got: {:#?}", formatted_argument);
-        } else {
-            break;
-        }
-    }
-    formatted_argument
-}
-
-fn substitute_game_arguments(
-    arg: &str,
-    resolution: &(String, String),
-    mc_version: &VanillaManifestVersion,
-    asset_index: &str,
-    argument_paths: &LaunchArgumentPaths,
-) -> Option<String> {
-    let substring = get_arg_substring(arg);
-
-    if let Some(substr) = substring {
-        info!("Substituting {} for game arguments", &substr);
-        match substr {
-            "${version_name}" => Some(arg.replace(substr, &mc_version.id)),
-            "${game_directory}" => {
-                Some(arg.replace(substr, path_to_utf8_str(&argument_paths.instance_path)))
-            }
-            "${assets_root}" => {
-                Some(arg.replace(substr, path_to_utf8_str(&argument_paths.asset_dir_path)))
-            }
-            "${game_assets}" => Some(arg.replace(
-                substr,
-                path_to_utf8_str(&argument_paths.asset_dir_path.join("virtual").join("legacy")),
-            )),
-            "${assets_index_name}" => Some(arg.replace(substr, asset_index)),
-            "${user_type}" => Some(arg.replace(substr, "mojang")),
-            "${version_type}" => Some(arg.replace(substr, &mc_version.version_type)),
-            "${resolution_width}" => Some(arg.replace(substr, &resolution.0)),
-            "${resolution_height}" => Some(arg.replace(substr, &resolution.1)),
-            "${user_properties}" => {
-                debug!("Substituting user_properties at substr: {}", substr);
-                Some(arg.replace(substr, "{}"))
-            }
-            _ => None,
-        }
-    } else {
-        None
-    }
-}
+/// Passed as the access token of offline accounts, the game refuses to start without one.
+const OFFLINE_ACCESS_TOKEN: &str = "0";
 
+/// Offline accounts have no tokens, the arguments only used online are left empty.
 pub fn substitute_account_specific_arguments(
     arg: &str,
     active_account: &MinecraftAccount,
 ) -> Option<String> {
-    if let Some(substr) = get_arg_substring(arg) {
-        match substr {
-            "${auth_player_name}" => Some(arg.replace(substr, &active_account.name)),
-            "${auth_uuid}" => Some(arg.replace(substr, &active_account.uuid)),
-            "${auth_access_token}" => {
-                Some(arg.replace(substr, &active_account.minecraft_access_token))
-            }
-            "${clientid}" => None,  // FIXME: Unknown
-            "${auth_xuid}" => None, // FIXME: Unknown
-            _ => None,
-        }
-    } else {
-        None
-    }
+    substitute_placeholders(arg, |name| match name {
+        "auth_player_name" => Some(active_account.name.clone()),
+        "auth_uuid" => Some(active_account.uuid.clone()),
+        "auth_access_token" if active_account.offline => Some(OFFLINE_ACCESS_TOKEN.into()),
+        "clientid" | "auth_xuid" if active_account.offline => Some(String::new()),
+        "auth_access_token" => Some(active_account.minecraft_access_token.clone()),
+        "clientid" => Some(client_id().into()),
+        "auth_xuid" => Some(active_account.xuid.clone()),
+        "user_type" => Some(active_account.user_type().into()),
+        _ => None,
+    })
 }
 
 struct LibraryData {
     downloadables: Vec<Box<dyn Downloadable + Send + Sync>>,
     classifiers: Vec<DownloadableClassifier>,
+    /// Since 1.19 natives are plain libraries named like `org.lwjgl:lwjgl:3.3.1:natives-linux`,
+    /// which stay on the classpath instead of being extracted.
+    native_libraries: Vec<Box<dyn Downloadable + Send + Sync>>,
+}
+
+/// Whether the library is one of the natives versions since 1.19 list as plain libraries.
+fn is_native_library(library: &Library) -> bool {
+    library
+        .name()
+        .split(':')
+        .nth(3)
+        .is_some_and(|classifier| classifier.starts_with("natives-"))
 }
 
-fn separate_classifiers_from_libraries(libraries: Vec<Library>) -> LibraryData {
+fn separate_classifiers_from_libraries(libraries: Vec<&Library>) -> LibraryData {
     let mut downloadables: Vec<Box<dyn Downloadable + Send + Sync>> = Vec::new();
     let mut classifiers: Vec<DownloadableClassifier> = Vec::new();
+    let mut native_libraries: Vec<Box<dyn Downloadable + Send + Sync>> = Vec::new();
 
     // Since libraries can have one artifact but no classifiers, or no artifact but (one or many) classifiers, or an artifact AND classifiers
     // we extract all the downloadable artifacts into a vec and perform a single buffered download with them.
     for library in libraries {
-        let downloads = &library.downloads;
+        let downloads = library.downloads();
         // Push the artifact if library has one.
-        if let Some(artifact) = &downloads.artifact {
-            downloadables.push(Box::new(artifact.to_owned()));
+        if let Some(artifact) = downloads.artifact() {
+            if is_native_library(library) {
+                native_libraries.push(Box::new(artifact.to_owned()));
+            } else {
+                downloadables.push(Box::new(artifact.to_owned()));
+            }
         }
         // If there is a natives json entry with an applicable (os dependent) classifier, get and append it
         let key = library.determine_key_for_classifiers();
@@ -526,12 +171,13 @@ fn separate_classifiers_from_libraries(libraries: Vec<Library>) -> LibraryData {
                 None => continue,
             };
             classifiers.push(classifier.clone());
-            downloadables.push(Box::new(classifier.classifier));
+            downloadables.push(Box::new(classifier.classifier().clone()));
         }
     }
     LibraryData {
         classifiers,
         downloadables,
+        native_libraries,
     }
 }
 
@@ -545,21 +191,26 @@ async fn download_libraries(
     }
     let start = Instant::now();
     // Perform one buffered download for all libraries, including classifiers
-    boxed_buffered_download_stream(libraries, libraries_dir, |bytes, artifact| {
-        // Skip empty hashes for forge 1.11 and older.
-        if !artifact.hash().is_empty() && !validate_hash_sha1(bytes, artifact.hash()) {
-            let err = format!("Error downloading {}, invalid hash.", &artifact.url());
-            error!("{}", err);
-            return Err(DownloadError::InvalidFileHash(err));
-        }
-        debug!("Downloading library: {}", artifact.name());
-        // Windows only?
-        // let artifact_path = str::replace(artifact.name(), "/", "\\");
-        let path = artifact.path(libraries_dir);
-        let mut file = File::create(path)?;
-        file.write_all(bytes)?;
-        Ok(())
-    })
+    boxed_buffered_download_stream(
+        libraries,
+        libraries_dir,
+        |bytes, artifact| {
+            // Skip empty hashes for forge 1.11 and older.
+            if !artifact.hash().is_empty() && !validate_hash_sha1(bytes, artifact.hash()) {
+                let err = format!("Error downloading {}, invalid hash.", &artifact.url());
+                error!("{}", err);
+                return Err(DownloadError::InvalidFileHash(err));
+            }
+            debug!("Downloading library: {}", artifact.name());
+            // Windows only?
+            // let artifact_path = str::replace(artifact.name(), "/", "\\");
+            let path = artifact.path(libraries_dir);
+            let mut file = File::create(path)?;
+            file.write_all(bytes)?;
+            Ok(())
+        },
+        |progress| report_progress(InstallPhase::Libraries, progress),
+    )
     .await?;
     info!(
         "Successfully downloaded libraries in {}ms",
@@ -573,37 +224,42 @@ async fn download_libraries(
     Ok(file_paths)
 }
 
+/// Path the obfuscation mappings of a version are stored at, next to its jar.
+pub fn mappings_path(versions_dir: &Path, jar_type: JarType, version_id: &str) -> PathBuf {
+    versions_dir
+        .join(version_id)
+        .join(jar_type.side())
+        .join(format!("{}.txt", version_id))
+}
+
 async fn download_game_jar(
     versions_dir: &Path,
     jar_type: JarType,
     download: &DownloadMetadata,
     version_id: &str,
 ) -> ManifestResult<PathBuf> {
-    let jar_str = match jar_type {
-        JarType::Client => "client",
-        JarType::Server => "server",
-    };
+    let jar_str = jar_type.side();
     // Create all dirs in path to file location.
     let dir_path = &versions_dir.join(version_id).join(jar_str);
     fs::create_dir_all(dir_path)?;
 
     let path = dir_path.join(format!("{}.jar", &version_id));
-    let valid_hash = download.hash();
-    // Check if the file exists and the hash matches the download's sha1.
-    if !validate_file_hash(&path, valid_hash) {
-        info!("Downloading {} {} jar", version_id, jar_str);
-        let bytes = download_bytes_from_url(download.url()).await?;
-        if !validate_hash_sha1(&bytes, valid_hash) {
-            let err = format!(
-                "Error downloading {} {} jar, invalid hash.",
-                version_id, jar_str
-            );
-            error!("{}", err);
-            return Err(ManifestError::MismatchedFileHash(err));
-        }
-        let mut file = File::create(&path)?;
-        file.write_all(&bytes)?;
+    download_validated_file(&path, download.url(), download.hash()).await?;
+    Ok(path)
+}
+
+/// Downloads the mappings used to deobfuscate the jar, stored alongside it.
+async fn download_game_mappings(
+    versions_dir: &Path,
+    jar_type: JarType,
+    download: &DownloadMetadata,
+    version_id: &str,
+) -> ManifestResult<PathBuf> {
+    let path = mappings_path(versions_dir, jar_type, version_id);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
     }
+    download_validated_file(&path, download.url(), download.hash()).await?;
     Ok(path)
 }
 
@@ -613,14 +269,14 @@ async fn download_java_from_runtime_manifest(
 ) -> ManifestResult<PathBuf> {
     info!("Downloading java runtime manifset");
     let version_manifest: JavaRuntimeManifest =
-        download_json_object_from_url(manifest.manifest.url()).await?;
-    let base_path = &java_dir.join(&manifest.version.name);
+        download_json_object_from_url(manifest.manifest().url()).await?;
+    let base_path = &java_dir.join(manifest.version().name());
 
-    let mut files: Vec<JavaRuntimeFile> = Vec::new();
+    let mut files: Vec<&JavaRuntimeFile> = Vec::new();
     // Links is a Vec<(Path, Target)>
-    let mut links: Vec<(String, String)> = Vec::new();
+    let mut links: Vec<(&str, &str)> = Vec::new();
     // Create directories first and save the remaining.
-    for jrt in version_manifest.files {
+    for jrt in version_manifest.files() {
         match jrt {
             JavaRuntimeType::File(jrt_file) => files.push(jrt_file),
             JavaRuntimeType::Directory(dir) => {
@@ -635,28 +291,20 @@ async fn download_java_from_runtime_manifest(
     // FIXME: Currently downloading `raw` files, switch to lzma and decompress locally.
     info!("Downloading all java files.");
     let start = Instant::now();
-    buffered_download_stream(&files, base_path, |bytes, jrt| {
-        if !validate_hash_sha1(bytes, jrt.hash()) {
-            let err = format!("Error downloading {}, invalid hash.", &jrt.url());
-            error!("{}", err);
-            return Err(DownloadError::InvalidFileHash(err));
-        }
-        let path = jrt.path(base_path);
-        let mut file = File::create(path)?;
-        #[cfg(target_family = "unix")]
-        {
-            use std::os::unix::prelude::PermissionsExt;
-
-            // Mark the file as executable on unix os's
-            if jrt.executable {
-                let mut permissions = file.metadata()?.permissions();
-                permissions.set_mode(0o775);
-                file.set_permissions(permissions)?;
+    buffered_download_stream(
+        &files,
+        base_path,
+        |bytes, jrt| {
+            if !validate_hash_sha1(bytes, jrt.hash()) {
+                let err = format!("Error downloading {}, invalid hash.", &jrt.url());
+                error!("{}", err);
+                return Err(DownloadError::InvalidFileHash(err));
             }
-        }
-        file.write_all(bytes)?;
-        Ok(())
-    })
+            write_runtime_file(&jrt.path(base_path), bytes, jrt.executable())?;
+            Ok(())
+        },
+        |progress| report_progress(InstallPhase::Java, progress),
+    )
     .await?;
     info!("Downloaded java in {}ms", start.elapsed().as_millis());
 
@@ -667,55 +315,218 @@ async fn download_java_from_runtime_manifest(
             // Cant fail since the dirs were made before
             let dir_path = to.parent().unwrap().join(link.1);
             let from = dir_path.canonicalize()?;
+            link_runtime_file(&from, to)?;
+        }
+    }
 
-            if from.is_dir() {
-                debug!(
-                    "Creating symlink between {} and {}",
-                    from.display(),
-                    to.display()
-                );
-                #[cfg(target_os = "linux")]
-                {
-                    use std::os::unix::fs::symlink;
+    // The macOS runtime is an app bundle, on Windows javaw doesn't open a console window.
+    let java_path = match env::consts::OS {
+        "windows" => base_path.join("bin").join("javaw.exe"),
+        "macos" => base_path
+            .join("jre.bundle")
+            .join("Contents")
+            .join("Home")
+            .join("bin")
+            .join("java"),
+        _ => base_path.join("bin").join("java"),
+    };
+    info!("Using java path: {:?}", java_path);
+    Ok(java_path)
+}
 
-                    // Create symlink FROM "target" TO "path"
-                    symlink(from, to)?;
-                }
-            } else {
-                debug!(
-                    "Creating hard link between {} and {}",
-                    from.display(),
-                    to.display()
-                );
-                // Create hard link FROM "target" TO "path"
-                fs::hard_link(from, to)?;
-            }
+/// Writes a file of the java runtime, marking it executable on unix if the manifest does. The mode
+/// is changed once the contents are written so a failed download never leaves a partial executable.
+fn write_runtime_file(path: &Path, bytes: &[u8], executable: bool) -> io::Result<()> {
+    fs::write(path, bytes)?;
+    #[cfg(unix)]
+    if executable {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut permissions = fs::metadata(path)?.permissions();
+        permissions.set_mode(permissions.mode() | 0o111);
+        fs::set_permissions(path, permissions)?;
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+    Ok(())
+}
+
+/// Links the runtime's `to` to `from`, copying `from` if the link can't be made. Directories are
+/// symlinked, which needs developer mode on Windows so junctions are tried next there. Files are
+/// hard linked.
+fn link_runtime_file(from: &Path, to: &Path) -> io::Result<()> {
+    let linked = if from.is_dir() {
+        debug!(
+            "Creating symlink between {} and {}",
+            from.display(),
+            to.display()
+        );
+        symlink_dir(from, to)
+    } else {
+        debug!(
+            "Creating hard link between {} and {}",
+            from.display(),
+            to.display()
+        );
+        fs::hard_link(from, to)
+    };
+    if let Err(e) = linked {
+        // Some file systems, like FAT32, support neither.
+        debug!("Could not link {}, copying it: {}", to.display(), e);
+        copy_recursively(from, to)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink_dir(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(from, to)
+}
+
+#[cfg(windows)]
+fn symlink_dir(from: &Path, to: &Path) -> io::Result<()> {
+    if std::os::windows::fs::symlink_dir(from, to).is_ok() {
+        return Ok(());
+    }
+    // Junctions don't need developer mode, but only point to local volumes.
+    let status = std::process::Command::new("cmd")
+        .args(["/C", "mklink", "/J"])
+        .arg(to)
+        .arg(from)
+        .stdout(std::process::Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "Could not create a junction to {}, mklink exited with {}",
+            from.display(),
+            status
+        )));
+    }
+    Ok(())
+}
+
+/// Copies the file or directory `from` to `to`.
+pub fn copy_recursively(from: &Path, to: &Path) -> io::Result<()> {
+    if !from.is_dir() {
+        fs::copy(from, to)?;
+        return Ok(());
+    }
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// The java major versions a game version and its modloader can run on.
+#[derive(Debug, PartialEq)]
+struct JavaRequirement {
+    min_major: u32,
+    max_major: Option<u32>,
+}
+
+impl JavaRequirement {
+    fn new(java: &JavaVersion, modloader_type: &ModloaderType) -> Self {
+        // Forge before 1.17 relies on the classloader that was replaced in java 9, vanilla runs
+        // on newer versions too.
+        let max_major =
+            (*modloader_type == ModloaderType::Forge && java.major_version() <= 8).then_some(8);
+        Self {
+            min_major: java.major_version(),
+            max_major,
         }
     }
 
-    let java_path = base_path.join("bin").join("java");
-    info!("Using java path: {:?}", java_path);
+    fn allows(&self, major: u32) -> bool {
+        major >= self.min_major && self.max_major.map_or(true, |max| major <= max)
+    }
+}
+
+impl fmt::Display for JavaRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max_major {
+            Some(max) if max == self.min_major => write!(f, "java {}", max),
+            Some(max) => write!(f, "java {} to {}", self.min_major, max),
+            None => write!(f, "java {} or newer", self.min_major),
+        }
+    }
+}
+
+/// The java override to create the instance with, `None` to use the launcher's own java when the
+/// override can't run the version. `ignore_requirement` uses the override regardless.
+fn checked_java_override(
+    java_path_override: &str,
+    requirement: &JavaRequirement,
+    ignore_requirement: bool,
+) -> Option<PathBuf> {
+    if java_path_override.is_empty() {
+        return None;
+    }
+    let path = PathBuf::from(java_path_override);
+    if ignore_requirement {
+        return Some(path);
+    }
+    match java_major_version(&path) {
+        Some(major) if requirement.allows(major) => Some(path),
+        Some(major) => {
+            warn!(
+                "Java {} at {} can't run this version, it needs {}. Using the launcher's java instead",
+                major,
+                path.display(),
+                requirement
+            );
+            None
+        }
+        None => {
+            warn!(
+                "Could not tell which java version is at {}, using it anyway",
+                path.display()
+            );
+            Some(path)
+        }
+    }
+}
+
+/// Downloads Mojang's runtime for `java` and checks that it runs. With `adoptium_fallback`, java is
+/// downloaded from Adoptium instead if there is no runtime for this system or it doesn't run.
+async fn download_java_version(
+    java_dir: &Path,
+    java: JavaVersion,
+    adoptium_fallback: bool,
+) -> ManifestResult<PathBuf> {
+    let error = match download_mojang_java(java_dir, &java).await {
+        Ok(java_path) => match check_java_runtime(&java_path, java.major_version()) {
+            Ok(version) => {
+                info!("Installed {}", version);
+                return Ok(java_path);
+            }
+            Err(e) => ManifestError::from(e),
+        },
+        Err(e) => e,
+    };
+    if !adoptium_fallback {
+        return Err(error);
+    }
+    warn!(
+        "Could not install java {} from Mojang, trying Adoptium: {:?}",
+        java.major_version(),
+        error
+    );
+    let java_path = download_adoptium_java(java_dir, java.major_version()).await?;
+    let version = check_java_runtime(&java_path, java.major_version())?;
+    info!("Installed {} from Adoptium", version);
     Ok(java_path)
 }
 
-async fn download_java_version(java_dir: &Path, java: JavaVersion) -> ManifestResult<PathBuf> {
+async fn download_mojang_java(java_dir: &Path, java: &JavaVersion) -> ManifestResult<PathBuf> {
     info!("Downloading java version manifest");
     let java_version_manifest: HashMap<String, JavaManifest> =
         download_json_object_from_url(JAVA_VERSION_MANIFEST_URL).await?;
     let manifest_key = determine_key_for_java_manifest(&java_version_manifest);
 
-    let java_manifest = &java_version_manifest.get(manifest_key).unwrap();
-    let runtime_opt = match java.component.as_str() {
-        "java-runtime-alpha" => &java_manifest.java_runtime_alpha,
-        "java-runtime-beta" => &java_manifest.java_runtime_beta,
-        "java-runtime-gamma" => &java_manifest.java_runtime_gamma,
-        "jre-legacy" => &java_manifest.jre_legacy,
-        "minecraft-java-exe" => &java_manifest.minecraft_java_exe,
-        _ => unreachable!(
-            "No such runtime found for java component: {}",
-            &java.component
-        ),
-    };
+    let java_manifest = java_version_manifest.get(manifest_key).unwrap();
+    let runtime_opt = java_manifest.runtime(java.component());
     info!("Downloading runtime: {:#?}", runtime_opt);
     match runtime_opt {
         Some(runtime) => {
@@ -723,13 +534,151 @@ async fn download_java_version(java_dir: &Path, java: JavaVersion) -> ManifestRe
             Ok(download_java_from_runtime_manifest(java_dir, runtime).await?)
         }
         None => {
-            let s = format!("Java runtime is empty for component {}", &java.component);
+            let s = format!("Java runtime is empty for component {}", java.component());
             error!("{}", s);
             Err(ManifestError::VersionRetrievalError(s))
         }
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct AdoptiumRelease {
+    release_name: String,
+    binary: AdoptiumBinary,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumBinary {
+    package: AdoptiumPackage,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumPackage {
+    /// The archive's file name, `.zip` on Windows and `.tar.gz` everywhere else.
+    name: String,
+    link: String,
+    /// SHA256 of the archive.
+    checksum: String,
+}
+
+/// The os and architecture names the Adoptium api uses for this system.
+fn adoptium_platform() -> Option<(&'static str, &'static str)> {
+    let os = match env::consts::OS {
+        "linux" => "linux",
+        "macos" => "mac",
+        "windows" => "windows",
+        _ => return None,
+    };
+    let arch = match env::consts::ARCH {
+        "x86_64" => "x64",
+        "x86" => "x32",
+        "aarch64" => "aarch64",
+        "arm" => "arm",
+        _ => return None,
+    };
+    Some((os, arch))
+}
+
+/// Downloads the latest Eclipse Temurin JRE of java `major` into `java_dir`.
+async fn download_adoptium_java(java_dir: &Path, major: u32) -> ManifestResult<PathBuf> {
+    let unavailable = || {
+        ManifestError::VersionRetrievalError(format!(
+            "Adoptium has no java {} for {} {}",
+            major,
+            env::consts::OS,
+            env::consts::ARCH
+        ))
+    };
+    let (os, arch) = adoptium_platform().ok_or_else(unavailable)?;
+    let url = format!(
+        "{}/assets/latest/{}/hotspot?os={}&architecture={}&image_type=jre&vendor=eclipse",
+        ADOPTIUM_API_URL, major, os, arch
+    );
+    let releases: Vec<AdoptiumRelease> = download_json_object_from_url(&url).await?;
+    let release = releases.into_iter().next().ok_or_else(unavailable)?;
+    let package = release.binary.package;
+
+    info!("Downloading {} from Adoptium", release.release_name);
+    let bytes = download_bytes_from_url(&package.link).await?;
+    if !validate_hash_sha256(&bytes, &package.checksum) {
+        return Err(ManifestError::MismatchedFileHash(format!(
+            "Error downloading {}, invalid hash.",
+            package.link
+        )));
+    }
+    let base_path = java_dir.join(format!("temurin-{}", major));
+    // Left over from an older release or a failed install.
+    if base_path.exists() {
+        fs::remove_dir_all(&base_path)?;
+    }
+    if package.name.ends_with(".zip") {
+        extract_java_zip(&bytes, &base_path)?;
+    } else {
+        extract_java_tarball(&bytes, &base_path)?;
+    }
+
+    let java_path = match os {
+        "windows" => base_path.join("bin").join("javaw.exe"),
+        "mac" => base_path
+            .join("Contents")
+            .join("Home")
+            .join("bin")
+            .join("java"),
+        _ => base_path.join("bin").join("java"),
+    };
+    Ok(java_path)
+}
+
+/// The path of an archive entry inside the folder it was packed in, like `jdk-17.0.8+7-jre/bin`.
+/// `None` for the folder itself and entries that would be extracted outside of it.
+fn strip_archive_root(path: &Path) -> Option<PathBuf> {
+    let mut components = path.components();
+    components.next()?;
+    let stripped = components.as_path();
+    let is_safe = stripped
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    (is_safe && !stripped.as_os_str().is_empty()).then(|| stripped.into())
+}
+
+/// Extracts the `.tar.gz` a JRE is packed in to `base_path`, keeping file modes and links.
+fn extract_java_tarball(bytes: &[u8], base_path: &Path) -> io::Result<()> {
+    let mut archive = tar::Archive::new(GzDecoder::new(bytes));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = match strip_archive_root(&entry.path()?) {
+            Some(path) => base_path.join(path),
+            None => continue,
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&path)?;
+    }
+    Ok(())
+}
+
+/// Extracts the `.zip` a JRE is packed in to `base_path`.
+fn extract_java_zip(bytes: &[u8], base_path: &Path) -> ManifestResult<()> {
+    let mut archive = ZipArchive::new(io::Cursor::new(bytes))?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let path = match file.enclosed_name().as_deref().and_then(strip_archive_root) {
+            Some(path) => base_path.join(path),
+            None => continue,
+        };
+        if file.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut file, &mut File::create(&path)?)?;
+    }
+    Ok(())
+}
+
 type PatchingResult<T> = Result<T, PatchingError>;
 
 #[derive(Debug)]
@@ -781,17 +730,19 @@ fn patch_logging_configuration(bytes: &Bytes) -> PatchingResult<Bytes> {
     Ok(Bytes::from(result))
 }
 
-/// Downloads a logging configureation into ${app_dir}/assets/objects/**first two hash chars**/${logging_configuration.id}
+/// Downloads the `jar_type` logging configureation into ${app_dir}/assets/objects/**first two hash chars**/${logging_configuration.id}
+/// Returns `None` if the version has no logging configuration for that side.
 async fn download_logging_configurations(
     asset_objects_dir: &Path,
     logging: &Logging,
-) -> ManifestResult<(String, PathBuf)> {
-    let client_logger = &logging.client;
-    info!(
-        "Downloading logging configuration {}",
-        client_logger.file_id()
-    );
-    let original_bytes = download_bytes_from_url(client_logger.file_url()).await?;
+    jar_type: JarType,
+) -> ManifestResult<Option<(String, PathBuf)>> {
+    let logger = match logging.logger(jar_type) {
+        Some(logger) => logger,
+        None => return Ok(None),
+    };
+    info!("Downloading logging configuration {}", logger.file_id());
+    let original_bytes = download_bytes_from_url(logger.file_url()).await?;
 
     let patched_bytes = match patch_logging_configuration(&original_bytes) {
         Ok(b) => b,
@@ -805,72 +756,155 @@ async fn download_logging_configurations(
     let objects_dir = &asset_objects_dir.join(first_two_chars.0);
     fs::create_dir_all(objects_dir)?;
 
-    let path = objects_dir.join(client_logger.file_id());
+    let path = objects_dir.join(logger.file_id());
     let mut file = File::create(&path)?;
     file.write_all(&patched_bytes)?;
-    Ok((client_logger.argument.clone(), path))
+    Ok(Some((logger.argument().to_owned(), path)))
 }
 
+/// Downloads the asset index and its objects into `${asset_dir}/objects`. Returns the asset index
+/// id and the directory the game should read its assets from.
 async fn download_assets(
     instance_dir: &Path,
     asset_dir: &Path,
     asset_index: &AssetIndex,
-) -> ManifestResult<String> {
-    let metadata = &asset_index.metadata;
-    let asset_object: AssetObject = download_json_object_from_url(metadata.url()).await?;
+) -> ManifestResult<(String, PathBuf)> {
+    let metadata = asset_index.metadata();
     let asset_index_dir = asset_dir.join("indexes");
     let index_bytes = download_bytes_from_url(metadata.url()).await?;
+    let asset_object: AssetObject = serde_json::from_slice(&index_bytes)?;
     fs::create_dir_all(&asset_index_dir)?;
 
     info!("Asset Index ID: {:?}", &asset_index);
 
-    let asset_index_name = format!("{}.json", asset_index.id);
+    let asset_index_name = format!("{}.json", asset_index.id());
     let index_path = &asset_index_dir.join(&asset_index_name);
     let mut index_file = File::create(index_path)?;
     index_file.write_all(&index_bytes)?;
-    info!("Downloading {} assets", &asset_object.objects.len());
+    info!("Downloading {} assets", asset_object.objects().len());
 
     let start = Instant::now();
 
-    // TODO: Dont download resources into each instance path directly, instead download once into
-    // ${assets_dir}/resources and copy into instance dir.
-    let asset_objects_dir = if asset_index.id == "legacy" {
-        asset_dir.join("virtual").join("legacy")
-    } else if asset_index.id == "pre-1.6" {
-        instance_dir.join("resources")
-    } else {
-        asset_dir.join("objects")
-    };
-
+    let asset_objects_dir = asset_dir.join("objects");
     fs::create_dir_all(&asset_objects_dir)?;
 
-    let x = buffered_download_stream(&asset_object.objects, &asset_objects_dir, |bytes, asset| {
-        if !validate_hash_sha1(bytes, asset.hash()) {
-            let err = format!(
-                "Error downloading asset {}, expected {} but got {}",
-                &asset.name(),
-                &asset.hash(),
-                hash_bytes_sha1(bytes)
-            );
-            error!("{}", err);
-            return Err(DownloadError::InvalidFileHash(err));
-        }
-        let path = asset.path(&asset_objects_dir);
+    let x = buffered_download_stream(
+        asset_object.objects(),
+        &asset_objects_dir,
+        |bytes, asset| {
+            if !validate_hash_sha1(bytes, asset.hash()) {
+                let err = format!(
+                    "Error downloading asset {}, expected {} but got {}",
+                    &asset.name(),
+                    &asset.hash(),
+                    hash_bytes_sha1(bytes)
+                );
+                error!("{}", err);
+                return Err(DownloadError::InvalidFileHash(err));
+            }
+            let path = asset.path(&asset_objects_dir);
 
-        fs::create_dir_all(path.parent().unwrap())?;
+            fs::create_dir_all(path.parent().unwrap())?;
 
-        debug!("Bulk Download asset path: {:#?}", &path);
-        let mut file = File::create(path)?;
-        file.write_all(bytes)?;
-        Ok(())
-    })
+            debug!("Bulk Download asset path: {:#?}", &path);
+            let mut file = File::create(path)?;
+            file.write_all(bytes)?;
+            Ok(())
+        },
+        |progress| report_progress(InstallPhase::Assets, progress),
+    )
     .await;
     info!(
         "Finished downloading assets in {}ms - {:#?}",
         start.elapsed().as_millis(),
         &x
     );
-    Ok(asset_index.id.clone())
+
+    let game_assets_dir = game_assets_dir(instance_dir, asset_dir, asset_index.id(), &asset_object);
+    if asset_object.is_virtual() || asset_object.map_to_resources() {
+        materialize_virtual_assets(asset_object.objects(), &asset_objects_dir, &game_assets_dir)?;
+    }
+    Ok((asset_index.id().to_owned(), game_assets_dir))
+}
+
+/// The asset index an instance was created with, so it can be checked before launching.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PinnedAssetIndex {
+    pub id: String,
+    pub url: String,
+    pub sha1: String,
+}
+
+impl From<&AssetIndex> for PinnedAssetIndex {
+    fn from(asset_index: &AssetIndex) -> Self {
+        Self {
+            id: asset_index.id().to_owned(),
+            url: asset_index.metadata().url().into(),
+            sha1: asset_index.metadata().hash().into(),
+        }
+    }
+}
+
+/// Downloads the asset index again if it is missing or was changed. Indexes are shared between
+/// instances, without one the game starts without any sounds or languages.
+pub async fn ensure_asset_index(
+    asset_dir: &Path,
+    asset_index: &PinnedAssetIndex,
+) -> ManifestResult<()> {
+    let index_path = asset_dir
+        .join("indexes")
+        .join(format!("{}.json", asset_index.id));
+    download_validated_file(&index_path, &asset_index.url, &asset_index.sha1).await?;
+    Ok(())
+}
+
+/// The directory the game reads its assets from for the given asset index.
+fn game_assets_dir(
+    instance_dir: &Path,
+    asset_dir: &Path,
+    asset_index_id: &str,
+    asset_object: &AssetObject,
+) -> PathBuf {
+    if asset_object.map_to_resources() {
+        instance_dir.join("resources")
+    } else if asset_object.is_virtual() {
+        asset_dir.join("virtual").join(asset_index_id)
+    } else {
+        asset_dir.to_path_buf()
+    }
+}
+
+/// Copies the hashed objects from `objects_dir` to their named paths in `target_dir`, which is
+/// the layout versions before 1.7.3 expect. Files that already exist with the right size are kept.
+fn materialize_virtual_assets(
+    assets: &[Asset],
+    objects_dir: &Path,
+    target_dir: &Path,
+) -> io::Result<()> {
+    debug!("Materializing virtual assets into {}", target_dir.display());
+    for asset in assets {
+        let object_path = asset.path(objects_dir);
+        let virtual_path = asset.virtual_path(target_dir);
+
+        if let Ok(metadata) = fs::metadata(&virtual_path) {
+            if metadata.len() == asset.size() as u64 {
+                continue;
+            }
+        }
+        if !object_path.is_file() {
+            warn!(
+                "Missing asset object {} for {}",
+                object_path.display(),
+                asset.name()
+            );
+            continue;
+        }
+        if let Some(parent) = virtual_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&object_path, &virtual_path)?;
+    }
+    Ok(())
 }
 
 fn extract_natives(
@@ -879,59 +913,67 @@ fn extract_natives(
     classifiers: Vec<DownloadableClassifier>,
 ) -> ManifestResult<()> {
     debug!("Extracting Natives");
+    let natives_path = instance_dir.join("natives");
     for classifier in classifiers {
         debug!("Classifier: {:#?}", classifier);
-        let classifier_path = classifier.path(libraries_dir);
-        let natives_path = instance_dir.join("natives");
-        let jar_file = File::open(&classifier_path);
-        debug!("Jar File: {:#?} at {}", jar_file, classifier_path.display());
-        let mut archive = ZipArchive::new(jar_file.unwrap())?;
-
-        'zip: for i in 0..archive.len() {
-            debug!("In loop");
-            if let Ok(mut file) = archive.by_index(i) {
-                debug!("File is ok");
-                if file.is_dir() {
-                    continue;
-                }
-                let zip_path = match file.enclosed_name() {
-                    Some(name) => name.to_owned(),
-                    None => continue,
-                };
+        let exclude = classifier
+            .extraction_rule()
+            .map_or(&[][..], |rule| rule.exclude());
+        extract_native_jar(&classifier.path(libraries_dir), &natives_path, exclude)?;
+    }
+    Ok(())
+}
 
-                debug!("ZipArchive Path: {}", zip_path.display());
-                // If the zip path starts with (or is) an excluded path, dont extract it.
-                if let Some(extraction_rule) = &classifier.extraction_rule {
-                    for exclusion in &extraction_rule.exclude {
-                        if zip_path.starts_with(exclusion) {
-                            debug!("Excluding {}", exclusion);
-                            continue 'zip;
-                        }
-                    }
-                }
-                let path = natives_path.join(zip_path);
-                if let Some(parent) = path.parent() {
-                    if !parent.exists() {
-                        fs::create_dir_all(parent)?;
-                    }
-                }
-                debug!("Copy from {:#?} to {:#?}", file.name(), path.display());
-                let mut output_file = File::create(&path)?;
-                io::copy(&mut file, &mut output_file)?;
-            }
+/// Extracts the jar at `jar_path` into `natives_path`, except for the entries matching `exclude`.
+fn extract_native_jar(
+    jar_path: &Path,
+    natives_path: &Path,
+    exclude: &[String],
+) -> ManifestResult<()> {
+    debug!("Extracting natives from {}", jar_path.display());
+    let mut archive = ZipArchive::new(File::open(jar_path)?)?;
+    for i in 0..archive.len() {
+        let mut file = match archive.by_index(i) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+        if file.is_dir() || is_excluded(file.name(), exclude) {
+            continue;
         }
+        let zip_path = match file.enclosed_name() {
+            Some(name) => name.to_owned(),
+            None => continue,
+        };
+        let path = natives_path.join(zip_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        debug!("Copy from {:#?} to {:#?}", file.name(), path.display());
+        let mut output_file = File::create(&path)?;
+        io::copy(&mut file, &mut output_file)?;
     }
     Ok(())
 }
 
+/// Whether the jar entry `name` starts with one of the `exclude` prefixes. Matched on the entry
+/// name like the official launcher does, so `META-INF/` excludes everything in `META-INF` and a
+/// partial prefix like `META-INF/MANIFEST` still excludes `META-INF/MANIFEST.MF`.
+fn is_excluded(name: &str, exclude: &[String]) -> bool {
+    // Some jars are zipped with windows separators.
+    let name = name.replace('\\', "/");
+    exclude
+        .iter()
+        .any(|exclusion| name.starts_with(exclusion.as_str()))
+}
+
 /// Applies library rules from the manifest and also patches
 /// forge universal library where the url is empty.
-fn apply_library_rules(libraries: Vec<Library>) -> Vec<Library> {
+fn apply_library_rules<'a>(libraries: impl IntoIterator<Item = &'a Library>) -> Vec<&'a Library> {
     libraries
         .into_iter()
         .filter_map(|lib| {
             // If we have any rules...
-            if let Some(rules) = &lib.rules {
+            if let Some(rules) = lib.rules() {
                 // and the rules dont match
                 if !rules_match(rules) {
                     // remove
@@ -948,39 +990,12 @@ fn apply_library_rules(libraries: Vec<Library>) -> Vec<Library> {
         .collect()
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
-pub enum ModloaderType {
-    Forge,
-    Fabric,
-    None,
-}
-
-impl From<&str> for ModloaderType {
-    fn from(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "forge" => ModloaderType::Forge,
-            "fabric" => ModloaderType::Fabric,
-            _ => ModloaderType::None,
-        }
-    }
-}
-
-impl ToString for ModloaderType {
-    fn to_string(&self) -> String {
-        match &self {
-            ModloaderType::Forge => "forge".into(),
-            ModloaderType::Fabric => "fabric".into(),
-            ModloaderType::None => "".into(),
-        }
-    }
-}
-
 /// Seperates libraries with empty url into a different vec.
 /// Returns a tuple of (<empty url>, <remaining libraries>)
-fn seperate_nondownloadables(libraries: Vec<Library>) -> (Vec<Library>, Vec<Library>) {
+fn seperate_nondownloadables(libraries: &[Library]) -> (Vec<&Library>, Vec<&Library>) {
     // Pull out forge libraries with empty url's so they can be extracted from the installer
-    libraries.into_iter().partition(|library| {
-        if let Some(artifact) = &library.downloads.artifact {
+    libraries.iter().partition(|library| {
+        if let Some(artifact) = library.downloads().artifact() {
             artifact.url().is_empty()
         } else {
             false
@@ -1006,6 +1021,24 @@ pub struct InstanceSettings {
     show_recorded_playtime: bool,
     override_options_txt: bool,
     override_servers_dat: bool,
+    /// Name of the template the instance starts from.
+    #[serde(default)]
+    template: Option<String>,
+    /// Use `java_path_override` even if it can't run the version.
+    #[serde(default)]
+    ignore_java_requirement: bool,
+    /// Heap size of the instance, `None` leaves it to the JVM.
+    #[serde(default)]
+    max_memory_mb: Option<u64>,
+    /// Folder in `instances` to install into, picked from the name if empty.
+    #[serde(skip)]
+    pub directory_name: String,
+    /// Name of the data root to install into, `None` for the launcher's own folder.
+    #[serde(default)]
+    data_root: Option<String>,
+    /// Download the assets and native jars into the instance instead of the shared folders.
+    #[serde(default)]
+    isolated_resources: bool,
 }
 
 fn as_modloader_type<'de, D>(deserializer: D) -> Result<ModloaderType, D::Error>
@@ -1032,237 +1065,467 @@ impl InstanceSettings {
             instance_icon,
             additional_jvm_arguments: "".into(),
             java_path_override: "".into(),
-            resolution_width: "800".into(),
-            resolution_height: "600".into(),
+            resolution_width: "".into(),
+            resolution_height: "".into(),
             start_window_maximized: false,
             record_playtime: true,
             show_recorded_playtime: true,
             override_options_txt: false,
             override_servers_dat: false,
+            template: None,
+            ignore_java_requirement: false,
+            max_memory_mb: None,
+            directory_name: String::new(),
+            data_root: None,
+            isolated_resources: false,
+        }
+    }
+
+    /// Gives the instance the heap size a modpack recommends, or as much as this machine can spare.
+    pub fn set_recommended_memory(&mut self, recommended_mb: u64) {
+        let bounds = MemoryBounds::detect();
+        let memory_mb = bounds.clamp(recommended_mb);
+        if memory_mb != recommended_mb {
+            warn!(
+                "{} recommends {}MB of memory, using {}MB instead",
+                self.instance_name, recommended_mb, memory_mb
+            );
         }
+        self.max_memory_mb = Some(memory_mb);
+    }
+
+    /// The instances folder of the data root the instance is installed into.
+    fn instances_dir(&self, resource_manager: &ResourceManager) -> ManifestResult<PathBuf> {
+        let data_root = self.data_root.as_deref();
+        resource_manager.instances_dir_in(data_root).ok_or_else(|| {
+            ManifestError::ResourceError(format!(
+                "There is no data root named `{}`",
+                data_root.unwrap_or_default()
+            ))
+        })
+    }
+
+    /// Fills in the settings left empty from `template`.
+    fn apply_template(&mut self, template: &InstanceTemplate) {
+        if self.resolution_width.trim().is_empty() {
+            if let Some(width) = template.resolution_width {
+                self.resolution_width = width.to_string();
+            }
+        }
+        if self.resolution_height.trim().is_empty() {
+            if let Some(height) = template.resolution_height {
+                self.resolution_height = height.to_string();
+            }
+        }
+        self.start_window_maximized |= template.fullscreen.unwrap_or(false);
+        self.max_memory_mb = self.max_memory_mb.or(template.max_memory_mb);
     }
 }
 
+/// Downloads everything needed to launch `settings` and adds it as a new instance. The time taken
+/// by each phase is saved as an install report. Installs run one at a time, the folder the instance
+/// is stored in is picked once the previous one is done.
 pub async fn create_instance(
+    mut settings: InstanceSettings,
+    app_handle: &AppHandle<Wry>,
+    author: Option<&str>,
+) -> ManifestResult<()> {
+    let resource_manager = ResourceManager::from_app_handle(app_handle);
+    let _install = resource_manager.lock_installs().await;
+    let instances_dir = settings.instances_dir(&resource_manager)?;
+    if settings.directory_name.is_empty() {
+        settings.directory_name = available_directory_name(&instances_dir, &settings.instance_name);
+    }
+    let instance_dir = instances_dir.join(&settings.directory_name);
+    let instance_name = settings.instance_name.clone();
+    let span = info_span!(
+        "create_instance",
+        instance = %instance_name,
+        version = %settings.vanilla_version,
+        modloader = ?settings.modloader_type,
+    );
+    track_install(
+        app_handle,
+        &instance_name,
+        &instance_dir,
+        install_instance(settings, app_handle, author).instrument(span),
+    )
+    .await
+}
+
+async fn install_instance(
     settings: InstanceSettings,
     app_handle: &AppHandle<Wry>,
     author: Option<&str>,
 ) -> ManifestResult<()> {
-    let resource_manager = ResourceManager::from_app_handle(&app_handle).await;
-    let start = Instant::now();
+    let launcher_settings = SettingsManager::from_app_handle(app_handle)
+        .await
+        .get_settings()
+        .clone();
+    let resource_manager = ResourceManager::from_app_handle(app_handle);
+    // Installing over an existing instance would mix both of their files.
+    let instance_dir = settings
+        .instances_dir(&resource_manager)?
+        .join(&settings.directory_name);
+    if instance_dir.exists()
+        || InstanceManager::from_app_handle(app_handle)
+            .await
+            .has_instance(&settings.instance_name)
+    {
+        return Err(ManifestError::InstanceExists(settings.instance_name));
+    }
 
-    let version: VanillaVersion = resource_manager
-        .download_vanilla_version(&settings.vanilla_version)
-        .await?;
+    let instance =
+        download_instance(settings, &resource_manager, &launcher_settings, author).await?;
+    InstanceManager::from_app_handle(app_handle)
+        .await
+        .add_instance(instance)?;
+    app_handle.emit_to("main", "instance-done", "").unwrap();
+    Ok(())
+}
 
-    // java versions is optional for versions 1.6.4 and older. We select java 8 for them by default.
-    let java_version = match version.java_version {
-        Some(version) => version,
-        None => JavaVersion {
-            component: "jre-legacy".into(),
-            major_version: 8,
-        },
+/// Downloads everything needed to launch `settings` into its instance folder and returns the
+/// configuration of the new instance, which isn't added to the launcher yet.
+pub(crate) async fn download_instance(
+    mut settings: InstanceSettings,
+    resource_manager: &ResourceManager,
+    launcher_settings: &LauncherSettings,
+    author: Option<&str>,
+) -> ManifestResult<InstanceConfiguration> {
+    let download_client_mappings = launcher_settings.download_client_mappings;
+    let instance_dir = settings
+        .instances_dir(resource_manager)?
+        .join(&settings.directory_name);
+
+    let template = match &settings.template {
+        Some(name) => Some(load_template(&resource_manager.templates_dir(), name)?),
+        None => None,
     };
-
-    let java_path = if settings.java_path_override.is_empty() {
-        download_java_version(&resource_manager.java_dir(), java_version).await?
-    } else {
-        PathBuf::from(settings.java_path_override)
+    if let Some(template) = &template {
+        settings.apply_template(template);
+    }
+    // Checked before downloading anything so a typo doesn't waste an install.
+    let additional_jvm_arguments = match parse_jvm_arguments(&settings.additional_jvm_arguments)? {
+        arguments if arguments.is_empty() => template
+            .as_ref()
+            .map(|template| template.additional_jvm_arguments.clone())
+            .unwrap_or_default(),
+        arguments => arguments,
     };
 
-    // Init vec of libraries to download.
-    let mut all_libraries: Vec<Box<dyn Downloadable + Send + Sync>> = Vec::new();
+    let phase_start = Instant::now();
+    let mut version: VanillaVersion = resource_manager
+        .download_vanilla_version(&settings.vanilla_version)
+        .await?;
+    if needs_library_overrides() {
+        override_lwjgl_for_arm64(version.libraries_mut()).await?;
+    }
+    record_phase(InstallPhase::Manifest, phase_start);
 
-    let vanilla_libraries = apply_library_rules(version.libraries);
+    let java_version = version.java_version();
+    let patches = resource_manager.version_patches();
+    let java_requirement = JavaRequirement::new(&java_version, &settings.modloader_type);
 
-    let mut vanilla_arguments = version.arguments;
+    // Init vec of libraries to download.
+    let mut all_libraries: Vec<Box<dyn Downloadable + Send + Sync>> = Vec::new();
 
-    let library_data = separate_classifiers_from_libraries(vanilla_libraries);
-    all_libraries.extend(library_data.downloadables);
+    let vanilla_libraries = apply_library_rules(version.libraries());
+
+    let mut vanilla_arguments = version.arguments().clone();
+
+    let mut library_data = separate_classifiers_from_libraries(vanilla_libraries);
+    all_libraries.append(&mut library_data.downloadables);
+    let assets_dir =
+        resource_manager.instance_assets_dir(&instance_dir, settings.isolated_resources);
+    let natives_dir =
+        resource_manager.instance_natives_dir(&instance_dir, settings.isolated_resources);
+    if settings.isolated_resources {
+        // Downloaded into the instance on their own below.
+        all_libraries.retain(|library| {
+            !library_data
+                .classifiers
+                .iter()
+                .any(|classifier| classifier.classifier().url() == library.url())
+        });
+    } else {
+        all_libraries.append(&mut library_data.native_libraries);
+    }
 
-    let mut main_class = version.main_class;
+    let mut main_class = version.main_class().to_owned();
 
     let mut library_paths: Vec<PathBuf> = Vec::new();
 
-    let game_jar_path = download_game_jar(
-        &resource_manager.version_dir(),
-        JarType::Client,
-        &version.downloads.client,
-        &version.id,
-    )
-    .await?;
+    let mut pinned_modloader: Option<PinnedModloader> = None;
 
-    // Future that, if present, will be executed after all libraries have been downloaded.
-    let mut deferred_forge_patcher: Option<BoxFuture<Result<(), io::Error>>> = None;
+    // Forge patching that, if present, will be executed once java and all libraries have been downloaded.
+    let mut deferred_forge_patcher: Option<
+        Box<dyn FnOnce(&Path) -> Result<(), ForgePatchError> + Send>,
+    > = None;
 
     // Temp dir for extracting forge installer into, closed/deleted at end of function.
-    let tmp_dir = TempDir::new("temp")?;
+    let tmp_dir = resource_manager.create_tmp_dir("temp")?;
 
-    let modloader_launch_arguments = match settings.modloader_type {
-        ModloaderType::Fabric => {
-            let profile =
-                download_fabric_profile(&settings.vanilla_version, &settings.modloader_version)
+    fs::create_dir_all(&instance_dir)?;
+
+    let version_id = version.id();
+    let client_download = version.downloads().client();
+    let client_mappings = version.downloads().client_mappings();
+    let logging_config = version.logging();
+    let version_asset_index = version.asset_index();
+    let pinned_asset_index = PinnedAssetIndex::from(version_asset_index);
+
+    let java = async {
+        let phase_start = Instant::now();
+        let java_path = match checked_java_override(
+            &settings.java_path_override,
+            &java_requirement,
+            settings.ignore_java_requirement,
+        ) {
+            Some(java_path) => java_path,
+            None => {
+                download_java_version(
+                    &resource_manager.java_dir(),
+                    java_version,
+                    launcher_settings.adoptium_java_fallback,
+                )
+                .await?
+            }
+        };
+        record_phase(InstallPhase::Java, phase_start);
+        Ok::<_, ManifestError>(java_path)
+    }
+    .instrument(debug_span!("download", phase = "java"));
+
+    let libraries = async {
+        let phase_start = Instant::now();
+        let game_jar_path = download_game_jar(
+            &resource_manager.version_dir(),
+            JarType::Client,
+            client_download,
+            version_id,
+        )
+        .await?;
+
+        if download_client_mappings {
+            // Mappings only exist for 1.14.4 and newer.
+            match client_mappings {
+                Some(mappings) => {
+                    download_game_mappings(
+                        &resource_manager.version_dir(),
+                        JarType::Client,
+                        mappings,
+                        version_id,
+                    )
                     .await?;
-            main_class = profile.main_class;
-            for fabric_library in obtain_fabric_library_hashes(&profile.libraries).await? {
-                all_libraries.push(Box::new(fabric_library));
+                }
+                None => info!("Version {} has no client mappings", version_id),
             }
-            Some(profile.arguments)
         }
-        ModloaderType::Forge => {
-            let forge_hashes = download_forge_hashes(&settings.modloader_version).await?;
-            let forge_installer_profile = download_forge_version(
-                &settings.modloader_version,
-                &settings.vanilla_version,
-                forge_hashes.installer_hash(),
-                &resource_manager.version_dir(),
-                tmp_dir.path(),
-            )
-            .await?;
-
-            let arguments: Option<LaunchArguments> = match forge_installer_profile {
-                ForgeInstallerProfile::Profile112 { version, profile } => {
-                    main_class = version.metadata.main_class;
-                    // Find the path to the forge universal jar from the profile jars list
-                    let forge_universal_path = profile
-                        .libraries
-                        .iter()
-                        .map(|library| library.name.clone())
-                        .find(|name| name.starts_with("net.minecraftforge:forge:"));
-
-                    debug!("forge_universal_path: {:#?}", forge_universal_path);
-
-                    // Pull out forge libraries with empty url's so they can be extracted from the installer
-                    let (forge_version_jars, remaining_version_libraries) =
-                        seperate_nondownloadables(version.libraries);
-                    let (forge_profile_jars, remaining_profile_libraries) =
-                        seperate_nondownloadables(profile.libraries);
-
-                    if remaining_version_libraries
-                        .iter()
-                        .any(|library| library.name.contains("log4j"))
-                    {
-                        // Filter out log4j-core and log4j-api versions from minecraft.
-                        // This fixes an issue with minecraft providing different versions of log4j-core and log4j-api which
-                        // conflict with the forge log4j libraries in the classpath.
-                        all_libraries.retain(|library| {
-                            let url = library.url();
-                            !(url.contains("log4j") && url.contains("libraries.minecraft.net"))
-                        });
-                    }
-
-                    // Pull jars out of extracted installer
-                    for jar in forge_version_jars
-                        .into_iter()
-                        .chain(forge_profile_jars.into_iter())
-                    {
-                        // Can unwrap here since the option was checked in the partition above
-                        let artifact = jar.downloads.artifact.unwrap();
-                        // Create path to jar in the extracted installer
-                        let archive_path = artifact.path(&tmp_dir.path().join("maven"));
-                        let library_path = artifact.path(&resource_manager.libraries_dir());
-                        if let Some(parent) = library_path.parent() {
-                            fs::create_dir_all(parent)?;
+        record_phase(InstallPhase::Libraries, phase_start);
+
+        // Everything done to resolve the modloader, including the forge installer and the libraries
+        // its processors need, counts towards the manifest phase.
+        let phase_start = Instant::now();
+        let modloader_launch_arguments = match settings.modloader_type {
+            ModloaderType::Fabric => {
+                let profile = download_fabric_profile(
+                    &settings.vanilla_version,
+                    &settings.modloader_version,
+                    &patches,
+                )
+                .await?;
+                main_class = profile.main_class().to_owned();
+                pinned_modloader = Some(PinnedModloader::Fabric {
+                    loader_version: settings.modloader_version.clone(),
+                });
+                for fabric_library in obtain_fabric_library_hashes(profile.libraries()).await? {
+                    all_libraries.push(Box::new(fabric_library));
+                }
+                Some(profile.arguments().clone())
+            }
+            ModloaderType::Forge => {
+                let forge_hashes = download_forge_hashes(&settings.modloader_version).await?;
+                pinned_modloader = Some(PinnedModloader::Forge {
+                    forge_version: settings.modloader_version.clone(),
+                    installer_md5: forge_hashes.installer_hash().hash().into(),
+                });
+                let forge_installer_profile = download_forge_version(
+                    &settings.modloader_version,
+                    &settings.vanilla_version,
+                    forge_hashes.installer_hash(),
+                    &resource_manager.version_dir(),
+                    tmp_dir.path(),
+                    &patches,
+                )
+                .await?;
+
+                let arguments: Option<LaunchArguments> = match forge_installer_profile {
+                    ForgeInstallerProfile::Profile112 { version, profile } => {
+                        // Fail before downloading anything if the installer can't be run.
+                        let forge_library = forge_library_name(&profile, &version)?;
+                        debug!("forge_library: {}", forge_library);
+                        main_class = version.metadata().main_class().to_owned();
+
+                        // Pull out forge libraries with empty url's so they can be extracted from the installer
+                        let (forge_version_jars, remaining_version_libraries) =
+                            seperate_nondownloadables(version.libraries());
+                        let (forge_profile_jars, remaining_profile_libraries) =
+                            seperate_nondownloadables(profile.libraries());
+
+                        if remaining_version_libraries
+                            .iter()
+                            .any(|library| library.name().contains("log4j"))
+                        {
+                            // Filter out log4j-core and log4j-api versions from minecraft.
+                            // This fixes an issue with minecraft providing different versions of log4j-core and log4j-api which
+                            // conflict with the forge log4j libraries in the classpath.
+                            all_libraries.retain(|library| {
+                                let url = library.url();
+                                !(url.contains("log4j") && url.contains("libraries.minecraft.net"))
+                            });
                         }
-                        fs::copy(archive_path, &library_path)?;
-                        library_paths.push(library_path);
-                    }
 
-                    let filtered_libraries = apply_library_rules(remaining_version_libraries);
-                    // If it is possible for forge libraries to have classifiers we are ignoring them here.
-                    let forge_library_data =
-                        separate_classifiers_from_libraries(filtered_libraries);
+                        // Pull jars out of extracted installer
+                        for jar in forge_version_jars
+                            .into_iter()
+                            .chain(forge_profile_jars.into_iter())
+                        {
+                            // Can unwrap here since the option was checked in the partition above
+                            let artifact = jar.downloads().artifact().unwrap();
+                            // Create path to jar in the extracted installer
+                            let archive_path = artifact.path(&tmp_dir.path().join("maven"));
+                            let library_path = artifact.path(&resource_manager.libraries_dir());
+                            if let Some(parent) = library_path.parent() {
+                                fs::create_dir_all(parent)?;
+                            }
+                            fs::copy(archive_path, &library_path)?;
+                            library_paths.push(library_path);
+                        }
 
-                    all_libraries.extend(forge_library_data.downloadables);
+                        let filtered_libraries = apply_library_rules(remaining_version_libraries);
+                        // If it is possible for forge libraries to have classifiers we are ignoring them here.
+                        let forge_library_data =
+                            separate_classifiers_from_libraries(filtered_libraries);
 
-                    // Download libraries used for forge processors without adding them to game's classpath
-                    download_libraries(
-                        &resource_manager.libraries_dir(),
-                        &separate_classifiers_from_libraries(remaining_profile_libraries)
-                            .downloadables,
-                    )
-                    .await?;
+                        all_libraries.extend(forge_library_data.downloadables);
 
-                    let forge_installer_paths = InstallerArgumentPaths {
-                        libraries_path: resource_manager.libraries_dir(),
-                        versions_dir_path: resource_manager.version_dir(),
-                        minecraft_version: settings.vanilla_version.clone(),
-                        forge_loader_version: settings.modloader_version.clone(),
-                        tmp_dir: tmp_dir.path().to_path_buf(),
-                    };
-
-                    deferred_forge_patcher = Some(Box::pin(async {
-                        patch_forge(
-                            &java_path.clone(),
-                            profile.processors,
-                            profile.data,
-                            forge_universal_path,
-                            forge_installer_paths,
+                        // Download libraries used for forge processors without adding them to game's classpath
+                        download_libraries(
+                            &resource_manager.libraries_dir(),
+                            &separate_classifiers_from_libraries(remaining_profile_libraries)
+                                .downloadables,
                         )
-                    }));
-                    Some(version.metadata.arguments)
-                }
-                ForgeInstallerProfile::Profile111(profile) => {
-                    let version = profile.version_info;
-
-                    for library in version.libraries {
-                        all_libraries.push(Box::new(library));
+                        .await?;
+
+                        let forge_installer_paths = InstallerArgumentPaths {
+                            libraries_path: resource_manager.libraries_dir(),
+                            versions_dir_path: resource_manager.version_dir(),
+                            minecraft_version: settings.vanilla_version.clone(),
+                            forge_loader_version: settings.modloader_version.clone(),
+                            tmp_dir: tmp_dir.path().to_path_buf(),
+                            log_path: instance_dir.join(FORGE_INSTALL_LOG),
+                            server_jar_path: profile.server_jar_path().map(str::to_owned),
+                        };
+
+                        deferred_forge_patcher = Some(Box::new(move |java_path: &Path| {
+                            patch_forge(
+                                java_path,
+                                profile.processors(),
+                                profile.data(),
+                                forge_library,
+                                JarType::Client,
+                                forge_installer_paths,
+                            )
+                        }));
+                        Some(version.metadata().arguments().clone())
                     }
+                    ForgeInstallerProfile::Profile111(profile) => {
+                        let version = profile.version_info();
 
-                    // Forge versions <= 1.11 supply the entire launch argument string, including
-                    // the vanilla arguments. We can overwrite the vanilla arguments and return no
-                    // modloader arguments.
-                    vanilla_arguments = version.metadata.arguments;
-                    None
-                }
-            };
+                        for library in version.libraries() {
+                            all_libraries.push(Box::new(library.clone()));
+                        }
 
-            arguments
-        }
-        _ => None,
-    };
+                        // Forge versions <= 1.11 supply the entire launch argument string, including
+                        // the vanilla arguments. We can overwrite the vanilla arguments and return no
+                        // modloader arguments.
+                        vanilla_arguments = version.metadata().arguments().clone();
+                        None
+                    }
+                };
 
-    library_paths.extend(
-        download_libraries(&resource_manager.libraries_dir(), &all_libraries)
+                arguments
+            }
+            _ => None,
+        };
+        record_phase(InstallPhase::Manifest, phase_start);
+
+        let phase_start = Instant::now();
+        library_paths.extend(
+            download_libraries(&resource_manager.libraries_dir(), &all_libraries)
+                .await?
+                .drain(..)
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .filter(|path| {
+                    // Filter out the classifier paths from the library paths since they were all donwloaded together but cannot be part of the
+                    // launch argument's classpath.
+                    let found = library_data.classifiers.iter().find(|classifier| {
+                        let classifier_path = classifier.path(&resource_manager.libraries_dir());
+                        classifier_path == *path
+                    });
+                    found.is_none()
+                })
+                .collect::<Vec<PathBuf>>(),
+        );
+        if settings.isolated_resources {
+            let natives: Vec<Box<dyn Downloadable + Send + Sync>> = library_data
+                .classifiers
+                .iter()
+                .map(|classifier| Box::new(classifier.classifier().clone()) as _)
+                .collect();
+            download_libraries(&natives_dir, &natives).await?;
+            library_paths
+                .extend(download_libraries(&natives_dir, &library_data.native_libraries).await?);
+        }
+        record_phase(InstallPhase::Libraries, phase_start);
+        Ok::<_, ManifestError>((game_jar_path, modloader_launch_arguments))
+    }
+    .instrument(debug_span!("download", phase = "libraries"));
+
+    let assets = async {
+        let phase_start = Instant::now();
+        let logging: Option<_> = if let Some(logging_config) = logging_config {
+            download_logging_configurations(
+                &assets_dir.join("objects"),
+                logging_config,
+                JarType::Client,
+            )
             .await?
-            .drain(..)
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .filter(|path| {
-                // Filter out the classifier paths from the library paths since they were all donwloaded together but cannot be part of the
-                // launch argument's classpath.
-                let found = library_data.classifiers.iter().find(|classifier| {
-                    let classifier_path = classifier.path(&resource_manager.libraries_dir());
-                    classifier_path == *path
-                });
-                found.is_none()
-            })
-            .collect::<Vec<PathBuf>>(),
-    );
+        } else {
+            None
+        };
 
-    if let Some(future) = deferred_forge_patcher {
-        future.await?;
+        let (asset_index, game_assets_path) =
+            download_assets(&instance_dir, &assets_dir, version_asset_index).await?;
+        record_phase(InstallPhase::Assets, phase_start);
+        Ok::<_, ManifestError>((logging, asset_index, game_assets_path))
+    }
+    .instrument(debug_span!("download", phase = "assets"));
+
+    // Java, libraries and assets don't depend on each other so they are downloaded at the same time.
+    // Every request goes through the downloader's shared limit, keeping the total bounded.
+    let (
+        java_path,
+        (game_jar_path, modloader_launch_arguments),
+        (logging, asset_index, game_assets_path),
+    ) = tokio::try_join!(java, libraries, assets)?;
+
+    if let Some(patcher) = deferred_forge_patcher {
+        let phase_start = Instant::now();
+        debug_span!("patch_forge").in_scope(|| patcher(&java_path))?;
+        record_phase(InstallPhase::Patching, phase_start);
     }
-
-    let logging: Option<_> = if let Some(logging_config) = version.logging {
-        Some(
-            download_logging_configurations(&resource_manager.asset_objects_dir(), &logging_config)
-                .await?,
-        )
-    } else {
-        None
-    };
-    let instance_dir = resource_manager
-        .instances_dir()
-        .join(&settings.instance_name);
-    fs::create_dir_all(&instance_dir)?;
-
-    let asset_index = download_assets(
-        &instance_dir,
-        &resource_manager.assets_dir(),
-        &version.asset_index,
-    )
-    .await?;
 
     let mc_version_manifest =
         resource_manager.get_vanilla_manifest_from_version(&settings.vanilla_version);
@@ -1273,27 +1536,29 @@ pub async fn create_instance(
         );
     }
     let persitent_arguments = construct_arguments(
-        main_class,
-        settings.additional_jvm_arguments,
-        (settings.resolution_width, settings.resolution_height),
-        &vanilla_arguments,
-        modloader_launch_arguments,
-        &settings.modloader_type,
-        mc_version_manifest.unwrap(),
+        VersionArguments {
+            main_class,
+            arguments: &vanilla_arguments,
+            modloader_arguments: modloader_launch_arguments,
+            modloader_type: &settings.modloader_type,
+        },
+        additional_jvm_arguments.clone(),
+        &mc_version_manifest.unwrap(),
         &asset_index,
         LaunchArgumentPaths {
             logging,
             library_paths,
             instance_path: instance_dir.clone(),
             jar_path: game_jar_path,
-            asset_dir_path: resource_manager.assets_dir(),
+            asset_dir_path: assets_dir.clone(),
+            game_assets_path,
             library_directory: resource_manager.libraries_dir(),
         },
     );
     debug!("Persistent Arguments: {}", &persitent_arguments.join(" "));
 
-    let instance_manager = InstanceManager::from_app_handle(&app_handle).await;
-
+    let vanilla_version = settings.vanilla_version.clone();
+    let snapshot = resource_manager.snapshot_manifests(&vanilla_version, pinned_modloader)?;
     // If there is no modloader, then set the "modloader_version" to the vanilla version for displaying
     // on the instances screen
     let instance_version = if settings.modloader_type == ModloaderType::None {
@@ -1302,7 +1567,7 @@ pub async fn create_instance(
         settings.modloader_version
     };
 
-    instance_manager.add_instance(InstanceConfiguration {
+    let instance = InstanceConfiguration {
         instance_name: settings.instance_name,
         jvm_path: java_path.clone(),
         arguments: persitent_arguments,
@@ -1311,18 +1576,414 @@ pub async fn create_instance(
         author: author.unwrap_or("You").into(),
         instance_icon: None,
         playtime: 0,
-    })?;
+        // The window size is applied at launch so changing the launcher defaults affects every
+        // instance that doesn't set its own.
+        resolution_width: settings.resolution_width.trim().parse().ok(),
+        resolution_height: settings.resolution_height.trim().parse().ok(),
+        fullscreen: settings.start_window_maximized.then_some(true),
+        vanilla_version: Some(vanilla_version),
+        sync: None,
+        max_memory_mb: settings.max_memory_mb,
+        prefer_discrete_gpu: template
+            .as_ref()
+            .is_some_and(|template| template.prefer_discrete_gpu),
+        additional_jvm_arguments,
+        asset_index: Some(pinned_asset_index),
+        directory_name: settings.directory_name,
+        data_root: settings.data_root,
+        isolated_resources: settings.isolated_resources,
+        update_watch: None,
+        restart_on_crash: None,
+    };
+    if let Some(template) = &template {
+        copy_template_files(&resource_manager.templates_dir(), template, &instance_dir)?;
+    }
+    snapshot.save(&instance_dir)?;
     debug!("After persistent args");
-    extract_natives(
+    extract_natives(&instance_dir, &natives_dir, library_data.classifiers)?;
+    tmp_dir.close()?;
+    Ok(instance)
+}
+
+#[cfg(test)]
+fn write_test_object(objects_dir: &Path, contents: &[u8]) -> String {
+    let hash = hash_bytes_sha1(&Bytes::copy_from_slice(contents));
+    let path = objects_dir.join(&hash[..2]).join(&hash);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+    hash
+}
+
+#[test]
+fn test_pre_1_6_assets_map_to_resources() {
+    // Excerpt of the `pre-1.6` asset index used by 1.5.2
+    let tmp_dir = TempDir::new("assets").unwrap();
+    let objects_dir = tmp_dir.path().join("assets").join("objects");
+    let instance_dir = tmp_dir.path().join("instance");
+    let sound_hash = write_test_object(&objects_dir, b"sound");
+    let music_hash = write_test_object(&objects_dir, b"music!");
+
+    let index = format!(
+        r#"{{
+            "map_to_resources": true,
+            "objects": {{
+                "sound/step/grass1.ogg": {{ "hash": "{}", "size": 5 }},
+                "music/calm1.ogg": {{ "hash": "{}", "size": 6 }}
+            }}
+        }}"#,
+        sound_hash, music_hash
+    );
+    let asset_object: AssetObject = serde_json::from_str(&index).unwrap();
+    assert!(asset_object.map_to_resources());
+    assert!(!asset_object.is_virtual());
+
+    let game_assets = game_assets_dir(
         &instance_dir,
-        &resource_manager.libraries_dir(),
-        library_data.classifiers,
-    )?;
-    info!(
-        "Finished download instance in {}ms",
-        start.elapsed().as_millis()
+        &tmp_dir.path().join("assets"),
+        "pre-1.6",
+        &asset_object,
     );
-    tmp_dir.close()?;
-    app_handle.emit_to("main", "instance-done", "").unwrap();
-    Ok(())
+    assert_eq!(game_assets, instance_dir.join("resources"));
+
+    materialize_virtual_assets(asset_object.objects(), &objects_dir, &game_assets).unwrap();
+    assert_eq!(
+        fs::read(game_assets.join("sound/step/grass1.ogg")).unwrap(),
+        b"sound"
+    );
+    assert_eq!(
+        fs::read(game_assets.join("music/calm1.ogg")).unwrap(),
+        b"music!"
+    );
+}
+
+#[test]
+fn test_legacy_assets_are_virtual() {
+    // Excerpt of the `legacy` asset index used by 1.6.4
+    let tmp_dir = TempDir::new("assets").unwrap();
+    let assets_dir = tmp_dir.path().join("assets");
+    let objects_dir = assets_dir.join("objects");
+    let hash = write_test_object(&objects_dir, b"lang");
+
+    let index = format!(
+        r#"{{
+            "virtual": true,
+            "objects": {{
+                "lang/en_US.lang": {{ "hash": "{}", "size": 4 }}
+            }}
+        }}"#,
+        hash
+    );
+    let asset_object: AssetObject = serde_json::from_str(&index).unwrap();
+    assert!(asset_object.is_virtual());
+    assert!(!asset_object.map_to_resources());
+
+    let game_assets = game_assets_dir(tmp_dir.path(), &assets_dir, "legacy", &asset_object);
+    assert_eq!(game_assets, assets_dir.join("virtual").join("legacy"));
+
+    materialize_virtual_assets(asset_object.objects(), &objects_dir, &game_assets).unwrap();
+    assert_eq!(
+        fs::read(game_assets.join("lang/en_US.lang")).unwrap(),
+        b"lang"
+    );
+    // The hashed object is kept for newer versions that share it.
+    assert!(objects_dir.join(&hash[..2]).join(&hash).is_file());
+}
+
+#[test]
+fn test_modern_assets_are_not_virtual() {
+    let asset_object: AssetObject = serde_json::from_str(r#"{ "objects": {} }"#).unwrap();
+    let assets_dir = PathBuf::from("assets");
+    assert_eq!(
+        game_assets_dir(Path::new("instance"), &assets_dir, "5", &asset_object),
+        assets_dir
+    );
+}
+
+#[test]
+fn test_java_requirement() {
+    let java_8: JavaVersion =
+        serde_json::from_str(r#"{"component": "jre-legacy", "majorVersion": 8}"#).unwrap();
+    let java_17: JavaVersion =
+        serde_json::from_str(r#"{"component": "java-runtime-gamma", "majorVersion": 17}"#).unwrap();
+
+    let legacy_forge = JavaRequirement::new(&java_8, &ModloaderType::Forge);
+    assert!(legacy_forge.allows(8));
+    assert!(!legacy_forge.allows(17));
+    assert_eq!(legacy_forge.to_string(), "java 8");
+
+    let legacy_vanilla = JavaRequirement::new(&java_8, &ModloaderType::None);
+    assert!(legacy_vanilla.allows(17));
+
+    let modern_forge = JavaRequirement::new(&java_17, &ModloaderType::Forge);
+    assert!(!modern_forge.allows(8));
+    assert!(modern_forge.allows(21));
+    assert_eq!(modern_forge.to_string(), "java 17 or newer");
+
+    assert_eq!(checked_java_override("", &modern_forge, false), None);
+    // Overrides are trusted when asked to, even if they can't be run.
+    assert_eq!(
+        checked_java_override("/missing/java", &modern_forge, true),
+        Some(PathBuf::from("/missing/java"))
+    );
+}
+
+#[test]
+fn test_account_specific_arguments() {
+    let account = MinecraftAccount {
+        name: "Steve".into(),
+        xuid: "2535400000000000".into(),
+        ..Default::default()
+    };
+    assert_eq!(
+        substitute_account_specific_arguments("${auth_player_name}", &account).as_deref(),
+        Some("Steve")
+    );
+    assert_eq!(
+        substitute_account_specific_arguments("${auth_xuid}", &account).as_deref(),
+        Some("2535400000000000")
+    );
+    assert_eq!(
+        substitute_account_specific_arguments("${clientid}", &account).as_deref(),
+        Some(client_id())
+    );
+    assert_eq!(
+        substitute_account_specific_arguments("${user_type}", &account).as_deref(),
+        Some("msa")
+    );
+    assert_eq!(
+        substitute_account_specific_arguments("--xuid", &account),
+        None
+    );
+    assert_eq!(
+        substitute_account_specific_arguments("${auth_player_name}:${auth_xuid}", &account)
+            .as_deref(),
+        Some("Steve:2535400000000000")
+    );
+
+    let offline = MinecraftAccount {
+        offline: true,
+        ..account
+    };
+    assert_eq!(
+        substitute_account_specific_arguments("${auth_access_token}", &offline).as_deref(),
+        Some(OFFLINE_ACCESS_TOKEN)
+    );
+    assert_eq!(
+        substitute_account_specific_arguments("${auth_xuid}", &offline).as_deref(),
+        Some("")
+    );
+    assert_eq!(
+        substitute_account_specific_arguments("${user_type}", &offline).as_deref(),
+        Some("legacy")
+    );
+}
+
+#[test]
+fn test_link_runtime_files() {
+    let tmp_dir = TempDir::new("runtime").unwrap();
+    let runtime_dir = tmp_dir.path();
+    fs::create_dir_all(runtime_dir.join("lib").join("server")).unwrap();
+    fs::write(
+        runtime_dir.join("lib").join("server").join("libjvm.so"),
+        "jvm",
+    )
+    .unwrap();
+    fs::write(runtime_dir.join("release"), "17").unwrap();
+
+    link_runtime_file(&runtime_dir.join("lib"), &runtime_dir.join("legal")).unwrap();
+    link_runtime_file(&runtime_dir.join("release"), &runtime_dir.join("version")).unwrap();
+    assert_eq!(
+        fs::read_to_string(runtime_dir.join("legal").join("server").join("libjvm.so")).unwrap(),
+        "jvm"
+    );
+    assert_eq!(
+        fs::read_to_string(runtime_dir.join("version")).unwrap(),
+        "17"
+    );
+
+    // The fallback when neither link can be made.
+    copy_recursively(&runtime_dir.join("lib"), &runtime_dir.join("copy")).unwrap();
+    assert!(!runtime_dir.join("copy").is_symlink());
+    assert_eq!(
+        fs::read_to_string(runtime_dir.join("copy").join("server").join("libjvm.so")).unwrap(),
+        "jvm"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_runtime_executables() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp_dir = TempDir::new("runtime").unwrap();
+    let bin_dir = tmp_dir.path().join("bin");
+    fs::create_dir_all(&bin_dir).unwrap();
+    let is_executable = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o111 != 0;
+
+    write_runtime_file(&bin_dir.join("java"), b"java", true).unwrap();
+    write_runtime_file(&bin_dir.join("release"), b"17", false).unwrap();
+    assert!(is_executable(&bin_dir.join("java")));
+    assert!(!is_executable(&bin_dir.join("release")));
+    assert_eq!(fs::read(bin_dir.join("java")).unwrap(), b"java");
+
+    // Links, and the copies made when they can't be, stay executable.
+    link_runtime_file(&bin_dir.join("java"), &bin_dir.join("linked")).unwrap();
+    copy_recursively(&bin_dir, &tmp_dir.path().join("copy")).unwrap();
+    assert!(is_executable(&bin_dir.join("linked")));
+    assert!(is_executable(&tmp_dir.path().join("copy").join("java")));
+}
+
+#[test]
+fn test_extract_native_jar() {
+    use std::io::Write;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    let entries = [
+        "META-INF/MANIFEST.MF",
+        "META-INF/versions/9/module-info.class",
+        "META-INFO.txt",
+        "linux/x64/org/lwjgl/liblwjgl.so",
+        "liblwjgl.so",
+        "liblwjgl.so.sha1",
+    ];
+    // <Exclusions, entries left in the natives folder>
+    let cases: [(&[&str], &[&str]); 4] = [
+        (&[], &entries),
+        (
+            &["META-INF/"],
+            &[
+                "META-INFO.txt",
+                "linux/x64/org/lwjgl/liblwjgl.so",
+                "liblwjgl.so",
+                "liblwjgl.so.sha1",
+            ],
+        ),
+        (
+            &["META-INF/MANIFEST", "linux/"],
+            &[
+                "META-INF/versions/9/module-info.class",
+                "META-INFO.txt",
+                "liblwjgl.so",
+                "liblwjgl.so.sha1",
+            ],
+        ),
+        (
+            &["META-INF", "liblwjgl.so.sha1"],
+            &["linux/x64/org/lwjgl/liblwjgl.so", "liblwjgl.so"],
+        ),
+    ];
+
+    let tmp_dir = TempDir::new("natives").unwrap();
+    let jar_path = tmp_dir.path().join("lwjgl-natives-linux.jar");
+    let mut zip = ZipWriter::new(File::create(&jar_path).unwrap());
+    zip.add_directory("META-INF/", SimpleFileOptions::default())
+        .unwrap();
+    for entry in entries {
+        zip.start_file(entry, SimpleFileOptions::default()).unwrap();
+        zip.write_all(entry.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+
+    for (i, (exclude, expected)) in cases.into_iter().enumerate() {
+        let natives_path = tmp_dir.path().join(format!("natives-{}", i));
+        let exclude: Vec<String> = exclude
+            .iter()
+            .map(|exclusion| exclusion.to_string())
+            .collect();
+        extract_native_jar(&jar_path, &natives_path, &exclude).unwrap();
+        for entry in entries {
+            assert_eq!(
+                natives_path.join(entry).is_file(),
+                expected.contains(&entry),
+                "{} excluding {:?}",
+                entry,
+                exclude
+            );
+        }
+    }
+}
+
+#[test]
+fn test_native_libraries_are_separated() {
+    // Synthetic libraries shaped like the ones of 1.19 and of 1.12, the hashes and sizes are made up.
+    let libraries: Vec<Library> = serde_json::from_str(
+        r#"[
+            {
+                "name": "org.lwjgl:lwjgl:3.3.1",
+                "downloads": {"artifact": {"path": "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar", "sha1": "0000000000000000000000000000000000000001", "size": 1, "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar"}}
+            },
+            {
+                "name": "org.lwjgl:lwjgl:3.3.1:natives-linux",
+                "downloads": {"artifact": {"path": "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar", "sha1": "0000000000000000000000000000000000000002", "size": 1, "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar"}},
+                "rules": [{"action": "allow", "os": {"name": "linux"}}]
+            },
+            {
+                "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4-nightly-20150209",
+                "downloads": {"classifiers": {
+                    "natives-linux": {"path": "lwjgl-platform-natives-linux.jar", "sha1": "0000000000000000000000000000000000000003", "size": 1, "url": "https://libraries.minecraft.net/lwjgl-platform-natives-linux.jar"},
+                    "natives-osx": {"path": "lwjgl-platform-natives-osx.jar", "sha1": "0000000000000000000000000000000000000004", "size": 1, "url": "https://libraries.minecraft.net/lwjgl-platform-natives-osx.jar"},
+                    "natives-windows": {"path": "lwjgl-platform-natives-windows.jar", "sha1": "0000000000000000000000000000000000000005", "size": 1, "url": "https://libraries.minecraft.net/lwjgl-platform-natives-windows.jar"}
+                }},
+                "natives": {"linux": "natives-linux", "osx": "natives-osx", "windows": "natives-windows"}
+            }
+        ]"#,
+    )
+    .unwrap();
+    assert!(!is_native_library(&libraries[0]));
+    assert!(is_native_library(&libraries[1]));
+    assert!(!is_native_library(&libraries[2]));
+
+    let library_data = separate_classifiers_from_libraries(libraries.iter().collect());
+    let urls = |downloadables: &[Box<dyn Downloadable + Send + Sync>]| {
+        downloadables
+            .iter()
+            .map(|downloadable| downloadable.url())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        urls(&library_data.native_libraries),
+        ["https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar"]
+    );
+    assert_eq!(library_data.downloadables.len(), 2);
+    assert!(!urls(&library_data.downloadables)
+        .iter()
+        .any(|url| url.contains("3.3.1-natives")));
+    assert_eq!(library_data.classifiers.len(), 1);
+}
+
+#[test]
+fn test_strip_archive_root() {
+    assert_eq!(
+        strip_archive_root(Path::new("jdk-17.0.8+7-jre/bin/java")),
+        Some(PathBuf::from("bin/java"))
+    );
+    assert_eq!(strip_archive_root(Path::new("jdk-17.0.8+7-jre/")), None);
+    assert_eq!(strip_archive_root(Path::new("jdk/../../evil")), None);
+}
+
+#[test]
+fn test_extract_java_tarball() {
+    use flate2::{write::GzEncoder, Compression};
+
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    let mut header = tar::Header::new_gnu();
+    header.set_size(4);
+    header.set_mode(0o755);
+    builder
+        .append_data(&mut header, "jdk-17.0.8+7-jre/bin/java", &b"java"[..])
+        .unwrap();
+    let bytes = builder.into_inner().unwrap().finish().unwrap();
+
+    let tmp_dir = TempDir::new("temurin").unwrap();
+    extract_java_tarball(&bytes, tmp_dir.path()).unwrap();
+    let java_path = tmp_dir.path().join("bin").join("java");
+    assert_eq!(fs::read(&java_path).unwrap(), b"java");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(
+            fs::metadata(&java_path).unwrap().permissions().mode() & 0o111,
+            0o111
+        );
+    }
 }